@@ -0,0 +1,118 @@
+//! Allocation counting.  With the `alloc-stats` feature enabled, every allocation made by
+//! the program goes through a counting wrapper around the system allocator, and
+//! [`phase`] reports how many allocations and bytes each phase of a solve needed.  The
+//! reports go to stderr, so stdout keeps only the lines the runner reads, and `aoc run`
+//! passes them on.  Without the feature, [`phase`] just runs the phase.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps the system allocator, counting every allocation and the bytes requested.
+pub struct CountingAllocator;
+
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { std::alloc::System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocation counters, either totals since the program started or the
+/// difference between two snapshots.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocations: usize,
+    pub bytes: usize,
+}
+impl AllocStats {
+    /// Gets the allocations made between an earlier snapshot and this one.
+    pub fn since(&self, earlier: &AllocStats) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations - earlier.allocations,
+            bytes: self.bytes - earlier.bytes,
+        }
+    }
+}
+
+/// Is allocation counting compiled in?
+pub fn enabled() -> bool {
+    cfg!(feature = "alloc-stats")
+}
+
+/// Gets the allocation totals so far.  Always zero unless `alloc-stats` is enabled.
+pub fn snapshot() -> AllocStats {
+    AllocStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Runs `f` and returns its result along with the allocations it made.  Allocations
+/// from other threads running at the same time are included.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocStats) {
+    let before = snapshot();
+    let value = f();
+    (value, snapshot().since(&before))
+}
+
+/// Runs one phase of a solve, printing its allocations to stderr when `alloc-stats` is
+/// enabled.
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return f();
+    }
+    let (value, stats) = measure(f);
+    eprintln!(
+        "{}: {} allocations, {} bytes",
+        name, stats.allocations, stats.bytes
+    );
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since() {
+        let earlier = AllocStats {
+            allocations: 3,
+            bytes: 100,
+        };
+        let later = AllocStats {
+            allocations: 5,
+            bytes: 164,
+        };
+        assert_eq!(
+            later.since(&earlier),
+            AllocStats {
+                allocations: 2,
+                bytes: 64
+            }
+        );
+    }
+
+    #[cfg(feature = "alloc-stats")]
+    #[test]
+    fn test_measure_counts_allocations() {
+        let (v, stats) = measure(|| vec![0_u8; 4096]);
+        assert_eq!(v.len(), 4096);
+        assert!(stats.allocations >= 1);
+        assert!(stats.bytes >= 4096);
+    }
+}
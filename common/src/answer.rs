@@ -0,0 +1,137 @@
+use crate::grid::Grid;
+
+/// The answer to one part of a puzzle, keeping its type so it can be checked against
+/// an expected value without both sides going through the same formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    String(String),
+    /// A rendered grid, one string per row.
+    Grid(Vec<String>),
+}
+
+impl Answer {
+    /// Renders a grid as an answer.
+    pub fn grid<Inner: std::fmt::Display>(grid: &Grid<Inner>) -> Self {
+        Answer::Grid(format!("{:?}", grid).lines().map(String::from).collect())
+    }
+
+    /// Does this answer match an expected value written as text?  Numbers are compared
+    /// by value, strings exactly, and grids row by row ignoring trailing whitespace.
+    pub fn matches(&self, expected: &str) -> bool {
+        let trimmed = expected.trim();
+        match self {
+            Answer::U64(value) => trimmed.parse() == Ok(*value),
+            Answer::I64(value) => trimmed.parse() == Ok(*value),
+            Answer::U128(value) => trimmed.parse() == Ok(*value),
+            Answer::String(value) => value == trimmed,
+            Answer::Grid(rows) => {
+                let expected = trimmed.lines().map(str::trim_end);
+                rows.iter().map(|row| row.trim_end()).eq(expected)
+            }
+        }
+    }
+
+    /// Fails with both values if this answer doesn't match the expected one.
+    pub fn check(&self, expected: &str) -> anyhow::Result<()> {
+        if self.matches(expected) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Expected {}, got {}",
+                expected.trim(),
+                self
+            ))
+        }
+    }
+}
+
+impl std::fmt::Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::U64(value) => write!(f, "{}", value),
+            Answer::I64(value) => write!(f, "{}", value),
+            Answer::U128(value) => write!(f, "{}", value),
+            Answer::String(value) => write!(f, "{}", value),
+            // Start a grid on its own line so it lines up after a "Part N: " label.
+            Answer::Grid(rows) => rows.iter().try_for_each(|row| write!(f, "\n{}", row)),
+        }
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(value: u64) -> Self {
+        Answer::U64(value)
+    }
+}
+impl From<u32> for Answer {
+    fn from(value: u32) -> Self {
+        Answer::U64(value as u64)
+    }
+}
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::U64(value as u64)
+    }
+}
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Answer::I64(value)
+    }
+}
+impl From<i32> for Answer {
+    fn from(value: i32) -> Self {
+        Answer::I64(value as i64)
+    }
+}
+impl From<u128> for Answer {
+    fn from(value: u128) -> Self {
+        Answer::U128(value)
+    }
+}
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::String(value)
+    }
+}
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::String(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_numbers() {
+        assert!(Answer::from(1600_u64).matches("1600\n"));
+        assert!(!Answer::from(1600_u64).matches("1601"));
+        assert!(!Answer::from(1600_u64).matches("-1600"));
+        assert!(Answer::from(-5_i64).matches(" -5"));
+        assert!(Answer::from(u128::MAX).matches(&u128::MAX.to_string()));
+    }
+
+    #[test]
+    fn test_matches_strings_and_grids() {
+        assert!(Answer::from("HBCER").matches("HBCER\n"));
+        assert!(!Answer::from("HBCER").matches("hbcer"));
+
+        let grid = "#.\n.#".parse::<Grid<char>>().unwrap();
+        let answer = Answer::grid(&grid);
+        assert_eq!(answer, Answer::Grid(vec!["#.".into(), ".#".into()]));
+        assert!(answer.matches("#.  \n.#\n"));
+        assert!(!answer.matches("#.\n##"));
+        assert_eq!(answer.to_string(), "\n#.\n.#");
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(Answer::from(3_u32).check("3").is_ok());
+        let err = Answer::from(3_u32).check("4").unwrap_err();
+        assert_eq!(err.to_string(), "Expected 4, got 3");
+    }
+}
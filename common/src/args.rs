@@ -0,0 +1,40 @@
+//! Reading a day's command line.
+
+/// Finds the input file among a day's arguments, without the program name: the first
+/// one that isn't a flag or the value of one of `flags_with_values`.  Flags can then go
+/// before the input as well as after it.
+pub fn input_path<'a>(
+    args: &'a [String],
+    flags_with_values: &[&str],
+) -> anyhow::Result<&'a String> {
+    args.iter()
+        .enumerate()
+        .find(|(i, arg)| {
+            !arg.starts_with("--")
+                && (*i == 0 || !flags_with_values.contains(&args[i - 1].as_str()))
+        })
+        .map(|(_, arg)| arg)
+        .ok_or_else(|| anyhow::anyhow!("No input file provided"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_input_path() {
+        let input = |list: &[&str]| input_path(&args(list), &["--bench"]).cloned();
+        assert_eq!(input(&["input.txt"]).unwrap(), "input.txt");
+        assert_eq!(input(&["--timing", "input.txt"]).unwrap(), "input.txt");
+        assert_eq!(input(&["input.txt", "--timing"]).unwrap(), "input.txt");
+        assert_eq!(input(&["--bench", "5", "input.txt"]).unwrap(), "input.txt");
+        assert_eq!(
+            input(&["--timing"]).unwrap_err().to_string(),
+            "No input file provided"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::grid::{CellInGrid, Grid, XY};
+
+/// A cellular simulation over a grid.  Every step, each cell's next value is worked
+/// out from the current grid, then all the changes are applied together.
+pub trait StepSim {
+    type Cell: Clone + Hash;
+
+    /// Gets a cell's value for the next step, or None to leave it as it is.
+    fn next_value(&self, cell: &CellInGrid<Self::Cell>) -> Option<Self::Cell>;
+
+    /// Should the simulation stop after a step with these changes?  By default it
+    /// stops once a step changes nothing.
+    fn is_done(&self, _grid: &Grid<Self::Cell>, changes: &[(XY, Self::Cell)]) -> bool {
+        changes.is_empty()
+    }
+}
+
+/// A simulation given by just a cell rule function.
+pub struct CellRule<C, F> {
+    rule: F,
+    cell: PhantomData<C>,
+}
+impl<C, F> StepSim for CellRule<C, F>
+where
+    C: Clone + Hash,
+    F: Fn(&CellInGrid<C>) -> Option<C>,
+{
+    type Cell = C;
+    fn next_value(&self, cell: &CellInGrid<C>) -> Option<C> {
+        (self.rule)(cell)
+    }
+}
+
+/// Makes a simulation from a cell rule function.
+pub fn cell_rule<C, F>(rule: F) -> CellRule<C, F>
+where
+    F: Fn(&CellInGrid<C>) -> Option<C>,
+{
+    CellRule {
+        rule,
+        cell: PhantomData,
+    }
+}
+
+/// Why a simulation run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// `is_done` said to stop.
+    Done,
+    /// The grid came back to an earlier state, so it would repeat forever.  The state
+    /// after step `start` is the same as the state after step `start + period`.
+    Cycle { start: usize, period: usize },
+    /// The step limit was reached first.
+    StepLimit,
+}
+
+/// The result of [`run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunReport {
+    /// The number of steps taken, including the final one.
+    pub steps: usize,
+    /// The total number of cell changes across all steps.
+    pub changes: usize,
+    pub outcome: Outcome,
+}
+
+/// Works out one step's changes and applies them, returning the changes.
+pub fn step<S: StepSim>(sim: &S, grid: &mut Grid<S::Cell>) -> Vec<(XY, S::Cell)> {
+    let changes = grid
+        .cells()
+        .filter_map(|cell| sim.next_value(&cell).map(|value| (cell.xy(), value)))
+        .collect::<Vec<_>>();
+    for (xy, value) in &changes {
+        if let Some(cell) = grid.get_mut(xy) {
+            *cell = value.clone();
+        }
+    }
+    changes
+}
+
+/// Steps the simulation until it is done, cycles, or takes `max_steps` steps.
+///
+/// Cycles are found by fingerprinting the grid after every step, so a long run keeps
+/// one hash per step rather than copies of the grid.  Two grids can share a
+/// fingerprint, so a match is confirmed by replaying from a copy of the starting grid
+/// to the earlier step and comparing the grids.
+pub fn run<S>(sim: &S, grid: &mut Grid<S::Cell>, max_steps: Option<usize>) -> RunReport
+where
+    S: StepSim,
+    S::Cell: PartialEq,
+{
+    run_fingerprinted(sim, grid, max_steps, fingerprint)
+}
+
+fn run_fingerprinted<S>(
+    sim: &S,
+    grid: &mut Grid<S::Cell>,
+    max_steps: Option<usize>,
+    fingerprint: impl Fn(&Grid<S::Cell>) -> u64,
+) -> RunReport
+where
+    S: StepSim,
+    S::Cell: PartialEq,
+{
+    let initial = grid.clone();
+    // The steps after which each fingerprint was seen.
+    let mut seen = HashMap::from([(fingerprint(grid), vec![0])]);
+    let mut report = RunReport {
+        steps: 0,
+        changes: 0,
+        outcome: Outcome::StepLimit,
+    };
+    while max_steps.is_none_or(|max| report.steps < max) {
+        let changes = step(sim, grid);
+        report.steps += 1;
+        report.changes += changes.len();
+        if sim.is_done(grid, &changes) {
+            report.outcome = Outcome::Done;
+            return report;
+        }
+        let earlier = seen.entry(fingerprint(grid)).or_default();
+        if let Some(start) = earlier
+            .iter()
+            .copied()
+            .find(|start| replay(sim, &initial, *start) == *grid)
+        {
+            report.outcome = Outcome::Cycle {
+                start,
+                period: report.steps - start,
+            };
+            return report;
+        }
+        earlier.push(report.steps);
+    }
+    report
+}
+
+/// Gets the grid after `steps` steps from `initial`.
+fn replay<S: StepSim>(sim: &S, initial: &Grid<S::Cell>, steps: usize) -> Grid<S::Cell> {
+    let mut grid = initial.clone();
+    for _ in 0..steps {
+        step(sim, &mut grid);
+    }
+    grid
+}
+
+fn fingerprint(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Conway's game of life, with `#` alive.
+    fn life() -> impl StepSim<Cell = char> {
+        cell_rule(|cell: &CellInGrid<char>| {
+            let alive = cell.adjacent_cells().filter(|c| *c.value() == '#').count();
+            match (*cell.value(), alive) {
+                ('#', 2 | 3) => None,
+                ('#', _) => Some('.'),
+                ('.', 3) => Some('#'),
+                _ => None,
+            }
+        })
+    }
+
+    #[test]
+    fn test_blinker_cycles() {
+        let mut grid = ".....\n..#..\n..#..\n..#..\n....."
+            .parse::<Grid<char>>()
+            .unwrap();
+        let report = run(&life(), &mut grid, Some(100));
+        assert_eq!(
+            report.outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+        assert_eq!(report.steps, 2);
+        assert_eq!(report.changes, 8);
+    }
+
+    #[test]
+    fn test_block_settles() {
+        let mut grid = "....\n.##.\n.#..\n....".parse::<Grid<char>>().unwrap();
+        let report = run(&life(), &mut grid, None);
+        // One cell is born, then nothing changes.
+        assert_eq!(report.outcome, Outcome::Done);
+        assert_eq!((report.steps, report.changes), (2, 1));
+        assert_eq!(
+            grid,
+            "....\n.##.\n.##.\n....".parse::<Grid<char>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_collisions_are_not_cycles() {
+        // Every grid shares one fingerprint, so only comparing the grids tells them apart.
+        let collide = |_: &Grid<char>| 0;
+        let mut grid = ".....\n..#..\n..#..\n..#..\n....."
+            .parse::<Grid<char>>()
+            .unwrap();
+        let report = run_fingerprinted(&life(), &mut grid, Some(100), collide);
+        assert_eq!(
+            report.outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+        let mut grid = "....\n.##.\n.#..\n....".parse::<Grid<char>>().unwrap();
+        let report = run_fingerprinted(&life(), &mut grid, None, collide);
+        assert_eq!(report.outcome, Outcome::Done);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mut grid = ".....\n..#..\n..#..\n..#..\n....."
+            .parse::<Grid<char>>()
+            .unwrap();
+        let report = run(&life(), &mut grid, Some(1));
+        assert_eq!(report.outcome, Outcome::StepLimit);
+        assert_eq!(report.steps, 1);
+    }
+}
@@ -0,0 +1,178 @@
+//! Saved per-day timings for `aoc bench`, so a refactor can be checked for performance
+//! regressions against the timings from before it.
+//!
+//! A baseline is a flat JSON object from day to median milliseconds:
+//!
+//! ```json
+//! {
+//!   "1": 1.25,
+//!   "7": 12.5
+//! }
+//! ```
+//!
+//! Only this flat form is read back.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::error::ErrorContext;
+
+/// The median time of each day's runs, in milliseconds.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Baseline {
+    pub days: BTreeMap<u32, f64>,
+}
+
+impl Baseline {
+    /// Reads a baseline saved by [`Baseline::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self::parse(&text).with_file(path)?)
+    }
+
+    /// Writes the baseline, replacing any file already there.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn to_json(&self) -> String {
+        let entries = self
+            .days
+            .iter()
+            .map(|(day, ms)| format!("  \"{}\": {}", day, ms))
+            .collect::<Vec<_>>();
+        format!("{{\n{}\n}}\n", entries.join(",\n"))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let body = text
+            .trim()
+            .strip_prefix('{')
+            .and_then(|body| body.strip_suffix('}'))
+            .ok_or_else(|| anyhow::anyhow!("Expected a JSON object"))?;
+        let mut days = BTreeMap::new();
+        for entry in body.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (day, ms) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Expected \"day\": ms, got {:?}", entry.trim()))?;
+            let day = day
+                .trim()
+                .strip_prefix('"')
+                .and_then(|day| day.strip_suffix('"'))
+                .and_then(|day| day.parse().ok())
+                .ok_or_else(|| anyhow::anyhow!("Invalid day {}", day.trim()))?;
+            let ms = ms
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid time for day {}", day))?;
+            days.insert(day, ms);
+        }
+        Ok(Baseline { days })
+    }
+
+    /// Compares the current timings to this baseline, for every day in either.
+    pub fn compare(&self, current: &Baseline) -> Vec<Delta> {
+        let days = self
+            .days
+            .keys()
+            .chain(current.days.keys())
+            .copied()
+            .collect::<std::collections::BTreeSet<_>>();
+        days.into_iter()
+            .map(|day| Delta {
+                day,
+                before_ms: self.days.get(&day).copied(),
+                after_ms: current.days.get(&day).copied(),
+            })
+            .collect()
+    }
+}
+
+/// How one day's time moved from the baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub day: u32,
+    /// None if the day isn't in the baseline.
+    pub before_ms: Option<f64>,
+    /// None if the day wasn't run this time.
+    pub after_ms: Option<f64>,
+}
+
+impl Delta {
+    /// The change in percent, positive when slower.  None unless the day has both
+    /// timings.
+    pub fn change_percent(&self) -> Option<f64> {
+        let (before, after) = (self.before_ms?, self.after_ms?);
+        (before > 0.0).then(|| (after - before) / before * 100.0)
+    }
+
+    /// Is the day more than `threshold_percent` slower than the baseline?
+    pub fn is_regression(&self, threshold_percent: f64) -> bool {
+        self.change_percent()
+            .is_some_and(|change| change > threshold_percent)
+    }
+}
+
+impl std::fmt::Display for Delta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |ms: Option<f64>| ms.map_or("-".to_string(), |ms| format!("{:.3}", ms));
+        let change = self
+            .change_percent()
+            .map_or("-".to_string(), |change| format!("{:+.1}%", change));
+        write!(
+            f,
+            "{:>3} {:>11} {:>11} {:>8}",
+            self.day,
+            ms(self.before_ms),
+            ms(self.after_ms),
+            change
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(days: &[(u32, f64)]) -> Baseline {
+        Baseline {
+            days: days.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let saved = baseline(&[(1, 1.25), (12, 300.0)]);
+        assert_eq!(Baseline::parse(&saved.to_json()).unwrap(), saved);
+        assert_eq!(Baseline::parse("{}").unwrap(), Baseline::default());
+        assert!(Baseline::parse("[1]").is_err());
+        let error = Baseline::parse("{\"x\": 1}").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid day \"x\"");
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = baseline(&[(1, 10.0), (2, 10.0), (3, 10.0)]);
+        let after = baseline(&[(1, 12.0), (2, 10.5), (4, 1.0)]);
+        let deltas = before.compare(&after);
+        assert_eq!(deltas.len(), 4);
+        assert_eq!(deltas[0].change_percent(), Some(20.0));
+        assert!(deltas[0].is_regression(10.0));
+        assert!(!deltas[1].is_regression(10.0));
+        assert_eq!(deltas[2].change_percent(), None);
+        assert!(!deltas[3].is_regression(0.0));
+        assert_eq!(
+            deltas[0].to_string(),
+            "  1      10.000      12.000   +20.0%"
+        );
+        assert_eq!(
+            deltas[3].to_string(),
+            "  4           -       1.000        -"
+        );
+    }
+}
@@ -0,0 +1,588 @@
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
+    process::{Command, Output, Stdio},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use common::{
+    baseline::Baseline,
+    deadline::{TIMEOUT_VAR, parse_duration},
+    error::{AocError, ErrorContext},
+    history::{History, Run},
+    mem::{format_kib, peak_rss_kib},
+    output::{DayReport, Report},
+    stats::Summary,
+    watch::{Watcher, diff_lines},
+};
+
+const USAGE: &str = "\
+Usage: aoc watch --day N [--input PATH] [--sequential] [FEATURES]
+       aoc run --day N [--input PATH] [--log PATH] [--timeout 30s] [--json PATH]
+               [FEATURES]
+       aoc bench (--save PATH | --compare PATH) [--days 1,2,...] [--runs N]
+                 [--threshold PERCENT] [--timeout 30s] [FEATURES]
+       aoc stats [--log PATH]
+       aoc verify --day N --page PATH [--log PATH]
+
+FEATURES are cargo's --no-default-features and --features LIST, used to build the
+days.  A day whose binary needs a feature that is turned off is skipped.";
+
+/// Where `aoc run` records answers, relative to the workspace root.
+const DEFAULT_LOG: &str = "answers.toml";
+
+/// How long past `--timeout` a day gets to report how far it got before it is killed.
+/// Days without deadline checks in their searches never notice the timeout.
+const TIMEOUT_GRACE: Duration = Duration::from_secs(5);
+
+/// How often a running day's memory use is sampled.
+const MEMORY_SAMPLE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How many times `aoc bench` runs each day by default.  The median is kept.
+const DEFAULT_BENCH_RUNS: usize = 5;
+
+/// How much slower than the baseline, in percent, a day may get before `aoc bench`
+/// calls it a regression.
+const DEFAULT_THRESHOLD_PERCENT: f64 = 10.0;
+
+fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("watch") => watch(&args[1..]),
+        Some("run") => run(&args[1..]),
+        Some("bench") => bench(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        _ => anyhow::bail!(USAGE),
+    }
+}
+
+/// Times every day on its input and saves the timings as a baseline, compares them
+/// with a saved one, or both.  Fails if any day fails or times out, or is slower than
+/// the baseline by more than the threshold.  Days skipped for their features are left
+/// out of the timings.  Run it from the workspace root.
+fn bench(args: &[String]) -> Result<()> {
+    let save = flag_value(args, "--save");
+    let compare = flag_value(args, "--compare");
+    if save.is_none() && compare.is_none() {
+        anyhow::bail!(USAGE);
+    }
+    let days = match flag_value(args, "--days") {
+        Some(days) => days
+            .split(',')
+            .map(|day| day.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .context("--days must be numbers like 1,4,7")?,
+        None => (1..=25)
+            .filter(|day| std::path::Path::new(&format!("day{}/part1.txt", day)).exists())
+            .collect(),
+    };
+    let runs = flag_value(args, "--runs")
+        .map(str::parse::<usize>)
+        .transpose()
+        .context("--runs must be a number")?
+        .unwrap_or(DEFAULT_BENCH_RUNS)
+        .max(1);
+    let threshold = flag_value(args, "--threshold")
+        .map(str::parse::<f64>)
+        .transpose()
+        .context("--threshold must be a percentage")?
+        .unwrap_or(DEFAULT_THRESHOLD_PERCENT);
+    let timeout = flag_value(args, "--timeout")
+        .map(|timeout| parse_duration(timeout).map(|duration| (timeout, duration)))
+        .transpose()
+        .context("Invalid --timeout")?;
+    // Load the baseline first, so a wrong path fails before the slow part.
+    let baseline = compare.map(Baseline::load).transpose()?;
+
+    let features = feature_args(args);
+    let mut built = Vec::new();
+    let mut skipped = Vec::new();
+    for day in days {
+        match build_day(day, &features)? {
+            Build::Ready => built.push(day),
+            Build::Skipped(reason) => {
+                println!("day{}: skipped, {}", day, reason);
+                skipped.push(day);
+            }
+        }
+    }
+
+    let mut current = Baseline::default();
+    // A day that fails has no time, so it counts against the bench like a regression
+    // rather than just showing as missing.
+    let mut failed = Vec::new();
+    for day in built {
+        match time_day(day, runs, timeout) {
+            Ok((ms, peak_kib)) => {
+                println!(
+                    "day{}: {:.3} ms, peak memory {}",
+                    day,
+                    ms,
+                    peak_kib.map_or("-".to_string(), format_kib)
+                );
+                current.days.insert(day, ms);
+            }
+            Err(e) => {
+                // The error names the day.
+                eprintln!("{:#}", e);
+                failed.push(day);
+            }
+        }
+    }
+    if let Some(path) = save {
+        // A baseline missing a day would let that day break unnoticed later.
+        if !failed.is_empty() {
+            anyhow::bail!("Not saving {}: days {:?} failed", path, failed);
+        }
+        current.save(path)?;
+        println!("Saved {} days to {}", current.days.len(), path);
+    }
+
+    let Some(baseline) = baseline else {
+        return Ok(());
+    };
+    println!(
+        "{:>3} {:>11} {:>11} {:>8}",
+        "day", "before ms", "after ms", "change"
+    );
+    let mut regressions = 0;
+    for delta in baseline.compare(&current) {
+        if failed.contains(&delta.day) {
+            println!("{}  failed", delta);
+        } else if skipped.contains(&delta.day) {
+            println!("{}  skipped", delta);
+        } else if delta.is_regression(threshold) {
+            regressions += 1;
+            println!("{}  regression", delta);
+        } else {
+            println!("{}", delta);
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Days {:?} failed, and {} are more than {}% slower than the baseline",
+            failed,
+            regressions,
+            threshold
+        );
+    }
+    if regressions > 0 {
+        anyhow::bail!(
+            "{} days are more than {}% slower than the baseline",
+            regressions,
+            threshold
+        );
+    }
+    Ok(())
+}
+
+/// Runs a built day on its input `runs` times, giving the median wall-clock time in
+/// milliseconds and the highest peak memory of any run in KiB.
+fn time_day(
+    day: u32,
+    runs: usize,
+    timeout: Option<(&str, Duration)>,
+) -> Result<(f64, Option<u64>)> {
+    let package = format!("day{}", day);
+    let mut times = Vec::new();
+    let mut peak_kib = None;
+    for _ in 0..runs {
+        let mut command = Command::new(format!("target/release/{}", package));
+        command.arg(format!("{}/part1.txt", package));
+        if let Some((text, _)) = timeout {
+            command.env(TIMEOUT_VAR, text);
+        }
+        let start = Instant::now();
+        let finished = run_measured(
+            command,
+            timeout.map(|(_, duration)| duration + TIMEOUT_GRACE),
+        )
+        .context("Failed to start")
+        .with_phase("bench")
+        .with_day(day)?;
+        let elapsed = start.elapsed();
+        if finished.killed || !finished.output.status.success() {
+            let error = anyhow::anyhow!("Failed after {:?}", elapsed);
+            return Err(AocError::new(error).in_phase("bench").for_day(day).into());
+        }
+        times.push(elapsed.as_secs_f64() * 1000.0);
+        peak_kib = peak_kib.max(finished.peak_kib);
+    }
+    let median = Summary::of(times)
+        .map(|summary| summary.median)
+        .ok_or_else(|| anyhow::anyhow!("No runs"))?;
+    Ok((median, peak_kib))
+}
+
+/// Runs a day once and records each part's answer, the run's wall-clock time and the
+/// day's peak memory by that part in the answer log.  Run it from the workspace root.
+/// With `--timeout`, the day is asked to give up through its deadline, and the parts it
+/// finished first are still recorded.  With `--json`, also writes the answers and time
+/// with details of the machine and commit, as a summary to keep with a write-up.
+fn run(args: &[String]) -> Result<()> {
+    let day = day_flag(args)?;
+    let package = format!("day{}", day);
+    let input = flag_value(args, "--input")
+        .map(String::from)
+        .unwrap_or_else(|| format!("{}/part1.txt", package));
+    let log = flag_value(args, "--log").unwrap_or(DEFAULT_LOG);
+    let timeout = flag_value(args, "--timeout")
+        .map(|timeout| parse_duration(timeout).map(|duration| (timeout, duration)))
+        .transpose()
+        .context("Invalid --timeout")?;
+    let json = flag_value(args, "--json");
+    // Gathered before the run, so the summary describes the tree that was built.
+    let mut report = json.map(|_| Report::new());
+
+    // Build first so the time is the solve and not the compile.
+    if let Build::Skipped(reason) = build_day(day, &feature_args(args))? {
+        println!("{}: skipped, {}", package, reason);
+        return Ok(());
+    }
+    let mut command = Command::new(format!("target/release/{}", package));
+    command.arg(&input);
+    if let Some((text, _)) = timeout {
+        command.env(TIMEOUT_VAR, text);
+    }
+    let start = Instant::now();
+    let Finished {
+        output,
+        killed,
+        peak_kib,
+        part_peaks,
+    } = run_measured(
+        command,
+        timeout.map(|(_, duration)| duration + TIMEOUT_GRACE),
+    )
+    .context("Failed to start")
+    .with_phase("run")
+    .with_day(day)?;
+    let elapsed = start.elapsed();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    print!("{}", stdout);
+    // Stderr carries what isn't an answer, like allocation counts from `alloc-stats`.
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+
+    // Every part shares the run's time, since the days don't time their parts.  A part
+    // printed as the day exited has no sample of its own, so it gets the day's peak.
+    let runs = parse_answers(&stdout)
+        .map(|(part, answer)| {
+            Run::now(day, part, answer, elapsed)
+                .with_peak_kib(part_peaks.get(&part).copied().or(peak_kib))
+        })
+        .collect::<Vec<_>>();
+    History::append(log, &runs)?;
+    println!(
+        "Recorded {} answers in {} ({:?}, peak memory {})",
+        runs.len(),
+        log,
+        elapsed,
+        peak_kib.map_or("-".to_string(), format_kib)
+    );
+    if let (Some(path), Some(report)) = (json, report.as_mut()) {
+        report.days.push(DayReport {
+            day,
+            answers: runs
+                .iter()
+                .map(|run| (run.part, run.answer.clone()))
+                .collect(),
+            elapsed,
+        });
+        report.save(path)?;
+        println!("Wrote run summary to {}", path);
+    }
+    let error = if killed {
+        anyhow::anyhow!("Killed after {:?}", elapsed)
+    } else if !output.status.success() {
+        anyhow::anyhow!("Exited with {}", output.status)
+    } else {
+        return Ok(());
+    };
+    Err(AocError::new(error).in_phase("run").for_day(day).into())
+}
+
+/// How building a day went.
+enum Build {
+    Ready,
+    /// The day's binary needs features that are turned off, for the reason cargo gave.
+    Skipped(String),
+}
+
+/// Builds a day's binary in release mode with the cargo feature flags given.  Days
+/// whose optional dependencies are features list them as the binary's
+/// `required-features`, so cargo refuses to build it without them, and that refusal
+/// is a skip rather than a failure.
+fn build_day(day: u32, features: &[&str]) -> Result<Build> {
+    let package = format!("day{}", day);
+    let output = Command::new("cargo")
+        .args(["build", "--release", "--quiet", "-p", &package])
+        .args(["--bin", &package])
+        .args(features)
+        .output()
+        .context("Failed to run cargo")
+        .with_phase("build")
+        .with_day(day)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && stderr.contains("requires the features") {
+        let reason = stderr.lines().next().unwrap_or_default();
+        return Ok(Build::Skipped(
+            reason.trim_start_matches("error: ").to_string(),
+        ));
+    }
+    eprint!("{}", stderr);
+    if !output.status.success() {
+        let error = anyhow::anyhow!("cargo build exited with {}", output.status);
+        return Err(AocError::new(error).in_phase("build").for_day(day).into());
+    }
+    Ok(Build::Ready)
+}
+
+/// Gets the cargo feature flags to build the days with: `--no-default-features` and
+/// `--features LIST`, passed through as given.
+fn feature_args(args: &[String]) -> Vec<&str> {
+    let mut features = Vec::new();
+    if args.iter().any(|arg| arg == "--no-default-features") {
+        features.push("--no-default-features");
+    }
+    if let Some(list) = flag_value(args, "--features") {
+        features.extend(["--features", list]);
+    }
+    features
+}
+
+/// Reads the features a day turns on by default from the `default = [...]` line of its
+/// Cargo.toml.
+fn default_features(package: &str) -> Result<Vec<String>> {
+    let path = format!("{}/Cargo.toml", package);
+    let manifest =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?;
+    let Some(list) = manifest
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("default = ["))
+    else {
+        return Ok(Vec::new());
+    };
+    Ok(list
+        .trim_end_matches(']')
+        .split(',')
+        .map(|feature| feature.trim().trim_matches('"').to_string())
+        .filter(|feature| !feature.is_empty())
+        .collect())
+}
+
+/// A day binary that has finished running.
+struct Finished {
+    output: Output,
+    /// Whether it was killed for running past its limit.
+    killed: bool,
+    /// Its peak resident set size in KiB, if that could be read while it ran.
+    peak_kib: Option<u64>,
+    /// Its peak so far when it printed each `Part N:` line.
+    part_peaks: PartPeaks,
+}
+
+/// Runs a command to completion like [`Command::output`], but kills it once `limit`
+/// has passed, and samples its peak memory while it runs.  A day that finishes within
+/// [`MEMORY_SAMPLE_INTERVAL`] may end before any sample is taken, and has no peak.
+fn run_measured(mut command: Command, limit: Option<Duration>) -> Result<Finished> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let pid = child.id();
+    // Drain the pipes as the child writes, so a chatty child never blocks on a full one.
+    let stdout = read_answers_in_background(child.stdout.take(), pid);
+    let stderr = read_in_background(child.stderr.take());
+    let start = Instant::now();
+    let mut killed = false;
+    let mut peak_kib = None;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if limit.is_some_and(|limit| start.elapsed() >= limit) {
+            child.kill()?;
+            killed = true;
+            break child.wait()?;
+        }
+        std::thread::sleep(MEMORY_SAMPLE_INTERVAL);
+        // Only sampled after a wait, since right after the spawn the day hasn't loaded.
+        // The high water mark only grows, so the last sample before exit is the peak.
+        peak_kib = peak_kib.max(peak_rss_kib(pid));
+    };
+    let (stdout, part_peaks) = stdout
+        .join()
+        .map_err(|_| anyhow::anyhow!("Output reader panicked"))?
+        .context("Failed to read output")?;
+    let stderr = stderr
+        .join()
+        .map_err(|_| anyhow::anyhow!("Output reader panicked"))?
+        .context("Failed to read output")?;
+    Ok(Finished {
+        output: Output {
+            status,
+            stdout,
+            stderr,
+        },
+        killed,
+        peak_kib: peak_kib.max(part_peaks.values().max().copied()),
+        part_peaks,
+    })
+}
+
+fn read_in_background(
+    pipe: Option<impl Read + Send + 'static>,
+) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer)?;
+        }
+        Ok(buffer)
+    })
+}
+
+/// The peak memory in KiB by each part's answer.
+type PartPeaks = BTreeMap<u32, u64>;
+
+/// Reads a day's stdout like [`read_in_background`], also sampling the day's peak
+/// memory as each `Part N:` line arrives.
+fn read_answers_in_background(
+    pipe: Option<impl Read + Send + 'static>,
+    pid: u32,
+) -> JoinHandle<std::io::Result<(Vec<u8>, PartPeaks)>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let mut part_peaks = BTreeMap::new();
+        let Some(pipe) = pipe else {
+            return Ok((buffer, part_peaks));
+        };
+        let mut reader = BufReader::new(pipe);
+        loop {
+            let start = buffer.len();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                return Ok((buffer, part_peaks));
+            }
+            let line = String::from_utf8_lossy(&buffer[start..]);
+            if let Some((part, _)) = parse_answers(&line).next()
+                && let Some(kib) = peak_rss_kib(pid)
+            {
+                part_peaks.insert(part, kib);
+            }
+        }
+    })
+}
+
+/// Prints the answer log as a table.
+fn stats(args: &[String]) -> Result<()> {
+    let log = flag_value(args, "--log").unwrap_or(DEFAULT_LOG);
+    print!("{}", History::load(log)?.table());
+    Ok(())
+}
+
+/// Checks the latest logged answers for a day against the ones revealed on its puzzle
+/// page, saved from the browser once the parts are solved.  Fails on any mismatch.
+fn verify(args: &[String]) -> Result<()> {
+    let day = day_flag(args)?;
+    let page = flag_value(args, "--page").ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let log = flag_value(args, "--log").unwrap_or(DEFAULT_LOG);
+    let page = std::fs::read_to_string(page).with_context(|| format!("Failed to read {}", page))?;
+    let checks = common::verify::verify(day, &page, &History::load(log)?);
+    if checks.is_empty() {
+        anyhow::bail!("No answers are revealed on the page for day {}", day);
+    }
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks.iter().filter(|check| !check.is_ok()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} answers don't match {}", failed, checks.len(), log);
+    }
+    Ok(())
+}
+
+/// Finds the `Part N: answer` lines in a day's output.
+fn parse_answers(stdout: &str) -> impl Iterator<Item = (u32, &str)> {
+    stdout.lines().filter_map(|line| {
+        let (part, answer) = line.strip_prefix("Part ")?.split_once(':')?;
+        Some((part.parse().ok()?, answer.trim()))
+    })
+}
+
+fn day_flag(args: &[String]) -> Result<u32> {
+    flag_value(args, "--day")
+        .ok_or_else(|| anyhow::anyhow!(USAGE))?
+        .parse::<u32>()
+        .context("--day must be a number")
+}
+
+/// Gets the value after a flag like `--day`.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Re-runs a day whenever its source or input changes, showing how the output moved.
+/// Run it from the workspace root.  `--sequential` builds the day without rayon, keeping
+/// its other default features.
+fn watch(args: &[String]) -> Result<()> {
+    let day = day_flag(args)?;
+    let package = format!("day{}", day);
+    let input = flag_value(args, "--input")
+        .map(String::from)
+        .unwrap_or_else(|| format!("{}/part1.txt", package));
+
+    let mut cargo_args = vec!["run", "--release", "--quiet", "-p", &package];
+    let sequential_features;
+    if args.iter().any(|arg| arg == "--sequential") {
+        sequential_features = default_features(&package)?
+            .into_iter()
+            .filter(|feature| feature != "rayon")
+            .collect::<Vec<_>>()
+            .join(",");
+        cargo_args.extend(["--no-default-features", "--features", &sequential_features]);
+    } else {
+        cargo_args.extend(feature_args(args));
+    }
+    cargo_args.extend(["--", &input]);
+
+    let mut watcher = Watcher::new([format!("{}/src", package), input.clone()])?;
+    let mut previous: Option<(String, Duration)> = None;
+    loop {
+        println!("=== Running {} on {}", package, input);
+        let start = Instant::now();
+        let output = Command::new("cargo")
+            .args(&cargo_args)
+            .output()
+            .context("Failed to run cargo")?;
+        let elapsed = start.elapsed();
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        if !output.status.success() {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        match &previous {
+            Some((before, before_elapsed)) => {
+                for change in diff_lines(before, &stdout) {
+                    println!("{}", change);
+                }
+                println!("Took {:?} (was {:?})", elapsed, before_elapsed);
+            }
+            None => {
+                print!("{}", stdout);
+                println!("Took {:?}", elapsed);
+            }
+        }
+        previous = Some((stdout, elapsed));
+
+        // Wait for the next change.
+        while watcher.poll()?.is_empty() {
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+}
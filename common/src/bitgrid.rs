@@ -0,0 +1,252 @@
+//! A fixed-size grid of bits packed 64 to a word, for occupancy checks that would be
+//! slow cell by cell.  Each row starts on a fresh word, so shifting a shape sideways is
+//! a few word shifts per row, and counting is popcounts.
+
+use crate::{
+    bounds::BoundingBox,
+    grid::{Grid, XY},
+};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A `width` by `height` grid of bits, all clear to start with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    // Row-major, `words_per_row` words per row.  Bits past `width` are always clear.
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Creates a grid with every bit clear.
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        BitGrid {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    /// Creates a grid the size of `grid`, with the bits set where `predicate` matches.
+    pub fn from_grid<Inner>(grid: &Grid<Inner>, mut predicate: impl FnMut(&Inner) -> bool) -> Self {
+        let mut bits = BitGrid::new(grid.width(), grid.height());
+        for cell in grid.cells() {
+            if predicate(cell.value()) {
+                bits.set(&cell.xy(), true);
+            }
+        }
+        bits
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets a bit.  Positions outside the grid are clear.
+    pub fn get(&self, xy: &XY) -> bool {
+        if xy.x >= self.width || xy.y >= self.height {
+            return false;
+        }
+        self.row(xy.y)[xy.x / WORD_BITS] & (1 << (xy.x % WORD_BITS)) != 0
+    }
+
+    /// Sets or clears a bit.  Panics if the position is outside the grid.
+    pub fn set(&mut self, xy: &XY, value: bool) {
+        assert!(
+            xy.x < self.width && xy.y < self.height,
+            "({}, {}) is outside the {} x {} grid",
+            xy.x,
+            xy.y,
+            self.width,
+            self.height
+        );
+        let word = &mut self.row_mut(xy.y)[xy.x / WORD_BITS];
+        let bit = 1 << (xy.x % WORD_BITS);
+        if value {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Counts the set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Counts the set bits in a rectangle, with both corners inclusive.  The part of
+    /// the rectangle outside the grid counts as clear.
+    pub fn count_ones_in_rect(&self, rect: &BoundingBox<XY>) -> usize {
+        if rect.min.x >= self.width || rect.min.y >= self.height || rect.min.x > rect.max.x {
+            return 0;
+        }
+        let max_x = rect.max.x.min(self.width - 1);
+        let (first_word, last_word) = (rect.min.x / WORD_BITS, max_x / WORD_BITS);
+        // Masks off the columns before the rectangle in its first word and after it in
+        // its last.
+        let first_mask = u64::MAX << (rect.min.x % WORD_BITS);
+        let last_mask = u64::MAX >> (WORD_BITS - 1 - max_x % WORD_BITS);
+        (rect.min.y..=rect.max.y.min(self.height - 1))
+            .map(|y| {
+                let row = &self.row(y)[first_word..=last_word];
+                let last = row.len() - 1;
+                row.iter()
+                    .enumerate()
+                    .map(|(i, word)| {
+                        let mut word = *word;
+                        if i == 0 {
+                            word &= first_mask;
+                        }
+                        if i == last {
+                            word &= last_mask;
+                        }
+                        word.count_ones() as usize
+                    })
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Sets every bit that is set in `mask` moved right and down by `offset`.  Bits
+    /// that land outside the grid are dropped.
+    pub fn or_shifted(&mut self, mask: &BitGrid, offset: &XY) {
+        for y in 0..mask.height {
+            let Some(row) = self.shifted_row(mask, y, offset) else {
+                continue;
+            };
+            let target = offset.y + y;
+            for (word, shifted) in self.row_mut(target).iter_mut().zip(row) {
+                *word |= shifted;
+            }
+        }
+    }
+
+    /// Checks whether any bit set in `mask`, moved right and down by `offset`, is also
+    /// set here.  Bits that land outside the grid never overlap.
+    pub fn overlaps(&self, mask: &BitGrid, offset: &XY) -> bool {
+        (0..mask.height).any(|y| {
+            self.shifted_row(mask, y, offset).is_some_and(|row| {
+                self.row(offset.y + y)
+                    .iter()
+                    .zip(row)
+                    .any(|(word, shifted)| word & shifted != 0)
+            })
+        })
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Gets row `y` of `mask` moved right by `offset.x`, laid out like a row of this
+    /// grid, or None if it lands below the grid.
+    fn shifted_row(&self, mask: &BitGrid, y: usize, offset: &XY) -> Option<Vec<u64>> {
+        if offset.y + y >= self.height {
+            return None;
+        }
+        let (word_shift, bit_shift) = (offset.x / WORD_BITS, offset.x % WORD_BITS);
+        let mut row = vec![0; self.words_per_row];
+        for (i, word) in mask.row(y).iter().enumerate() {
+            let target = i + word_shift;
+            if let Some(slot) = row.get_mut(target) {
+                *slot |= word << bit_shift;
+            }
+            if bit_shift > 0
+                && let Some(slot) = row.get_mut(target + 1)
+            {
+                *slot |= word >> (WORD_BITS - bit_shift);
+            }
+        }
+        // Clear anything shifted past the right edge.
+        if let Some(last) = row.last_mut()
+            && !self.width.is_multiple_of(WORD_BITS)
+        {
+            *last &= (1 << (self.width % WORD_BITS)) - 1;
+        }
+        Some(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min: (usize, usize), max: (usize, usize)) -> BoundingBox<XY> {
+        BoundingBox {
+            min: XY::new(min.0, min.1),
+            max: XY::new(max.0, max.1),
+        }
+    }
+
+    #[test]
+    fn test_get_and_set() {
+        let mut bits = BitGrid::new(130, 3);
+        for x in [0, 63, 64, 129] {
+            bits.set(&XY::new(x, 1), true);
+        }
+        assert!(bits.get(&XY::new(64, 1)));
+        assert!(!bits.get(&XY::new(65, 1)));
+        assert!(!bits.get(&XY::new(200, 1)));
+        assert_eq!(bits.count_ones(), 4);
+        bits.set(&XY::new(63, 1), false);
+        assert_eq!(bits.count_ones(), 3);
+    }
+
+    #[test]
+    fn test_count_ones_in_rect() {
+        let grid = Grid::from_rows(vec![vec![1, 0, 1, 1], vec![0, 1, 1, 0]]).unwrap();
+        let bits = BitGrid::from_grid(&grid, |value| *value == 1);
+        assert_eq!(bits.count_ones_in_rect(&rect((1, 0), (2, 1))), 3);
+        assert_eq!(bits.count_ones_in_rect(&rect((3, 0), (9, 9))), 1);
+        assert_eq!(bits.count_ones_in_rect(&rect((5, 0), (9, 9))), 0);
+
+        // Across word boundaries, against counting bit by bit.
+        let mut wide = BitGrid::new(200, 4);
+        for x in (0..200).step_by(3) {
+            wide.set(&XY::new(x, x % 4), true);
+        }
+        for (min_x, max_x) in [(0, 199), (60, 70), (63, 64), (64, 127), (10, 150)] {
+            let expected = (min_x..=max_x)
+                .flat_map(|x| (1..=2).map(move |y| XY::new(x, y)))
+                .filter(|xy| wide.get(xy))
+                .count();
+            let counted = wide.count_ones_in_rect(&rect((min_x, 1), (max_x, 2)));
+            assert_eq!(counted, expected, "{}..={}", min_x, max_x);
+        }
+    }
+
+    #[test]
+    fn test_or_shifted_and_overlaps() {
+        // An L shape.
+        let shape = Grid::from_rows(vec![vec![true, false], vec![true, true]]).unwrap();
+        let mask = BitGrid::from_grid(&shape, |value| *value);
+        let mut board = BitGrid::new(100, 4);
+        board.or_shifted(&mask, &XY::new(62, 1));
+        assert_eq!(board.count_ones(), 3);
+        assert!(board.get(&XY::new(62, 1)));
+        assert!(board.get(&XY::new(63, 2)));
+        assert!(board.overlaps(&mask, &XY::new(62, 1)));
+        assert!(board.overlaps(&mask, &XY::new(61, 0)));
+        assert!(!board.overlaps(&mask, &XY::new(64, 1)));
+
+        // Bits past the edges are dropped.
+        board.or_shifted(&mask, &XY::new(99, 3));
+        assert_eq!(board.count_ones(), 4);
+        assert!(!board.overlaps(&mask, &XY::new(0, 4)));
+    }
+}
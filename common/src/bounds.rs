@@ -0,0 +1,99 @@
+use crate::grid::XY;
+
+/// The smallest axis-aligned box containing a collection of points.  Both corners are inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoundingBox<P> {
+    pub min: P,
+    pub max: P,
+}
+
+impl<P> From<BoundingBox<P>> for (P, P) {
+    fn from(bounds: BoundingBox<P>) -> Self {
+        (bounds.min, bounds.max)
+    }
+}
+
+/// Gets the bounding box of a collection of positions, or None if there are none.
+pub fn bounding_box<'a>(points: impl IntoIterator<Item = &'a XY>) -> Option<BoundingBox<XY>> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let bounds = BoundingBox {
+        min: first.clone(),
+        max: first.clone(),
+    };
+    Some(points.fold(bounds, |mut bounds, xy| {
+        bounds.min = XY::new(bounds.min.x.min(xy.x), bounds.min.y.min(xy.y));
+        bounds.max = XY::new(bounds.max.x.max(xy.x), bounds.max.y.max(xy.y));
+        bounds
+    }))
+}
+
+/// Gets the bounding box of a collection of [x, y, z] points, or None if there are none.
+pub fn bounding_box_3d<T: Ord + Copy>(
+    points: impl IntoIterator<Item = [T; 3]>,
+) -> Option<BoundingBox<[T; 3]>> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let bounds = BoundingBox {
+        min: first,
+        max: first,
+    };
+    Some(points.fold(bounds, |mut bounds, point| {
+        for (axis, value) in point.into_iter().enumerate() {
+            bounds.min[axis] = bounds.min[axis].min(value);
+            bounds.max[axis] = bounds.max[axis].max(value);
+        }
+        bounds
+    }))
+}
+
+impl BoundingBox<XY> {
+    /// Grows the box by `n` on every side.  The minimum corner stops at zero.
+    pub fn expand(&self, n: usize) -> Self {
+        BoundingBox {
+            min: XY::new(self.min.x.saturating_sub(n), self.min.y.saturating_sub(n)),
+            max: XY::new(self.max.x + n, self.max.y + n),
+        }
+    }
+    /// Gets the number of columns covered by the box.
+    pub fn width(&self) -> usize {
+        self.max.x - self.min.x + 1
+    }
+    /// Gets the number of rows covered by the box.
+    pub fn height(&self) -> usize {
+        self.max.y - self.min.y + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounding_box() {
+        let points = [XY::new(7, 1), XY::new(11, 7), XY::new(2, 3)];
+        let bounds = bounding_box(&points).unwrap();
+        assert_eq!(bounds.min, XY::new(2, 1));
+        assert_eq!(bounds.max, XY::new(11, 7));
+        assert_eq!((bounds.width(), bounds.height()), (10, 7));
+        assert_eq!(bounding_box(&[]), None);
+    }
+
+    #[test]
+    fn test_expand() {
+        let bounds = BoundingBox {
+            min: XY::new(0, 3),
+            max: XY::new(4, 5),
+        };
+        let expanded = bounds.expand(2);
+        assert_eq!(expanded.min, XY::new(0, 1));
+        assert_eq!(expanded.max, XY::new(6, 7));
+    }
+
+    #[test]
+    fn test_bounding_box_3d() {
+        let bounds = bounding_box_3d([[1, -2, 3], [-4, 5, 6], [7, 8, -9]]).unwrap();
+        assert_eq!(bounds.min, [-4, -2, -9]);
+        assert_eq!(bounds.max, [7, 8, 6]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+/// The version of the cache file's own layout, the header line.
+const FORMAT: u32 = 1;
+
+/// A parsed structure that can be written to and read back from a cache file.
+pub trait Cacheable: Sized {
+    /// The version of the encoding and of the parser that builds the structure.  Bump
+    /// it whenever either changes, so caches written before are parsed again.
+    const VERSION: u32;
+    /// Writes the structure as text.
+    fn encode(&self) -> String;
+    /// Reads the structure back from the text written by `encode`.
+    fn decode(s: &str) -> Result<Self>;
+}
+
+/// Caches a day's parsed input in a file next to the input, so repeated runs on the
+/// same input can skip parsing.  The cache records a hash of the input it was built
+/// from, and the version of the cache layout and of the cached type, and is rebuilt
+/// whenever any of them changes.
+pub struct ParsedCache {
+    path: PathBuf,
+}
+
+impl ParsedCache {
+    /// Creates the cache for an input file.  The cache file is the input path with
+    /// `.parsed` appended.
+    pub fn for_input(input_path: impl AsRef<Path>) -> Self {
+        let mut path = input_path.as_ref().as_os_str().to_owned();
+        path.push(".parsed");
+        Self { path: path.into() }
+    }
+
+    /// Gets the path of the cache file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the parsed form of `data` from the cache, or parses it with `parse` and
+    /// saves the result if there is no usable cache.
+    pub fn load_or_parse<T: Cacheable>(
+        &self,
+        data: &str,
+        parse: impl FnOnce(&str) -> Result<T>,
+    ) -> Result<T> {
+        let header = format!(
+            "parsed-cache {} version {} hash {:016x}\n",
+            FORMAT,
+            T::VERSION,
+            content_hash(data)
+        );
+        if let Some(cached) = std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| contents.strip_prefix(&header).map(String::from))
+            .and_then(|body| T::decode(&body).ok())
+        {
+            return Ok(cached);
+        }
+
+        let parsed = parse(data)?;
+        // A cache that can't be written only costs a parse next time.
+        _ = std::fs::write(&self.path, header + &parsed.encode());
+        Ok(parsed)
+    }
+}
+
+/// Hashes the input with FNV-1a, which unlike the standard library's hasher gives the
+/// same result across builds.
+pub(crate) fn content_hash(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Numbers(Vec<u32>);
+    impl Cacheable for Numbers {
+        const VERSION: u32 = 1;
+        fn encode(&self) -> String {
+            self.0
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+        fn decode(s: &str) -> Result<Self> {
+            Ok(Numbers(
+                s.split(',').map(|n| n.parse()).collect::<Result<_, _>>()?,
+            ))
+        }
+    }
+
+    /// The same numbers from a newer parser.
+    #[derive(Debug, PartialEq)]
+    struct NewerNumbers(Vec<u32>);
+    impl Cacheable for NewerNumbers {
+        const VERSION: u32 = 2;
+        fn encode(&self) -> String {
+            Numbers(self.0.clone()).encode()
+        }
+        fn decode(s: &str) -> Result<Self> {
+            Ok(NewerNumbers(Numbers::decode(s)?.0))
+        }
+    }
+
+    fn parse_numbers(data: &str) -> Result<Numbers> {
+        Ok(Numbers(
+            data.lines().map(|n| n.parse()).collect::<Result<_, _>>()?,
+        ))
+    }
+
+    #[test]
+    fn test_load_or_parse() {
+        let input = std::env::temp_dir().join(format!("parsed_cache_{}.txt", std::process::id()));
+        let cache = ParsedCache::for_input(&input);
+        let _ = std::fs::remove_file(cache.path());
+
+        // The first load parses and writes the cache.
+        let parsed = cache.load_or_parse("1\n2\n3", parse_numbers).unwrap();
+        assert_eq!(parsed, Numbers(vec![1, 2, 3]));
+        assert!(cache.path().exists());
+
+        // The second load uses the cache.
+        let cached: Numbers = cache
+            .load_or_parse("1\n2\n3", |_| anyhow::bail!("should not parse"))
+            .unwrap();
+        assert_eq!(cached, parsed);
+
+        // A newer version of the type ignores the old cache.
+        let newer = cache
+            .load_or_parse("1\n2\n3", |data| Ok(NewerNumbers(parse_numbers(data)?.0)))
+            .unwrap();
+        assert_eq!(newer, NewerNumbers(vec![1, 2, 3]));
+        let stale: Result<Numbers> =
+            cache.load_or_parse("1\n2\n3", |_| anyhow::bail!("parsed again"));
+        assert!(stale.is_err());
+
+        // Changed input is parsed again.
+        let changed = cache.load_or_parse("4", parse_numbers).unwrap();
+        assert_eq!(changed, Numbers(vec![4]));
+
+        std::fs::remove_file(cache.path()).unwrap();
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), 0xcbf29ce484222325);
+        assert_ne!(content_hash("1\n2"), content_hash("2\n1"));
+    }
+}
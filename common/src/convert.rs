@@ -0,0 +1,59 @@
+//! Checked integer conversions that fail with a message naming the value, for the
+//! places where an `as` cast would silently truncate or wrap.
+
+use std::fmt::Display;
+
+use anyhow::Result;
+
+fn convert<T, U>(value: T, target: &str) -> Result<U>
+where
+    T: TryInto<U> + Copy + Display,
+{
+    value
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("{} does not fit in {}", value, target))
+}
+
+/// Converts to a usize, failing if the value is negative or too large.
+pub fn to_usize<T>(value: T) -> Result<usize>
+where
+    T: TryInto<usize> + Copy + Display,
+{
+    convert(value, "usize")
+}
+
+/// Converts to a u64, failing if the value is negative or too large.
+pub fn to_u64<T>(value: T) -> Result<u64>
+where
+    T: TryInto<u64> + Copy + Display,
+{
+    convert(value, "u64")
+}
+
+/// Converts to an i64, failing if the value is too large.
+pub fn to_i64<T>(value: T) -> Result<i64>
+where
+    T: TryInto<i64> + Copy + Display,
+{
+    convert(value, "i64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(to_usize(42_u64).unwrap(), 42);
+        assert_eq!(to_u64(7_usize).unwrap(), 7);
+        assert_eq!(to_i64(u32::MAX).unwrap(), u32::MAX as i64);
+        assert_eq!(
+            to_usize(-1_i64).unwrap_err().to_string(),
+            "-1 does not fit in usize"
+        );
+        assert_eq!(
+            to_i64(u64::MAX).unwrap_err().to_string(),
+            "18446744073709551615 does not fit in i64"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Deadlines for long searches.  A search checks its deadline as it goes and gives up
+//! with [`Expired`] once it has passed, so a slow part can be stopped with a note of how
+//! far it got instead of hanging.
+//!
+//! `aoc run --timeout 30s` passes the timeout to a day in the [`TIMEOUT_VAR`]
+//! environment variable, which [`Deadline::from_env`] reads.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+/// The environment variable holding a day's timeout, like `30s`.
+pub const TIMEOUT_VAR: &str = "AOC_TIMEOUT";
+
+/// When a search should give up.  Copy it into each search that should share it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// A deadline that never passes.
+    pub fn never() -> Self {
+        Self::default()
+    }
+
+    /// A deadline `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Deadline {
+            // A timeout too far off to represent may as well be never.
+            at: Instant::now().checked_add(timeout),
+        }
+    }
+
+    /// The deadline set by [`TIMEOUT_VAR`], starting now, or never if it isn't set.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(TIMEOUT_VAR) {
+            Ok(timeout) => Ok(Self::after(
+                parse_duration(&timeout).with_context(|| format!("Invalid {}", TIMEOUT_VAR))?,
+            )),
+            Err(_) => Ok(Self::never()),
+        }
+    }
+
+    /// Has the deadline passed?
+    pub fn expired(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Fails with [`Expired`] if the deadline has passed.
+    pub fn check(&self) -> Result<(), Expired> {
+        if self.expired() { Err(Expired) } else { Ok(()) }
+    }
+}
+
+/// The error a search gives when its deadline passes.  Callers add how far they got
+/// as context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expired;
+
+impl std::fmt::Display for Expired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ran out of time")
+    }
+}
+
+impl std::error::Error for Expired {}
+
+/// Whether an error, or anything in its chain, is an [`Expired`] deadline.
+pub fn is_expired(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<Expired>())
+}
+
+/// Parses a duration like `30s`, `500ms` or `2m`.  A bare number is seconds.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number = number
+        .parse::<f64>()
+        .with_context(|| format!("Invalid duration {:?}", text))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => anyhow::bail!("Unknown unit {:?} in duration {:?}", unit, text),
+    };
+    Duration::try_from_secs_f64(seconds).with_context(|| format!("Invalid duration {:?}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline() {
+        assert!(!Deadline::never().expired());
+        assert!(Deadline::after(Duration::ZERO).expired());
+        assert_eq!(Deadline::after(Duration::ZERO).check(), Err(Expired));
+        assert_eq!(Deadline::after(Duration::from_secs(3600)).check(), Ok(()));
+        assert!(!Deadline::after(Duration::MAX).expired());
+
+        let error = anyhow::Error::from(Expired).context("Solved 3 of 5");
+        assert!(is_expired(&error));
+        assert!(!is_expired(&anyhow::anyhow!("Other")));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration(" 2h ").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("10 days").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Base 10 digit arithmetic on integers, without going through strings.
+
+/// Unsigned integer types whose decimal digits can be worked with.
+pub trait Decimal: Copy + Eq {
+    const ZERO: Self;
+    const TEN: Self;
+    /// Converts a single digit, which must be at most 9.
+    fn from_digit(digit: u8) -> Self;
+    /// Gets the digit at a decimal place, where place 0 is the ones.
+    fn digit_at(self, place: u32) -> u8;
+    fn checked_ilog10(self) -> Option<u32>;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+}
+
+macro_rules! impl_decimal {
+    ($($t:ty),*) => {
+        $(
+            impl Decimal for $t {
+                const ZERO: Self = 0;
+                const TEN: Self = 10;
+                fn from_digit(digit: u8) -> Self {
+                    digit.into()
+                }
+                fn digit_at(self, place: u32) -> u8 {
+                    (self / <$t>::pow(10, place) % 10) as u8
+                }
+                fn checked_ilog10(self) -> Option<u32> {
+                    <$t>::checked_ilog10(self)
+                }
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+                fn checked_pow(self, exp: u32) -> Option<Self> {
+                    <$t>::checked_pow(self, exp)
+                }
+            }
+        )*
+    };
+}
+impl_decimal!(u32, u64, u128, usize);
+
+/// Gets the number of decimal digits in `n`.  Zero has one digit.
+pub fn num_digits<T: Decimal>(n: T) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
+/// Gets the decimal digits of `n`, most significant first.
+pub fn digits_of<T: Decimal>(n: T) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator {
+    (0..num_digits(n)).rev().map(move |place| n.digit_at(place))
+}
+
+/// Builds a number from decimal digits, most significant first.  Returns None if a
+/// digit is over 9 or the number doesn't fit in the type.
+pub fn from_digits<T: Decimal>(digits: impl IntoIterator<Item = u8>) -> Option<T> {
+    digits.into_iter().try_fold(T::ZERO, |acc, digit| {
+        if digit > 9 {
+            return None;
+        }
+        acc.checked_mul(T::TEN)?.checked_add(T::from_digit(digit))
+    })
+}
+
+/// Writes the digits of `b` after the digits of `a`, so `concat(12, 345)` is 12345.
+/// Returns None on overflow.
+pub fn concat<T: Decimal>(a: T, b: T) -> Option<T> {
+    a.checked_mul(T::TEN.checked_pow(num_digits(b))?)?
+        .checked_add(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_num_digits() {
+        assert_eq!(num_digits(0_u64), 1);
+        assert_eq!(num_digits(9_u64), 1);
+        assert_eq!(num_digits(10_u64), 2);
+        assert_eq!(num_digits(u64::MAX), 20);
+        assert_eq!(num_digits(u128::MAX), 39);
+    }
+
+    #[test]
+    fn test_digits_round_trip() {
+        assert_eq!(digits_of(9052_u64).collect::<Vec<_>>(), vec![9, 0, 5, 2]);
+        assert_eq!(digits_of(0_u64).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(digits_of(9052_u64).next_back(), Some(2));
+        for n in [0_u64, 7, 10, 9052, u64::MAX] {
+            assert_eq!(from_digits(digits_of(n)), Some(n));
+        }
+        assert_eq!(from_digits::<u64>([1, 10]), None);
+        assert_eq!(from_digits::<u64>([9; 21]), None);
+        assert_eq!(from_digits::<u128>([9; 21]), Some(10_u128.pow(21) - 1));
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(concat(12_u64, 345), Some(12345));
+        assert_eq!(concat(5_u64, 0), Some(50));
+        assert_eq!(concat(0_u64, 7), Some(7));
+        assert_eq!(concat(u64::MAX, 1), None);
+        assert_eq!(concat(u64::MAX as u128, 1), Some(u64::MAX as u128 * 10 + 1));
+    }
+}
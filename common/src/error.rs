@@ -0,0 +1,280 @@
+use std::path::{Path, PathBuf};
+
+/// An error with where it happened: which day, which phase of the solve, and which
+/// file, line and column of the input.  Every field is optional, and they are filled in
+/// as the error travels up, so a cell parser only needs to say what was wrong with the
+/// cell.
+///
+/// Converts into `anyhow::Error` with `?` like any other error.
+#[derive(Debug)]
+pub struct AocError {
+    error: anyhow::Error,
+    day: Option<u32>,
+    phase: Option<String>,
+    file: Option<PathBuf>,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+impl AocError {
+    /// Wraps an error with no context yet.  An error that already is an `AocError`
+    /// keeps the context it has, but only if nothing was added on top of it: an
+    /// `AocError` under other context is wrapped like any other error, so that context
+    /// isn't lost.
+    pub fn new(error: impl Into<anyhow::Error>) -> Self {
+        let error = error.into();
+        let outermost = error
+            .chain()
+            .next()
+            .is_some_and(|outer| outer.is::<AocError>());
+        // Downcasting also looks through context, which it would drop, so only the
+        // outermost error is taken apart.
+        let error = if outermost {
+            match error.downcast::<AocError>() {
+                Ok(error) => return error,
+                Err(error) => error,
+            }
+        } else {
+            error
+        };
+        Self {
+            error,
+            day: None,
+            phase: None,
+            file: None,
+            line: None,
+            column: None,
+        }
+    }
+    pub fn for_day(mut self, day: u32) -> Self {
+        self.day = Some(day);
+        self
+    }
+    pub fn in_phase(mut self, phase: impl Into<String>) -> Self {
+        self.phase = Some(phase.into());
+        self
+    }
+    pub fn in_file(mut self, file: impl AsRef<Path>) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+    /// Sets the 1-based input line.
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+    /// Sets the 1-based column within the line.
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Gets the underlying error, without the context.
+    pub fn error(&self) -> &anyhow::Error {
+        &self.error
+    }
+    pub fn day(&self) -> Option<u32> {
+        self.day
+    }
+    pub fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+}
+
+/// Prints the context first, like `Day 4 parse: input.txt:3:5: Invalid cell: x`.
+impl std::fmt::Display for AocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.day, &self.phase) {
+            (Some(day), Some(phase)) => write!(f, "Day {} {}: ", day, phase)?,
+            (Some(day), None) => write!(f, "Day {}: ", day)?,
+            (None, Some(phase)) => write!(f, "{}: ", phase)?,
+            (None, None) => {}
+        }
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}", file.display(), line)?,
+            (Some(file), None) => write!(f, "{}", file.display())?,
+            (None, Some(line)) => write!(f, "line {}", line)?,
+            (None, None) => {}
+        }
+        if let Some(column) = self.column {
+            if self.file.is_some() && self.line.is_some() {
+                write!(f, ":{}", column)?;
+            } else if self.file.is_some() || self.line.is_some() {
+                write!(f, ", column {}", column)?;
+            } else {
+                write!(f, "column {}", column)?;
+            }
+        }
+        if self.file.is_some() || self.line.is_some() || self.column.is_some() {
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+/// The wrapped error's own message is already part of [`Display`](std::fmt::Display), so
+/// the chain carries on from its source rather than repeating it.
+impl std::error::Error for AocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Adds [`AocError`] context to any fallible result.
+pub trait ErrorContext<T> {
+    fn with_day(self, day: u32) -> Result<T, AocError>;
+    fn with_phase(self, phase: impl Into<String>) -> Result<T, AocError>;
+    fn with_file(self, file: impl AsRef<Path>) -> Result<T, AocError>;
+    /// Says which 1-based input line the error came from.
+    fn with_line(self, line: usize) -> Result<T, AocError>;
+    /// Says which 1-based column of the line the error came from.
+    fn with_column(self, column: usize) -> Result<T, AocError>;
+}
+
+impl<T, E: Into<anyhow::Error>> ErrorContext<T> for Result<T, E> {
+    fn with_day(self, day: u32) -> Result<T, AocError> {
+        self.map_err(|e| AocError::new(e).for_day(day))
+    }
+    fn with_phase(self, phase: impl Into<String>) -> Result<T, AocError> {
+        self.map_err(|e| AocError::new(e).in_phase(phase))
+    }
+    fn with_file(self, file: impl AsRef<Path>) -> Result<T, AocError> {
+        self.map_err(|e| AocError::new(e).in_file(file))
+    }
+    fn with_line(self, line: usize) -> Result<T, AocError> {
+        self.map_err(|e| AocError::new(e).at_line(line))
+    }
+    fn with_column(self, column: usize) -> Result<T, AocError> {
+        self.map_err(|e| AocError::new(e).at_column(column))
+    }
+}
+
+/// Gets the 1-based line and column of a byte offset into some input, for parsers that
+/// only know how much input is left when they fail.
+pub fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Shows a line of input with a caret under a 1-based column, for error messages that
+/// need to point at something.
+pub fn caret_snippet(line: &str, column: usize) -> String {
+    format!("  {}\n  {}^", line, " ".repeat(column.saturating_sub(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail() -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("Invalid cell: x"))
+    }
+
+    #[test]
+    fn test_context_accumulates() {
+        let error = fail()
+            .with_column(5)
+            .with_line(3)
+            .with_file("input.txt")
+            .with_phase("parse")
+            .with_day(4)
+            .unwrap_err();
+        assert_eq!(error.line(), Some(3));
+        assert_eq!(error.error().to_string(), "Invalid cell: x");
+        assert_eq!(
+            error.to_string(),
+            "Day 4 parse: input.txt:3:5: Invalid cell: x"
+        );
+    }
+
+    #[test]
+    fn test_partial_context() {
+        assert_eq!(fail().unwrap_err().to_string(), "Invalid cell: x");
+        assert_eq!(
+            fail().with_line(2).unwrap_err().to_string(),
+            "line 2: Invalid cell: x"
+        );
+        assert_eq!(
+            fail().with_column(1).with_line(2).unwrap_err().to_string(),
+            "line 2, column 1: Invalid cell: x"
+        );
+        // Survives a trip through anyhow.
+        let error = anyhow::Error::from(fail().with_line(7).unwrap_err());
+        assert_eq!(
+            Err::<(), _>(error)
+                .with_file("a.txt")
+                .unwrap_err()
+                .to_string(),
+            "a.txt:7: Invalid cell: x"
+        );
+    }
+
+    #[test]
+    fn test_context_on_top_is_kept() {
+        use anyhow::Context;
+        let error = fail()
+            .with_line(7)
+            .context("Region 2")
+            .with_file("a.txt")
+            .unwrap_err();
+        // The line is under "Region 2", so it stays there rather than moving out.
+        assert_eq!(error.line(), None);
+        let chain = error
+            .error()
+            .chain()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(chain[..2], ["Region 2", "line 7: Invalid cell: x"]);
+        assert_eq!(error.to_string(), "a.txt: Region 2");
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(source.to_string(), "line 7: Invalid cell: x");
+    }
+
+    #[test]
+    fn test_debug_names_each_error_once() {
+        use anyhow::Context;
+        let error = anyhow::Error::from(fail().with_line(3).with_file("input.txt").unwrap_err());
+        let debug = format!("{:?}", error);
+        assert_eq!(debug.matches("Invalid cell: x").count(), 1, "{}", debug);
+        let error = anyhow::Error::from(
+            fail()
+                .context("Region 2")
+                .with_file("input.txt")
+                .unwrap_err(),
+        );
+        let debug = format!("{:?}", error);
+        assert_eq!(debug.matches("Region 2").count(), 1, "{}", debug);
+        assert_eq!(debug.matches("Invalid cell: x").count(), 1, "{}", debug);
+        assert_eq!(
+            format!("{:#}", error),
+            "input.txt: Region 2: Invalid cell: x"
+        );
+    }
+
+    #[test]
+    fn test_line_and_column() {
+        let input = "ab\ncde\nf";
+        assert_eq!(line_and_column(input, 0), (1, 1));
+        assert_eq!(line_and_column(input, 4), (2, 2));
+        assert_eq!(line_and_column(input, input.len()), (3, 2));
+    }
+
+    #[test]
+    fn test_caret_snippet() {
+        assert_eq!(caret_snippet("1 2 x", 5), "  1 2 x\n      ^");
+        assert_eq!(caret_snippet("", 1), "  \n  ^");
+    }
+}
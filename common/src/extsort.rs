@@ -0,0 +1,350 @@
+//! Sorting more items than fit in memory.
+//!
+//! [`sort_by_key`] sorts the items a chunk at a time, writes each sorted chunk to a
+//! temporary file, and merges the files as the result is read, so only one chunk, and
+//! then one item per file, is in memory at once.  At most [`MAX_FAN_IN`] files are
+//! open at a time: with more chunks than that, groups of them are first merged into
+//! bigger files, as many passes as it takes.  Items go to disk as [`Record`]s, a
+//! fixed little-endian layout, since the workspace has no serialization crate.
+//!
+//! The sort is stable: items with equal keys come out in the order they went in.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Context, Result};
+
+/// The most files merged at once, so a big sort doesn't run out of file handles.
+pub const MAX_FAN_IN: usize = 64;
+
+/// Something that can be written to a temporary file and read back.
+pub trait Record: Sized {
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()>;
+    /// Reads the next record, or None at the end of the input.
+    fn read_from(input: &mut impl Read) -> io::Result<Option<Self>>;
+}
+
+macro_rules! impl_record_for_int {
+    ($($int:ty),+) => {
+        $(
+            impl Record for $int {
+                fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+                    out.write_all(&self.to_le_bytes())
+                }
+                fn read_from(input: &mut impl Read) -> io::Result<Option<Self>> {
+                    let mut bytes = [0; size_of::<$int>()];
+                    match input.read_exact(&mut bytes) {
+                        Ok(()) => Ok(Some(<$int>::from_le_bytes(bytes))),
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+                        Err(e) => Err(e),
+                    }
+                }
+            }
+        )+
+    };
+}
+impl_record_for_int!(u32, u64, usize, i32, i64);
+
+impl<A: Record, B: Record> Record for (A, B) {
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.0.write_to(out)?;
+        self.1.write_to(out)
+    }
+    fn read_from(input: &mut impl Read) -> io::Result<Option<Self>> {
+        let Some(a) = A::read_from(input)? else {
+            return Ok(None);
+        };
+        Ok(Some((a, rest_of_record(input)?)))
+    }
+}
+
+impl<A: Record, B: Record, C: Record> Record for (A, B, C) {
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.0.write_to(out)?;
+        self.1.write_to(out)?;
+        self.2.write_to(out)
+    }
+    fn read_from(input: &mut impl Read) -> io::Result<Option<Self>> {
+        let Some(a) = A::read_from(input)? else {
+            return Ok(None);
+        };
+        Ok(Some((a, rest_of_record(input)?, rest_of_record(input)?)))
+    }
+}
+
+/// Reads a field after a record's first, which has to be there.
+fn rest_of_record<T: Record>(input: &mut impl Read) -> io::Result<T> {
+    T::read_from(input)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated record"))
+}
+
+/// Sorts items by key, keeping at most `chunk_len` of them in memory while sorting.
+/// Items that fit in one chunk are sorted in memory without touching the disk.
+pub fn sort_by_key<T, K, F>(
+    items: impl IntoIterator<Item = T>,
+    chunk_len: usize,
+    mut key: F,
+) -> Result<Sorted<T, K, F>>
+where
+    T: Record,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    let chunk_len = chunk_len.max(1);
+    let mut items = items.into_iter().peekable();
+    let mut chunk = Vec::new();
+    let mut files = Vec::new();
+    loop {
+        chunk.clear();
+        chunk.extend(items.by_ref().take(chunk_len));
+        chunk.sort_by_key(&mut key);
+        if files.is_empty() && items.peek().is_none() {
+            // Everything fit, so there is nothing to merge.
+            return Ok(Sorted {
+                state: State::Memory(std::mem::take(&mut chunk).into_iter()),
+                spilled: 0,
+            });
+        }
+        if chunk.is_empty() {
+            break;
+        }
+        files.push(TempFile::write(chunk.drain(..).map(Ok))?);
+    }
+
+    let spilled = files.len();
+    while files.len() > MAX_FAN_IN {
+        // Merging neighbouring files keeps earlier items in earlier files, so the
+        // final merge stays stable.
+        let mut merged = Vec::with_capacity(files.len().div_ceil(MAX_FAN_IN));
+        let mut files_left = files.into_iter();
+        loop {
+            let group = files_left.by_ref().take(MAX_FAN_IN).collect::<Vec<_>>();
+            if group.is_empty() {
+                break;
+            }
+            merged.push(TempFile::write(Merge::open(group, &mut key)?)?);
+        }
+        files = merged;
+    }
+    Ok(Sorted {
+        state: State::Merge(Merge::open(files, key)?),
+        spilled,
+    })
+}
+
+/// The sorted items from [`sort_by_key`], read lazily.  Reading can fail once the
+/// items have gone to disk.
+pub struct Sorted<T, K, F> {
+    state: State<T, K, F>,
+    /// How many sorted chunks were written to disk.
+    spilled: usize,
+}
+
+enum State<T, K, F> {
+    Memory(std::vec::IntoIter<T>),
+    Merge(Merge<T, K, F>),
+}
+
+struct Merge<T, K, F> {
+    /// Kept so the files are deleted once the merge is dropped.
+    files: Vec<TempFile>,
+    readers: Vec<BufReader<File>>,
+    /// The next item from each file.
+    heads: Vec<Option<T>>,
+    /// The key of each file's next item, smallest first.  Ties go to the earlier file,
+    /// which holds the earlier items, keeping the sort stable.
+    heap: BinaryHeap<Reverse<(K, usize)>>,
+    key: F,
+}
+
+impl<T: Record, K: Ord, F: FnMut(&T) -> K> Merge<T, K, F> {
+    /// Opens sorted files to merge, with earlier files holding earlier items.
+    fn open(files: Vec<TempFile>, mut key: F) -> Result<Self> {
+        let mut readers = files
+            .iter()
+            .map(TempFile::open)
+            .collect::<Result<Vec<_>>>()?;
+        let mut heads = Vec::with_capacity(readers.len());
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (i, reader) in readers.iter_mut().enumerate() {
+            let head = T::read_from(reader)?;
+            if let Some(head) = &head {
+                heap.push(Reverse((key(head), i)));
+            }
+            heads.push(head);
+        }
+        Ok(Merge {
+            files,
+            readers,
+            heads,
+            heap,
+            key,
+        })
+    }
+}
+
+impl<T: Record, K: Ord, F: FnMut(&T) -> K> Iterator for Merge<T, K, F> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, i)) = self.heap.pop()?;
+        let item = self.heads[i].take()?;
+        match T::read_from(&mut self.readers[i]) {
+            Ok(Some(next)) => {
+                self.heap.push(Reverse(((self.key)(&next), i)));
+                self.heads[i] = Some(next);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return Some(
+                    Err(e).with_context(|| {
+                        format!("Failed to read {}", self.files[i].path.display())
+                    }),
+                );
+            }
+        }
+        Some(Ok(item))
+    }
+}
+
+impl<T, K, F> Sorted<T, K, F> {
+    /// Gets how many sorted chunks were written to disk.
+    pub fn spilled_chunks(&self) -> usize {
+        self.spilled
+    }
+}
+
+impl<T: Record, K: Ord, F: FnMut(&T) -> K> Iterator for Sorted<T, K, F> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.state {
+            State::Memory(items) => items.next().map(Ok),
+            State::Merge(merge) => merge.next(),
+        }
+    }
+}
+
+/// A file in the temporary directory that is deleted when dropped.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Writes records to a new temporary file, stopping at the first that couldn't be
+    /// read.
+    fn write<T: Record>(records: impl IntoIterator<Item = Result<T>>) -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "aoc-extsort-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = TempFile { path };
+        let mut out = BufWriter::new(
+            File::create(&file.path)
+                .with_context(|| format!("Failed to write {}", file.path.display()))?,
+        );
+        for record in records {
+            record?
+                .write_to(&mut out)
+                .with_context(|| format!("Failed to write {}", file.path.display()))?;
+        }
+        out.flush()
+            .with_context(|| format!("Failed to write {}", file.path.display()))?;
+        Ok(file)
+    }
+
+    fn open(&self) -> Result<BufReader<File>> {
+        File::open(&self.path)
+            .map(BufReader::new)
+            .with_context(|| format!("Failed to read {}", self.path.display()))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // Nothing to do if it's already gone.
+        _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut bytes = Vec::new();
+        (7_u64, -3_i64, 9_usize).write_to(&mut bytes).unwrap();
+        (1_u64, 2_i64, 3_usize).write_to(&mut bytes).unwrap();
+        let mut input = bytes.as_slice();
+        assert_eq!(
+            <(u64, i64, usize)>::read_from(&mut input).unwrap(),
+            Some((7, -3, 9))
+        );
+        assert_eq!(
+            <(u64, i64, usize)>::read_from(&mut input).unwrap(),
+            Some((1, 2, 3))
+        );
+        assert_eq!(<(u64, i64, usize)>::read_from(&mut input).unwrap(), None);
+        // A record cut off after its first field is an error, not the end.
+        let mut truncated = &bytes[..12];
+        assert!(<(u64, i64)>::read_from(&mut truncated).is_err());
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        // Sort by the first field only, so the second shows the sort is stable.
+        let items = (0..1000_u64)
+            .map(|i| ((i * 7919) % 31, i))
+            .collect::<Vec<_>>();
+        let mut expected = items.clone();
+        expected.sort_by_key(|item| item.0);
+        // 7 spills more chunks than MAX_FAN_IN, so it merges in two passes.
+        for chunk_len in [7, 100, 999, 1000, 5000] {
+            let sorted = sort_by_key(items.clone(), chunk_len, |item| item.0).unwrap();
+            let spilled = sorted.spilled_chunks();
+            assert_eq!(
+                sorted.collect::<Result<Vec<_>>>().unwrap(),
+                expected,
+                "chunk_len {}",
+                chunk_len
+            );
+            let expected_spills = if chunk_len < 1000 {
+                1000_usize.div_ceil(chunk_len)
+            } else {
+                0
+            };
+            assert_eq!(spilled, expected_spills);
+        }
+        assert_eq!(
+            sort_by_key(Vec::<u64>::new(), 4, |item| *item)
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_temp_files_removed() {
+        let sorted = sort_by_key((0..10_u32).rev(), 3, |item| *item).unwrap();
+        let State::Merge(merge) = &sorted.state else {
+            panic!("Expected the chunks to spill");
+        };
+        let paths = merge
+            .files
+            .iter()
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>();
+        assert!(paths.iter().all(|path| path.exists()));
+        drop(sorted);
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+}
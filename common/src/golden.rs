@@ -0,0 +1,127 @@
+//! Golden-output checks for the day binaries, so the `Part 1:`/`Part 2:` lines the
+//! runner and scripts read stay the same.
+//!
+//! A golden file holds the full expected stdout.  `[..]` in a golden line matches any
+//! text, for output like timings that change from run to run.  Run the tests with
+//! `UPDATE_GOLDEN=1` to rewrite the golden files from the current output.
+//!
+//! [`check_error`] checks what a day says when it fails instead.
+
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+/// Runs `exe` on `input` and checks its stdout against the `golden` file.  The binary
+/// runs on one rayon thread so output printed from parallel work comes out in order.
+pub fn check_stdout(exe: &str, input: &str, golden: &str) -> Result<()> {
+    let output = Command::new(exe)
+        .arg(input)
+        .env("RAYON_NUM_THREADS", "1")
+        .output()
+        .with_context(|| format!("Failed to run {}", exe))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed with {}:\n{}",
+            exe,
+            input,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let actual = String::from_utf8(output.stdout).context("Output is not UTF-8")?;
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        return std::fs::write(golden, actual)
+            .with_context(|| format!("Failed to write {}", golden));
+    }
+    let expected =
+        std::fs::read_to_string(golden).with_context(|| format!("Failed to read {}", golden))?;
+    compare(&expected, &actual).with_context(|| format!("{} differs from {}", input, golden))
+}
+
+/// Runs `exe` on `input`, which it must fail on, and checks the first line of the error
+/// it prints against `expected`, which may use `[..]` like a golden line.
+pub fn check_error(exe: &str, input: &str, expected: &str) -> Result<()> {
+    let output = Command::new(exe)
+        .arg(input)
+        .output()
+        .with_context(|| format!("Failed to run {}", exe))?;
+    if output.status.success() {
+        anyhow::bail!("{} {} succeeded, but should have failed", exe, input);
+    }
+    let stderr = String::from_utf8(output.stderr).context("Output is not UTF-8")?;
+    // A failing main prints its error as "Error: " and then the message.
+    let first = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("Error: "))
+        .ok_or_else(|| anyhow::anyhow!("{} {} printed no error:\n{}", exe, input, stderr))?;
+    if !line_matches(expected, first) {
+        anyhow::bail!("expected error {:?}, got {:?}", expected, first);
+    }
+    Ok(())
+}
+
+/// Compares output line by line, naming the first line that differs.
+fn compare(expected: &str, actual: &str) -> Result<()> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return Ok(()),
+            (Some(want), Some(got)) if line_matches(want, got) => {}
+            (want, got) => anyhow::bail!(
+                "line {}: expected {:?}, got {:?}",
+                number,
+                want.unwrap_or("<end of output>"),
+                got.unwrap_or("<end of output>")
+            ),
+        }
+    }
+    unreachable!()
+}
+
+/// Whether a line matches a golden line, where each `[..]` matches any text.
+fn line_matches(pattern: &str, line: &str) -> bool {
+    let mut pieces = pattern.split("[..]");
+    let first = pieces.next().unwrap_or("");
+    let Some(mut rest) = line.strip_prefix(first) else {
+        return false;
+    };
+    let pieces = pieces.collect::<Vec<_>>();
+    let Some((last, middle)) = pieces.split_last() else {
+        return rest.is_empty();
+    };
+    for piece in middle {
+        match rest.find(piece) {
+            Some(at) => rest = &rest[at + piece.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_matches() {
+        assert!(line_matches("Part 1: 50", "Part 1: 50"));
+        assert!(!line_matches("Part 1: 50", "Part 1: 500"));
+        assert!(line_matches("Solved in [..] ms", "Solved in 2080 ms"));
+        assert!(line_matches("[..]", ""));
+        assert!(line_matches("a[..]b[..]c", "a1b2b3c"));
+        assert!(!line_matches("a[..]b[..]c", "a1c"));
+        assert!(!line_matches("ab[..]ba", "aba"));
+    }
+
+    #[test]
+    fn test_compare() {
+        assert!(compare("a\nb\n", "a\nb\n").is_ok());
+        let error = compare("a\nb\n", "a\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected \"b\", got \"<end of output>\""
+        );
+    }
+}
@@ -2,6 +2,8 @@ use std::str::FromStr;
 
 use anyhow::Result;
 
+use crate::error::AocError;
+
 /// An x,y position in a two-dimensional grid.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct XY {
@@ -15,12 +17,24 @@ impl FromStr for XY {
             .split_once(',')
             .ok_or_else(|| anyhow::anyhow!("Invalid XY: {}", s))?;
         Ok(XY {
-            x: x.parse()?,
-            y: y.parse()?,
+            x: crate::parse::int(x)?,
+            y: crate::parse::int(y)?,
         })
     }
 }
 
+/// Splits a line of coordinates on commas or semicolons, trimming spaces around each
+/// field, or on whitespace if it has neither.  Empty fields are kept so they fail to
+/// parse rather than being skipped.
+pub fn coordinate_fields(s: &str) -> Vec<&str> {
+    let s = s.trim();
+    if s.contains([',', ';']) {
+        s.split([',', ';']).map(str::trim).collect()
+    } else {
+        s.split_whitespace().collect()
+    }
+}
+
 impl std::hash::Hash for XY {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.x.hash(state);
@@ -33,6 +47,42 @@ impl XY {
     pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
+    /// Parses a position like [`XY::from_str`], but also accepts `"1, 2"`, `"1;2"` and
+    /// `"1\t2"`.
+    pub fn from_str_lenient(s: &str) -> Result<Self> {
+        let [x, y] = coordinate_fields(s)[..] else {
+            anyhow::bail!("Invalid XY: {}", s);
+        };
+        Ok(XY {
+            x: crate::parse::int(x)?,
+            y: crate::parse::int(y)?,
+        })
+    }
+    /// Gets the positions on the straight line from here to `other`, in order and
+    /// including both ends.  The line may run in any of the 8 directions: horizontal,
+    /// vertical or at 45 degrees.  Any other slope is an error.
+    pub fn line_to(&self, other: &XY) -> Result<impl Iterator<Item = XY> + use<>> {
+        let (dx, dy) = (self.x.abs_diff(other.x), self.y.abs_diff(other.y));
+        if dx != 0 && dy != 0 && dx != dy {
+            anyhow::bail!(
+                "Line from ({}, {}) to ({}, {}) is not horizontal, vertical or diagonal",
+                self.x,
+                self.y,
+                other.x,
+                other.y
+            );
+        }
+        // Moves `i` steps from `start` towards `end`, or stays put if they're equal.
+        fn step(start: usize, end: usize, i: usize) -> usize {
+            match end.cmp(&start) {
+                std::cmp::Ordering::Greater => start + i,
+                std::cmp::Ordering::Less => start - i,
+                std::cmp::Ordering::Equal => start,
+            }
+        }
+        let (from, to) = (self.clone(), other.clone());
+        Ok((0..=dx.max(dy)).map(move |i| XY::new(step(from.x, to.x, i), step(from.y, to.y, i))))
+    }
     pub fn add(&self, other: &XY) -> XY {
         XY {
             x: self.x + other.x,
@@ -40,33 +90,22 @@ impl XY {
         }
     }
     /// Returns an iterator of the adjacent cardinal positions.
-    pub fn adjacent_cardinal_positions(&self) -> impl Iterator<Item = XY> {
-        const DIRECTIONS: &[(isize, isize)] = &[(0, -1), (1, 0), (0, 1), (-1, 0)];
-        DIRECTIONS.iter().filter_map(|(dx, dy)| {
-            Some(XY {
-                x: self.x.checked_add_signed(*dx)?,
-                y: self.y.checked_add_signed(*dy)?,
-            })
-        })
+    pub fn adjacent_cardinal_positions(&self) -> impl Iterator<Item = XY> + use<> {
+        self.neighbor_positions(Connectivity::Cardinal)
     }
 
     /// Returns an iterator of all adjacent positions, including diagonals.
-    pub fn adjacent_positions(&self) -> impl Iterator<Item = XY> {
-        const DIRECTIONS: &[(isize, isize)] = &[
-            // Up
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            // Center
-            (-1, 0),
-            (1, 0),
-            // Down
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-        ];
+    pub fn adjacent_positions(&self) -> impl Iterator<Item = XY> + use<> {
+        self.neighbor_positions(Connectivity::All)
+    }
+
+    /// Returns an iterator of the adjacent positions for the given connectivity.
+    pub fn neighbor_positions(
+        &self,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = XY> + use<> {
         let (x, y) = (self.x, self.y);
-        DIRECTIONS.iter().filter_map(move |(dx, dy)| {
+        connectivity.offsets().iter().filter_map(move |(dx, dy)| {
             Some(XY {
                 x: x.checked_add_signed(*dx)?,
                 y: y.checked_add_signed(*dy)?,
@@ -74,6 +113,22 @@ impl XY {
         })
     }
 
+    /// Replaces the contents of `buf` with the adjacent positions for the given
+    /// connectivity, so a loop can reuse one buffer instead of collecting each time.
+    pub fn neighbor_positions_into(&self, connectivity: Connectivity, buf: &mut Vec<XY>) {
+        buf.clear();
+        buf.extend(self.neighbor_positions(connectivity));
+    }
+
+    /// Replaces the contents of `buf` with all adjacent positions, including diagonals.
+    pub fn adjacent_positions_into(&self, buf: &mut Vec<XY>) {
+        self.neighbor_positions_into(Connectivity::All, buf);
+    }
+
+    /// Returns the position one step up.
+    pub fn up(&self) -> Option<XY> {
+        self.y.checked_sub(1).map(|y| XY::new(self.x, y))
+    }
     /// Returns the position one step down.
     pub fn down(&self) -> Option<XY> {
         self.y.checked_add(1).map(|y| XY::new(self.x, y))
@@ -88,6 +143,128 @@ impl XY {
     }
 }
 
+/// Which surrounding positions count as adjacent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// Up, right, down and left.
+    Cardinal,
+    /// The cardinal directions plus the four diagonals.
+    #[default]
+    All,
+}
+impl Connectivity {
+    /// Gets the (dx, dy) offsets of the adjacent positions.
+    pub fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Cardinal => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Connectivity::All => &[
+                // Up
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                // Center
+                (-1, 0),
+                (1, 0),
+                // Down
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// One of the eight ways to rotate or reflect a grid onto itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    /// Rotate 90° clockwise.
+    R90,
+    R180,
+    R270,
+    /// Mirror left to right.
+    FlipH,
+    /// Mirror top to bottom.
+    FlipV,
+    /// Mirror across the main diagonal, swapping rows and columns.
+    Transpose,
+    /// Mirror across the other diagonal.
+    AntiTranspose,
+}
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::R90,
+        Transform::R180,
+        Transform::R270,
+        Transform::FlipH,
+        Transform::FlipV,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    /// Gets the position `xy` of a `width` by `height` grid moves to under this
+    /// transform, matching [`Grid::transform`].
+    pub fn apply_to_xy(&self, xy: &XY, width: usize, height: usize) -> XY {
+        let (x, y) = (xy.x, xy.y);
+        let (far_x, far_y) = (width - 1 - x, height - 1 - y);
+        match self {
+            Transform::Identity => XY::new(x, y),
+            Transform::R90 => XY::new(far_y, x),
+            Transform::R180 => XY::new(far_x, far_y),
+            Transform::R270 => XY::new(y, far_x),
+            Transform::FlipH => XY::new(far_x, y),
+            Transform::FlipV => XY::new(x, far_y),
+            Transform::Transpose => XY::new(y, x),
+            Transform::AntiTranspose => XY::new(far_y, far_x),
+        }
+    }
+
+    /// Whether the transform swaps width and height.
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Transform::R90 | Transform::R270 | Transform::Transpose | Transform::AntiTranspose
+        )
+    }
+
+    /// The transform that does `self` and then `then`.
+    pub fn compose(&self, then: Transform) -> Transform {
+        let [[a, b], [c, d]] = self.matrix();
+        let [[e, f], [g, h]] = then.matrix();
+        let product = [
+            [e * a + f * c, e * b + f * d],
+            [g * a + h * c, g * b + h * d],
+        ];
+        Transform::ALL
+            .into_iter()
+            .find(|transform| transform.matrix() == product)
+            .expect("the eight transforms are closed under composition")
+    }
+
+    /// The transform that undoes this one.
+    pub fn inverse(&self) -> Transform {
+        Transform::ALL
+            .into_iter()
+            .find(|transform| self.compose(*transform) == Transform::Identity)
+            .expect("every transform has an inverse")
+    }
+
+    /// How the transform moves a position relative to the grid's centre, with y down.
+    fn matrix(&self) -> [[i8; 2]; 2] {
+        match self {
+            Transform::Identity => [[1, 0], [0, 1]],
+            Transform::R90 => [[0, -1], [1, 0]],
+            Transform::R180 => [[-1, 0], [0, -1]],
+            Transform::R270 => [[0, 1], [-1, 0]],
+            Transform::FlipH => [[-1, 0], [0, 1]],
+            Transform::FlipV => [[1, 0], [0, -1]],
+            Transform::Transpose => [[0, 1], [1, 0]],
+            Transform::AntiTranspose => [[0, -1], [-1, 0]],
+        }
+    }
+}
+
 /// A two-dimensional grid of cells.
 #[derive(Eq, PartialEq)]
 pub struct Grid<Inner> {
@@ -100,6 +277,17 @@ impl<Inner: Clone> Clone for Grid<Inner> {
             cells: self.cells.clone(),
         }
     }
+    // Reuses the existing rows, so copying into a scratch grid doesn't allocate.
+    fn clone_from(&mut self, source: &Self) {
+        self.cells.clone_from(&source.cells);
+    }
+}
+
+/// An empty grid with no rows.
+impl<Inner> Default for Grid<Inner> {
+    fn default() -> Self {
+        Grid { cells: Vec::new() }
+    }
 }
 
 impl<Inner: std::hash::Hash> std::hash::Hash for Grid<Inner> {
@@ -115,8 +303,12 @@ impl<Inner: Clone> Grid<Inner> {
     }
 }
 
-impl<Inner: FromStr> FromStr for Grid<Inner> {
-    type Err = <Inner as FromStr>::Err;
+impl<Inner> FromStr for Grid<Inner>
+where
+    Inner: FromStr,
+    Inner::Err: Into<anyhow::Error>,
+{
+    type Err = AocError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Self::from_lines(s.lines())
     }
@@ -124,26 +316,49 @@ impl<Inner: FromStr> FromStr for Grid<Inner> {
 impl<Inner> Grid<Inner>
 where
     Inner: FromStr,
+    Inner::Err: Into<anyhow::Error>,
 {
     /// Creates a grid from an iterator of lines, parsing each character as a cell.
-    pub fn from_lines(
-        lines: impl IntoIterator<Item = impl AsRef<str>>,
-    ) -> Result<Self, <Inner as FromStr>::Err> {
+    /// Errors say which line and column held the bad cell.
+    pub fn from_lines(lines: impl IntoIterator<Item = impl AsRef<str>>) -> Result<Self, AocError> {
         let cells = lines
             .into_iter()
+            .enumerate()
             // For every row
-            .map(|line| {
+            .map(|(y, line)| {
                 line.as_ref()
                     .chars()
-                    .map(|c| Inner::from_str(&c.to_string()))
-                    .collect::<Result<Vec<_>, <Inner as FromStr>::Err>>()
+                    .enumerate()
+                    .map(|(x, c)| {
+                        Inner::from_str(&c.to_string())
+                            .map_err(|e| AocError::new(e).at_line(y + 1).at_column(x + 1))
+                    })
+                    .collect::<Result<Vec<_>, AocError>>()
             })
-            .collect::<Result<Vec<Vec<_>>, <Inner as FromStr>::Err>>()?;
+            .collect::<Result<Vec<Vec<_>>, AocError>>()?;
         Ok(Grid { cells })
     }
 }
 
 impl<Inner> Grid<Inner> {
+    /// Creates a grid from rows of cells, failing if the rows aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<Inner>>) -> Result<Self> {
+        if let Some(first) = rows.first()
+            && let Some(y) = rows.iter().position(|row| row.len() != first.len())
+        {
+            anyhow::bail!(
+                "Row {} has {} cells, expected {}",
+                y,
+                rows[y].len(),
+                first.len()
+            );
+        }
+        Ok(Grid { cells: rows })
+    }
+    /// Returns an iterator over the rows of the grid, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Inner]> {
+        self.cells.iter().map(|row| row.as_slice())
+    }
     pub fn width(&self) -> usize {
         self.cells.first().map(|row| row.len()).unwrap_or(0)
     }
@@ -202,6 +417,84 @@ impl<Inner: Clone> Grid<Inner> {
         let cells = self.cells.iter().rev().cloned().collect();
         Grid { cells }
     }
+    /// Swaps rows and columns: new[x][y] = old[y][x].
+    pub fn transpose(&self) -> Self {
+        let cells = (0..self.width())
+            .map(|x| self.cells.iter().map(|row| row[x].clone()).collect())
+            .collect();
+        Grid { cells }
+    }
+
+    /// Gets the grid rotated or reflected by `transform`.
+    pub fn transform(&self, transform: Transform) -> Self {
+        match transform {
+            Transform::Identity => self.clone(),
+            Transform::R90 => self.rotate_90(),
+            Transform::R180 => self.rotate_180(),
+            Transform::R270 => self.rotate_270(),
+            Transform::FlipH => self.flip_horizontal(),
+            Transform::FlipV => self.flip_vertical(),
+            Transform::Transpose => self.transpose(),
+            Transform::AntiTranspose => self.transpose().rotate_180(),
+        }
+    }
+}
+
+// Rotation of grid without allocating.  Square grids keep their shape, so they are
+// rearranged in place; other grids change shape and get new rows.
+impl<Inner: Clone> Grid<Inner> {
+    pub fn is_square(&self) -> bool {
+        self.width() == self.height()
+    }
+    pub fn flip_horizontal_in_place(&mut self) {
+        for row in &mut self.cells {
+            row.reverse();
+        }
+    }
+    pub fn flip_vertical_in_place(&mut self) {
+        self.cells.reverse();
+    }
+    pub fn transpose_in_place(&mut self) {
+        if !self.is_square() {
+            *self = self.transpose();
+            return;
+        }
+        for y in 0..self.height() {
+            for x in y + 1..self.width() {
+                // Row y is before row x, so they can be borrowed separately.
+                let (above, below) = self.cells.split_at_mut(x);
+                std::mem::swap(&mut above[y][x], &mut below[0][y]);
+            }
+        }
+    }
+    pub fn rotate_90_in_place(&mut self) {
+        self.transpose_in_place();
+        self.flip_horizontal_in_place();
+    }
+    pub fn rotate_180_in_place(&mut self) {
+        self.flip_vertical_in_place();
+        self.flip_horizontal_in_place();
+    }
+    pub fn rotate_270_in_place(&mut self) {
+        self.transpose_in_place();
+        self.flip_vertical_in_place();
+    }
+    /// Rotates or reflects the grid by `transform`, in place if it is square.
+    pub fn transform_in_place(&mut self, transform: Transform) {
+        match transform {
+            Transform::Identity => {}
+            Transform::R90 => self.rotate_90_in_place(),
+            Transform::R180 => self.rotate_180_in_place(),
+            Transform::R270 => self.rotate_270_in_place(),
+            Transform::FlipH => self.flip_horizontal_in_place(),
+            Transform::FlipV => self.flip_vertical_in_place(),
+            Transform::Transpose => self.transpose_in_place(),
+            Transform::AntiTranspose => {
+                self.transpose_in_place();
+                self.rotate_180_in_place();
+            }
+        }
+    }
 }
 
 impl<Inner> std::fmt::Debug for Grid<Inner>
@@ -218,6 +511,77 @@ where
         Ok(())
     }
 }
+
+/// Run-length encoding, for grids too big to be handy as plain text.  Each row is a
+/// list of runs like `12.` (twelve `.` cells) or `#` (a single `#`), and rows are
+/// separated by `/`.  Cells must display as one character that isn't a digit or `/`.
+impl<Inner> Grid<Inner>
+where
+    Inner: std::fmt::Display + PartialEq,
+{
+    pub fn to_rle(&self) -> String {
+        let mut out = String::new();
+        for (y, row) in self.cells.iter().enumerate() {
+            if y > 0 {
+                out.push('/');
+            }
+            for run in row.chunk_by(|a, b| a == b) {
+                if run.len() > 1 {
+                    out.push_str(&run.len().to_string());
+                }
+                out.push_str(&run[0].to_string());
+            }
+        }
+        out
+    }
+}
+impl<Inner> Grid<Inner>
+where
+    Inner: FromStr + Clone,
+    Inner::Err: Into<anyhow::Error>,
+{
+    /// Reads a grid written by [`Grid::to_rle`].  Errors give the 1-based row and the
+    /// column within the encoded row.
+    pub fn from_rle(s: &str) -> Result<Self, AocError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Grid { cells: Vec::new() });
+        }
+        let rows = s
+            .split('/')
+            .enumerate()
+            .map(|(y, row)| {
+                let at =
+                    |x: usize, e: anyhow::Error| AocError::new(e).at_line(y + 1).at_column(x + 1);
+                let mut cells = Vec::new();
+                let mut count = None::<usize>;
+                for (x, c) in row.chars().enumerate() {
+                    if let Some(digit) = c.to_digit(10) {
+                        count = Some(
+                            count
+                                .unwrap_or(0)
+                                .checked_mul(10)
+                                .and_then(|n| n.checked_add(digit as usize))
+                                .ok_or_else(|| at(x, anyhow::anyhow!("Run length overflows")))?,
+                        );
+                        continue;
+                    }
+                    let cell = Inner::from_str(&c.to_string()).map_err(|e| at(x, e.into()))?;
+                    cells.extend(std::iter::repeat_n(cell, count.take().unwrap_or(1)));
+                }
+                if count.is_some() {
+                    return Err(at(
+                        row.chars().count(),
+                        anyhow::anyhow!("Run length with no cell after it"),
+                    ));
+                }
+                Ok(cells)
+            })
+            .collect::<Result<Vec<_>, AocError>>()?;
+        Grid::from_rows(rows).map_err(AocError::new)
+    }
+}
+
 impl<Inner> Grid<Inner> {
     /// Returns an iterator over all cells in the grid.
     pub fn cells<'a>(&'a self) -> impl Iterator<Item = CellInGrid<'a, Inner>> {
@@ -229,6 +593,57 @@ impl<Inner> Grid<Inner> {
             })
         })
     }
+    /// Finds the first cell, in row-major order, whose value matches `predicate`.
+    pub fn find(&self, predicate: impl FnMut(&Inner) -> bool) -> Option<CellInGrid<'_, Inner>> {
+        self.find_from(&XY::new(0, 0), predicate)
+    }
+    /// Finds the first cell at or after `start` in row-major order whose value matches
+    /// `predicate`.  Each row is searched as a slice, so this is the fast way to find
+    /// the next free cell.
+    pub fn find_from(
+        &self,
+        start: &XY,
+        mut predicate: impl FnMut(&Inner) -> bool,
+    ) -> Option<CellInGrid<'_, Inner>> {
+        self.cells
+            .iter()
+            .enumerate()
+            .skip(start.y)
+            .find_map(|(y, row)| {
+                let first_x = if y == start.y { start.x } else { 0 };
+                let x = row.get(first_x..)?.iter().position(&mut predicate)? + first_x;
+                self.get(XY::new(x, y))
+            })
+    }
+    /// Returns the positions of every cell equal to `value`, in row-major order.
+    pub fn positions_of<'a>(&'a self, value: &'a Inner) -> impl Iterator<Item = XY> + 'a
+    where
+        Inner: PartialEq,
+    {
+        self.cells.iter().enumerate().flat_map(move |(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |(_, cell)| *cell == value)
+                .map(move |(x, _)| XY::new(x, y))
+        })
+    }
+    /// Counts the cells that match `predicate`.  The predicate gets the whole cell, so
+    /// it can look at neighbors too.
+    pub fn count(&self, mut predicate: impl FnMut(&CellInGrid<'_, Inner>) -> bool) -> usize {
+        self.cells().filter(|cell| predicate(cell)).count()
+    }
+    /// Adds up `f` over every cell.
+    pub fn sum_by<S: std::iter::Sum>(&self, mut f: impl FnMut(&CellInGrid<'_, Inner>) -> S) -> S {
+        self.cells().map(|cell| f(&cell)).sum()
+    }
+    /// Finds the cell with the largest key.  Ties go to the last in row-major order,
+    /// like [`Iterator::max_by_key`].
+    pub fn max_by_key<K: Ord>(
+        &self,
+        mut f: impl FnMut(&CellInGrid<'_, Inner>) -> K,
+    ) -> Option<CellInGrid<'_, Inner>> {
+        self.cells().max_by_key(|cell| f(cell))
+    }
     /// Gets a cell at the specified position.
     pub fn get<'a>(&'a self, xy: XY) -> Option<CellInGrid<'a, Inner>> {
         Some(CellInGrid {
@@ -242,6 +657,19 @@ impl<Inner> Grid<Inner> {
     pub fn get_mut(&mut self, xy: &XY) -> Option<&mut Inner> {
         self.cells.get_mut(xy.y)?.get_mut(xy.x)
     }
+    /// Returns the positions and values of the neighbors of a position that are in the grid.
+    pub fn neighbors<'a>(
+        &'a self,
+        xy: &XY,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (XY, &'a Inner)> + use<'a, Inner> {
+        let (x, y) = (xy.x, xy.y);
+        connectivity.offsets().iter().filter_map(move |(dx, dy)| {
+            let xy = XY::new(x.checked_add_signed(*dx)?, y.checked_add_signed(*dy)?);
+            let value = self.cells.get(xy.y)?.get(xy.x)?;
+            Some((xy, value))
+        })
+    }
 }
 
 /// A cell within a grid, providing access to the cell value and its position.
@@ -305,17 +733,27 @@ impl<'a, Inner> CellInGrid<'a, Inner> {
     }
 
     /// Returns an iterator over cells adjacent in cardinal directions.
-    pub fn cardinal_direction_adjacent_cells(&self) -> impl Iterator<Item = CellInGrid<'_, Inner>> {
-        self.xy
-            .adjacent_cardinal_positions()
-            .filter_map(|xy| self.grid.get(xy))
+    pub fn cardinal_direction_adjacent_cells(
+        &self,
+    ) -> impl Iterator<Item = CellInGrid<'a, Inner>> + use<'a, Inner> {
+        self.neighbor_cells(Connectivity::Cardinal)
     }
 
     /// Returns an iterator over all adjacent cells, including diagonals.
-    pub fn adjacent_cells(&self) -> impl Iterator<Item = CellInGrid<'_, Inner>> {
+    pub fn adjacent_cells(&self) -> impl Iterator<Item = CellInGrid<'a, Inner>> + use<'a, Inner> {
+        self.neighbor_cells(Connectivity::All)
+    }
+
+    /// Returns an iterator over the adjacent cells for the given connectivity.  The
+    /// iterator borrows the grid rather than this handle, so it can outlive the handle.
+    pub fn neighbor_cells(
+        &self,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = CellInGrid<'a, Inner>> + use<'a, Inner> {
+        let grid = self.grid;
         self.xy
-            .adjacent_positions()
-            .filter_map(move |xy| self.grid.get(xy))
+            .neighbor_positions(connectivity)
+            .filter_map(move |xy| grid.get(xy))
     }
 
     /// Gets the value stored in this cell.
@@ -329,10 +767,112 @@ impl<Inner> AsRef<Inner> for CellInGrid<'_, Inner> {
     }
 }
 
+/// A position where two grids differ.  A side is None where that grid doesn't reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellDiff<'a, Inner> {
+    pub xy: XY,
+    pub left: Option<&'a Inner>,
+    pub right: Option<&'a Inner>,
+}
+
+/// The differences between two grids, which may be different sizes.
+pub struct GridDiff<'a, Inner> {
+    left: &'a Grid<Inner>,
+    right: &'a Grid<Inner>,
+    cells: Vec<CellDiff<'a, Inner>>,
+}
+
+/// Compares two grids cell by cell.
+pub fn diff<'a, Inner: PartialEq>(
+    left: &'a Grid<Inner>,
+    right: &'a Grid<Inner>,
+) -> GridDiff<'a, Inner> {
+    let value = |grid: &'a Grid<Inner>, xy: &XY| grid.cells.get(xy.y)?.get(xy.x);
+    let height = left.height().max(right.height());
+    let width = left.width().max(right.width());
+    let cells = (0..height)
+        .flat_map(|y| (0..width).map(move |x| XY::new(x, y)))
+        .filter_map(|xy| {
+            let (l, r) = (value(left, &xy), value(right, &xy));
+            (l != r).then_some(CellDiff {
+                xy,
+                left: l,
+                right: r,
+            })
+        })
+        .collect();
+    GridDiff { left, right, cells }
+}
+
+impl<'a, Inner> GridDiff<'a, Inner> {
+    /// Are the grids the same?
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+    /// Gets the differing positions, in row order.
+    pub fn cells(&self) -> &[CellDiff<'a, Inner>] {
+        &self.cells
+    }
+}
+
+impl<Inner: std::fmt::Display> GridDiff<'_, Inner> {
+    /// Renders the grids next to each other, with a `|` between rows that differ.
+    pub fn side_by_side(&self) -> String {
+        let render = |grid: &Grid<Inner>, y: usize| {
+            let row = grid
+                .cells
+                .get(y)
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect::<String>());
+            format!("{:<width$}", row.unwrap_or_default(), width = grid.width())
+        };
+        (0..self.left.height().max(self.right.height()))
+            .map(|y| {
+                let differs = self.cells.iter().any(|cell| cell.xy.y == y);
+                let separator = if differs { " | " } else { "   " };
+                format!(
+                    "{}{}{}\n",
+                    render(self.left, y),
+                    separator,
+                    render(self.right, y)
+                )
+            })
+            .collect()
+    }
+
+    /// Renders the left grid with every differing cell replaced by `marker`.
+    pub fn overlay(&self, marker: char) -> String {
+        let height = self.left.height().max(self.right.height());
+        let width = self.left.width().max(self.right.width());
+        let mut differing = self.cells.iter().map(|cell| &cell.xy).peekable();
+        let mut out = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                if differing.next_if(|xy| **xy == XY::new(x, y)).is_some() {
+                    out.push(marker);
+                } else if let Some(cell) = self.left.cells.get(y).and_then(|row| row.get(x)) {
+                    out.push_str(&cell.to_string());
+                } else {
+                    out.push(' ');
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<Inner: std::fmt::Display> std::fmt::Display for GridDiff<'_, Inner> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} differing cells", self.cells.len())?;
+        write!(f, "{}", self.side_by_side())
+    }
+}
+
 /// Parses a string into a grid of cells by splitting on newlines and parsing each character.
-pub fn parse_data_into_grid<Inner>(data: &str) -> Result<Grid<Inner>, <Inner as FromStr>::Err>
+pub fn parse_data_into_grid<Inner>(data: &str) -> Result<Grid<Inner>, AocError>
 where
     Inner: FromStr,
+    Inner::Err: Into<anyhow::Error>,
 {
     data.parse()
 }
@@ -341,6 +881,78 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_find_and_positions_of() {
+        let grid = Grid::from_lines(["ab.", "..b", "b.a"]).unwrap();
+        assert_eq!(
+            grid.find(|c| *c == 'b').map(|c| c.xy()),
+            Some(XY::new(1, 0))
+        );
+        assert!(grid.find(|c| *c == 'z').is_none());
+        let from = |xy, c| grid.find_from(&xy, |v| *v == c).map(|c| c.xy());
+        assert_eq!(from(XY::new(2, 0), 'b'), Some(XY::new(2, 1)));
+        assert_eq!(from(XY::new(1, 1), '.'), Some(XY::new(1, 1)));
+        assert_eq!(from(XY::new(2, 1), 'a'), Some(XY::new(2, 2)));
+        assert_eq!(from(XY::new(0, 3), 'a'), None);
+        assert_eq!(
+            grid.positions_of(&'b').collect::<Vec<_>>(),
+            vec![XY::new(1, 0), XY::new(2, 1), XY::new(0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let grid = Grid::from_rows(vec![vec![1, 5, 2], vec![5, 0, 3]]).unwrap();
+        assert_eq!(grid.count(|cell| *cell.value() > 1), 4);
+        assert_eq!(grid.sum_by(|cell| *cell.value()), 16);
+        assert_eq!(grid.sum_by(|cell| cell.xy().x), 6);
+        let max = grid.max_by_key(|cell| *cell.value()).unwrap();
+        assert_eq!(max.xy(), XY::new(0, 1));
+        // Cells with no neighbor above.
+        assert_eq!(grid.count(|cell| cell.xy().up().is_none()), 3);
+        assert!(
+            Grid::<u8>::from_rows(Vec::new())
+                .unwrap()
+                .max_by_key(|_| 0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_from_str_lenient() {
+        for line in ["3,4", " 3, 4 ", "3;4", "3\t4", "3 ;\t4"] {
+            assert_eq!(
+                XY::from_str_lenient(line).unwrap(),
+                XY::new(3, 4),
+                "{:?}",
+                line
+            );
+        }
+        assert!(XY::from_str_lenient("3,,4").is_err());
+        assert!(XY::from_str_lenient("3 4 5").is_err());
+        assert!(XY::from_str_lenient("").is_err());
+    }
+
+    #[test]
+    fn test_line_to() {
+        let line = |from: (usize, usize), to: (usize, usize)| {
+            XY::new(from.0, from.1)
+                .line_to(&XY::new(to.0, to.1))
+                .map(|cells| cells.map(|xy| (xy.x, xy.y)).collect::<Vec<_>>())
+        };
+        assert_eq!(line((3, 1), (1, 1)).unwrap(), vec![(3, 1), (2, 1), (1, 1)]);
+        assert_eq!(line((0, 0), (0, 2)).unwrap(), vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(line((0, 0), (2, 2)).unwrap(), vec![(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(line((2, 0), (0, 2)).unwrap(), vec![(2, 0), (1, 1), (0, 2)]);
+        assert_eq!(line((1, 3), (3, 1)).unwrap(), vec![(1, 3), (2, 2), (3, 1)]);
+        assert_eq!(line((4, 4), (4, 4)).unwrap(), vec![(4, 4)]);
+        let error = line((0, 0), (2, 1)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line from (0, 0) to (2, 1) is not horizontal, vertical or diagonal"
+        );
+    }
+
     #[test]
     fn test_parse_data() {
         let data = "..@..\n@.@.@\n..@..";
@@ -357,6 +969,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rle_round_trip() {
+        let grid = parse_data_into_grid::<char>("....##\n#.#.#.\n......").unwrap();
+        assert_eq!(grid.to_rle(), "4.2#/#.#.#./6.");
+        assert_eq!(Grid::<char>::from_rle(&grid.to_rle()).unwrap(), grid);
+
+        let wide = Grid::new_sized(1234, 2, '.');
+        assert_eq!(wide.to_rle(), "1234./1234.");
+        assert_eq!(Grid::<char>::from_rle(&wide.to_rle()).unwrap(), wide);
+        assert_eq!(Grid::<char>::from_rle("").unwrap().height(), 0);
+    }
+
+    #[test]
+    fn test_rle_errors() {
+        let error = Grid::<u8>::from_rle("3.").unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(1), Some(2)));
+        assert!(Grid::<char>::from_rle("3./2.").is_err());
+        assert!(Grid::<char>::from_rle("3./3").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let error = parse_data_into_grid::<u8>("12\n3x").unwrap_err();
+        assert_eq!((error.line(), error.column()), (Some(2), Some(2)));
+        assert!(error.to_string().starts_with("line 2, column 2: "));
+    }
+
     #[test]
     fn test_adjacent_positions() {
         let xy = XY::new(0, 0);
@@ -367,6 +1006,48 @@ mod tests {
         assert!(adjacent_positions.contains(&XY::new(1, 0)));
     }
 
+    #[test]
+    fn test_transform_algebra() {
+        let grid = Grid::<char>::from_lines(["ab.", "c.d"]).unwrap();
+        for first in Transform::ALL {
+            let once = grid.transform(first);
+            assert_eq!(once.transform(first.inverse()), grid, "{:?}", first);
+            for then in Transform::ALL {
+                assert_eq!(
+                    once.transform(then),
+                    grid.transform(first.compose(then)),
+                    "{:?} then {:?}",
+                    first,
+                    then
+                );
+            }
+            // Every cell lands where apply_to_xy says.
+            for cell in grid.cells() {
+                let moved = first.apply_to_xy(&cell.xy(), grid.width(), grid.height());
+                assert_eq!(once.get(moved).map(|c| *c.value()), Some(*cell.value()));
+            }
+            assert_eq!(first.swaps_axes(), once.width() != grid.width());
+        }
+        assert_eq!(Transform::R90.compose(Transform::R90), Transform::R180);
+        assert_eq!(Transform::R90.inverse(), Transform::R270);
+    }
+
+    #[test]
+    fn test_transform_in_place_matches_copies() {
+        let square = Grid::<char>::from_lines(["ab.", "c..", "d.e"]).unwrap();
+        let wide = Grid::<char>::from_lines(["abc", "d.e"]).unwrap();
+        for grid in [square, wide] {
+            for transform in Transform::ALL {
+                let mut in_place = grid.clone();
+                in_place.transform_in_place(transform);
+                assert_eq!(in_place, grid.transform(transform), "{:?}", transform);
+            }
+        }
+        let grid = Grid::<char>::from_lines(["ab", "cd"]).unwrap();
+        let anti = grid.transform(Transform::AntiTranspose);
+        assert_eq!(anti, Grid::<char>::from_lines(["db", "ca"]).unwrap());
+    }
+
     #[test]
     fn test_rotate_90() {
         let grid = Grid {
@@ -476,4 +1157,102 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_diff() {
+        let a = parse_data_into_grid::<char>("..#\n.#.\n#..").unwrap();
+        let b = parse_data_into_grid::<char>("..#\n...\n#..\n..#").unwrap();
+        let d = diff(&a, &b);
+        assert_eq!(
+            d.cells(),
+            &[
+                CellDiff {
+                    xy: XY::new(1, 1),
+                    left: Some(&'#'),
+                    right: Some(&'.')
+                },
+                CellDiff {
+                    xy: XY::new(0, 3),
+                    left: None,
+                    right: Some(&'.')
+                },
+                CellDiff {
+                    xy: XY::new(1, 3),
+                    left: None,
+                    right: Some(&'.')
+                },
+                CellDiff {
+                    xy: XY::new(2, 3),
+                    left: None,
+                    right: Some(&'#')
+                },
+            ]
+        );
+        assert_eq!(d.overlay('*'), "..#\n.*.\n#..\n***\n");
+        assert_eq!(
+            d.side_by_side(),
+            "..#   ..#\n.#. | ...\n#..   #..\n    | ..#\n"
+        );
+        assert!(diff(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        let transposed = grid.transpose();
+        assert_eq!(
+            transposed.rows().collect::<Vec<_>>(),
+            vec![&[1, 4][..], &[2, 5], &[3, 6]]
+        );
+        assert_eq!(transposed.transpose(), grid);
+        assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+    }
+
+    #[test]
+    fn test_positions_into_reuse_the_buffer() {
+        let mut buf = vec![XY::new(9, 9); 20];
+        XY::new(0, 0).adjacent_positions_into(&mut buf);
+        assert_eq!(buf, vec![XY::new(1, 0), XY::new(0, 1), XY::new(1, 1)]);
+        let capacity = buf.capacity();
+        XY::new(1, 1).neighbor_positions_into(Connectivity::Cardinal, &mut buf);
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = parse_data_into_grid::<char>("ab\ncd").unwrap();
+        let neighbors = grid
+            .neighbors(&XY::new(0, 0), Connectivity::All)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            neighbors,
+            vec![
+                (XY::new(1, 0), &'b'),
+                (XY::new(0, 1), &'c'),
+                (XY::new(1, 1), &'d')
+            ]
+        );
+        assert_eq!(
+            grid.neighbors(&XY::new(1, 1), Connectivity::Cardinal)
+                .map(|(_, value)| *value)
+                .collect::<String>(),
+            "bc"
+        );
+    }
+
+    #[test]
+    fn test_neighbor_cells_outlive_handle() {
+        let grid = parse_data_into_grid::<char>("@.@\n.@.\n@@.").unwrap();
+        // The handles are temporaries; only the iterators over the grid are kept.
+        let paper_neighbors = grid
+            .cells()
+            .map(|cell| cell.adjacent_cells().filter(|c| *c.value() == '@'))
+            .collect::<Vec<_>>();
+        let counts = paper_neighbors
+            .into_iter()
+            .map(Iterator::count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![1, 3, 1, 4, 4, 3, 2, 2, 2]);
+    }
 }
@@ -0,0 +1,322 @@
+//! A local log of answers and how long each run took, so it is easy to see when each
+//! part was first answered and how its runtime changed over later refactors.
+//!
+//! The log is TOML, one `[[run]]` table per recorded part:
+//!
+//! ```toml
+//! [[run]]
+//! day = 7
+//! part = 1
+//! answer = "1600"
+//! timestamp = 1765094400
+//! elapsed_ms = 12.5
+//! peak_kib = 2048
+//! ```
+//!
+//! `peak_kib` is left out when the peak memory wasn't measured.
+//!
+//! Only this flat form is read back, so the file can be appended to without parsing it.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{error::ErrorContext, mem::format_kib};
+
+/// One part's answer from one run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub day: u32,
+    pub part: u32,
+    pub answer: String,
+    /// When the run finished, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub elapsed: Duration,
+    /// The day's peak resident set size in KiB by the time it printed this part's
+    /// answer, if it could be measured.
+    pub peak_kib: Option<u64>,
+}
+
+impl Run {
+    /// A run that finished now.
+    pub fn now(day: u32, part: u32, answer: impl Into<String>, elapsed: Duration) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        Run {
+            day,
+            part,
+            answer: answer.into(),
+            timestamp,
+            elapsed,
+            peak_kib: None,
+        }
+    }
+
+    /// Sets the peak memory measured for the part.
+    pub fn with_peak_kib(self, peak_kib: Option<u64>) -> Self {
+        Self { peak_kib, ..self }
+    }
+
+    fn to_toml(&self) -> String {
+        let peak = self
+            .peak_kib
+            .map(|kib| format!("peak_kib = {}\n", kib))
+            .unwrap_or_default();
+        format!(
+            "[[run]]\nday = {}\npart = {}\nanswer = {:?}\ntimestamp = {}\nelapsed_ms = {}\n{}\n",
+            self.day,
+            self.part,
+            self.answer,
+            self.timestamp,
+            self.elapsed.as_secs_f64() * 1000.0,
+            peak
+        )
+    }
+}
+
+/// Every recorded run, oldest first.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct History {
+    pub runs: Vec<Run>,
+}
+
+impl History {
+    /// Reads a log.  A log that doesn't exist yet is empty.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text).with_file(path)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Adds runs to the end of a log, creating it if needed.
+    pub fn append(path: impl AsRef<Path>, runs: &[Run]) -> Result<()> {
+        let path = path.as_ref();
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        for run in runs {
+            file.write_all(run.to_toml().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let mut runs = Vec::new();
+        // The fields of the table being read, with the line it started on.
+        let mut table: Option<(usize, BTreeMap<&str, &str>)> = None;
+        for (number, line) in (1..).zip(text.lines().chain(["[[run]]"])) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "[[run]]" {
+                if let Some((start, fields)) = table.take() {
+                    runs.push(run_from_fields(&fields).with_line(start)?);
+                }
+                table = Some((number, BTreeMap::new()));
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected key = value: {}", line))
+                .with_line(number)?;
+            table
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Field before the first [[run]]"))
+                .with_line(number)?
+                .1
+                .insert(key.trim(), value.trim());
+        }
+        Ok(History { runs })
+    }
+
+    /// Gets the answer most recently logged for a day's part.
+    pub fn latest_answer(&self, day: u32, part: u32) -> Option<&str> {
+        self.runs
+            .iter()
+            .rev()
+            .find(|run| run.day == day && run.part == part)
+            .map(|run| run.answer.as_str())
+    }
+
+    /// Draws a table with a row per day and part: when it was first answered, the
+    /// latest answer, how many runs there were, the first, best and latest times, and
+    /// the latest run's peak memory.
+    pub fn table(&self) -> String {
+        let mut parts = BTreeMap::<(u32, u32), Vec<&Run>>::new();
+        for run in &self.runs {
+            parts.entry((run.day, run.part)).or_default().push(run);
+        }
+        let mut out = format!(
+            "{:>3} {:>4}  {:<20} {:>20} {:>5} {:>11} {:>11} {:>11} {:>11}\n",
+            "day",
+            "part",
+            "first answered",
+            "latest answer",
+            "runs",
+            "first ms",
+            "best ms",
+            "latest ms",
+            "peak mem"
+        );
+        for ((day, part), runs) in parts {
+            let (Some(first), Some(latest)) = (runs.first(), runs.last()) else {
+                continue;
+            };
+            let best = runs.iter().map(|run| run.elapsed).min().unwrap_or_default();
+            let ms = |elapsed: Duration| format!("{:.3}", elapsed.as_secs_f64() * 1000.0);
+            let peak = latest.peak_kib.map_or("-".to_string(), format_kib);
+            out.push_str(&format!(
+                "{:>3} {:>4}  {:<20} {:>20} {:>5} {:>11} {:>11} {:>11} {:>11}\n",
+                day,
+                part,
+                format_timestamp(first.timestamp),
+                latest.answer,
+                runs.len(),
+                ms(first.elapsed),
+                ms(best),
+                ms(latest.elapsed),
+                peak
+            ));
+        }
+        out
+    }
+}
+
+fn run_from_fields(fields: &BTreeMap<&str, &str>) -> Result<Run> {
+    let field = |key: &str| {
+        fields
+            .get(key)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Run is missing {}", key))
+    };
+    let answer = field("answer")?;
+    let answer = answer
+        .strip_prefix('"')
+        .and_then(|answer| answer.strip_suffix('"'))
+        .ok_or_else(|| anyhow::anyhow!("Answer must be quoted: {}", answer))?
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\");
+    let elapsed_ms = field("elapsed_ms")?
+        .parse::<f64>()
+        .context("Invalid elapsed_ms")?;
+    Ok(Run {
+        day: field("day")?.parse().context("Invalid day")?,
+        part: field("part")?.parse().context("Invalid part")?,
+        answer,
+        timestamp: field("timestamp")?.parse().context("Invalid timestamp")?,
+        elapsed: Duration::try_from_secs_f64(elapsed_ms / 1000.0).context("Invalid elapsed_ms")?,
+        peak_kib: fields
+            .get("peak_kib")
+            .map(|kib| kib.parse())
+            .transpose()
+            .context("Invalid peak_kib")?,
+    })
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // Howard Hinnant's days-to-civil, shifted so years start on 1 March.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let runs = vec![
+            Run {
+                day: 7,
+                part: 1,
+                answer: "1600".to_string(),
+                timestamp: 1_765_094_400,
+                elapsed: Duration::from_micros(12_500),
+                peak_kib: Some(2048),
+            },
+            Run {
+                day: 4,
+                part: 2,
+                answer: "a \"quoted\" answer".to_string(),
+                timestamp: 1_765_094_460,
+                elapsed: Duration::from_millis(3),
+                peak_kib: None,
+            },
+        ];
+        let text = runs.iter().map(Run::to_toml).collect::<String>();
+        assert_eq!(History::parse(&text).unwrap().runs, runs);
+        assert_eq!(History::parse("").unwrap(), History::default());
+        let error = History::parse("[[run]]\nday = 1\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: Run is missing answer");
+    }
+
+    #[test]
+    fn test_table() {
+        let run = |part, answer: &str, timestamp, ms| Run {
+            day: 1,
+            part,
+            answer: answer.to_string(),
+            timestamp,
+            elapsed: Duration::from_millis(ms),
+            peak_kib: Some(ms * 1024),
+        };
+        let history = History {
+            runs: vec![
+                run(1, "5", 1_764_547_200, 20),
+                run(1, "6", 1_764_550_800, 10),
+                run(1, "6", 1_764_554_400, 15),
+            ],
+        };
+        let table = history.table();
+        let row = table.lines().nth(1).unwrap();
+        assert!(row.starts_with("  1    1  2025-12-01 00:00"), "{}", row);
+        assert!(row.contains("      6     3"), "{}", row);
+        assert!(
+            row.ends_with("20.000      10.000      15.000    15.0 MiB"),
+            "{}",
+            row
+        );
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_765_094_400 + 3_723), "2025-12-07 09:02");
+    }
+}
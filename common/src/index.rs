@@ -0,0 +1,83 @@
+//! Typed indices, so an index into one Vec can't be used to look up another by mistake.
+
+/// Defines a `usize` backed index type that can index a `Vec<T>` or `[T]`.
+///
+/// The index has no arithmetic; make one with `new`, get the raw value back with
+/// `index`, or walk a collection's indices with `range`.
+///
+/// ```
+/// common::define_index!(
+///     /// Which widget.
+///     pub WidgetId
+/// );
+/// let widgets = vec!["a", "b"];
+/// assert_eq!(widgets[WidgetId::new(1)], "b");
+/// ```
+#[macro_export]
+macro_rules! define_index {
+    ($(#[$meta:meta])* $vis:vis $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name(usize);
+
+        #[allow(dead_code)]
+        impl $name {
+            pub const fn new(index: usize) -> Self {
+                Self(index)
+            }
+            /// Gets the raw index.
+            pub const fn index(self) -> usize {
+                self.0
+            }
+            /// Iterates over the indices of a collection with `len` items.
+            pub fn range(len: usize) -> impl DoubleEndedIterator<Item = Self> + Clone {
+                (0..len).map(Self)
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl<T> ::std::ops::Index<$name> for [T] {
+            type Output = T;
+            fn index(&self, index: $name) -> &T {
+                &self[index.0]
+            }
+        }
+        impl<T> ::std::ops::IndexMut<$name> for [T] {
+            fn index_mut(&mut self, index: $name) -> &mut T {
+                &mut self[index.0]
+            }
+        }
+        impl<T> ::std::ops::Index<$name> for Vec<T> {
+            type Output = T;
+            fn index(&self, index: $name) -> &T {
+                &self[index.0]
+            }
+        }
+        impl<T> ::std::ops::IndexMut<$name> for Vec<T> {
+            fn index_mut(&mut self, index: $name) -> &mut T {
+                &mut self[index.0]
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    crate::define_index!(TestId);
+
+    #[test]
+    fn test_typed_indexing() {
+        let mut values = vec![10, 20, 30];
+        values[TestId::new(1)] += 5;
+        assert_eq!(values[TestId::new(1)], 25);
+        assert_eq!(values.as_slice()[TestId::new(2)], 30);
+        let ids = TestId::range(values.len()).collect::<Vec<_>>();
+        assert_eq!(ids.last().map(|id| id.index()), Some(2));
+        assert_eq!(TestId::new(7).to_string(), "7");
+    }
+}
@@ -0,0 +1,250 @@
+//! Iterator adapters that keep coming up in puzzle parsing and solving.
+
+use anyhow::{Context, Result};
+
+/// Extra adapters for every iterator.
+pub trait IterExt: Iterator + Sized {
+    /// Groups lines into the blocks between blank lines.  Lines that are only
+    /// whitespace count as blank, and runs of blank lines never give an empty block.
+    fn chunk_by_blank_lines<'a>(self) -> BlankLineChunks<Self>
+    where
+        Self: Iterator<Item = &'a str>,
+    {
+        BlankLineChunks { lines: self }
+    }
+
+    /// Gives every run of `N` consecutive items, like `[a, b]`, `[b, c]`, ... for `N` of
+    /// 2.  There are none if the iterator has fewer than `N` items.
+    fn windows_tuple<const N: usize>(self) -> Windows<Self, N>
+    where
+        Self::Item: Clone,
+    {
+        Windows {
+            iter: self,
+            window: None,
+        }
+    }
+
+    /// Counts the items that match `predicate`.
+    fn count_where(self, mut predicate: impl FnMut(&Self::Item) -> bool) -> usize {
+        self.fold(0, |count, item| count + usize::from(predicate(&item)))
+    }
+
+    /// Gets the smallest and largest items in one pass, or None if there are none.
+    /// Ties go to the first smallest and the last largest, like [`Iterator::min`] and
+    /// [`Iterator::max`].
+    fn min_max(mut self) -> Option<(Self::Item, Self::Item)>
+    where
+        Self::Item: Ord + Clone,
+    {
+        let first = self.next()?;
+        Some(self.fold((first.clone(), first), |(min, max), item| {
+            if item < min {
+                (item, max)
+            } else if item >= max {
+                (min, item)
+            } else {
+                (min, max)
+            }
+        }))
+    }
+
+    /// Gets the only item, failing if there are none or more than one.
+    fn exactly_one(mut self) -> Result<Self::Item> {
+        let first = self
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Expected exactly one item, found none"))?;
+        if self.next().is_some() {
+            anyhow::bail!("Expected exactly one item, found more than one");
+        }
+        Ok(first)
+    }
+
+    /// Collects the items of an iterator of results, stopping at the first error.  The
+    /// error says which item, counting from 0, failed.
+    fn try_collect_vec<T, E>(self) -> Result<Vec<T>>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        self.enumerate()
+            .map(|(index, item)| {
+                item.map_err(Into::into)
+                    .with_context(|| format!("Item {}", index))
+            })
+            .collect()
+    }
+
+    /// Calls `f` with each item and its index, stopping at the first error, which says
+    /// which item failed.
+    fn try_for_each_indexed<E: Into<anyhow::Error>>(
+        self,
+        mut f: impl FnMut(usize, Self::Item) -> Result<(), E>,
+    ) -> Result<()> {
+        for (index, item) in self.enumerate() {
+            f(index, item)
+                .map_err(Into::into)
+                .with_context(|| format!("Item {}", index))?;
+        }
+        Ok(())
+    }
+
+    /// Folds the values of an iterator of results, stopping at the first error, which
+    /// says which item failed.
+    fn fold_ok<T, E, B>(self, init: B, mut f: impl FnMut(B, T) -> B) -> Result<B>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        let mut acc = init;
+        for (index, item) in self.enumerate() {
+            let item = item
+                .map_err(Into::into)
+                .with_context(|| format!("Item {}", index))?;
+            acc = f(acc, item);
+        }
+        Ok(acc)
+    }
+}
+
+impl<I: Iterator> IterExt for I {}
+
+/// The iterator from [`IterExt::chunk_by_blank_lines`].
+pub struct BlankLineChunks<I> {
+    lines: I,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for BlankLineChunks<I> {
+    type Item = Vec<&'a str>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.lines.by_ref().find(|line| !line.trim().is_empty())?;
+        let mut chunk = vec![first];
+        chunk.extend(
+            self.lines
+                .by_ref()
+                .take_while(|line| !line.trim().is_empty()),
+        );
+        Some(chunk)
+    }
+}
+
+/// The iterator from [`IterExt::windows_tuple`].
+pub struct Windows<I: Iterator, const N: usize> {
+    iter: I,
+    window: Option<[I::Item; N]>,
+}
+
+impl<I: Iterator, const N: usize> Iterator for Windows<I, N>
+where
+    I::Item: Clone,
+{
+    type Item = [I::Item; N];
+    fn next(&mut self) -> Option<Self::Item> {
+        if N == 0 {
+            return None;
+        }
+        match &mut self.window {
+            Some(window) => {
+                window.rotate_left(1);
+                window[N - 1] = self.iter.next()?;
+            }
+            None => {
+                let first = self.iter.by_ref().take(N).collect::<Vec<_>>();
+                self.window = Some(first.try_into().ok()?);
+            }
+        }
+        self.window.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_by_blank_lines() {
+        let text = "\na\nb\n\n  \nc\n\n";
+        let chunks = text.lines().chunk_by_blank_lines().collect::<Vec<_>>();
+        assert_eq!(chunks, vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!("".lines().chunk_by_blank_lines().count(), 0);
+    }
+
+    #[test]
+    fn test_windows_tuple() {
+        let windows = (1..=4).windows_tuple::<2>().collect::<Vec<_>>();
+        assert_eq!(windows, vec![[1, 2], [2, 3], [3, 4]]);
+        assert_eq!((1..=4).windows_tuple::<4>().count(), 1);
+        assert_eq!((1..=3).windows_tuple::<4>().count(), 0);
+        assert_eq!((1..=3).windows_tuple::<0>().count(), 0);
+    }
+
+    #[test]
+    fn test_count_where_and_min_max() {
+        assert_eq!((1..=10).count_where(|n| n % 3 == 0), 3);
+        assert_eq!([3, 1, 4, 1, 5].into_iter().min_max(), Some((1, 5)));
+        assert_eq!([7].into_iter().min_max(), Some((7, 7)));
+        assert_eq!(std::iter::empty::<u8>().min_max(), None);
+    }
+
+    #[test]
+    fn test_try_collect_vec() {
+        let numbers = ["1", "2", "3"].iter().map(|n| n.parse::<u32>());
+        assert_eq!(numbers.try_collect_vec().unwrap(), vec![1, 2, 3]);
+        let error = ["1", "x", "y"]
+            .iter()
+            .map(|n| n.parse::<u32>())
+            .try_collect_vec()
+            .unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Item 1: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_try_for_each_indexed_and_fold_ok() {
+        let mut seen = Vec::new();
+        [10, 20, 30]
+            .into_iter()
+            .try_for_each_indexed(|index, n| {
+                seen.push((index, n));
+                anyhow::Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, vec![(0, 10), (1, 20), (2, 30)]);
+        let error = [1, 2, 3]
+            .into_iter()
+            .try_for_each_indexed(|_, n| {
+                if n == 3 {
+                    anyhow::bail!("{} is too big", n)
+                }
+                Ok(())
+            })
+            .unwrap_err();
+        assert_eq!(format!("{:#}", error), "Item 2: 3 is too big");
+
+        let sum = ["1", "2", "3"]
+            .iter()
+            .map(|n| n.parse::<u32>())
+            .fold_ok(0, |sum, n| sum + n);
+        assert_eq!(sum.unwrap(), 6);
+        let error = ["4", "-"]
+            .iter()
+            .map(|n| n.parse::<u32>())
+            .fold_ok(0, |sum, n| sum + n)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Item 1");
+    }
+
+    #[test]
+    fn test_exactly_one() {
+        assert_eq!([5].into_iter().exactly_one().unwrap(), 5);
+        let none = std::iter::empty::<u8>().exactly_one().unwrap_err();
+        assert_eq!(none.to_string(), "Expected exactly one item, found none");
+        let many = [1, 2].into_iter().exactly_one().unwrap_err();
+        assert_eq!(
+            many.to_string(),
+            "Expected exactly one item, found more than one"
+        );
+    }
+}
@@ -1,20 +1,73 @@
+pub mod alloc;
+pub mod answer;
+pub mod args;
+pub mod automaton;
+pub mod baseline;
+pub mod bitgrid;
+pub mod bounds;
+pub mod cache;
+pub mod convert;
+pub mod deadline;
+pub mod digits;
+pub mod error;
+pub mod extsort;
+pub mod golden;
 pub mod grid;
+pub mod history;
+pub mod index;
+pub mod iter;
+pub mod mem;
+pub mod ocr;
+pub mod output;
+pub mod par;
+pub mod parse;
+pub mod profile;
+pub mod random;
+pub mod ranges;
+pub mod recurse;
+pub mod sample;
+pub mod scratch;
+pub mod search;
+pub mod shapes;
+pub mod smallvec;
+pub mod stats;
+pub mod strings;
+pub mod sweep;
+pub mod topk;
+pub mod verify;
+pub mod watch;
 
 /// Reads the contents of a file.
 pub fn read_file(path: &str) -> std::io::Result<String> {
-    Ok(std::fs::read_to_string(path)?)
+    std::fs::read_to_string(path)
 }
 
 pub fn sum_results<T: CheckedAdd<T> + Default, E>(
     i: impl Iterator<Item = anyhow::Result<T>>,
 ) -> anyhow::Result<T> {
-    let mut sum = T::default();
-    for v in i {
-        sum = sum
-            .checked_add(v?)
-            .ok_or_else(|| anyhow::anyhow!("add overflowed"))?;
+    try_fold_checked(i, T::default(), T::checked_add)
+}
+
+/// Multiplies the values in an iterator of results, stopping at the first error and
+/// failing if the product overflows.  An empty iterator gives 1.
+pub fn product_results<T: CheckedMul<T> + From<u8>>(
+    i: impl IntoIterator<Item = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    try_fold_checked(i, T::from(1), T::checked_mul)
+}
+
+/// Folds an iterator of results with an operation that returns None on overflow,
+/// stopping at the first error or overflow.
+pub fn try_fold_checked<T, U>(
+    i: impl IntoIterator<Item = anyhow::Result<U>>,
+    init: T,
+    op: impl Fn(T, U) -> Option<T>,
+) -> anyhow::Result<T> {
+    let mut acc = init;
+    for (index, v) in i.into_iter().enumerate() {
+        acc = op(acc, v?).ok_or_else(|| anyhow::anyhow!("Overflow at item {}", index))?;
     }
-    Ok(sum)
+    Ok(acc)
 }
 
 // checked_add functions on u16, i16, u32, i32, etc are not defined as a trait.
@@ -33,12 +86,42 @@ impl CheckedAdd<u64> for u64 {
         self.checked_add(rhs)
     }
 }
+impl CheckedAdd<u128> for u128 {
+    fn checked_add(self, rhs: u128) -> Option<u128> {
+        self.checked_add(rhs)
+    }
+}
 impl CheckedAdd<usize> for usize {
     fn checked_add(self, rhs: usize) -> Option<usize> {
         self.checked_add(rhs)
     }
 }
 
+// Same as CheckedAdd, for checked_mul.
+pub trait CheckedMul<T> {
+    fn checked_mul(self, other: T) -> Option<T>;
+}
+impl CheckedMul<u32> for u32 {
+    fn checked_mul(self, rhs: u32) -> Option<u32> {
+        self.checked_mul(rhs)
+    }
+}
+impl CheckedMul<u64> for u64 {
+    fn checked_mul(self, rhs: u64) -> Option<u64> {
+        self.checked_mul(rhs)
+    }
+}
+impl CheckedMul<u128> for u128 {
+    fn checked_mul(self, rhs: u128) -> Option<u128> {
+        self.checked_mul(rhs)
+    }
+}
+impl CheckedMul<usize> for usize {
+    fn checked_mul(self, rhs: usize) -> Option<usize> {
+        self.checked_mul(rhs)
+    }
+}
+
 pub trait CountResults<T, E> {
     fn count_results(self) -> Result<usize, E>;
 }
@@ -76,3 +159,32 @@ where
         Some(sum)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_results() {
+        let values = [2_u64, 3, 7].map(anyhow::Ok);
+        assert_eq!(product_results(values).unwrap(), 42);
+        assert_eq!(
+            product_results(Vec::<anyhow::Result<u32>>::new()).unwrap(),
+            1
+        );
+        assert!(product_results([u64::MAX, 2].map(anyhow::Ok)).is_err());
+        let with_error = vec![Ok(2_usize), Err(anyhow::anyhow!("bad")), Ok(3)];
+        assert_eq!(product_results(with_error).unwrap_err().to_string(), "bad");
+    }
+
+    #[test]
+    fn test_try_fold_checked() {
+        let values = [1_u32, 2, 3].map(anyhow::Ok);
+        assert_eq!(
+            try_fold_checked(values, 0_u64, |acc, v| acc.checked_add(v as u64)).unwrap(),
+            6
+        );
+        let err = try_fold_checked([200_u8, 100].map(anyhow::Ok), 0_u8, u8::checked_add);
+        assert_eq!(err.unwrap_err().to_string(), "Overflow at item 1");
+    }
+}
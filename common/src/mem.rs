@@ -0,0 +1,54 @@
+//! Memory use of the day binaries, read by the runner while they run.
+
+/// Gets the peak resident set size of a running process in KiB, read from
+/// `/proc/<pid>/status`.  Returns None where that isn't available (non-Linux platforms,
+/// or a process that has already exited).
+pub fn peak_rss_kib(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status_value_kib(&status, "VmHWM:")
+}
+
+/// Parses a line like "VmHWM:     1234 kB" from the status file.
+fn status_value_kib(status: &str, key: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with(key))?;
+    line[key.len()..]
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Formats a size in KiB for display, using MiB once it is large enough.
+pub fn format_kib(kib: u64) -> String {
+    if kib >= 10 * 1024 {
+        format!("{:.1} MiB", kib as f64 / 1024.0)
+    } else {
+        format!("{} KiB", kib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_value_kib() {
+        let status = "Name:\ttest\nVmHWM:\t    2048 kB\nVmRSS:\t    1024 kB\n";
+        assert_eq!(status_value_kib(status, "VmHWM:"), Some(2048));
+        assert_eq!(status_value_kib(status, "VmRSS:"), Some(1024));
+        assert_eq!(status_value_kib(status, "VmSwap:"), None);
+    }
+
+    #[test]
+    fn test_format_kib() {
+        assert_eq!(format_kib(512), "512 KiB");
+        assert_eq!(format_kib(20 * 1024), "20.0 MiB");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_peak_rss_of_this_process() {
+        assert!(peak_rss_kib(std::process::id()).is_some_and(|kib| kib > 0));
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+
+use crate::grid::{Grid, XY};
+
+/// The 4 wide by 6 tall letters.  Leading and trailing blank columns are ignored when matching.
+const GLYPHS_6: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The 6 wide by 10 tall letters.
+const GLYPHS_10: &[(char, [&str; 10])] = &[
+    (
+        'A',
+        [
+            "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'B',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+            "#....#", "#####.",
+        ],
+    ),
+    (
+        'C',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#....#", ".####.",
+        ],
+    ),
+    (
+        'E',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'F',
+        [
+            "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'G',
+        [
+            ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+            "#...##", ".###.#",
+        ],
+    ),
+    (
+        'H',
+        [
+            "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+            "#...#.", ".###..",
+        ],
+    ),
+    (
+        'K',
+        [
+            "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+            "#...#.", "#....#",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+    (
+        'N',
+        [
+            "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+            "#...##", "#....#",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+            "#.....", "#.....",
+        ],
+    ),
+    (
+        'R',
+        [
+            "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'X',
+        [
+            "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+            "#....#", "#....#",
+        ],
+    ),
+    (
+        'Z',
+        [
+            "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+            "#.....", "######",
+        ],
+    ),
+];
+
+/// Reads the block letters drawn in a grid, where `true` cells are lit.
+///
+/// Letters are separated by at least one blank column.  Blank rows and columns around
+/// the text are ignored, and the letter size is picked from the height of the text.
+pub fn recognize(grid: &Grid<bool>) -> Result<String> {
+    let is_lit = |x: usize, y: usize| grid.get(XY::new(x, y)).is_some_and(|cell| *cell.value());
+    let lit_rows = (0..grid.height())
+        .filter(|y| (0..grid.width()).any(|x| is_lit(x, *y)))
+        .collect::<Vec<_>>();
+    let (Some(top), Some(bottom)) = (lit_rows.first(), lit_rows.last()) else {
+        anyhow::bail!("No letters found in grid");
+    };
+    let rows = *top..=*bottom;
+
+    // Render each letter as text, one string per row.
+    let mut letters = Vec::<Vec<String>>::new();
+    let mut in_letter = false;
+    for x in 0..grid.width() {
+        let column = rows.clone().map(|y| is_lit(x, y)).collect::<Vec<_>>();
+        if !column.contains(&true) {
+            in_letter = false;
+            continue;
+        }
+        if !in_letter {
+            letters.push(vec![String::new(); column.len()]);
+            in_letter = true;
+        }
+        let letter = letters.last_mut().expect("a letter was just started");
+        for (row, lit) in letter.iter_mut().zip(column) {
+            row.push(if lit { '#' } else { '.' });
+        }
+    }
+
+    letters
+        .iter()
+        .map(|letter| match letter.len() {
+            6 => find_glyph(GLYPHS_6, letter),
+            10 => find_glyph(GLYPHS_10, letter),
+            height => Err(anyhow::anyhow!("Unsupported letter height: {}", height)),
+        })
+        .collect()
+}
+
+fn find_glyph<const N: usize>(glyphs: &[(char, [&str; N])], letter: &[String]) -> Result<char> {
+    glyphs
+        .iter()
+        .find(|(_, glyph)| trim_columns(glyph) == letter)
+        .map(|(c, _)| *c)
+        .ok_or_else(|| anyhow::anyhow!("Unknown letter:\n{}", letter.join("\n")))
+}
+
+/// Removes the blank columns on either side of a glyph.
+fn trim_columns(glyph: &[&str]) -> Vec<String> {
+    let width = glyph.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_blank = |x: usize| glyph.iter().all(|row| row.as_bytes().get(x) != Some(&b'#'));
+    let first = (0..width).find(|x| !is_blank(*x)).unwrap_or(0);
+    let last = (0..width).rfind(|x| !is_blank(*x)).map_or(0, |x| x + 1);
+    glyph
+        .iter()
+        .map(|row| row.get(first..last).unwrap_or_default().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_from_text(text: &str) -> Grid<bool> {
+        let lines = text.lines().collect::<Vec<_>>();
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut grid = Grid::new_sized(width, lines.len(), false);
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                *grid.get_mut(&XY::new(x, y)).unwrap() = c == '#';
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_recognize_small() {
+        let text = "\
+..........................
+.#..#.###...##..####.###..
+.#..#.#..#.#..#.#....#..#.
+.####.###..#....###..#..#.
+.#..#.#..#.#....#....###..
+.#..#.#..#.#..#.#....#.#..
+.#..#.###...##..####.#..#.
+..........................";
+        assert_eq!(recognize(&grid_from_text(text)).unwrap(), "HBCER");
+    }
+
+    #[test]
+    fn test_recognize_large() {
+        let text = "\
+#....#..######
+#....#..#.....
+.#..#...#.....
+.#..#...#.....
+..##....#####.
+..##....#.....
+.#..#...#.....
+.#..#...#.....
+#....#..#.....
+#....#..######";
+        assert_eq!(recognize(&grid_from_text(text)).unwrap(), "XE");
+    }
+
+    #[test]
+    fn test_all_small_glyphs() {
+        let text = (0..6)
+            .map(|row| {
+                GLYPHS_6
+                    .iter()
+                    .map(|(_, glyph)| glyph[row])
+                    .collect::<Vec<_>>()
+                    .join("..")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let expected = GLYPHS_6.iter().map(|(c, _)| *c).collect::<String>();
+        assert_eq!(recognize(&grid_from_text(&text)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unknown_letter() {
+        let text = "#.#\n.#.\n#.#\n.#.\n#.#\n.#.";
+        assert!(recognize(&grid_from_text(text)).is_err());
+    }
+}
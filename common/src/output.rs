@@ -0,0 +1,268 @@
+//! A machine-readable summary of a run, for committing alongside write-ups so the
+//! answers and timings can be compared with the machine and code that produced them.
+//!
+//! The summary is written as JSON:
+//!
+//! ```json
+//! {
+//!   "environment": {
+//!     "common_version": "0.1.0",
+//!     "rustc": "rustc 1.91.0 (f8297e351 2025-10-28)",
+//!     "git_commit": "36ab782...",
+//!     "git_dirty": false,
+//!     "cpu": "AMD Ryzen 9 7950X 16-Core Processor",
+//!     "threads": 32,
+//!     "os": "linux",
+//!     "arch": "x86_64"
+//!   },
+//!   "days": [
+//!     {
+//!       "day": 7,
+//!       "answers": {"1": "1600", "2": "8632253783011"},
+//!       "elapsed_ms": 12.5
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Anything that can't be found out, like the CPU model off Linux, is `null`.
+
+use std::{path::Path, process::Command, time::Duration};
+
+use anyhow::{Context, Result};
+
+/// Where a run happened.  Gathered once at startup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    pub common_version: String,
+    pub rustc: Option<String>,
+    pub git_commit: Option<String>,
+    /// Whether the work tree had uncommitted changes, so the commit isn't the whole story.
+    pub git_dirty: Option<bool>,
+    pub cpu: Option<String>,
+    pub threads: Option<usize>,
+    pub os: String,
+    pub arch: String,
+}
+
+impl Environment {
+    /// Asks the toolchain, git and the OS about the current machine and work tree.
+    pub fn gather() -> Self {
+        Environment {
+            common_version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc: command_output("rustc", &["--version"]),
+            git_commit: command_output("git", &["rev-parse", "HEAD"]),
+            git_dirty: command_output("git", &["status", "--porcelain"])
+                .map(|status| !status.is_empty()),
+            cpu: std::fs::read_to_string("/proc/cpuinfo")
+                .ok()
+                .and_then(|cpuinfo| cpu_model(&cpuinfo)),
+            threads: std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .ok(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+
+    fn to_json(&self, indent: &str) -> String {
+        let fields = [
+            ("common_version", json_string(&self.common_version)),
+            ("rustc", json_option(self.rustc.as_deref().map(json_string))),
+            (
+                "git_commit",
+                json_option(self.git_commit.as_deref().map(json_string)),
+            ),
+            ("git_dirty", json_option(self.git_dirty)),
+            ("cpu", json_option(self.cpu.as_deref().map(json_string))),
+            ("threads", json_option(self.threads)),
+            ("os", json_string(&self.os)),
+            ("arch", json_string(&self.arch)),
+        ];
+        let fields = fields
+            .iter()
+            .map(|(name, value)| format!("{}  \"{}\": {}", indent, name, value))
+            .collect::<Vec<_>>();
+        format!("{{\n{}\n{}}}", fields.join(",\n"), indent)
+    }
+}
+
+/// One day's answers from one run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayReport {
+    pub day: u32,
+    /// Each part's answer, in the order the day printed them.
+    pub answers: Vec<(u32, String)>,
+    pub elapsed: Duration,
+}
+
+impl DayReport {
+    fn to_json(&self, indent: &str) -> String {
+        let answers = self
+            .answers
+            .iter()
+            .map(|(part, answer)| format!("\"{}\": {}", part, json_string(answer)))
+            .collect::<Vec<_>>();
+        format!(
+            "{{\n{indent}  \"day\": {},\n{indent}  \"answers\": {{{}}},\n{indent}  \"elapsed_ms\": {}\n{indent}}}",
+            self.day,
+            answers.join(", "),
+            self.elapsed.as_secs_f64() * 1000.0,
+        )
+    }
+}
+
+/// The environment and every day's results from one invocation of the runner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub environment: Environment,
+    pub days: Vec<DayReport>,
+}
+
+impl Report {
+    /// An empty report for the current machine.
+    pub fn new() -> Self {
+        Report {
+            environment: Environment::gather(),
+            days: Vec::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let days = self
+            .days
+            .iter()
+            .map(|day| format!("    {}", day.to_json("    ")))
+            .collect::<Vec<_>>();
+        let days = if days.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n  ]", days.join(",\n"))
+        };
+        format!(
+            "{{\n  \"environment\": {},\n  \"days\": {}\n}}\n",
+            self.environment.to_json("  "),
+            days
+        )
+    }
+
+    /// Writes the report, replacing any file already there.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a command and gets its trimmed output, or None if it failed.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Finds the CPU's name in the contents of `/proc/cpuinfo`.
+fn cpu_model(cpuinfo: &str) -> Option<String> {
+    cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "model name").then(|| value.trim().to_string())
+    })
+}
+
+fn json_option<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |value| value.to_string())
+}
+
+/// Quotes a string for JSON.  Rust's `{:?}` isn't enough, since it writes `\u{1b}`
+/// where JSON wants `\u001b`.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("1600"), "\"1600\"");
+        assert_eq!(
+            json_string("a \"b\"\\\n\u{1b}"),
+            "\"a \\\"b\\\"\\\\\\n\\u001b\""
+        );
+    }
+
+    #[test]
+    fn test_cpu_model() {
+        let cpuinfo =
+            "processor\t: 0\nvendor_id\t: AuthenticAMD\nmodel name\t: AMD Ryzen 9 7950X\n";
+        assert_eq!(cpu_model(cpuinfo).as_deref(), Some("AMD Ryzen 9 7950X"));
+        assert_eq!(cpu_model("processor\t: 0\n"), None);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let report = Report {
+            environment: Environment {
+                common_version: "0.1.0".to_string(),
+                rustc: None,
+                git_commit: Some("abc123".to_string()),
+                git_dirty: Some(true),
+                cpu: None,
+                threads: Some(8),
+                os: "linux".to_string(),
+                arch: "x86_64".to_string(),
+            },
+            days: vec![DayReport {
+                day: 7,
+                answers: vec![(1, "1600".to_string()), (2, "8632253783011".to_string())],
+                elapsed: Duration::from_micros(12500),
+            }],
+        };
+        assert_eq!(
+            report.to_json(),
+            r#"{
+  "environment": {
+    "common_version": "0.1.0",
+    "rustc": null,
+    "git_commit": "abc123",
+    "git_dirty": true,
+    "cpu": null,
+    "threads": 8,
+    "os": "linux",
+    "arch": "x86_64"
+  },
+  "days": [
+    {
+      "day": 7,
+      "answers": {"1": "1600", "2": "8632253783011"},
+      "elapsed_ms": 12.5
+    }
+  ]
+}
+"#
+        );
+    }
+}
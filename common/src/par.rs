@@ -0,0 +1,202 @@
+//! Parallel iteration.  With the `rayon` feature (on by default), [`prelude`] is
+//! rayon's prelude.  Without it, [`prelude`] gives the same method names on plain
+//! sequential iterators, so a day builds without rayon and without `cfg` at each call.
+
+use std::collections::VecDeque;
+
+use prelude::*;
+
+/// rayon's prelude.
+#[cfg(feature = "rayon")]
+pub mod prelude {
+    pub use rayon::prelude::*;
+}
+
+/// Sequential stand-ins for the parts of rayon's prelude the days use.
+#[cfg(not(feature = "rayon"))]
+pub mod prelude {
+    pub trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+    impl<'a, I: ?Sized + 'a> IntoParallelRefIterator<'a> for I
+    where
+        &'a I: IntoIterator,
+    {
+        type Iter = <&'a I as IntoIterator>::IntoIter;
+        fn par_iter(&'a self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    pub trait ParallelIterator: Iterator + Sized {
+        fn flat_map_iter<U: IntoIterator, F: FnMut(Self::Item) -> U>(
+            self,
+            f: F,
+        ) -> std::iter::FlatMap<Self, U, F> {
+            self.flat_map(f)
+        }
+        fn find_map_any<R, F: FnMut(Self::Item) -> Option<R>>(mut self, f: F) -> Option<R> {
+            self.find_map(f)
+        }
+    }
+    impl<I: Iterator> ParallelIterator for I {}
+
+    pub trait ParallelSliceMut<T> {
+        fn par_sort_unstable(&mut self)
+        where
+            T: Ord;
+    }
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_sort_unstable(&mut self)
+        where
+            T: Ord,
+        {
+            self.sort_unstable();
+        }
+    }
+}
+
+/// How many threads parallel work is spread over.
+pub fn current_num_threads() -> usize {
+    #[cfg(feature = "rayon")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "rayon"))]
+    return 1;
+}
+
+/// How many items [`map_ordered`] processes per chunk, for each rayon thread.
+const ITEMS_PER_THREAD: usize = 4;
+
+/// Maps items on rayon, yielding the results in input order.
+///
+/// Items are pulled from the input a chunk at a time and each chunk is mapped in
+/// parallel, so only one chunk of items and results is held at once however long the
+/// input is.
+pub fn map_ordered<I, T, U, F>(items: I, f: F) -> MapOrdered<I::IntoIter, U, F>
+where
+    I: IntoIterator<Item = T>,
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Sync,
+{
+    map_ordered_chunked(items, current_num_threads() * ITEMS_PER_THREAD, f)
+}
+
+/// Like [`map_ordered`], with a chosen number of items per chunk.
+pub fn map_ordered_chunked<I, T, U, F>(
+    items: I,
+    chunk_size: usize,
+    f: F,
+) -> MapOrdered<I::IntoIter, U, F>
+where
+    I: IntoIterator<Item = T>,
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Sync,
+{
+    MapOrdered {
+        items: items.into_iter(),
+        chunk_size: chunk_size.max(1),
+        f,
+        results: VecDeque::new(),
+    }
+}
+
+/// Like [`map_ordered`] for fallible work.  Yields results in input order up to and
+/// including the first error, then stops.  Items in the same chunk as the error may
+/// still have been processed.
+pub fn try_map_ordered<I, T, U, E, F>(items: I, f: F) -> impl Iterator<Item = Result<U, E>>
+where
+    I: IntoIterator<Item = T>,
+    T: Send,
+    U: Send,
+    E: Send,
+    F: Fn(T) -> Result<U, E> + Sync,
+{
+    let mut failed = false;
+    map_ordered(items, f).map_while(move |result| {
+        if failed {
+            return None;
+        }
+        failed = result.is_err();
+        Some(result)
+    })
+}
+
+/// The iterator returned by [`map_ordered`].
+pub struct MapOrdered<I, U, F> {
+    items: I,
+    chunk_size: usize,
+    f: F,
+    // Results of the current chunk not yet yielded.
+    results: VecDeque<U>,
+}
+
+impl<I, T, U, F> Iterator for MapOrdered<I, U, F>
+where
+    I: Iterator<Item = T>,
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Sync,
+{
+    type Item = U;
+
+    fn next(&mut self) -> Option<U> {
+        if self.results.is_empty() {
+            let chunk = self
+                .items
+                .by_ref()
+                .take(self.chunk_size)
+                .collect::<Vec<_>>();
+            let f = &self.f;
+            self.results = chunk.into_par_iter().map(f).collect::<Vec<_>>().into();
+        }
+        self.results.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_ordered_keeps_order() {
+        let results = map_ordered_chunked(0..100_u64, 7, |n| {
+            // Later items finish first.
+            std::thread::sleep(std::time::Duration::from_micros(100 - n));
+            n * n
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(results, (0..100).map(|n| n * n).collect::<Vec<_>>());
+        assert_eq!(map_ordered(Vec::<u32>::new(), |n| n).count(), 0);
+    }
+
+    #[test]
+    fn test_map_ordered_is_lazy() {
+        let pulled = std::sync::atomic::AtomicUsize::new(0);
+        let mut results = map_ordered_chunked(
+            (0..).inspect(|_| {
+                pulled.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }),
+            5,
+            |n: u64| n + 1,
+        );
+        assert_eq!(results.next(), Some(1));
+        assert_eq!(pulled.load(std::sync::atomic::Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_try_map_ordered_stops_at_first_error() {
+        let results = try_map_ordered(1..=10, |n| if n % 4 == 0 { Err(n) } else { Ok(n) })
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![Ok(1), Ok(2), Ok(3), Err(4)]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Parse errors that say what the input should have looked like, and integer parsing
+//! that accepts the forms puzzle statements use.
+
+use std::str::FromStr;
+
+use crate::error::{AocError, ErrorContext};
+
+/// Parses an integer like `str::parse`, also accepting `_` between digits, like
+/// `1_000_000`.  A leading `+` is already accepted.  Underscores anywhere else are left
+/// in, so they fail like any other stray character.
+pub fn int<T: FromStr>(s: &str) -> Result<T, T::Err> {
+    let bytes = s.as_bytes();
+    let is_digit = |i: Option<usize>| i.and_then(|i| bytes.get(i)).is_some_and(u8::is_ascii_digit);
+    let grouped = s.contains('_')
+        && (0..bytes.len())
+            .filter(|i| bytes[*i] == b'_')
+            .all(|i| is_digit(i.checked_sub(1)) && is_digit(Some(i + 1)));
+    if grouped {
+        s.replace('_', "").parse()
+    } else {
+        s.parse()
+    }
+}
+
+/// Whether a character can be part of an integer [`int`] parses, for parsers that
+/// need to find where the integer ends.
+pub fn is_int_char(c: char) -> bool {
+    c.is_ascii_digit() || c == '_' || c == '+'
+}
+
+/// Adds the accepted format and the offending text to a parse failure.
+pub trait ExpectFormat<T> {
+    /// On failure, wraps the error as `Expected <description>, got "<text>"`.  The
+    /// original error stays in the chain, so `{:#}` shows both.
+    ///
+    /// Apply it before [`crate::error::ErrorContext`], which adds the line and file on
+    /// the outside.
+    fn expect_format(self, description: &str, text: &str) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> ExpectFormat<T> for Result<T, E> {
+    fn expect_format(self, description: &str, text: &str) -> anyhow::Result<T> {
+        self.map_err(|error| expect_format(error, description, text))
+    }
+}
+
+/// Wraps an error as `Expected <description>, got "<text>"`, for parsers that build
+/// their errors by hand rather than through a `Result`.
+pub fn expect_format(
+    error: impl Into<anyhow::Error>,
+    description: &str,
+    text: &str,
+) -> anyhow::Error {
+    error
+        .into()
+        .context(format!("Expected {}, got {:?}", description, text))
+}
+
+/// Parses input one line at a time.
+pub trait ExpectWithLine<'a>: Iterator<Item = &'a str> + Sized {
+    /// Parses every line, stopping at the first failure.  The error says which 1-based
+    /// line failed and what it should have looked like, as [`ExpectFormat`] and
+    /// [`ErrorContext::with_line`] would.
+    fn expect_with_line<T, E: Into<anyhow::Error>>(
+        self,
+        description: &str,
+        mut parse: impl FnMut(&'a str) -> Result<T, E>,
+    ) -> Result<Vec<T>, AocError> {
+        (1..)
+            .zip(self)
+            .map(|(number, line)| {
+                parse(line)
+                    .expect_format(description, line)
+                    .with_line(number)
+            })
+            .collect()
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a str>> ExpectWithLine<'a> for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int() {
+        assert_eq!(int::<u64>("1_000_000"), Ok(1_000_000));
+        assert_eq!(int::<i32>("+123"), Ok(123));
+        assert_eq!(int::<i32>("-1_0"), Ok(-10));
+        assert_eq!(int::<u32>("+4_2"), Ok(42));
+        for bad in ["_1", "1_", "1__0", "+_1", "_", "1,0"] {
+            assert!(int::<u32>(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            int::<u32>("1__0").unwrap_err().to_string(),
+            "invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_expect_format() {
+        let error = "1,x"
+            .parse::<u32>()
+            .expect_format("a non-negative integer", "1,x")
+            .with_line(3)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: Expected a non-negative integer, got \"1,x\""
+        );
+        assert_eq!(
+            format!("{:#}", error.error()),
+            "Expected a non-negative integer, got \"1,x\": invalid digit found in string"
+        );
+        assert_eq!(Ok::<_, anyhow::Error>(5).expect_format("", "").unwrap(), 5);
+    }
+
+    #[test]
+    fn test_expect_with_line() {
+        let numbers = "1\n2\n3"
+            .lines()
+            .expect_with_line("a number", str::parse::<u32>);
+        assert_eq!(numbers.unwrap(), vec![1, 2, 3]);
+        let error = "1\n2\nthree\nfour"
+            .lines()
+            .expect_with_line("a number", str::parse::<u32>)
+            .unwrap_err();
+        assert_eq!(error.line(), Some(3));
+        assert_eq!(
+            error.to_string(),
+            "line 3: Expected a number, got \"three\""
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! A small span profiler.  With the `profile` feature enabled, [`span`] times each named
+//! section of a solve, and [`write_folded`] writes the times as folded stacks
+//! (`outer;inner microseconds` per line), the input format of `flamegraph.pl` and
+//! `inferno-flamegraph`.  Without the feature, [`span`] just runs the section.
+//!
+//! Stacks are tracked per thread, so a span started on a rayon worker is a root of
+//! its own rather than a child of the span that started the parallel iterator.
+
+#[cfg(feature = "profile")]
+mod recorder {
+    use std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    struct Frame {
+        name: &'static str,
+        start: Instant,
+        // Time spent in child spans, which isn't this frame's own time.
+        children: Duration,
+    }
+
+    thread_local! {
+        static STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Self time in microseconds for each folded stack.
+    pub static SAMPLES: Mutex<BTreeMap<String, u128>> = Mutex::new(BTreeMap::new());
+
+    pub fn enter(name: &'static str) {
+        STACK.with_borrow_mut(|stack| {
+            stack.push(Frame {
+                name,
+                start: Instant::now(),
+                children: Duration::ZERO,
+            })
+        });
+    }
+
+    pub fn exit() {
+        STACK.with_borrow_mut(|stack| {
+            let folded = stack
+                .iter()
+                .map(|frame| frame.name)
+                .collect::<Vec<_>>()
+                .join(";");
+            let Some(frame) = stack.pop() else {
+                return;
+            };
+            let elapsed = frame.start.elapsed();
+            if let Some(parent) = stack.last_mut() {
+                parent.children += elapsed;
+            }
+            let own = elapsed.saturating_sub(frame.children).as_micros();
+            *SAMPLES
+                .lock()
+                .expect("profile samples lock")
+                .entry(folded)
+                .or_default() += own;
+        });
+    }
+}
+
+/// Is span profiling compiled in?
+pub fn enabled() -> bool {
+    cfg!(feature = "profile")
+}
+
+/// Runs `f` as a named span, nested inside whichever span is running on this thread.
+#[inline]
+pub fn span<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "profile")]
+    {
+        recorder::enter(name);
+        let value = f();
+        recorder::exit();
+        value
+    }
+    #[cfg(not(feature = "profile"))]
+    {
+        _ = name;
+        f()
+    }
+}
+
+/// Writes the recorded spans as folded stacks.  Writes nothing unless `profile` is enabled.
+pub fn write_folded(mut out: impl std::io::Write) -> std::io::Result<()> {
+    #[cfg(feature = "profile")]
+    for (stack, micros) in recorder::SAMPLES
+        .lock()
+        .expect("profile samples lock")
+        .iter()
+    {
+        writeln!(out, "{} {}", stack, micros)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_returns_value() {
+        assert_eq!(span("test_span_returns_value", || 42), 42);
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn test_folded_stacks() {
+        span("test_outer", || {
+            span("test_inner", || {
+                std::thread::sleep(std::time::Duration::from_millis(2))
+            })
+        });
+        let mut out = Vec::new();
+        write_folded(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let inner = out
+            .lines()
+            .find_map(|line| line.strip_prefix("test_outer;test_inner "))
+            .expect("nested stack recorded");
+        assert!(inner.parse::<u128>().unwrap() >= 2000);
+        assert!(out.lines().any(|line| line.starts_with("test_outer ")));
+    }
+}
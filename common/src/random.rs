@@ -0,0 +1,63 @@
+//! Repeatable pseudo-random numbers for generated inputs and randomized searches.
+//!
+//! Not for anything that needs good randomness: it is a 64-bit linear congruential
+//! generator, using only the high bits of its state, which are the least predictable.
+//! What matters here is that the same seed always gives the same numbers, so
+//! benchmarks and failing fuzz cases can be replayed.
+
+/// A seeded 64-bit linear congruential generator.
+#[derive(Debug, Clone)]
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random(seed)
+    }
+
+    /// Gets the next 31 random bits.
+    pub fn next_bits(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    /// Gets a number below `below`, or 0 if it is 0.
+    pub fn below(&mut self, below: u64) -> u64 {
+        self.next_bits() % below.max(1)
+    }
+
+    /// Gets an index below `len`, or 0 if it is 0.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// Gets a number in `0.0..1.0`.
+    pub fn unit(&mut self) -> f64 {
+        self.next_bits() as f64 / (1_u64 << 31) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeatable() {
+        let draw = |seed| {
+            let mut random = Random::new(seed);
+            (0..100).map(|_| random.below(10)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(7), draw(7));
+        assert_ne!(draw(7), draw(8));
+        assert!(draw(7).iter().all(|n| *n < 10));
+        assert_eq!(Random::new(1).below(0), 0);
+        let mut random = Random::new(3);
+        assert!(
+            (0..100)
+                .map(|_| random.unit())
+                .all(|x| (0.0..1.0).contains(&x))
+        );
+    }
+}
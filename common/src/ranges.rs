@@ -0,0 +1,487 @@
+use std::ops::RangeInclusive;
+
+/// Integer types where every value has a well defined next and previous value.  This
+/// is what lets adjacent intervals like 1..=5 and 6..=9 be merged.
+pub trait Discrete: Copy + Ord {
+    /// Gets the next value, or None if there isn't one.
+    fn successor(self) -> Option<Self>;
+    /// Gets the previous value, or None if there isn't one.
+    fn predecessor(self) -> Option<Self>;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                fn successor(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+                fn predecessor(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+            }
+        )*
+    };
+}
+impl_discrete!(u32, u64, u128, usize, i32, i64);
+
+/// A map from non-overlapping inclusive key intervals to values.  Lookups use a
+/// binary search over the intervals, which are kept sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeMap<K, V> {
+    entries: Vec<(RangeInclusive<K>, V)>,
+}
+
+impl<K, V> Default for RangeMap<K, V> {
+    fn default() -> Self {
+        RangeMap {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K: Discrete, V> RangeMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a map from intervals and their values, in any order.  Fails like
+    /// [`RangeMap::insert`] if any interval is empty or overlaps another.
+    pub fn from_entries(
+        entries: impl IntoIterator<Item = (RangeInclusive<K>, V)>,
+    ) -> anyhow::Result<Self> {
+        let mut map = RangeMap::new();
+        for (range, value) in entries {
+            map.insert(range, value)?;
+        }
+        Ok(map)
+    }
+
+    /// Maps every key in the range to the value.  Fails if the range is empty or
+    /// overlaps an interval that is already mapped.
+    pub fn insert(&mut self, range: RangeInclusive<K>, value: V) -> anyhow::Result<()> {
+        if range.start() > range.end() {
+            anyhow::bail!("Can not insert an empty range");
+        }
+        let index = self
+            .entries
+            .partition_point(|(r, _)| r.start() < range.start());
+        let overlaps_previous = index > 0 && self.entries[index - 1].0.end() >= range.start();
+        let overlaps_next = self
+            .entries
+            .get(index)
+            .is_some_and(|(r, _)| r.start() <= range.end());
+        if overlaps_previous || overlaps_next {
+            anyhow::bail!("Range overlaps an existing range");
+        }
+        self.entries.insert(index, (range, value));
+        Ok(())
+    }
+
+    /// Gets the interval containing the key along with its value.
+    pub fn get_key_value(&self, key: &K) -> Option<(&RangeInclusive<K>, &V)> {
+        // Intervals are sorted and disjoint, so only the last one starting at or
+        // before the key can contain it.
+        let index = self.entries.partition_point(|(r, _)| r.start() <= key);
+        let (range, value) = self.entries.get(index.checked_sub(1)?)?;
+        range.contains(key).then_some((range, value))
+    }
+
+    /// Gets the value of the interval containing the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Checks if any interval contains the key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get_key_value(key).is_some()
+    }
+
+    /// Splits the range wherever it crosses an interval boundary.  The pieces cover
+    /// the whole range in order, each with the value of the interval it is in, or None
+    /// for pieces that are not in any interval.
+    pub fn split(&self, range: RangeInclusive<K>) -> Vec<(RangeInclusive<K>, Option<&V>)> {
+        let (mut start, end) = range.into_inner();
+        let mut pieces = Vec::new();
+        if start > end {
+            return pieces;
+        }
+        let first = self.entries.partition_point(|(r, _)| *r.end() < start);
+        for (r, value) in &self.entries[first..] {
+            if *r.start() > end {
+                break;
+            }
+            // The gap before this interval.
+            if start < *r.start() {
+                let gap_end = r
+                    .start()
+                    .predecessor()
+                    .expect("start is after another value");
+                pieces.push((start..=gap_end, None));
+                start = *r.start();
+            }
+            let piece_end = end.min(*r.end());
+            pieces.push((start..=piece_end, Some(value)));
+            match piece_end.successor() {
+                Some(next) if piece_end < end => start = next,
+                _ => return pieces,
+            }
+        }
+        pieces.push((start..=end, None));
+        pieces
+    }
+
+    /// Returns an iterator over the intervals and their values, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = (&RangeInclusive<K>, &V)> {
+        self.entries.iter().map(|(range, value)| (range, value))
+    }
+
+    /// Gets the number of intervals.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A translation maps each key in an interval by adding the interval's offset.
+/// Keys outside of every interval map to themselves.
+pub type Translation = RangeMap<i64, i64>;
+
+impl Translation {
+    /// Maps a single key.  Fails if the key's offset takes it past an i64.
+    pub fn translate(&self, key: i64) -> anyhow::Result<i64> {
+        key.checked_add(self.get(&key).copied().unwrap_or(0))
+            .ok_or_else(translation_overflow)
+    }
+
+    /// Maps a whole range of keys, splitting it wherever it crosses an interval boundary.
+    /// Fails if an offset takes any of the keys past an i64.
+    pub fn translate_range(
+        &self,
+        range: RangeInclusive<i64>,
+    ) -> anyhow::Result<Vec<RangeInclusive<i64>>> {
+        self.split(range)
+            .into_iter()
+            .map(|(piece, offset)| {
+                let offset = offset.copied().unwrap_or(0);
+                let start = piece.start().checked_add(offset);
+                let end = piece.end().checked_add(offset);
+                start.zip(end).map(|(start, end)| start..=end)
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(translation_overflow)
+    }
+
+    /// Composes two translations into one that maps a key by this translation and
+    /// then by `next`.  Fails if a key or offset along the way overflows an i64.
+    pub fn then(&self, next: &Translation) -> anyhow::Result<Translation> {
+        let mut entries = Vec::new();
+
+        // Keys this translation maps: follow each interval through `next`.
+        for (range, offset) in self.iter() {
+            let translated = range
+                .start()
+                .checked_add(*offset)
+                .ok_or_else(translation_overflow)?
+                ..=range
+                    .end()
+                    .checked_add(*offset)
+                    .ok_or_else(translation_overflow)?;
+            for (piece, next_offset) in next.split(translated) {
+                let total = offset
+                    .checked_add(next_offset.copied().unwrap_or(0))
+                    .ok_or_else(translation_overflow)?;
+                // The piece came from adding the offset, so taking it off again can't
+                // overflow.
+                entries.push((piece.start() - offset..=piece.end() - offset, total));
+            }
+        }
+        // Keys this translation leaves alone: only `next` applies.
+        for (range, next_offset) in next.iter() {
+            for (piece, offset) in self.split(range.clone()) {
+                if offset.is_none() {
+                    entries.push((piece, *next_offset));
+                }
+            }
+        }
+
+        entries.retain(|(_, offset)| *offset != 0);
+        entries.sort_by_key(|(range, _)| *range.start());
+        Ok(RangeMap { entries })
+    }
+}
+
+fn translation_overflow() -> anyhow::Error {
+    anyhow::anyhow!("Translation overflows an i64")
+}
+
+/// A set of integers stored as sorted inclusive intervals.  Overlapping and
+/// adjacent intervals are merged as they are inserted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: RangeMap<T, ()>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet {
+            intervals: RangeMap::default(),
+        }
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a range, merging it with any intervals it overlaps or touches.
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        let entries = &mut self.intervals.entries;
+        // The first interval that ends at or after the value just before `start`.
+        let first =
+            entries.partition_point(|(r, _)| r.end().successor().is_some_and(|next| next < start));
+        // One past the last interval that starts at or before the value just after `end`.
+        let last =
+            entries.partition_point(|(r, _)| end.successor().is_none_or(|next| *r.start() <= next));
+
+        if first < last {
+            start = start.min(*entries[first].0.start());
+            end = end.max(*entries[last - 1].0.end());
+        }
+        entries.splice(first..last, [(start..=end, ())]);
+    }
+
+    /// Checks if the value is in any of the intervals.
+    pub fn contains(&self, value: &T) -> bool {
+        self.intervals.contains_key(value)
+    }
+
+    /// Checks if every value in the range is in the set.
+    pub fn contains_range(&self, range: &RangeInclusive<T>) -> bool {
+        // Adjacent intervals are merged, so the range must fit in a single one.
+        self.intervals
+            .get_key_value(range.start())
+            .is_some_and(|(interval, _)| interval.end() >= range.end())
+    }
+
+    /// Returns an iterator over the merged intervals, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &RangeInclusive<T>> {
+        self.intervals.iter().map(|(range, _)| range)
+    }
+
+    /// Returns an iterator over the values between consecutive intervals, in ascending
+    /// order.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> {
+        self.iter()
+            .zip(self.iter().skip(1))
+            .filter_map(|(a, b)| Some(a.end().successor()?..=b.start().predecessor()?))
+    }
+
+    /// Gets the values in `universe` that aren't in any interval.
+    pub fn complement(&self, universe: RangeInclusive<T>) -> IntervalSet<T> {
+        let (start, end) = universe.into_inner();
+        let mut complement = IntervalSet::new();
+        // The smallest value not yet known to be covered, or None past the type's max.
+        let mut next = Some(start);
+        for range in self.iter() {
+            let Some(from) = next else {
+                break;
+            };
+            if *range.start() > end {
+                break;
+            }
+            if *range.end() < from {
+                continue;
+            }
+            if let Some(before) = range.start().predecessor()
+                && from <= before
+            {
+                complement.insert(from..=before);
+            }
+            next = range.end().successor();
+        }
+        if let Some(from) = next {
+            complement.insert(from..=end);
+        }
+        complement
+    }
+
+    /// Gets the number of merged intervals.
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+}
+
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T> IntoIterator for IntervalSet<T> {
+    type Item = RangeInclusive<T>;
+    type IntoIter = std::iter::Map<
+        std::vec::IntoIter<(RangeInclusive<T>, ())>,
+        fn((RangeInclusive<T>, ())) -> RangeInclusive<T>,
+    >;
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.entries.into_iter().map(|(range, _)| range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_merges() {
+        let set = [10..=14, 3..=5, 16..=20, 12..=18]
+            .into_iter()
+            .collect::<IntervalSet<u64>>();
+        assert_eq!(
+            set.iter().cloned().collect::<Vec<_>>(),
+            vec![3..=5, 10..=20]
+        );
+        assert!(set.contains_range(&(11..=20)));
+        assert!(!set.contains_range(&(4..=10)));
+        assert!(!set.contains_range(&(0..=3)));
+    }
+
+    #[test]
+    fn test_insert_merges_adjacent() {
+        let set = [1..=5, 7..=9, 6..=6]
+            .into_iter()
+            .collect::<IntervalSet<u64>>();
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![1..=9]);
+    }
+
+    #[test]
+    fn test_insert_at_max() {
+        let set = [u64::MAX - 1..=u64::MAX, 0..=u64::MAX - 2]
+            .into_iter()
+            .collect::<IntervalSet<u64>>();
+        assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![0..=u64::MAX]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = [3..=5, 10..=14].into_iter().collect::<IntervalSet<u64>>();
+        let contained = (0..16).filter(|v| set.contains(v)).collect::<Vec<_>>();
+        assert_eq!(contained, vec![3, 4, 5, 10, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn test_gaps_and_complement() {
+        let set = [3..=5, 10..=14, 20..=20]
+            .into_iter()
+            .collect::<IntervalSet<u64>>();
+        assert_eq!(set.gaps().collect::<Vec<_>>(), vec![6..=9, 15..=19]);
+        let complement = set.complement(0..=25);
+        assert_eq!(
+            complement.iter().cloned().collect::<Vec<_>>(),
+            vec![0..=2, 6..=9, 15..=19, 21..=25]
+        );
+        assert_eq!(
+            set.complement(4..=12).iter().cloned().collect::<Vec<_>>(),
+            vec![6..=9]
+        );
+        assert!(set.complement(10..=14).is_empty());
+        assert!(set.complement(RangeInclusive::new(9, 8)).is_empty());
+        let full = [0..=u64::MAX].into_iter().collect::<IntervalSet<u64>>();
+        assert!(full.complement(0..=u64::MAX).is_empty());
+        assert_eq!(full.gaps().count(), 0);
+    }
+
+    #[test]
+    fn test_range_map_get() {
+        let map = RangeMap::<u64, char>::from_entries([(30..=39, 'b'), (10..=19, 'a')]).unwrap();
+        assert_eq!(map.get(&9), None);
+        assert_eq!(map.get(&10), Some(&'a'));
+        assert_eq!(map.get(&19), Some(&'a'));
+        assert_eq!(map.get(&25), None);
+        assert_eq!(map.get_key_value(&35), Some((&(30..=39), &'b')));
+        assert_eq!(map.get(&40), None);
+    }
+
+    #[test]
+    fn test_range_map_rejects_overlap() {
+        let mut map = RangeMap::new();
+        map.insert(10..=19_u64, ()).unwrap();
+        assert!(map.insert(19..=25, ()).is_err());
+        assert!(map.insert(5..=10, ()).is_err());
+        assert!(map.insert(12..=14, ()).is_err());
+        map.insert(20..=25, ()).unwrap();
+        assert_eq!(map.len(), 2);
+        let error = RangeMap::from_entries([(10..=19_u64, ()), (15..=25, ())]).unwrap_err();
+        assert_eq!(error.to_string(), "Range overlaps an existing range");
+    }
+
+    #[test]
+    fn test_split() {
+        let map =
+            RangeMap::<u64, char>::from_entries([(10..=19, 'a'), (20..=24, 'b'), (30..=39, 'c')])
+                .unwrap();
+        assert_eq!(
+            map.split(5..=32),
+            vec![
+                (5..=9, None),
+                (10..=19, Some(&'a')),
+                (20..=24, Some(&'b')),
+                (25..=29, None),
+                (30..=32, Some(&'c')),
+            ]
+        );
+        assert_eq!(map.split(12..=14), vec![(12..=14, Some(&'a'))]);
+        assert_eq!(map.split(40..=50), vec![(40..=50, None)]);
+    }
+
+    #[test]
+    fn test_translation_compose() {
+        // The seed-to-soil and soil-to-fertilizer maps from a well known puzzle.
+        let first = Translation::from_entries([(98..=99, -48), (50..=97, 2)]).unwrap();
+        let second =
+            Translation::from_entries([(15..=51, -15), (52..=53, -15), (0..=14, 39)]).unwrap();
+        let composed = first.then(&second).unwrap();
+        for key in -5..110 {
+            assert_eq!(
+                composed.translate(key).unwrap(),
+                second.translate(first.translate(key).unwrap()).unwrap(),
+                "{}",
+                key
+            );
+        }
+        assert_eq!(
+            first.translate_range(90..=99).unwrap(),
+            vec![92..=99, 50..=51]
+        );
+
+        // Two big offsets that each fit, but not together.
+        let up = Translation::from_entries([(0..=9, i64::MAX - 10)]).unwrap();
+        let further = Translation::from_entries([(i64::MAX - 10..=i64::MAX - 5, 11)]).unwrap();
+        let error = up.then(&further).unwrap_err();
+        assert_eq!(error.to_string(), "Translation overflows an i64");
+        // An interval pushed past the largest key.
+        let past = Translation::from_entries([(i64::MAX - 1..=i64::MAX, 5)]).unwrap();
+        assert!(past.then(&Translation::new()).is_err());
+        assert_eq!(past.translate(i64::MAX - 10).unwrap(), i64::MAX - 10);
+        let error = past.translate(i64::MAX).unwrap_err();
+        assert_eq!(error.to_string(), "Translation overflows an i64");
+        assert!(past.translate_range(i64::MAX - 3..=i64::MAX).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Memoized recursion on an explicit heap stack, for recursive descents that are too
+//! deep for the call stack.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use anyhow::Result;
+
+/// What a node turns into before its children are known.
+pub enum Step<N, V> {
+    /// The node's value is known directly.
+    Leaf(V),
+    /// The node's value depends on these children, combined in this order.
+    Children(Vec<N>),
+}
+
+/// The error [`trampoline`] returns when a node turns out to depend on itself.  It can
+/// be found with `anyhow::Error::is` to report the loop in the caller's terms.
+#[derive(Debug)]
+pub struct Cycle {
+    /// The node that was reached again, formatted with Debug.
+    pub node: String,
+}
+
+impl std::fmt::Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cycle through {}", self.node)
+    }
+}
+impl std::error::Error for Cycle {}
+
+/// Evaluates `root` the way a memoized recursive function would, without recursing.
+///
+/// `step` says whether a node is a leaf or which children it needs, and `combine`
+/// builds a node's value from its children's values, paired with the children they
+/// came from.  Every node is evaluated at most once, and a node that is reached again
+/// while its own children are still being evaluated fails with [`Cycle`].
+pub fn trampoline<N, V>(
+    root: N,
+    step: impl FnMut(&N) -> Result<Step<N, V>>,
+    combine: impl FnMut(&N, Vec<(N, V)>) -> Result<V>,
+) -> Result<V>
+where
+    N: Hash + Eq + Clone + std::fmt::Debug,
+    V: Clone,
+{
+    trampoline_with_cache(root, &mut HashMap::new(), step, combine)
+}
+
+/// Like [`trampoline`], but with the cache of finished nodes supplied by the caller, so
+/// their values can be looked at afterwards or reused across several roots.
+pub fn trampoline_with_cache<N, V>(
+    root: N,
+    cache: &mut HashMap<N, V>,
+    mut step: impl FnMut(&N) -> Result<Step<N, V>>,
+    mut combine: impl FnMut(&N, Vec<(N, V)>) -> Result<V>,
+) -> Result<V>
+where
+    N: Hash + Eq + Clone + std::fmt::Debug,
+    V: Clone,
+{
+    struct Frame<N, V> {
+        node: N,
+        children: std::vec::IntoIter<N>,
+        // The child being evaluated, and the values of the ones already done.
+        current: Option<N>,
+        done: Vec<(N, V)>,
+    }
+
+    let mut stack = Vec::<Frame<N, V>>::new();
+    // The nodes with a frame on the stack.
+    let mut visiting = HashSet::new();
+    let mut pending = Some(root);
+    let mut returned = None;
+    loop {
+        if let Some(node) = pending.take() {
+            if let Some(value) = cache.get(&node) {
+                returned = Some(value.clone());
+            } else if visiting.contains(&node) {
+                return Err(Cycle {
+                    node: format!("{:?}", node),
+                }
+                .into());
+            } else {
+                match step(&node)? {
+                    Step::Leaf(value) => {
+                        cache.insert(node, value.clone());
+                        returned = Some(value);
+                    }
+                    Step::Children(children) => {
+                        visiting.insert(node.clone());
+                        stack.push(Frame {
+                            node,
+                            children: children.into_iter(),
+                            current: None,
+                            done: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let Some(frame) = stack.last_mut() else {
+            return Ok(returned.expect("the root has been evaluated"));
+        };
+        if let Some(value) = returned.take() {
+            let child = frame.current.take().expect("a child was being evaluated");
+            frame.done.push((child, value));
+        }
+        match frame.children.next() {
+            Some(child) => {
+                frame.current = Some(child.clone());
+                pending = Some(child);
+            }
+            None => {
+                let frame = stack.pop().expect("frame was just looked at");
+                visiting.remove(&frame.node);
+                let value = combine(&frame.node, frame.done)?;
+                cache.insert(frame.node, value.clone());
+                returned = Some(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts lattice paths from (x, y) down to (0, 0).
+    fn lattice_paths(x: u32, y: u32) -> u64 {
+        trampoline(
+            (x, y),
+            |&(x, y)| {
+                Ok(match (x, y) {
+                    (0, _) | (_, 0) => Step::Leaf(1),
+                    _ => Step::Children(vec![(x - 1, y), (x, y - 1)]),
+                })
+            },
+            |_, children| Ok(children.iter().map(|(_, count)| count).sum()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_memoized() {
+        assert_eq!(lattice_paths(0, 0), 1);
+        assert_eq!(lattice_paths(2, 2), 6);
+        assert_eq!(lattice_paths(16, 16), 601080390);
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        // Far deeper than the call stack would allow.
+        let depth = trampoline(
+            0_u32,
+            |&n| {
+                Ok(if n == 1_000_000 {
+                    Step::Leaf(0)
+                } else {
+                    Step::Children(vec![n + 1])
+                })
+            },
+            |_, children| Ok(children[0].1 + 1),
+        )
+        .unwrap();
+        assert_eq!(depth, 1_000_000);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let err = trampoline(
+            0_u32,
+            |&n| Ok(Step::<u32, u32>::Children(vec![(n + 1) % 3])),
+            |_, _| Ok(0),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Cycle>().map(|c| c.node.as_str()),
+            Some("0")
+        );
+        assert_eq!(err.to_string(), "Cycle through 0");
+    }
+
+    #[test]
+    fn test_errors_stop_the_walk() {
+        let err = trampoline(
+            3_u32,
+            |&n| {
+                if n == 0 {
+                    anyhow::bail!("Reached zero");
+                }
+                Ok(Step::<u32, u32>::Children(vec![n - 1]))
+            },
+            |_, _| Ok(0),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Reached zero");
+    }
+}
@@ -0,0 +1,81 @@
+//! Embedding a day's `sample.txt` in its tests.
+//!
+//! [`include_sample!`](crate::include_sample) reads the file at compile time from the
+//! day's crate directory, wherever the test module is, so tests don't depend on the
+//! directory they're run from.  [`sample_tests!`](crate::sample_tests) turns a list of
+//! sample answers into tests:
+//!
+//! ```ignore
+//! common::sample_tests! {
+//!     part1: |input: &str| solve(input, 2) => 357,
+//!     part2: |input: &str| solve(input, 12) => 3121910778619,
+//! }
+//! ```
+//!
+//! Each solver takes the sample text and returns a `Result`.  The tests show up as
+//! `tests::part1::sample` and so on.
+
+/// Embeds `sample.txt`, or another file next to the day's `Cargo.toml`.  A missing file
+/// fails the build naming the path, and an empty one panics when used, since that is
+/// usually a placeholder the example hasn't been pasted into yet.
+#[macro_export]
+macro_rules! include_sample {
+    () => {
+        $crate::include_sample!("sample.txt")
+    };
+    ($name:literal) => {
+        $crate::sample::non_empty(
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $name)),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/", $name),
+        )
+    };
+}
+
+/// Generates a test for each `part: solver => answer`, checking the solver's answer for
+/// `sample.txt`.
+#[macro_export]
+macro_rules! sample_tests {
+    ($($part:ident: $solve:expr => $expected:expr),+ $(,)?) => {
+        $(
+            // A module rather than a function, so the test doesn't shadow a solver
+            // with the same name as the part.
+            mod $part {
+                use super::*;
+
+                #[test]
+                fn sample() {
+                    let solve = $solve;
+                    let answer = solve($crate::include_sample!())
+                        .unwrap_or_else(|error| panic!("{} failed: {:?}", stringify!($part), error));
+                    assert_eq!(answer, $expected, "{} of sample.txt", stringify!($part));
+                }
+            }
+        )+
+    };
+}
+
+/// Used by [`include_sample!`](crate::include_sample) to reject an empty sample.
+pub fn non_empty(sample: &'static str, path: &str) -> &'static str {
+    if sample.trim().is_empty() {
+        panic!("{} is empty; paste the puzzle's example into it", path);
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_empty() {
+        assert_eq!(non_empty("1 2\n", "day0/sample.txt"), "1 2\n");
+        let panic = std::panic::catch_unwind(|| non_empty(" \n", "day0/sample.txt"))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *panic,
+            "day0/sample.txt is empty; paste the puzzle's example into it"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    cell::{Cell, RefCell},
+    ops::{Deref, DerefMut},
+};
+
+/// A pool of reusable buffers for hot loops and recursions that would otherwise
+/// allocate a fresh buffer every step.
+///
+/// Checking out a buffer hands back one returned earlier, or a new default one if none
+/// are free, and dropping the [`Scratch`] handle returns it.  Buffers keep their old
+/// contents (and capacity), so overwrite them with something like `clone_from`, or use
+/// [`ScratchPool::checkout_vec`] for an empty Vec.
+///
+/// The pool is not `Sync`.  With rayon, give each task its own pool, or keep one per
+/// thread in a `thread_local!`.
+pub struct ScratchPool<T> {
+    free: RefCell<Vec<T>>,
+    created: Cell<usize>,
+}
+
+impl<T> Default for ScratchPool<T> {
+    fn default() -> Self {
+        Self {
+            free: RefCell::new(Vec::new()),
+            created: Cell::new(0),
+        }
+    }
+}
+
+impl<T: Default> ScratchPool<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks out a buffer, which goes back to the pool when the handle is dropped.
+    pub fn checkout(&self) -> Scratch<'_, T> {
+        Scratch {
+            pool: self,
+            value: Some(self.take()),
+        }
+    }
+
+    /// Takes a buffer out of the pool without a handle, for one that has to outlive a
+    /// borrow of the pool, like a search node.  Give it back with
+    /// [`ScratchPool::put_back`] once it is finished with.
+    pub fn take(&self) -> T {
+        self.free.borrow_mut().pop().unwrap_or_else(|| {
+            self.created.set(self.created.get() + 1);
+            T::default()
+        })
+    }
+
+    /// Gets the number of buffers the pool has had to create.
+    pub fn created(&self) -> usize {
+        self.created.get()
+    }
+
+    /// Returns a buffer to the pool, so a later checkout can reuse it.
+    pub fn put_back(&self, value: T) {
+        self.free.borrow_mut().push(value);
+    }
+
+    /// Gets the number of buffers waiting to be checked out.
+    pub fn available(&self) -> usize {
+        self.free.borrow().len()
+    }
+}
+
+impl<U> ScratchPool<Vec<U>> {
+    /// Checks out an empty Vec, keeping whatever capacity it had before.
+    pub fn checkout_vec(&self) -> Scratch<'_, Vec<U>> {
+        let mut scratch = self.checkout();
+        scratch.clear();
+        scratch
+    }
+}
+
+/// A buffer checked out of a [`ScratchPool`].
+pub struct Scratch<'a, T> {
+    pool: &'a ScratchPool<T>,
+    // Only None while being dropped.
+    value: Option<T>,
+}
+
+impl<T> Deref for Scratch<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+            .as_ref()
+            .expect("scratch buffer is present until dropped")
+    }
+}
+impl<T> DerefMut for Scratch<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+            .as_mut()
+            .expect("scratch buffer is present until dropped")
+    }
+}
+impl<T> Drop for Scratch<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.pool.free.borrow_mut().push(value);
+        }
+    }
+}
+
+/// Hands out a pooled Vec's items by value, so a buffer can be returned where an owned
+/// collection is wanted, like the successors of a `pathfinding` search.  The emptied Vec
+/// goes back to the pool once the iterator is dropped.
+impl<'a, U> IntoIterator for Scratch<'a, Vec<U>> {
+    type Item = U;
+    type IntoIter = ScratchIntoIter<'a, U>;
+    fn into_iter(mut self) -> Self::IntoIter {
+        // Popping from the back then gives the items in their original order.
+        self.reverse();
+        ScratchIntoIter { scratch: self }
+    }
+}
+
+/// The iterator from [`Scratch::into_iter`](IntoIterator::into_iter) on a pooled Vec.
+pub struct ScratchIntoIter<'a, U> {
+    scratch: Scratch<'a, Vec<U>>,
+}
+
+impl<U> Iterator for ScratchIntoIter<'_, U> {
+    type Item = U;
+    fn next(&mut self) -> Option<U> {
+        self.scratch.pop()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.scratch.len(), Some(self.scratch.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let pool = ScratchPool::<Vec<u32>>::new();
+        for i in 0..10 {
+            let mut buffer = pool.checkout_vec();
+            assert!(buffer.is_empty());
+            buffer.extend(0..i);
+        }
+        assert_eq!(pool.created(), 1);
+        assert_eq!(pool.available(), 1);
+        assert!(pool.checkout().capacity() >= 9);
+    }
+
+    #[test]
+    fn test_take_and_put_back() {
+        let pool = ScratchPool::<Vec<u32>>::new();
+        let mut taken = pool.take();
+        taken.push(1);
+        pool.put_back(taken);
+        assert_eq!(pool.available(), 1);
+        // The buffer comes back as it was left.
+        assert_eq!(pool.checkout().as_slice(), &[1]);
+        assert_eq!(pool.created(), 1);
+    }
+
+    #[test]
+    fn test_into_iter_returns_the_buffer() {
+        let pool = ScratchPool::<Vec<u32>>::new();
+        for _ in 0..3 {
+            let mut buffer = pool.checkout_vec();
+            buffer.extend([1, 2, 3]);
+            assert_eq!(buffer.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+        }
+        assert_eq!(pool.created(), 1);
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_nested_checkouts() {
+        fn recurse(pool: &ScratchPool<Vec<u32>>, depth: u32) {
+            let mut buffer = pool.checkout_vec();
+            buffer.push(depth);
+            if depth > 0 {
+                recurse(pool, depth - 1);
+            }
+            assert_eq!(buffer.as_slice(), &[depth]);
+        }
+        let pool = ScratchPool::new();
+        recurse(&pool, 4);
+        recurse(&pool, 4);
+        assert_eq!(pool.created(), 5);
+        assert_eq!(pool.available(), 5);
+    }
+}
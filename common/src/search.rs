@@ -0,0 +1,552 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::deadline::{Deadline, Expired};
+use crate::par::prelude::*;
+
+/// How many levels of the tree [`parallel_dfs`] splits into rayon tasks.
+pub const DEFAULT_PARALLEL_DEPTH: usize = 4;
+
+/// How many nodes a search visits between looks at its deadline, since reading the
+/// clock costs more than visiting a small node.
+pub const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Searches a tree depth first for a goal node, exploring branches in parallel.
+///
+/// `expand` gets a node's children, and `is_goal` says whether a node is a solution.
+/// The first `DEFAULT_PARALLEL_DEPTH` levels are spread over rayon tasks, and each
+/// subtree below that is searched on a single thread, so tasks stay big enough to be
+/// worth stealing.  Every task stops as soon as any of them finds a goal, so which goal
+/// is returned when there are several depends on timing.
+pub fn parallel_dfs<N, E, G>(root: N, expand: E, is_goal: G) -> Option<N>
+where
+    N: Send,
+    E: Fn(&N) -> Vec<N> + Sync,
+    G: Fn(&N) -> bool + Sync,
+{
+    parallel_dfs_to_depth(root, expand, is_goal, DEFAULT_PARALLEL_DEPTH)
+}
+
+/// Like [`parallel_dfs`], but giving up with [`Expired`] once `deadline` passes.
+pub fn parallel_dfs_until<N, E, G>(
+    root: N,
+    expand: E,
+    is_goal: G,
+    deadline: Deadline,
+) -> Result<Option<N>, Expired>
+where
+    N: Send,
+    E: Fn(&N) -> Vec<N> + Sync,
+    G: Fn(&N) -> bool + Sync,
+{
+    parallel_dfs_remembering(root, expand, is_goal, |_| {}, deadline)
+}
+
+/// Like [`parallel_dfs_until`], but calling `exhausted` on every node whose subtree was
+/// searched to the end without finding a goal, like [`BranchAndBound::exhausted`].  A
+/// subtree cut short because a goal was found elsewhere or time ran out doesn't count.
+/// Branches share what they remember only through `exhausted` and `expand`, so both
+/// have to be safe to call from several threads.
+pub fn parallel_dfs_remembering<N, E, G, X>(
+    root: N,
+    expand: E,
+    is_goal: G,
+    exhausted: X,
+    deadline: Deadline,
+) -> Result<Option<N>, Expired>
+where
+    N: Send,
+    E: Fn(&N) -> Vec<N> + Sync,
+    G: Fn(&N) -> bool + Sync,
+    X: Fn(&N) + Sync,
+{
+    let search = Search::new(
+        &expand,
+        &is_goal,
+        &exhausted,
+        DEFAULT_PARALLEL_DEPTH,
+        deadline,
+    );
+    search.run(root)
+}
+
+/// Like [`parallel_dfs`], but splitting the first `parallel_depth` levels into tasks.
+/// A depth of zero searches on the calling thread only.
+pub fn parallel_dfs_to_depth<N, E, G>(
+    root: N,
+    expand: E,
+    is_goal: G,
+    parallel_depth: usize,
+) -> Option<N>
+where
+    N: Send,
+    E: Fn(&N) -> Vec<N> + Sync,
+    G: Fn(&N) -> bool + Sync,
+{
+    let search = Search::new(
+        &expand,
+        &is_goal,
+        &|_: &N| {},
+        parallel_depth,
+        Deadline::never(),
+    );
+    match search.run(root) {
+        Ok(found) => found,
+        Err(Expired) => unreachable!("a deadline that never passes expired"),
+    }
+}
+
+struct Search<'a, E, G, X> {
+    expand: &'a E,
+    is_goal: &'a G,
+    exhausted: &'a X,
+    // Set once any task finds a goal or runs out of time, so the others can give up.
+    found: AtomicBool,
+    expired: AtomicBool,
+    parallel_depth: usize,
+    deadline: Deadline,
+}
+
+impl<'a, E, G, X> Search<'a, E, G, X> {
+    fn new(
+        expand: &'a E,
+        is_goal: &'a G,
+        exhausted: &'a X,
+        parallel_depth: usize,
+        deadline: Deadline,
+    ) -> Self {
+        Search {
+            expand,
+            is_goal,
+            exhausted,
+            found: AtomicBool::new(false),
+            expired: AtomicBool::new(false),
+            parallel_depth,
+            deadline,
+        }
+    }
+
+    fn run<N>(&self, root: N) -> Result<Option<N>, Expired>
+    where
+        N: Send,
+        E: Fn(&N) -> Vec<N> + Sync,
+        G: Fn(&N) -> bool + Sync,
+        X: Fn(&N) + Sync,
+    {
+        let found = self.visit(root, 0);
+        if found.is_none() && self.expired.load(Ordering::Relaxed) {
+            return Err(Expired);
+        }
+        Ok(found)
+    }
+
+    /// Checks the deadline, telling every task to give up if it has passed.
+    fn out_of_time(&self) -> bool {
+        if self.deadline.expired() {
+            self.expired.store(true, Ordering::Relaxed);
+            self.found.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+
+    fn visit<N>(&self, node: N, depth: usize) -> Option<N>
+    where
+        N: Send,
+        E: Fn(&N) -> Vec<N> + Sync,
+        G: Fn(&N) -> bool + Sync,
+        X: Fn(&N) + Sync,
+    {
+        if depth >= self.parallel_depth {
+            return self.visit_sequential(node);
+        }
+        if self.found.load(Ordering::Relaxed) || self.out_of_time() {
+            return None;
+        }
+        if (self.is_goal)(&node) {
+            self.found.store(true, Ordering::Relaxed);
+            return Some(node);
+        }
+        let found = (self.expand)(&node)
+            .into_par_iter()
+            .find_map_any(|child| self.visit(child, depth + 1));
+        // Once any task stops early, the children may not all have been searched.
+        if found.is_none() && !self.found.load(Ordering::Relaxed) {
+            (self.exhausted)(&node);
+        }
+        found
+    }
+
+    /// Searches a subtree with an explicit stack, since deep trees would overflow the
+    /// rayon worker's call stack.
+    fn visit_sequential<N>(&self, root: N) -> Option<N>
+    where
+        E: Fn(&N) -> Vec<N>,
+        G: Fn(&N) -> bool,
+        X: Fn(&N),
+    {
+        struct Frame<N> {
+            node: N,
+            children: std::vec::IntoIter<N>,
+        }
+
+        let mut stack = Vec::<Frame<N>>::new();
+        let mut next = Some(root);
+        let mut visited = 0_usize;
+        loop {
+            if let Some(node) = next.take() {
+                if self.found.load(Ordering::Relaxed) {
+                    return None;
+                }
+                visited += 1;
+                if visited.is_multiple_of(DEADLINE_CHECK_INTERVAL) && self.out_of_time() {
+                    return None;
+                }
+                if (self.is_goal)(&node) {
+                    self.found.store(true, Ordering::Relaxed);
+                    return Some(node);
+                }
+                let children = (self.expand)(&node).into_iter();
+                stack.push(Frame { node, children });
+            }
+
+            let frame = stack.last_mut()?;
+            match frame.children.next() {
+                Some(child) => next = Some(child),
+                None => {
+                    let frame = stack.pop().expect("frame was just looked at");
+                    (self.exhausted)(&frame.node);
+                }
+            }
+        }
+    }
+}
+
+/// An optimization problem searched by [`branch_and_bound`], maximizing a score.
+///
+/// Nodes with a [`score`](BranchAndBound::score) are complete solutions and are not
+/// branched further.  Any other node is pruned when its upper bound can't beat the best
+/// solution so far, or falls below the best lower bound seen.
+pub trait BranchAndBound {
+    type Node;
+    type Score: Ord + Copy;
+
+    /// Gets a node's children, in the order they should be explored.
+    fn branch(&mut self, node: &Self::Node) -> Vec<Self::Node>;
+
+    /// Gets a score that no solution under this node can beat, or None if there is no
+    /// solution under it at all.
+    fn upper_bound(&mut self, node: &Self::Node) -> Option<Self::Score>;
+
+    /// Gets a score that some solution under this node is known to reach, if one is
+    /// cheap to find.  Raising the floor early prunes more of the tree.
+    fn lower_bound(&mut self, _node: &Self::Node) -> Option<Self::Score> {
+        None
+    }
+
+    /// Gets the score of a complete solution, or None if the node isn't one.
+    fn score(&mut self, node: &Self::Node) -> Option<Self::Score>;
+
+    /// Called once every child of a node has been explored or pruned without finding a
+    /// better solution.  A place to remember dead ends.
+    fn exhausted(&mut self, _node: &Self::Node) {}
+
+    /// Takes back a node the search is finished with: pruned, beaten, or fully
+    /// explored.  A place to return the node's buffers to a pool.
+    fn discard(&mut self, _node: Self::Node) {}
+}
+
+/// The best solution a [`BranchAndBound`] search found, with its score.
+pub type Best<P> = Option<(<P as BranchAndBound>::Score, <P as BranchAndBound>::Node)>;
+
+/// Searches depth first for the best scoring solution under `root`.
+///
+/// Ties are broken deterministically: of several solutions with the best score, the
+/// first one in branch order wins.  The search stops early once a solution reaches the
+/// root's upper bound, since nothing can beat it.
+pub fn branch_and_bound<P: BranchAndBound>(
+    problem: &mut P,
+    root: P::Node,
+) -> Option<(P::Score, P::Node)> {
+    match branch_and_bound_until(problem, root, Deadline::never()) {
+        Ok(best) => best,
+        Err(Expired) => unreachable!("a deadline that never passes expired"),
+    }
+}
+
+/// Like [`branch_and_bound`], but giving up with [`Expired`] once `deadline` passes,
+/// even if a solution has been found, since it may not be the best.
+pub fn branch_and_bound_until<P: BranchAndBound>(
+    problem: &mut P,
+    root: P::Node,
+    deadline: Deadline,
+) -> Result<Best<P>, Expired> {
+    struct Frame<N> {
+        node: N,
+        children: std::vec::IntoIter<N>,
+        // Did this subtree improve the best solution?
+        improved: bool,
+    }
+
+    let Some(ceiling) = problem.upper_bound(&root) else {
+        return Ok(None);
+    };
+    let mut best: Option<(P::Score, P::Node)> = None;
+    let mut floor: Option<P::Score> = None;
+    let mut stack = Vec::<Frame<P::Node>>::new();
+    let mut next = Some(root);
+    let mut visited = 0_usize;
+    loop {
+        if let Some(node) = next.take() {
+            visited += 1;
+            if visited.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+                deadline.check()?;
+            }
+            let bound = problem.upper_bound(&node);
+            let pruned = bound.is_none_or(|bound| {
+                best.as_ref().is_some_and(|(score, _)| bound <= *score)
+                    || floor.is_some_and(|floor| bound < floor)
+            });
+            if pruned {
+                // Skipped without exploring, so there is nothing to mark as exhausted.
+                problem.discard(node);
+            } else if let Some(score) = problem.score(&node) {
+                if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                    if let Some((_, beaten)) = best.replace((score, node)) {
+                        problem.discard(beaten);
+                    }
+                    stack.iter_mut().for_each(|frame| frame.improved = true);
+                    if score >= ceiling {
+                        return Ok(best);
+                    }
+                } else {
+                    problem.discard(node);
+                }
+            } else {
+                if let Some(lower) = problem.lower_bound(&node) {
+                    floor = floor.max(Some(lower));
+                }
+                let children = problem.branch(&node).into_iter();
+                stack.push(Frame {
+                    node,
+                    children,
+                    improved: false,
+                });
+            }
+        }
+
+        let Some(frame) = stack.last_mut() else {
+            return Ok(best);
+        };
+        match frame.children.next() {
+            Some(child) => next = Some(child),
+            None => {
+                let frame = stack.pop().expect("frame was just looked at");
+                if !frame.improved {
+                    problem.exhausted(&frame.node);
+                }
+                problem.discard(frame.node);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Subset sum: each node is (next item, total so far).
+    fn subset_sum(items: &[u64], target: u64, parallel_depth: usize) -> Option<(usize, u64)> {
+        parallel_dfs_to_depth(
+            (0, 0),
+            |&(next, total)| {
+                if next == items.len() || total > target {
+                    return Vec::new();
+                }
+                vec![(next + 1, total + items[next]), (next + 1, total)]
+            },
+            |&(_, total)| total == target,
+            parallel_depth,
+        )
+    }
+
+    #[test]
+    fn test_finds_goal() {
+        let items = [3, 34, 4, 12, 5, 2];
+        for depth in [0, 2, 10] {
+            assert_eq!(
+                subset_sum(&items, 9, depth).map(|(_, total)| total),
+                Some(9)
+            );
+            assert_eq!(subset_sum(&items, 30, depth), None);
+        }
+    }
+
+    #[test]
+    fn test_sequential_order() {
+        // With no parallel levels, the first child is always explored first.
+        assert_eq!(subset_sum(&[1, 2, 3], 3, 0), Some((2, 3)));
+    }
+
+    #[test]
+    fn test_deep_tree() {
+        let found = parallel_dfs(
+            0_u32,
+            |&n| {
+                if n < 1_000_000 {
+                    vec![n + 1]
+                } else {
+                    Vec::new()
+                }
+            },
+            |&n| n == 1_000_000,
+        );
+        assert_eq!(found, Some(1_000_000));
+    }
+
+    #[test]
+    fn test_remembers_exhausted_nodes() {
+        use std::sync::Mutex;
+
+        // Counting up from 0 by 1 or 2 to exactly 5, with dead ends past it.
+        let expand = |&n: &u32| {
+            if n < 5 {
+                vec![n + 1, n + 2]
+            } else {
+                Vec::new()
+            }
+        };
+        let exhausted = Mutex::new(Vec::new());
+        let found = parallel_dfs_remembering(
+            0,
+            expand,
+            |_| false,
+            |&n| exhausted.lock().unwrap().push(n),
+            Deadline::never(),
+        );
+        assert_eq!(found, Ok(None));
+        // Nothing was found, so every node reached was exhausted, the root included.
+        let exhausted = exhausted.into_inner().unwrap();
+        assert!(exhausted.contains(&0) && exhausted.contains(&6));
+
+        let exhausted = Mutex::new(Vec::new());
+        let found = parallel_dfs_remembering(
+            0,
+            expand,
+            |&n| n == 5,
+            |&n| exhausted.lock().unwrap().push(n),
+            Deadline::never(),
+        );
+        assert_eq!(found, Ok(Some(5)));
+        // The goal's ancestors weren't searched to the end.
+        assert!(!exhausted.into_inner().unwrap().contains(&0));
+    }
+
+    #[test]
+    fn test_deadline() {
+        // An endless tree, so only the deadline can stop the search.
+        let expand = |&n: &u64| vec![n + 1, n + 2];
+        let expired = Deadline::after(std::time::Duration::ZERO);
+        assert_eq!(
+            parallel_dfs_until(0, expand, |_| false, expired),
+            Err(Expired)
+        );
+        let found = parallel_dfs_until(0, expand, |&n| n == 3, Deadline::never());
+        assert_eq!(found, Ok(Some(3)));
+
+        let mut problem = Knapsack {
+            items: vec![(1, 1); 40],
+            capacity: 20,
+        };
+        let until = branch_and_bound_until(&mut problem, (0, 0, 0), expired);
+        assert_eq!(until, Err(Expired));
+    }
+
+    /// 0/1 knapsack: each node is (next item, weight, value).
+    struct Knapsack {
+        items: Vec<(u32, u32)>,
+        capacity: u32,
+    }
+    impl BranchAndBound for Knapsack {
+        type Node = (usize, u32, u32);
+        type Score = u32;
+        fn branch(&mut self, &(next, weight, value): &Self::Node) -> Vec<Self::Node> {
+            let (w, v) = self.items[next];
+            let mut children = Vec::new();
+            if weight + w <= self.capacity {
+                children.push((next + 1, weight + w, value + v));
+            }
+            children.push((next + 1, weight, value));
+            children
+        }
+        fn upper_bound(&mut self, &(next, _, value): &Self::Node) -> Option<u32> {
+            // Take every item that is left.
+            Some(value + self.items[next..].iter().map(|(_, v)| v).sum::<u32>())
+        }
+        fn score(&mut self, &(next, _, value): &Self::Node) -> Option<u32> {
+            (next == self.items.len()).then_some(value)
+        }
+    }
+
+    #[test]
+    fn test_discards_every_node_but_the_best() {
+        /// Counts the nodes a knapsack search hands back.
+        struct Counted {
+            knapsack: Knapsack,
+            branched: usize,
+            discarded: usize,
+        }
+        impl BranchAndBound for Counted {
+            type Node = (usize, u32, u32);
+            type Score = u32;
+            fn branch(&mut self, node: &Self::Node) -> Vec<Self::Node> {
+                let children = self.knapsack.branch(node);
+                self.branched += children.len();
+                children
+            }
+            fn upper_bound(&mut self, node: &Self::Node) -> Option<u32> {
+                self.knapsack.upper_bound(node)
+            }
+            fn score(&mut self, node: &Self::Node) -> Option<u32> {
+                self.knapsack.score(node)
+            }
+            fn discard(&mut self, _node: Self::Node) {
+                self.discarded += 1;
+            }
+        }
+
+        let mut problem = Counted {
+            knapsack: Knapsack {
+                items: vec![(5, 10), (4, 40), (6, 30), (3, 50)],
+                capacity: 10,
+            },
+            branched: 0,
+            discarded: 0,
+        };
+        let best = branch_and_bound(&mut problem, (0, 0, 0));
+        assert_eq!(best.map(|(score, _)| score), Some(90));
+        // 90 is under the root's bound, so the whole tree is searched, and every node
+        // but the best comes back: the root and every child, less one.
+        assert_eq!(problem.discarded, problem.branched);
+    }
+
+    #[test]
+    fn test_knapsack() {
+        let mut problem = Knapsack {
+            items: vec![(5, 10), (4, 40), (6, 30), (3, 50)],
+            capacity: 10,
+        };
+        let (score, (_, weight, _)) = branch_and_bound(&mut problem, (0, 0, 0)).unwrap();
+        assert_eq!((score, weight), (90, 7));
+    }
+
+    #[test]
+    fn test_ties_keep_first_solution() {
+        // Both items are worth the same, and only one fits.
+        let mut problem = Knapsack {
+            items: vec![(5, 10), (5, 10)],
+            capacity: 5,
+        };
+        let (score, node) = branch_and_bound(&mut problem, (0, 0, 0)).unwrap();
+        assert_eq!((score, node), (10, (2, 5, 10)));
+    }
+}
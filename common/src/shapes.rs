@@ -0,0 +1,82 @@
+//! Shapes made of grid cells, compared regardless of where they are or which way
+//! round they lie, like the pieces of a tetromino or pentomino puzzle.
+
+use crate::grid::{Transform, XY};
+
+/// Moves the cells so the smallest x and y are zero, and sorts them in row-major order.
+pub fn translate_to_origin(cells: &[XY]) -> Vec<XY> {
+    let min_x = cells.iter().map(|xy| xy.x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|xy| xy.y).min().unwrap_or(0);
+    let mut moved = cells
+        .iter()
+        .map(|xy| XY::new(xy.x - min_x, xy.y - min_y))
+        .collect::<Vec<_>>();
+    moved.sort_by_key(|xy| (xy.y, xy.x));
+    moved
+}
+
+/// Gets the normal form of a shape: of its eight rotations and reflections moved to
+/// the origin, the one whose row-major cells come first.  Two shapes are the same
+/// piece exactly when their normal forms are equal.  Repeated cells are kept.
+pub fn normalize(cells: &[XY]) -> Vec<XY> {
+    let moved = translate_to_origin(cells);
+    let width = moved.iter().map(|xy| xy.x + 1).max().unwrap_or(0);
+    let height = moved.iter().map(|xy| xy.y + 1).max().unwrap_or(0);
+    Transform::ALL
+        .into_iter()
+        .map(|transform| {
+            let turned = moved
+                .iter()
+                .map(|xy| transform.apply_to_xy(xy, width, height))
+                .collect::<Vec<_>>();
+            translate_to_origin(&turned)
+        })
+        .min_by_key(|shape| shape.iter().map(|xy| (xy.y, xy.x)).collect::<Vec<_>>())
+        .unwrap_or_default()
+}
+
+/// Hashes a shape's normal form, so every orientation of a piece hashes the same.  The
+/// hash is the same across builds and platforms, so it can be saved or compared with a
+/// known piece's.
+pub fn shape_hash(cells: &[XY]) -> u64 {
+    let text = normalize(cells)
+        .iter()
+        .map(|xy| format!("{},{}", xy.x, xy.y))
+        .collect::<Vec<_>>()
+        .join(";");
+    crate::cache::content_hash(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(coordinates: &[(usize, usize)]) -> Vec<XY> {
+        coordinates.iter().map(|(x, y)| XY::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        // An L tetromino, as drawn and then turned, flipped and moved.
+        let l = cells(&[(0, 0), (0, 1), (0, 2), (1, 2)]);
+        let turned = cells(&[(5, 7), (6, 7), (7, 7), (5, 8)]);
+        let flipped = cells(&[(3, 0), (3, 1), (3, 2), (2, 2)]);
+        assert_eq!(normalize(&l), normalize(&turned));
+        assert_eq!(normalize(&l), normalize(&flipped));
+        assert_eq!(normalize(&l), cells(&[(0, 0), (1, 0), (2, 0), (0, 1)]));
+        assert_eq!(shape_hash(&l), shape_hash(&turned));
+
+        let s = cells(&[(1, 0), (2, 0), (0, 1), (1, 1)]);
+        assert_ne!(normalize(&l), normalize(&s));
+        assert_ne!(shape_hash(&l), shape_hash(&s));
+        assert!(normalize(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_translate_to_origin() {
+        assert_eq!(
+            translate_to_origin(&cells(&[(4, 3), (3, 4)])),
+            cells(&[(1, 0), (0, 1)])
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! A vector that keeps its first few items inline, for the tiny lists hot loops clone
+//! and throw away.  With the `smallvec` feature, up to `N` items live in the value
+//! itself and only longer lists allocate.  Without it, every list is an ordinary `Vec`,
+//! which makes it easy to measure what the inline storage buys.
+
+use std::ops::{Deref, DerefMut};
+
+/// A vector holding up to `N` items inline.  Items are `Copy` so the inline buffer can
+/// be filled with defaults instead of tracking which slots are initialized.
+#[derive(Clone)]
+pub struct SmallVec<T: Copy + Default, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+#[derive(Clone)]
+enum Storage<T, const N: usize> {
+    Inline { len: usize, items: [T; N] },
+    Heap(Vec<T>),
+}
+
+impl<T: Copy + Default, const N: usize> SmallVec<T, N> {
+    pub fn new() -> Self {
+        let storage = if cfg!(feature = "smallvec") {
+            Storage::Inline {
+                len: 0,
+                items: [T::default(); N],
+            }
+        } else {
+            Storage::Heap(Vec::new())
+        };
+        SmallVec { storage }
+    }
+
+    /// A vector of `len` copies of `value`.
+    pub fn from_elem(value: T, len: usize) -> Self {
+        let mut small = Self::new();
+        small.extend(std::iter::repeat_n(value, len));
+        small
+    }
+
+    pub fn push(&mut self, value: T) {
+        match &mut self.storage {
+            Storage::Inline { len, items } if *len < N => {
+                items[*len] = value;
+                *len += 1;
+            }
+            Storage::Inline { len, items } => {
+                // Out of inline room: move to the heap for good.
+                let mut heap = Vec::with_capacity(*len * 2 + 1);
+                heap.extend_from_slice(&items[..*len]);
+                heap.push(value);
+                self.storage = Storage::Heap(heap);
+            }
+            Storage::Heap(heap) => heap.push(value),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline { len, items } => {
+                *len = len.checked_sub(1)?;
+                Some(items[*len])
+            }
+            Storage::Heap(heap) => heap.pop(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.storage {
+            Storage::Inline { len, .. } => *len = 0,
+            Storage::Heap(heap) => heap.clear(),
+        }
+    }
+
+    /// Whether the items have moved to the heap.
+    pub fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            Storage::Inline { len, items } => &items[..*len],
+            Storage::Heap(heap) => heap,
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            Storage::Inline { len, items } => &mut items[..*len],
+            Storage::Heap(heap) => heap,
+        }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for SmallVec<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+impl<T: Copy + Default, const N: usize> DerefMut for SmallVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Extend<T> for SmallVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            self.push(item);
+        }
+    }
+}
+impl<T: Copy + Default, const N: usize> FromIterator<T> for SmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut small = Self::new();
+        small.extend(items);
+        small
+    }
+}
+impl<T: Copy + Default, const N: usize> From<Vec<T>> for SmallVec<T, N> {
+    fn from(items: Vec<T>) -> Self {
+        if items.len() > N || !cfg!(feature = "smallvec") {
+            SmallVec {
+                storage: Storage::Heap(items),
+            }
+        } else {
+            items.into_iter().collect()
+        }
+    }
+}
+impl<'a, T: Copy + Default, const N: usize> IntoIterator for &'a SmallVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Compared and hashed as slices, wherever the items are stored.
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for SmallVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl<T: Copy + Default + Eq, const N: usize> Eq for SmallVec<T, N> {}
+impl<T: Copy + Default + std::hash::Hash, const N: usize> std::hash::Hash for SmallVec<T, N> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+impl<T: Copy + Default + std::fmt::Debug, const N: usize> std::fmt::Debug for SmallVec<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_past_inline() {
+        let mut small = SmallVec::<u32, 2>::new();
+        small.push(1);
+        small.push(2);
+        assert_eq!(small.spilled(), !cfg!(feature = "smallvec"));
+        small.push(3);
+        assert!(small.spilled());
+        assert_eq!(small.as_slice(), &[1, 2, 3]);
+        assert_eq!(small.pop(), Some(3));
+        small[0] = 5;
+        assert_eq!(small.iter().sum::<u32>(), 7);
+    }
+
+    #[test]
+    fn test_equal_wherever_stored() {
+        let inline = [1_u8, 2].into_iter().collect::<SmallVec<u8, 4>>();
+        let mut heap = SmallVec::<u8, 4>::from(vec![9; 5]);
+        heap.clear();
+        heap.extend([1, 2]);
+        assert_eq!(inline, heap);
+        let state = std::hash::RandomState::new();
+        assert_eq!(
+            std::hash::BuildHasher::hash_one(&state, &inline),
+            std::hash::BuildHasher::hash_one(&state, &heap)
+        );
+        assert_eq!(format!("{:?}", inline), "[1, 2]");
+        assert_eq!(SmallVec::<u8, 4>::from_elem(0, 3).len(), 3);
+    }
+}
@@ -0,0 +1,130 @@
+//! Summary statistics and text histograms, for benchmark timings and for getting a feel
+//! for a puzzle input.
+
+/// The spread of some values.  Percentiles use the nearest rank, so they are always one
+/// of the values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+impl Summary {
+    /// Summarizes the values, or gives `None` if there are none.  Values are ordered
+    /// with [`f64::total_cmp`].
+    // Not `FromIterator`, since there is no summary of nothing.
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut values = values.into_iter().collect::<Vec<_>>();
+        values.sort_by(f64::total_cmp);
+        let count = values.len();
+        let (min, max) = (*values.first()?, *values.last()?);
+        let median = if count % 2 == 0 {
+            (values[count / 2 - 1] + values[count / 2]) / 2.0
+        } else {
+            values[count / 2]
+        };
+        Some(Self {
+            count,
+            min,
+            max,
+            mean: values.iter().sum::<f64>() / count as f64,
+            median,
+            p95: nearest_rank(&values, 95),
+        })
+    }
+}
+
+/// Gets the `percent`th percentile of sorted, non-empty values.
+fn nearest_rank(sorted: &[f64], percent: usize) -> f64 {
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "n={} min={:.3} max={:.3} mean={:.3} median={:.3} p95={:.3}",
+            self.count, self.min, self.max, self.mean, self.median, self.p95
+        )
+    }
+}
+
+/// Draws a histogram of the values with `buckets` equal-width buckets between the
+/// smallest and largest value, one line per bucket, like `[1.000, 2.500) ### 3` with the bounds padded.
+/// The longest bar is `width` characters.  Empty input draws nothing.
+pub fn histogram(values: impl IntoIterator<Item = f64>, buckets: usize, width: usize) -> String {
+    let values = values.into_iter().collect::<Vec<_>>();
+    let Some(summary) = Summary::of(values.iter().copied()) else {
+        return String::new();
+    };
+    let buckets = buckets.max(1);
+    let bucket_width = (summary.max - summary.min) / buckets as f64;
+    let mut counts = vec![0_usize; buckets];
+    for value in &values {
+        let bucket = if bucket_width > 0.0 {
+            ((value - summary.min) / bucket_width) as usize
+        } else {
+            0
+        };
+        // The largest value belongs in the last bucket rather than one of its own.
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+
+    let tallest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut out = String::new();
+    for (i, count) in counts.iter().enumerate() {
+        let low = summary.min + bucket_width * i as f64;
+        let (high, close) = if i + 1 == buckets {
+            (summary.max, ']')
+        } else {
+            (low + bucket_width, ')')
+        };
+        out.push_str(&format!(
+            "[{:>12.3}, {:>12.3}{} {} {}\n",
+            low,
+            high,
+            close,
+            "#".repeat(count * width / tallest),
+            count
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::of((1..=20).map(f64::from)).unwrap();
+        assert_eq!(summary.count, 20);
+        assert_eq!((summary.min, summary.max), (1.0, 20.0));
+        assert_eq!(summary.mean, 10.5);
+        assert_eq!(summary.median, 10.5);
+        assert_eq!(summary.p95, 19.0);
+        let single = Summary::of([4.0]).unwrap();
+        assert_eq!((single.median, single.p95), (4.0, 4.0));
+        assert_eq!(Summary::of([]), None);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let drawn = histogram([1.0, 2.0, 2.0, 3.0, 5.0], 2, 4);
+        assert_eq!(
+            drawn,
+            "[       1.000,        3.000) #### 3\n\
+             [       3.000,        5.000] ## 2\n"
+        );
+        assert_eq!(
+            histogram([7.0, 7.0], 3, 2).lines().next(),
+            Some("[       7.000,        7.000) ## 2")
+        );
+        assert_eq!(histogram([], 3, 10), "");
+    }
+}
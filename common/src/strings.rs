@@ -0,0 +1,109 @@
+//! String helpers for puzzles that look at characters rather than parsed values.
+//! Widths and positions count chars, not bytes.
+
+use std::collections::BTreeMap;
+
+/// Counts how often each char appears.
+pub fn char_counts(s: &str) -> BTreeMap<char, usize> {
+    let mut counts = BTreeMap::new();
+    for c in s.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Gives every run of `n` consecutive chars as a slice of `s`, without allocating.
+/// There are none if `s` is shorter than `n` or `n` is zero.
+pub fn windows(s: &str, n: usize) -> impl Iterator<Item = &str> {
+    let mut starts = s.char_indices().map(|(offset, _)| offset);
+    let mut ends = s
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain([s.len()])
+        .skip(n);
+    std::iter::from_fn(move || {
+        if n == 0 {
+            return None;
+        }
+        Some(&s[starts.next()?..ends.next()?])
+    })
+}
+
+/// Checks whether `s` is made of copies of its first `k` chars, like `"abab"` for a
+/// `k` of 2.  A single copy counts, so any non-empty `s` is a repeat of itself.
+pub fn is_repeated_block(s: &str, k: usize) -> bool {
+    let length = s.chars().count();
+    if k == 0 || length == 0 || !length.is_multiple_of(k) {
+        return false;
+    }
+    let mut blocks = split_fixed_width(s, std::iter::repeat(k));
+    let first = blocks.next();
+    blocks.all(|block| Some(block) == first)
+}
+
+/// Splits `s` into consecutive fields of the given widths.  Stops when `s` runs out,
+/// so the last field may be short, and ignores whatever is past the last width.
+pub fn split_fixed_width(
+    s: &str,
+    widths: impl IntoIterator<Item = usize>,
+) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    widths.into_iter().map_while(move |width| {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest
+            .char_indices()
+            .nth(width)
+            .map_or(rest.len(), |(offset, _)| offset);
+        let (field, after) = rest.split_at(end);
+        rest = after;
+        Some(field)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_counts() {
+        let counts = char_counts("abracadabra");
+        assert_eq!(counts[&'a'], 5);
+        assert_eq!(counts.get(&'z'), None);
+        assert_eq!(counts.keys().collect::<String>(), "abcdr");
+    }
+
+    #[test]
+    fn test_windows() {
+        assert_eq!(
+            windows("abcd", 2).collect::<Vec<_>>(),
+            vec!["ab", "bc", "cd"]
+        );
+        assert_eq!(windows("héllo", 3).next(), Some("hél"));
+        assert_eq!(windows("abc", 3).count(), 1);
+        assert_eq!(windows("abc", 4).count(), 0);
+        assert_eq!(windows("abc", 0).count(), 0);
+    }
+
+    #[test]
+    fn test_is_repeated_block() {
+        assert!(is_repeated_block("1212", 2));
+        assert!(is_repeated_block("111", 1));
+        assert!(is_repeated_block("123", 3));
+        assert!(!is_repeated_block("1213", 2));
+        assert!(!is_repeated_block("12121", 2));
+        assert!(!is_repeated_block("", 1));
+        assert!(!is_repeated_block("11", 0));
+    }
+
+    #[test]
+    fn test_split_fixed_width() {
+        let fields = split_fixed_width("1234567", [3, 3, 3]).collect::<Vec<_>>();
+        assert_eq!(fields, vec!["123", "456", "7"]);
+        let fields = split_fixed_width("ab cd", [2, 1, 9]).collect::<Vec<_>>();
+        assert_eq!(fields, vec!["ab", " ", "cd"]);
+        assert_eq!(split_fixed_width("abc", [1]).collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(split_fixed_width("", [1]).count(), 0);
+    }
+}
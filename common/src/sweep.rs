@@ -0,0 +1,197 @@
+//! Sweep lines: visit events in position order while keeping track of what is active
+//! at the current position.
+//!
+//! Push every event with its position into a [`SweepLine`], then walk the positions
+//! with [`SweepLine::batches`], applying each batch to an [`ActiveSet`] before looking
+//! at what is active.  Events at the same position come as one batch, so a start and
+//! an end at the same place never leave a gap in between.
+
+use std::collections::BTreeMap;
+
+/// Something joining or leaving the active set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<T> {
+    Insert(T),
+    Remove(T),
+}
+
+/// Events waiting to be swept, in any order.
+#[derive(Debug, Clone)]
+pub struct SweepLine<P, E> {
+    events: Vec<(P, E)>,
+}
+
+impl<P, E> Default for SweepLine<P, E> {
+    fn default() -> Self {
+        SweepLine { events: Vec::new() }
+    }
+}
+
+impl<P: Ord, E> SweepLine<P, E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event at a position.
+    pub fn push(&mut self, position: P, event: E) {
+        self.events.push((position, event));
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Gets each position that has events, in ascending order, with its events in the
+    /// order they were pushed.
+    pub fn batches(mut self) -> impl Iterator<Item = (P, Vec<E>)> {
+        // Stable, so events at the same position keep their push order.
+        self.events.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut events = self.events.into_iter().peekable();
+        std::iter::from_fn(move || {
+            let (position, first) = events.next()?;
+            let mut batch = vec![first];
+            while let Some((_, event)) = events.next_if(|(next, _)| *next == position) {
+                batch.push(event);
+            }
+            Some((position, batch))
+        })
+    }
+}
+
+/// What is active at the sweep's current position, in order.  The same value may be
+/// active more than once, like two edges at the same height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveSet<T> {
+    counts: BTreeMap<T, usize>,
+    len: usize,
+}
+
+impl<T> Default for ActiveSet<T> {
+    fn default() -> Self {
+        ActiveSet {
+            counts: BTreeMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T: Ord> ActiveSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, value: T) {
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.len += 1;
+    }
+
+    /// Removes one copy of a value.  Returns false if it wasn't active.
+    pub fn remove(&mut self, value: &T) -> bool {
+        let Some(count) = self.counts.get_mut(value) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.counts.remove(value);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Inserts or removes a value.  Removing a value that isn't active does nothing.
+    pub fn apply(&mut self, event: Event<T>) {
+        match event {
+            Event::Insert(value) => self.insert(value),
+            Event::Remove(value) => {
+                self.remove(&value);
+            }
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.counts.contains_key(value)
+    }
+
+    /// Gets the number of active values, counting repeats.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the active values in ascending order, with repeats.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.counts
+            .iter()
+            .flat_map(|(value, count)| std::iter::repeat_n(value, *count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches() {
+        let mut sweep = SweepLine::new();
+        sweep.push(5, 'a');
+        sweep.push(1, 'b');
+        sweep.push(5, 'c');
+        sweep.push(3, 'd');
+        assert_eq!(sweep.len(), 4);
+        assert_eq!(
+            sweep.batches().collect::<Vec<_>>(),
+            vec![(1, vec!['b']), (3, vec!['d']), (5, vec!['a', 'c'])]
+        );
+        assert_eq!(SweepLine::<u32, ()>::new().batches().count(), 0);
+    }
+
+    #[test]
+    fn test_active_set() {
+        let mut active = ActiveSet::new();
+        active.apply(Event::Insert(4));
+        active.apply(Event::Insert(2));
+        active.apply(Event::Insert(4));
+        assert_eq!(active.iter().collect::<Vec<_>>(), vec![&2, &4, &4]);
+        assert_eq!(active.len(), 3);
+        assert!(active.remove(&4));
+        assert!(active.contains(&4));
+        assert!(!active.remove(&7));
+        active.apply(Event::Remove(4));
+        active.apply(Event::Remove(2));
+        assert!(active.is_empty());
+        assert!(!active.contains(&4));
+    }
+
+    #[test]
+    fn test_coverage() {
+        // The total length covered by some half-open intervals.
+        let intervals = [(0, 4), (2, 6), (8, 9), (9, 12)];
+        let mut sweep = SweepLine::new();
+        for (i, (start, end)) in intervals.into_iter().enumerate() {
+            sweep.push(start, Event::Insert(i));
+            sweep.push(end, Event::Remove(i));
+        }
+        let mut active = ActiveSet::new();
+        let mut covered = 0;
+        let mut since = None;
+        for (position, events) in sweep.batches() {
+            events.into_iter().for_each(|event| active.apply(event));
+            match (since, active.is_empty()) {
+                (None, false) => since = Some(position),
+                (Some(start), true) => {
+                    covered += position - start;
+                    since = None;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(covered, 6 + 4);
+    }
+}
@@ -0,0 +1,97 @@
+//! Picking the k best items without sorting everything.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Gets the `k` items with the largest keys, largest first.  Items with equal keys keep
+/// their input order.  Only `k` items are held at once.
+pub fn top_k_by_key<T, K: Ord>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    key: impl Fn(&T) -> K,
+) -> Vec<T> {
+    best_k(items, k, |index, item| (key(item), Reverse(index)))
+}
+
+/// Gets the `k` items with the smallest keys, smallest first.  Items with equal keys
+/// keep their input order.  Only `k` items are held at once.
+pub fn smallest_k_by_key<T, K: Ord>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    key: impl Fn(&T) -> K,
+) -> Vec<T> {
+    best_k(items, k, |index, item| (Reverse(key(item)), Reverse(index)))
+}
+
+/// An item ordered by its rank alone.
+struct Ranked<R, T> {
+    rank: R,
+    item: T,
+}
+impl<R: Ord, T> PartialEq for Ranked<R, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+impl<R: Ord, T> Eq for Ranked<R, T> {}
+impl<R: Ord, T> PartialOrd for Ranked<R, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<R: Ord, T> Ord for Ranked<R, T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Keeps the `k` highest ranked items in a min-heap, so the worst of them is the one
+/// replaced when a better item comes along.
+fn best_k<T, R: Ord>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    rank: impl Fn(usize, &T) -> R,
+) -> Vec<T> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap = BinaryHeap::<Reverse<Ranked<R, T>>>::with_capacity(k);
+    for (index, item) in items.into_iter().enumerate() {
+        let ranked = Ranked {
+            rank: rank(index, &item),
+            item,
+        };
+        if heap.len() < k {
+            heap.push(Reverse(ranked));
+        } else if let Some(mut worst) = heap.peek_mut()
+            && ranked > worst.0
+        {
+            *worst = Reverse(ranked);
+        }
+    }
+    // Ascending order of Reverse is best first.
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k() {
+        let values = [5, 1, 9, 3, 9, 7];
+        assert_eq!(top_k_by_key(values, 3, |v| *v), vec![9, 9, 7]);
+        assert_eq!(smallest_k_by_key(values, 2, |v| *v), vec![1, 3]);
+        assert_eq!(top_k_by_key(values, 10, |v| *v), vec![9, 9, 7, 5, 3, 1]);
+        assert_eq!(top_k_by_key(values, 0, |v| *v), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn test_ties_keep_input_order() {
+        let words = ["bb", "a", "cc", "dd", "e"];
+        assert_eq!(top_k_by_key(words, 2, |w| w.len()), vec!["bb", "cc"]);
+        assert_eq!(smallest_k_by_key(words, 2, |w| w.len()), vec!["a", "e"]);
+    }
+}
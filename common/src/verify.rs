@@ -0,0 +1,157 @@
+//! Cross-checking the answer log against the answers the puzzle page reveals.
+//!
+//! Once a part is solved, its puzzle page shows the accepted answer as
+//! `Your puzzle answer was <code>1600</code>.`, part 1 first.  A wrong answer in the
+//! log, like one pasted from a run before a fix, shows up as a mismatch here instead of
+//! going unnoticed until the next refactor is checked against it.
+
+use crate::history::History;
+
+/// The accepted answers shown on a puzzle page, in part order.  Parts that aren't
+/// solved yet aren't shown, so there are at most two.
+pub fn revealed_answers(page: &str) -> Vec<String> {
+    const PREFIX: &str = "Your puzzle answer was <code>";
+    let mut answers = Vec::new();
+    let mut rest = page;
+    while let Some(start) = rest.find(PREFIX) {
+        rest = &rest[start + PREFIX.len()..];
+        let Some(end) = rest.find("</code>") else {
+            break;
+        };
+        answers.push(unescape_html(&rest[..end]));
+        rest = &rest[end..];
+    }
+    answers
+}
+
+/// How one part's revealed answer compares with the latest one in the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Matches {
+        part: u32,
+        answer: String,
+    },
+    Differs {
+        part: u32,
+        revealed: String,
+        logged: String,
+    },
+    NotLogged {
+        part: u32,
+        revealed: String,
+    },
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Check::Matches { .. })
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Matches { part, answer } => write!(f, "Part {}: {} matches", part, answer),
+            Check::Differs {
+                part,
+                revealed,
+                logged,
+            } => write!(
+                f,
+                "Part {}: the log has {} but the accepted answer is {}",
+                part, logged, revealed
+            ),
+            Check::NotLogged { part, revealed } => write!(
+                f,
+                "Part {}: the accepted answer is {} but the log has no answer",
+                part, revealed
+            ),
+        }
+    }
+}
+
+/// Checks each revealed answer for a day against its latest logged answer.
+pub fn verify(day: u32, page: &str, history: &History) -> Vec<Check> {
+    (1..)
+        .zip(revealed_answers(page))
+        .map(|(part, revealed)| match history.latest_answer(day, part) {
+            Some(logged) if logged == revealed => Check::Matches {
+                part,
+                answer: revealed,
+            },
+            Some(logged) => Check::Differs {
+                part,
+                revealed,
+                logged: logged.to_string(),
+            },
+            None => Check::NotLogged { part, revealed },
+        })
+        .collect()
+}
+
+/// Undoes the escaping the page uses inside `<code>`.
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::history::Run;
+
+    const PAGE: &str = "<main><article class=\"day-desc\">...</article>\
+        <p>Your puzzle answer was <code>1600</code>.</p>\
+        <article class=\"day-desc\">...</article>\
+        <p>Your puzzle answer was <code>8632253783011</code>.</p>\
+        <p class=\"day-success\">Both parts of this puzzle are complete!</p></main>";
+
+    #[test]
+    fn test_revealed_answers() {
+        assert_eq!(revealed_answers(PAGE), vec!["1600", "8632253783011"]);
+        assert_eq!(
+            revealed_answers("<p>Your puzzle answer was <code>a&lt;b&amp;c</code>.</p>"),
+            vec!["a<b&c"]
+        );
+        assert!(revealed_answers("<main>To begin, get your puzzle input.</main>").is_empty());
+    }
+
+    #[test]
+    fn test_verify() {
+        let run = |part, answer| Run {
+            day: 7,
+            part,
+            answer: String::from(answer),
+            timestamp: 0,
+            elapsed: Duration::ZERO,
+            peak_kib: None,
+        };
+        let history = History {
+            runs: vec![run(1, "1599"), run(1, "1600"), run(2, "8632253783010")],
+        };
+        let checks = verify(7, PAGE, &history);
+        assert_eq!(
+            checks
+                .iter()
+                .map(|check| check.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "Part 1: 1600 matches",
+                "Part 2: the log has 8632253783010 but the accepted answer is 8632253783011",
+            ]
+        );
+        assert!(checks[0].is_ok() && !checks[1].is_ok());
+        assert_eq!(
+            verify(8, PAGE, &history)[0],
+            Check::NotLogged {
+                part: 1,
+                revealed: "1600".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+
+/// Watches files and directories for changes by polling their modification times.
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    snapshot: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    /// Starts watching the paths.  Directories are watched recursively.
+    pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Result<Self> {
+        let paths = paths.into_iter().map(Into::into).collect::<Vec<_>>();
+        let snapshot = snapshot(&paths)?;
+        Ok(Self { paths, snapshot })
+    }
+
+    /// Gets the files that were added, removed, or modified since the last poll.
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>> {
+        let snapshot = snapshot(&self.paths)?;
+        let mut changed = snapshot
+            .iter()
+            .filter(|(path, modified)| self.snapshot.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        changed.extend(
+            self.snapshot
+                .keys()
+                .filter(|path| !snapshot.contains_key(*path))
+                .cloned(),
+        );
+        self.snapshot = snapshot;
+        Ok(changed)
+    }
+}
+
+/// Gets the modification time of every file under the paths.  Missing paths are skipped,
+/// so a file that is deleted and recreated shows up as a change rather than an error.
+fn snapshot(paths: &[PathBuf]) -> Result<BTreeMap<PathBuf, SystemTime>> {
+    let mut files = BTreeMap::new();
+    for path in paths {
+        collect(path, &mut files)?;
+    }
+    Ok(files)
+}
+
+fn collect(path: &Path, files: &mut BTreeMap<PathBuf, SystemTime>) -> Result<()> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(());
+    };
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect(&entry?.path(), files)?;
+        }
+    } else {
+        files.insert(path.to_path_buf(), metadata.modified()?);
+    }
+    Ok(())
+}
+
+/// One line of a run's output compared with the previous run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineChange<'a> {
+    Same(&'a str),
+    Changed { before: &'a str, after: &'a str },
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+/// Compares two runs' output line by line.
+pub fn diff_lines<'a>(before: &'a str, after: &'a str) -> Vec<LineChange<'a>> {
+    let (mut before, mut after) = (before.lines(), after.lines());
+    let mut changes = Vec::new();
+    loop {
+        let change = match (before.next(), after.next()) {
+            (None, None) => return changes,
+            (Some(b), Some(a)) if a == b => LineChange::Same(a),
+            (Some(before), Some(after)) => LineChange::Changed { before, after },
+            (None, Some(a)) => LineChange::Added(a),
+            (Some(b), None) => LineChange::Removed(b),
+        };
+        changes.push(change);
+    }
+}
+
+impl std::fmt::Display for LineChange<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineChange::Same(line) => write!(f, "  {}", line),
+            LineChange::Changed { before, after } => write!(f, "~ {}  (was: {})", after, before),
+            LineChange::Added(line) => write!(f, "+ {}", line),
+            LineChange::Removed(line) => write!(f, "- {}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watcher_sees_changes() {
+        let dir = std::env::temp_dir().join(format!("aoc-watch-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let file = dir.join("src").join("lib.rs");
+        std::fs::write(&file, "a").unwrap();
+
+        let mut watcher = Watcher::new([&dir]).unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+
+        let added = dir.join("input.txt");
+        std::fs::write(&added, "1").unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec![added.clone()]);
+
+        std::fs::remove_file(&added).unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec![added]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let changes = diff_lines("Part 1: 5\nPart 2: 7", "Part 1: 5\nPart 2: 8\nDone");
+        assert_eq!(
+            changes,
+            vec![
+                LineChange::Same("Part 1: 5"),
+                LineChange::Changed {
+                    before: "Part 2: 7",
+                    after: "Part 2: 8"
+                },
+                LineChange::Added("Done"),
+            ]
+        );
+        assert_eq!(changes[1].to_string(), "~ Part 2: 8  (was: Part 2: 7)");
+    }
+}
@@ -1,4 +1,19 @@
 use anyhow::Result;
+use common::parse::int;
+#[cfg(feature = "nom")]
+use common::{
+    error::{AocError, ErrorContext},
+    parse::{ExpectFormat, is_int_char},
+};
+#[cfg(feature = "nom")]
+use nom::{
+    IResult, Parser,
+    bytes::complete::take_while1,
+    character::complete::{char, one_of, space0},
+    combinator::{all_consuming, map_res, opt},
+    multi::many0,
+    sequence::{preceded, terminated},
+};
 pub mod lock;
 
 #[derive(Debug)]
@@ -31,28 +46,60 @@ impl std::str::FromStr for Rotation {
 
         // Parse both the direction and the count
         let direction = direction.parse::<Direction>()?;
-        let count = count.parse::<i32>()?;
+        let count = int::<i32>(count)?;
+        Ok(Rotation::new(direction, count))
+    }
+}
 
+impl Rotation {
+    pub fn new(direction: Direction, count: i32) -> Self {
         // Convert the count to a signed count based on the direction
         let count = match direction {
             Direction::Left => -count,
             Direction::Right => count,
         };
-        Ok(Rotation {
+        Rotation {
             _direction: direction,
             count,
-        })
+        }
     }
-}
-
-impl Rotation {
     pub fn signed_direction(&self) -> i32 {
         self.count
     }
 }
 
-pub fn parse_data(lines: impl Iterator<Item = String>) -> impl Iterator<Item = Rotation> {
-    lines.map(|line| line.parse::<Rotation>().unwrap())
+/// Parses the rotations on every line, in order.  The error says which 1-based line
+/// failed.
+#[cfg(feature = "nom")]
+pub fn parse_data(lines: impl Iterator<Item = String>) -> Result<Vec<Rotation>, AocError> {
+    let mut rotations = Vec::new();
+    for (number, line) in (1..).zip(lines) {
+        rotations.extend(parse_line(&line).with_line(number)?);
+    }
+    Ok(rotations)
+}
+
+/// Parses every rotation on a line.  Rotations can be run together, like `L10R5`, or
+/// separated by commas and spaces, like `L10, R5`.  A blank line has none.
+#[cfg(feature = "nom")]
+pub fn parse_line(line: &str) -> Result<Vec<Rotation>> {
+    let separator = (space0, opt(char(',')), space0);
+    let (_, rotations) = all_consuming(terminated(many0(preceded(separator, rotation)), space0))
+        .parse(line)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .expect_format("rotations like 'L10R5' or 'L10, R5'", line)?;
+    Ok(rotations)
+}
+
+#[cfg(feature = "nom")]
+fn rotation(input: &str) -> IResult<&str, Rotation> {
+    map_res(
+        (one_of("LR"), take_while1(is_int_char)),
+        |(direction, count): (char, &str)| {
+            Ok::<_, anyhow::Error>(Rotation::new(direction.to_string().parse()?, int(count)?))
+        },
+    )
+    .parse(input)
 }
 
 pub fn read_data_lines(path: &str) -> Result<impl Iterator<Item = String>> {
@@ -62,3 +109,43 @@ pub fn read_data_lines(path: &str) -> Result<impl Iterator<Item = String>> {
         .lines()
         .map(|line| line.expect("Failed to read line")))
 }
+
+#[cfg(all(test, feature = "nom"))]
+mod tests {
+    use super::*;
+
+    fn counts(line: &str) -> Vec<i32> {
+        parse_line(line)
+            .unwrap()
+            .iter()
+            .map(Rotation::signed_direction)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(counts("L68"), vec![-68]);
+        assert_eq!(counts("L10R5"), vec![-10, 5]);
+        assert_eq!(counts("R1, L2,R3 L4"), vec![1, -2, 3, -4]);
+        assert_eq!(counts(""), Vec::<i32>::new());
+        assert_eq!(counts("L1_000R+5"), vec![-1000, 5]);
+        assert!(parse_line("L10X5").is_err());
+        assert!(parse_line("L").is_err());
+        assert_eq!(
+            parse_line("L10X5").unwrap_err().to_string(),
+            "Expected rotations like 'L10R5' or 'L10, R5', got \"L10X5\""
+        );
+    }
+
+    #[test]
+    fn test_parse_data() {
+        let lines = |text: &str| text.lines().map(String::from).collect::<Vec<_>>();
+        let rotations = parse_data(lines("L68\n\nR5, L2").into_iter()).unwrap();
+        assert_eq!(rotations.len(), 3);
+        let error = parse_data(lines("L68\nR5\nL2X").into_iter()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: Expected rotations like 'L10R5' or 'L10, R5', got \"L2X\""
+        );
+    }
+}
@@ -13,15 +13,15 @@ impl Lock {
     }
     // rotates and returns the new position
     pub fn rotate(&mut self, count: i32) -> u32 {
-        // Deal with over and underflow.  Need to do wrapped arithmetic.
-        // Convert count to i64 to handle large values safely
-        let count_i64 = count as i64;
-        let modulus_i64 = self.count as i64;
-
-        // Compute count mod modulus, handling negative values
-        // This gives us a value in range [0, modulus)
-        let offset_i64 = ((count_i64 % modulus_i64) + modulus_i64) % modulus_i64;
-        let offset = offset_i64 as u32;
+        // Deal with over and underflow by working with the size of the rotation,
+        // which always fits in a u32.  A left rotation is the same as a right
+        // rotation by the rest of the dial.
+        let clicks = count.unsigned_abs() % self.count;
+        let offset = if count < 0 {
+            (self.count - clicks) % self.count
+        } else {
+            clicks
+        };
 
         // Add offset to position and wrap around
         self.position = (self.position + offset) % self.count;
@@ -30,4 +30,138 @@ impl Lock {
     pub fn at_zero(&self) -> bool {
         self.position == 0
     }
+    /// Gets the number of times the dial points at zero while rotating by `count`
+    /// clicks, including where it stops but not where it starts.
+    pub fn zero_crossings(&self, count: i32) -> u32 {
+        let clicks = count.unsigned_abs();
+        if count >= 0 {
+            (self.position + clicks) / self.count
+        } else if self.position == 0 {
+            clicks / self.count
+        } else if clicks >= self.position {
+            (clicks - self.position) / self.count + 1
+        } else {
+            0
+        }
+    }
+    /// Rotates by `count` clicks like [`Lock::rotate`], also working out how many
+    /// times the dial passed zero and how many full turns it made on the way.
+    pub fn rotate_counting(&mut self, count: i32) -> RotationOutcome {
+        let zero_crossings = self.zero_crossings(count);
+        RotationOutcome {
+            final_pos: self.rotate(count),
+            zero_crossings,
+            full_revolutions: count.unsigned_abs() / self.count,
+        }
+    }
+    /// Applies each rotation in turn, recording where the dial started and ended and
+    /// how many times it passed zero.
+    pub fn trace(&mut self, rotations: impl IntoIterator<Item = i32>) -> Vec<LockEvent> {
+        rotations
+            .into_iter()
+            .map(|rotation| {
+                let start = self.position;
+                let outcome = self.rotate_counting(rotation);
+                LockEvent {
+                    rotation,
+                    start,
+                    end: outcome.final_pos,
+                    zero_crossings: outcome.zero_crossings,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where one rotation left the dial, from [`Lock::rotate_counting`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationOutcome {
+    pub final_pos: u32,
+    /// The times the dial pointed at zero, including where it stopped but not where it
+    /// started.
+    pub zero_crossings: u32,
+    /// The number of whole turns of the dial in the rotation.
+    pub full_revolutions: u32,
+}
+
+/// One rotation of the lock, as recorded by [`Lock::trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockEvent {
+    pub rotation: i32,
+    pub start: u32,
+    pub end: u32,
+    pub zero_crossings: u32,
+}
+impl std::fmt::Display for LockEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = if self.rotation < 0 { 'L' } else { 'R' };
+        write!(
+            f,
+            "{}{:<5} {:>3} -> {:>3}  zeros: {}",
+            direction,
+            self.rotation.unsigned_abs(),
+            self.start,
+            self.end,
+            self.zero_crossings
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_crossings() {
+        let lock = Lock::new(50, 100);
+        assert_eq!(lock.zero_crossings(49), 0);
+        assert_eq!(lock.zero_crossings(50), 1);
+        assert_eq!(lock.zero_crossings(1000), 10);
+        assert_eq!(lock.zero_crossings(-50), 1);
+        assert_eq!(lock.zero_crossings(-150), 2);
+        let lock = Lock::new(0, 100);
+        assert_eq!(lock.zero_crossings(-5), 0);
+        assert_eq!(lock.zero_crossings(-100), 1);
+        assert_eq!(lock.zero_crossings(5), 0);
+    }
+
+    #[test]
+    fn test_rotate_counting() {
+        let mut lock = Lock::new(50, 100);
+        assert_eq!(
+            lock.rotate_counting(-250),
+            RotationOutcome {
+                final_pos: 0,
+                zero_crossings: 3,
+                full_revolutions: 2
+            }
+        );
+        assert_eq!(
+            lock.rotate_counting(99),
+            RotationOutcome {
+                final_pos: 99,
+                zero_crossings: 0,
+                full_revolutions: 0
+            }
+        );
+        assert_eq!(lock.rotate_counting(i32::MIN).full_revolutions, 21_474_836);
+    }
+
+    #[test]
+    fn test_trace_sample() {
+        let rotations = [-68, -30, 48, -5, 60, -55, -1, -99, 14, -82];
+        let events = Lock::new(50, 100).trace(rotations);
+        assert_eq!(
+            events[0],
+            LockEvent {
+                rotation: -68,
+                start: 50,
+                end: 82,
+                zero_crossings: 1
+            }
+        );
+        // Part 1 counts the rotations that stop at zero, part 2 every zero.
+        assert_eq!(events.iter().filter(|e| e.end == 0).count(), 3);
+        assert_eq!(events.iter().map(|e| e.zero_crossings).sum::<u32>(), 6);
+    }
 }
@@ -1,32 +1,48 @@
 use anyhow::Result;
+use common::error::ErrorContext;
 use day1::{Rotation, lock::Lock, parse_data, read_data_lines};
 
-// Note: this will panic on bad input
 fn data(input_file: &str) -> Result<impl Iterator<Item = Rotation>> {
     let lines = read_data_lines(input_file)?;
-    Ok(parse_data(lines))
+    let rotations = parse_data(lines)
+        .with_file(input_file)
+        .with_phase("parse")
+        .with_day(1)?;
+    Ok(rotations.into_iter())
 }
 
 fn main() -> Result<()> {
-    // read data from arg 1
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &[])?;
+    let trace = args.iter().any(|arg| arg == "--trace");
 
-    part1(data(&arg1)?)?;
-    part2(data(&arg1)?)?;
+    if trace {
+        print_trace(data(arg1)?);
+    }
+    part1(data(arg1)?).with_phase("part 1").with_day(1)?;
+    part2(data(arg1)?).with_phase("part 2").with_day(1)?;
 
     Ok(())
 }
 
-fn part2(data: impl Iterator<Item = Rotation>) -> Result<()> {
-    // given the data, get the rotation directions
-    let rotation_directions = data.map(|rotation| rotation.signed_direction());
-
-    // impl Iterator<Item = i32>
-    let one_click_per = rotation_directions.flat_map(create_single_clicks);
+/// Prints each rotation with where the dial started, where it stopped, and how many
+/// times it pointed at zero on the way.
+fn print_trace(data: impl Iterator<Item = Rotation>) {
+    let events = Lock::new(50, 100).trace(data.map(|rotation| rotation.signed_direction()));
+    for event in events.iter() {
+        println!("{}", event);
+    }
+}
 
-    let count = count_zero_positions_of_lock(Lock::new(50, 100), one_click_per);
+fn part2(data: impl Iterator<Item = Rotation>) -> Result<()> {
+    // Every time the dial points at zero counts, not just where each rotation stops.
+    let mut lock = Lock::new(50, 100);
+    let count = data
+        .map(|rotation| {
+            lock.rotate_counting(rotation.signed_direction())
+                .zero_crossings as usize
+        })
+        .sum::<usize>();
 
     println!("Part 2: Count: {}", count);
 
@@ -44,16 +60,6 @@ fn count_zero_positions_of_lock(mut lock: Lock, directions: impl Iterator<Item =
     zero_positions.count()
 }
 
-/// Create an iterator of single clicks for a given count
-/// Example: create_single_clicks(10) -> [1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
-/// Example: create_single_clicks(-5) -> [-1, -1, -1, -1]
-fn create_single_clicks(count: i32) -> impl Iterator<Item = i32> {
-    // Based on our direction, get the number of clicks and the unit click value
-    let (count, unit_click) = if count < 0 { (-count, -1) } else { (count, 1) };
-
-    (0..count).map(move |_| unit_click)
-}
-
 fn part1(data: impl Iterator<Item = Rotation>) -> Result<()> {
     // given the data, get the rotation directions
     // impl Iterator<Item = i32>
@@ -66,22 +72,3 @@ fn part1(data: impl Iterator<Item = Rotation>) -> Result<()> {
     println!("Part 1: Count: {}", count);
     Ok(())
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_clicks() {
-        let clicks = create_single_clicks(10);
-        assert_eq!(
-            clicks.collect::<Vec<i32>>(),
-            vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1]
-        );
-        let clicks = create_single_clicks(-10);
-        assert_eq!(
-            clicks.collect::<Vec<i32>>(),
-            vec![-1, -1, -1, -1, -1, -1, -1, -1, -1, -1]
-        );
-    }
-}
@@ -0,0 +1,9 @@
+#[test]
+fn test_sample_output() {
+    common::golden::check_stdout(
+        env!("CARGO_BIN_EXE_day1"),
+        "sample.txt",
+        "tests/golden/sample.stdout",
+    )
+    .unwrap();
+}
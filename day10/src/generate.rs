@@ -0,0 +1,155 @@
+//! Random machines with known solutions, for fuzzing the solvers against each other.
+//!
+//! A machine is built from random buttons and a random number of presses of each, and
+//! its desired lights and joltages are whatever those presses produce.  So every
+//! generated machine can be solved, and the presses used to build it bound the answer.
+
+use anyhow::Result;
+use common::random::Random;
+
+use crate::{ButtonPressAction, Light, MachineDescription};
+
+/// The most times the generator presses any one button.
+const MAX_PRESSES_PER_BUTTON: u64 = 3;
+
+/// A generated machine and bounds on its answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomMachine {
+    /// The machine written the way the input writes it, like `[.##.] (3) (1,3) {3,5,4,7}`.
+    pub text: String,
+    pub description: MachineDescription,
+    /// The presses that built the machine, by button.
+    pub presses: Vec<u32>,
+    /// At least the fewest presses that light the machine: the buttons pressed an odd
+    /// number of times.
+    pub light_presses_bound: u32,
+    /// At least the fewest presses that meet the joltages: every press made.
+    pub joltage_presses_bound: u32,
+}
+
+/// Generates a machine with `lights` lights and `buttons` buttons from a fixed seed, so
+/// a failing fuzz case can be replayed.  Every button toggles at least one light.
+pub fn random_machine(lights: usize, buttons: usize, seed: u64) -> Result<RandomMachine> {
+    if !(1..=64).contains(&lights) {
+        anyhow::bail!("Machines need 1 to 64 lights, not {}", lights);
+    }
+    let mut random = Random::new(seed);
+    let mut next = move |below: u64| random.below(below);
+
+    let actions = (0..buttons)
+        .map(|_| {
+            let mut toggles = (0..lights).filter(|_| next(2) == 1).collect::<Vec<_>>();
+            if toggles.is_empty() {
+                toggles.push(next(lights as u64) as usize);
+            }
+            ButtonPressAction::new(toggles)
+        })
+        .collect::<Vec<_>>();
+    let presses = (0..buttons)
+        .map(|_| next(MAX_PRESSES_PER_BUTTON + 1) as u32)
+        .collect::<Vec<_>>();
+
+    let mut desired = vec![Light::Off; lights];
+    let mut joltages = vec![0; lights];
+    for (action, count) in actions.iter().zip(&presses) {
+        for toggle in &action.toggles {
+            if count % 2 == 1 {
+                desired[*toggle].toggle();
+            }
+            joltages[*toggle] += count;
+        }
+    }
+    let description = MachineDescription::new(desired, actions, joltages);
+    Ok(RandomMachine {
+        text: machine_text(&description),
+        light_presses_bound: presses.iter().filter(|count| *count % 2 == 1).count() as u32,
+        joltage_presses_bound: presses.iter().sum(),
+        description,
+        presses,
+    })
+}
+
+fn machine_text(description: &MachineDescription) -> String {
+    let buttons = description.actions.iter().map(|action| {
+        let toggles = action
+            .toggles
+            .iter()
+            .map(|toggle| toggle.to_string())
+            .collect::<Vec<_>>();
+        format!("({})", toggles.join(","))
+    });
+    let joltages = description
+        .joltage_requirements
+        .iter()
+        .map(|joltage| joltage.to_string())
+        .collect::<Vec<_>>();
+    format!(
+        "{} {} {{{}}}",
+        description.desired_state,
+        buttons.collect::<Vec<_>>().join(" "),
+        joltages.join(",")
+    )
+}
+
+#[cfg(all(test, feature = "nom", feature = "pathfinding"))]
+mod tests {
+    use super::*;
+    use crate::lights::{self, Strategy};
+    use common::deadline::Deadline;
+
+    #[test]
+    fn test_random_machine() {
+        let machine = random_machine(6, 4, 7).unwrap();
+        assert_eq!(machine, random_machine(6, 4, 7).unwrap());
+        assert_eq!(
+            crate::parse_data(&machine.text).unwrap(),
+            vec![machine.description.clone()]
+        );
+        assert_eq!(machine.description.actions.len(), 4);
+        assert!(random_machine(0, 4, 7).is_err());
+        assert!(random_machine(65, 4, 7).is_err());
+    }
+
+    #[test]
+    fn test_fuzz_light_solvers() {
+        for seed in 0..50 {
+            let machine = random_machine(2 + seed as usize % 7, 1 + seed as usize % 6, seed)
+                .unwrap()
+                .description;
+            let bfs =
+                lights::fewest_presses(&machine.desired_state, &machine.actions, Strategy::Bfs)
+                    .unwrap();
+            let dijkstra = lights::fewest_presses(
+                &machine.desired_state,
+                &machine.actions,
+                Strategy::Dijkstra,
+            )
+            .unwrap();
+            let sequence =
+                lights::press_sequence(&machine.desired_state, &machine.actions).unwrap();
+            assert_eq!(bfs, dijkstra, "seed {}", seed);
+            assert_eq!(sequence.len() as u32, bfs, "seed {}", seed);
+            machine.verify(&sequence).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        for seed in 0..50 {
+            let machine = random_machine(5, 4, seed).unwrap();
+            let fewest = lights::fewest_presses(
+                &machine.description.desired_state,
+                &machine.description.actions,
+                Strategy::Bfs,
+            )
+            .unwrap();
+            assert!(fewest <= machine.light_presses_bound, "seed {}", seed);
+            // The presses that built the machine meet its joltages, so the solver can't
+            // need more.
+            let (fewest, _) =
+                crate::joltage::fewest_presses_counted(&machine.description, Deadline::never())
+                    .unwrap();
+            assert!(fewest <= machine.joltage_presses_bound, "seed {}", seed);
+        }
+    }
+}
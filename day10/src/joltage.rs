@@ -0,0 +1,127 @@
+//! The fewest button presses that meet a machine's joltage requirements, for part 2.
+
+use anyhow::Result;
+use common::{
+    deadline::{Deadline, Expired},
+    scratch::ScratchPool,
+    search::DEADLINE_CHECK_INTERVAL,
+    smallvec::SmallVec,
+};
+
+use crate::{ButtonPressAction, MachineDescription};
+
+/// Finds the fewest presses that meet a machine's joltages, and how many nodes the
+/// search expanded.  Only the presses the requirements don't force are searched.
+/// Fails with [`Expired`] if the deadline passes first.
+pub fn fewest_presses_counted(
+    machine: &MachineDescription,
+    deadline: Deadline,
+) -> Result<(u32, usize)> {
+    let simplified = machine.simplify()?;
+    let (searched, nodes) = search(
+        &simplified.machine.joltage_requirements,
+        &simplified.machine.actions,
+        deadline,
+    )?;
+    Ok((searched + simplified.fixed_presses(), nodes))
+}
+
+fn overvoltage(joltage: &[u32], desired_joltage: &[u32]) -> bool {
+    joltage
+        .iter()
+        .enumerate()
+        .any(|(i, j)| *j > desired_joltage[i])
+}
+
+/// Joltages are cloned for every press, so short ones stay inline.
+type Joltage = SmallVec<u32, 16>;
+
+/// Adds the joltages one press away to `successors`.
+fn joltage_successors(
+    joltage: &Joltage,
+    desired_joltage: &[u32],
+    actions: &[ButtonPressAction],
+    successors: &mut Vec<(Joltage, u32)>,
+) -> Result<()> {
+    if overvoltage(joltage, desired_joltage) {
+        return Ok(());
+    }
+    for action in actions {
+        let mut new_joltage = joltage.clone();
+        apply_joltage_action(&mut new_joltage, action)?;
+        successors.push((new_joltage, 1));
+    }
+    Ok(())
+}
+
+/// Finds the fewest presses to reach the joltage, and how many nodes the search
+/// expanded.  Fails with [`Expired`] if the deadline passes first.
+fn search(
+    desired_joltage: &[u32],
+    actions: &[ButtonPressAction],
+    deadline: Deadline,
+) -> Result<(u32, usize)> {
+    let start_joltage = Joltage::from_elem(0, desired_joltage.len());
+    let mut expanded = 0_usize;
+    let mut out_of_time = false;
+    let mut failed = None;
+    // The successor lists go back to the pool once dijkstra has read them.
+    let buffers = ScratchPool::new();
+    let res = pathfinding::directed::dijkstra::dijkstra(
+        &start_joltage,
+        |joltage| {
+            let mut successors = buffers.checkout_vec();
+            expanded += 1;
+            if expanded.is_multiple_of(DEADLINE_CHECK_INTERVAL) && deadline.expired() {
+                out_of_time = true;
+            }
+            // Once out of time or a press fails, no node has successors, so the search
+            // winds down.
+            if out_of_time || failed.is_some() {
+                return successors;
+            }
+            if let Err(error) =
+                joltage_successors(joltage, desired_joltage, actions, &mut successors)
+            {
+                failed = Some(error.context("Invalid button"));
+                successors.clear();
+            }
+            successors
+        },
+        |joltage| joltage.as_slice() == desired_joltage,
+    );
+    if let Some(error) = failed {
+        return Err(error);
+    }
+    if out_of_time {
+        return Err(anyhow::Error::from(Expired)
+            .context(format!("Gave up after expanding {} nodes", expanded)));
+    }
+    let res = res.ok_or_else(|| anyhow::anyhow!("No path found"))?;
+    Ok((res.1, expanded))
+}
+
+fn apply_joltage_action<'a>(
+    joltage: &'a mut [u32],
+    action: &ButtonPressAction,
+) -> Result<&'a [u32]> {
+    for increment in action.toggles.iter() {
+        *joltage
+            .get_mut(*increment)
+            .ok_or_else(|| anyhow::anyhow!("Invalid increment: {}", increment))? += 1;
+    }
+    Ok(joltage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_joltage_action() {
+        let mut joltage = vec![0, 0, 0];
+        let action = ButtonPressAction::new(vec![0, 1]);
+        apply_joltage_action(&mut joltage, &action).unwrap();
+        assert_eq!(joltage, vec![1, 1, 0]);
+    }
+}
@@ -1,9 +1,21 @@
 use anyhow::Result;
+#[cfg(feature = "nom")]
+use common::{error::ErrorContext, parse::ExpectFormat};
+use common::{iter::IterExt, smallvec::SmallVec};
+pub mod generate;
+#[cfg(feature = "pathfinding")]
+pub mod joltage;
+#[cfg(feature = "pathfinding")]
+pub mod lights;
+#[cfg(feature = "nom")]
 pub mod parser;
+pub mod report;
 
+#[cfg(feature = "nom")]
 pub fn parse_data(data: &str) -> Result<Vec<MachineDescription>> {
-    data.lines()
-        .map(|line| {
+    (1..)
+        .zip(data.lines())
+        .map(|(number, line)| {
             parser::parse_machine_description(line)
                 .map_err(|e| anyhow::anyhow!("Invalid input: {}", e))
                 .and_then(|(remaining, description)| {
@@ -16,12 +28,16 @@ pub fn parse_data(data: &str) -> Result<Vec<MachineDescription>> {
                         ))
                     }
                 })
+                .expect_format("a machine like '[.##.] (3) (1,3) (2) {3,5,4,7}'", line)
+                .with_line(number)
         })
-        .collect::<Result<Vec<_>>>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Into::into)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Light {
+    #[default]
     Off,
     On,
 }
@@ -34,6 +50,7 @@ impl Light {
     }
 }
 
+#[cfg(feature = "nom")]
 impl Light {
     fn from_char(c: char) -> Result<Self> {
         match c {
@@ -44,24 +61,71 @@ impl Light {
     }
 }
 
+/// The most lights a machine's state keeps inline; the searches clone states for
+/// every press, so they shouldn't allocate.
+const INLINE_LIGHTS: usize = 16;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MachineState {
-    lights: Vec<Light>,
+    lights: SmallVec<Light, INLINE_LIGHTS>,
 }
 impl MachineState {
     pub fn new(lights: Vec<Light>) -> Self {
-        MachineState { lights }
+        MachineState {
+            lights: lights.into(),
+        }
     }
     pub fn from_len(len: usize) -> Self {
         MachineState {
-            lights: vec![Light::Off; len],
+            lights: SmallVec::from_elem(Light::Off, len),
         }
     }
     pub fn len(&self) -> usize {
         self.lights.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+    /// Gets the lights as a bitmask, with light 0 in the lowest bit.  Only meaningful
+    /// for up to 64 lights.
+    pub fn to_bits(&self) -> u64 {
+        self.lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| **light == Light::On)
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+    }
+}
+/// Shows the lights the way the input does, like `[.##.]`.
+impl std::fmt::Display for MachineState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for light in &self.lights {
+            write!(f, "{}", if *light == Light::On { '#' } else { '.' })?;
+        }
+        write!(f, "]")
+    }
 }
 impl MachineState {
+    /// Presses the machine's buttons in order, by index, starting with every light off.
+    /// Returns the state after each press.
+    pub fn replay(
+        presses: &[usize],
+        description: &MachineDescription,
+    ) -> Result<Vec<MachineState>> {
+        let mut state = MachineState::from_len(description.desired_state.len());
+        presses
+            .iter()
+            .map(|press| {
+                let action = description
+                    .actions
+                    .get(*press)
+                    .ok_or_else(|| anyhow::anyhow!("No button {}", press))?;
+                state.apply_action(action)?;
+                Ok(state.clone())
+            })
+            .collect()
+    }
     pub fn apply_action(&mut self, action: &ButtonPressAction) -> Result<&[Light]> {
         for toggle in action.toggles.iter() {
             self.lights
@@ -81,6 +145,15 @@ impl ButtonPressAction {
     pub fn new(toggles: Vec<usize>) -> Self {
         ButtonPressAction { toggles }
     }
+    /// Gets the lights this button toggles as a bitmask for a machine with `lights` lights.
+    pub fn to_bits(&self, lights: usize) -> Result<u64> {
+        self.toggles.iter().try_fold(0, |bits, toggle| {
+            if *toggle >= lights.min(64) {
+                anyhow::bail!("Invalid toggle: {}", toggle);
+            }
+            Ok(bits | 1 << toggle)
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -96,11 +169,161 @@ impl MachineDescription {
         joltage_requirements: Vec<u32>,
     ) -> Self {
         MachineDescription {
-            desired_state: MachineState { lights },
+            desired_state: MachineState::new(lights),
             actions,
             joltage_requirements,
         }
     }
+    /// Checks that pressing these buttons, by index, lights the machine as desired.
+    pub fn verify(&self, presses: &[usize]) -> Result<()> {
+        let states = MachineState::replay(presses, self)?;
+        let last = states
+            .last()
+            .cloned()
+            .unwrap_or_else(|| MachineState::from_len(self.desired_state.len()));
+        if last != self.desired_state {
+            anyhow::bail!(
+                "Presses {:?} end at {}, not {}",
+                presses,
+                last,
+                self.desired_state
+            );
+        }
+        Ok(())
+    }
+
+    /// Fixes the presses the joltage requirements force, before any search.  A slot
+    /// that only one button still reaches fixes how often that button is pressed, and
+    /// a slot that is already met rules out every button that reaches it.  Each fix can
+    /// force others, so this repeats until nothing changes.
+    ///
+    /// Fails if the requirements can't be met at all.  The lights of the reduced machine
+    /// are the desired lights with the fixed presses applied.
+    pub fn simplify(&self) -> Result<Simplified> {
+        let slots = self.joltage_requirements.len();
+        let mut remaining = self.joltage_requirements.clone();
+        let mut fixed = vec![0; self.actions.len()];
+        let mut live_buttons = vec![true; self.actions.len()];
+        let mut live_slots = vec![true; slots];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for slot in 0..slots {
+                if !live_slots[slot] {
+                    continue;
+                }
+                let buttons = (0..self.actions.len())
+                    .filter(|button| {
+                        live_buttons[*button] && self.actions[*button].toggles.contains(&slot)
+                    })
+                    .collect::<Vec<_>>();
+                match (buttons.as_slice(), remaining[slot]) {
+                    // Any press would overshoot the slot.
+                    (_, 0) => buttons
+                        .iter()
+                        .for_each(|button| live_buttons[*button] = false),
+                    ([], needed) => anyhow::bail!(
+                        "Joltage slot {} needs {} more, but no button reaches it",
+                        slot,
+                        needed
+                    ),
+                    (&[button], needed) => {
+                        for toggle in &self.actions[button].toggles {
+                            let left = remaining
+                                .get_mut(*toggle)
+                                .ok_or_else(|| anyhow::anyhow!("Invalid toggle: {}", toggle))?;
+                            *left = left.checked_sub(needed).ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "Button {} must be pressed {} times, which overshoots slot {}",
+                                    button,
+                                    needed,
+                                    toggle
+                                )
+                            })?;
+                        }
+                        fixed[button] = needed;
+                        live_buttons[button] = false;
+                    }
+                    _ => continue,
+                }
+                live_slots[slot] = false;
+                changed = true;
+            }
+        }
+
+        // Renumber the slots that are left.
+        let kept = (0..slots)
+            .filter(|slot| live_slots[*slot])
+            .collect::<Vec<_>>();
+        let buttons = (0..self.actions.len())
+            .filter(|button| live_buttons[*button])
+            .collect::<Vec<_>>();
+        let actions = buttons
+            .iter()
+            .map(|button| {
+                let mut toggles = Vec::new();
+                for toggle in &self.actions[*button].toggles {
+                    if *toggle >= slots {
+                        anyhow::bail!("Invalid toggle: {}", toggle);
+                    }
+                    // Toggles of retired slots are dropped; the rest are renumbered.
+                    if let Some(slot) = kept.iter().position(|slot| slot == toggle) {
+                        toggles.push(slot);
+                    }
+                }
+                Ok(ButtonPressAction::new(toggles))
+            })
+            .try_collect_vec()?;
+        let mut lights = self.desired_state.clone();
+        for (button, presses) in fixed.iter().enumerate() {
+            if presses % 2 == 1 {
+                lights.apply_action(&self.actions[button])?;
+            }
+        }
+        let lights = kept
+            .iter()
+            .map(|slot| lights.lights.get(*slot).copied().unwrap_or_default())
+            .collect();
+        let joltage = kept.iter().map(|slot| remaining[*slot]).collect();
+        Ok(Simplified {
+            machine: MachineDescription::new(lights, actions, joltage),
+            fixed,
+            buttons,
+        })
+    }
+}
+
+/// A machine with the presses [`MachineDescription::simplify`] could fix taken out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simplified {
+    /// The unfixed buttons and the joltage they still have to add.
+    pub machine: MachineDescription,
+    /// How many times each original button must be pressed; 0 for the unfixed ones.
+    pub fixed: Vec<u32>,
+    /// The original index of each of the reduced machine's buttons.
+    pub buttons: Vec<usize>,
+}
+impl Simplified {
+    /// The total presses already fixed.
+    pub fn fixed_presses(&self) -> u32 {
+        self.fixed.iter().sum()
+    }
+    /// Maps press counts for the reduced machine's buttons back to counts for every
+    /// original button, fixed ones included.
+    pub fn original_presses(&self, presses: &[u32]) -> Result<Vec<u32>> {
+        if presses.len() != self.buttons.len() {
+            anyhow::bail!(
+                "Expected presses for {} buttons, got {}",
+                self.buttons.len(),
+                presses.len()
+            );
+        }
+        let mut all = self.fixed.clone();
+        for (button, count) in self.buttons.iter().zip(presses) {
+            all[*button] += count;
+        }
+        Ok(all)
+    }
 }
 
 #[cfg(test)]
@@ -120,8 +343,83 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            machine_state.lights,
-            vec![Light::Off, Light::On, Light::On, Light::Off]
+            machine_state.lights.as_slice(),
+            [Light::Off, Light::On, Light::On, Light::Off]
         );
     }
+
+    #[cfg(feature = "nom")]
+    #[test]
+    fn test_replay_and_verify() {
+        let machines = parse_data(&std::fs::read_to_string("sample.txt").unwrap()).unwrap();
+        let machine = &machines[0];
+        let states = MachineState::replay(&[4, 5], machine).unwrap();
+        assert_eq!(states.len(), 2);
+        assert_eq!(states[0].to_string(), "[#.#.]");
+        assert_eq!(states[1], machine.desired_state);
+        assert!(machine.verify(&[4, 5]).is_ok());
+
+        let err = machine.verify(&[4]).unwrap_err();
+        assert_eq!(err.to_string(), "Presses [4] end at [#.#.], not [.##.]");
+        assert!(machine.verify(&[]).is_err());
+        assert!(MachineState::replay(&[9], machine).is_err());
+    }
+
+    #[test]
+    fn test_simplify() {
+        // Slot 0 is only reached by button 0, which fixes it at 2 presses.  That meets
+        // slot 1, ruling out button 1, and leaves slot 2 to button 2 alone.
+        let machine = MachineDescription::new(
+            vec![Light::Off; 3],
+            vec![
+                ButtonPressAction::new(vec![0, 1]),
+                ButtonPressAction::new(vec![1, 2]),
+                ButtonPressAction::new(vec![2]),
+            ],
+            vec![2, 2, 5],
+        );
+        let simplified = machine.simplify().unwrap();
+        assert_eq!(simplified.fixed, vec![2, 0, 5]);
+        assert_eq!(simplified.fixed_presses(), 7);
+        assert!(simplified.machine.actions.is_empty());
+        assert!(simplified.machine.joltage_requirements.is_empty());
+        assert_eq!(simplified.original_presses(&[]).unwrap(), vec![2, 0, 5]);
+
+        let overshoot = MachineDescription::new(
+            vec![Light::Off; 2],
+            vec![ButtonPressAction::new(vec![0, 1])],
+            vec![3, 1],
+        );
+        assert!(overshoot.simplify().is_err());
+
+        // Neither button is fixed, so the bad toggle is only seen when renumbering.
+        let out_of_range = MachineDescription::new(
+            vec![Light::Off; 2],
+            vec![
+                ButtonPressAction::new(vec![0, 5]),
+                ButtonPressAction::new(vec![0, 1]),
+                ButtonPressAction::new(vec![1]),
+            ],
+            vec![2, 2],
+        );
+        let error = out_of_range.simplify().unwrap_err();
+        assert_eq!(format!("{:#}", error), "Item 0: Invalid toggle: 5");
+    }
+
+    #[cfg(feature = "nom")]
+    #[test]
+    fn test_simplify_sample() {
+        let machines = parse_data(&std::fs::read_to_string("sample.txt").unwrap()).unwrap();
+        // The first machine has nothing to fix.
+        let simplified = machines[0].simplify().unwrap();
+        assert_eq!(simplified.machine.actions, machines[0].actions);
+        assert_eq!(simplified.buttons, vec![0, 1, 2, 3, 4, 5]);
+        for machine in &machines {
+            let simplified = machine.simplify().unwrap();
+            let presses = vec![0; simplified.buttons.len()];
+            let all = simplified.original_presses(&presses).unwrap();
+            assert_eq!(all.iter().sum::<u32>(), simplified.fixed_presses());
+            assert!(simplified.original_presses(&[1; 9]).is_err());
+        }
+    }
 }
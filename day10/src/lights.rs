@@ -0,0 +1,195 @@
+use anyhow::Result;
+use common::{convert::to_usize, iter::IterExt, scratch::ScratchPool};
+use std::collections::VecDeque;
+
+use crate::{ButtonPressAction, MachineState};
+
+/// How to search for the fewest button presses that light a machine.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Breadth-first search over the lights as a bitmask.  Every press costs one, so
+    /// the first time the desired lights are reached is the fewest presses.
+    #[default]
+    Bfs,
+    /// Dijkstra over the light states.  Slower for unit costs, but ready for presses
+    /// that cost different amounts.
+    Dijkstra,
+}
+
+/// The most lights the BFS visited bitset will cover; beyond this it would need more
+/// than 32 MiB, so the search falls back to Dijkstra.
+const MAX_BFS_LIGHTS: usize = 28;
+
+/// Finds the fewest presses that take every light from off to the desired state.
+pub fn fewest_presses(
+    desired_state: &MachineState,
+    actions: &[ButtonPressAction],
+    strategy: Strategy,
+) -> Result<u32> {
+    fewest_presses_counted(desired_state, actions, strategy).map(|(presses, _)| presses)
+}
+
+/// Like [`fewest_presses`], also giving how many search nodes were expanded to find
+/// the answer.
+pub fn fewest_presses_counted(
+    desired_state: &MachineState,
+    actions: &[ButtonPressAction],
+    strategy: Strategy,
+) -> Result<(u32, usize)> {
+    match strategy {
+        Strategy::Bfs if desired_state.len() <= MAX_BFS_LIGHTS => bfs(desired_state, actions),
+        _ => dijkstra(desired_state, actions),
+    }
+}
+
+/// The most lights [`press_sequence`] handles, one bit of a `u64` each.
+pub const MAX_SEQUENCE_LIGHTS: usize = 64;
+
+/// Finds a shortest sequence of button presses, by index, that lights the machine.
+/// Only handles machines with up to [`MAX_SEQUENCE_LIGHTS`] lights.
+pub fn press_sequence(
+    desired_state: &MachineState,
+    actions: &[ButtonPressAction],
+) -> Result<Vec<usize>> {
+    let lights = desired_state.len();
+    let desired = desired_state.to_bits();
+    let masks = actions
+        .iter()
+        .map(|action| action.to_bits(lights))
+        .try_collect_vec()?;
+    let states = pathfinding::directed::bfs::bfs(
+        &0_u64,
+        |state| masks.iter().map(|mask| state ^ mask).collect::<Vec<_>>(),
+        |state| *state == desired,
+    )
+    .ok_or_else(|| anyhow::anyhow!("No path found"))?;
+    // Each step toggled exactly the lights of the button that was pressed.
+    states
+        .windows(2)
+        .map(|pair| {
+            masks
+                .iter()
+                .position(|mask| *mask == pair[0] ^ pair[1])
+                .ok_or_else(|| anyhow::anyhow!("No button toggles {:b}", pair[0] ^ pair[1]))
+        })
+        .collect()
+}
+
+fn bfs(desired_state: &MachineState, actions: &[ButtonPressAction]) -> Result<(u32, usize)> {
+    let lights = desired_state.len();
+    let desired = desired_state.to_bits();
+    let masks = actions
+        .iter()
+        .map(|action| action.to_bits(lights))
+        .try_collect_vec()?;
+
+    // One bit per possible light state.
+    let mut visited = vec![0_u64; (1_usize << lights).div_ceil(64)];
+    let mut visit = |state: u64| -> Result<bool> {
+        let (word, bit) = (to_usize(state / 64)?, state % 64);
+        let seen = visited[word] & (1 << bit) != 0;
+        visited[word] |= 1 << bit;
+        Ok(!seen)
+    };
+
+    let mut queue = VecDeque::from([(0_u64, 0_u32)]);
+    visit(0)?;
+    let mut expanded = 0;
+    while let Some((state, presses)) = queue.pop_front() {
+        if state == desired {
+            return Ok((presses, expanded));
+        }
+        expanded += 1;
+        for mask in &masks {
+            let next = state ^ mask;
+            if visit(next)? {
+                queue.push_back((next, presses + 1));
+            }
+        }
+    }
+    Err(anyhow::anyhow!("No path found"))
+}
+
+/// Adds the states one press away to `successors`.
+fn light_successors(
+    state: &MachineState,
+    actions: &[ButtonPressAction],
+    successors: &mut Vec<(MachineState, u32)>,
+) -> Result<()> {
+    for action in actions {
+        let mut state = state.clone();
+        state.apply_action(action)?;
+        successors.push((state, 1));
+    }
+    Ok(())
+}
+
+fn dijkstra(desired_state: &MachineState, actions: &[ButtonPressAction]) -> Result<(u32, usize)> {
+    let start_state = MachineState::from_len(desired_state.len());
+    let mut expanded = 0;
+    let mut failed = None;
+    // The successor lists go back to the pool once dijkstra has read them.
+    let buffers = ScratchPool::new();
+    let res = pathfinding::directed::dijkstra::dijkstra(
+        &start_state,
+        |state| {
+            let mut successors = buffers.checkout_vec();
+            expanded += 1;
+            // Once a press fails, no node has successors, so the search winds down.
+            if failed.is_some() {
+                return successors;
+            }
+            if let Err(error) = light_successors(state, actions, &mut successors) {
+                failed = Some(error.context("Invalid button"));
+                successors.clear();
+            }
+            successors
+        },
+        |state| state == desired_state,
+    );
+    if let Some(error) = failed {
+        return Err(error);
+    }
+    let res = res.ok_or_else(|| anyhow::anyhow!("No path found"))?;
+    Ok((res.1, expanded))
+}
+
+#[cfg(all(test, feature = "nom"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategies_agree_on_sample() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let machines = crate::parse_data(&data).unwrap();
+        let presses = |strategy| {
+            machines
+                .iter()
+                .map(|m| fewest_presses(&m.desired_state, &m.actions, strategy).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(presses(Strategy::Bfs), vec![2, 3, 2]);
+        assert_eq!(presses(Strategy::Dijkstra), vec![2, 3, 2]);
+    }
+
+    #[test]
+    fn test_press_sequences_verify() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let machines = crate::parse_data(&data).unwrap();
+        for machine in &machines {
+            let presses = press_sequence(&machine.desired_state, &machine.actions).unwrap();
+            let fewest =
+                fewest_presses(&machine.desired_state, &machine.actions, Strategy::Bfs).unwrap();
+            assert_eq!(presses.len(), fewest as usize);
+            machine.verify(&presses).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let desired = MachineState::new(vec![crate::Light::On, crate::Light::Off]);
+        let actions = [ButtonPressAction::new(vec![1])];
+        assert!(fewest_presses(&desired, &actions, Strategy::Bfs).is_err());
+        assert!(fewest_presses(&desired, &actions, Strategy::Dijkstra).is_err());
+    }
+}
@@ -1,130 +1,114 @@
-use anyhow::Result;
-use day10::{ButtonPressAction, MachineDescription, MachineState};
-use rayon::prelude::*;
+use anyhow::{Context, Result};
+use common::{convert::to_usize, deadline::Deadline, error::ErrorContext, iter::IterExt};
+use day10::{
+    MachineDescription, joltage,
+    lights::{self, Strategy},
+    report::SolveReport,
+};
+
+/// How many of the slowest machines --report lists.
+const SLOWEST_SHOWN: usize = 10;
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let data = day10::parse_data(&data)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &[])?;
+    // --report shows how hard each part's machines were after its answer.
+    let show_report = args.iter().any(|arg| arg == "--report");
+    let data = common::read_file(arg1)?;
+    let data = day10::parse_data(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(10)?;
 
-    println!("Part 1: {:?}", part1(&data)?);
-    println!("Part 2: {:?}", part2(&data)?);
+    let report = part1(&data).with_phase("part 1").with_day(10)?;
+    println!("Part 1: {:?}", report.total);
+    if show_report {
+        print_report(&report);
+    }
+    // --verify replays a press sequence for every machine to check part 1's answer.
+    if args.iter().any(|arg| arg == "--verify") {
+        verify_part1(&data, &report)?;
+    }
+    // AOC_TIMEOUT, set by `aoc run --timeout`, stops the part 2 search if it runs too
+    // long.
+    let report = part2(&data, Deadline::from_env()?)
+        .with_phase("part 2")
+        .with_day(10)?;
+    // Machines are solved in parallel, so each one's result is printed afterwards to
+    // keep them in input order.
+    for machine in &report.machines {
+        println!(
+            "Joltage: {:?}, Path: {:?}",
+            data[machine.index].joltage_requirements, machine.presses
+        );
+    }
+    println!("Part 2: {:?}", report.total);
+    if show_report {
+        print_report(&report);
+    }
 
     Ok(())
 }
 
-fn part1(data: &[MachineDescription]) -> Result<u32> {
-    Ok(data
-        .par_iter()
-        .map(|desc| {
-            find_shortest_path_lights(&desc.desired_state, &desc.actions).expect("Invalid path")
-        })
-        .sum())
-}
-
-fn part2(data: &[MachineDescription]) -> Result<u32> {
-    Ok(data
-        .iter()
-        .map(|desc| {
-            let res = find_shortest_path_joltage(&desc.joltage_requirements, &desc.actions)
-                .expect("Invalid path");
-            println!("Joltage: {:?}, Path: {:?}", desc.joltage_requirements, res);
-            res
-        })
-        .sum::<u32>())
-}
-
-fn light_successors(
-    state: MachineState,
-    actions: &[ButtonPressAction],
-) -> impl Iterator<Item = (MachineState, u32)> {
-    actions.iter().map(move |action| {
-        let mut state = state.clone();
-        state.apply_action(action).expect("Invalid action");
-        (state, 1)
-    })
-}
-
-fn find_shortest_path_lights(
-    desired_state: &MachineState,
-    actions: &[ButtonPressAction],
-) -> Result<u32> {
-    let start_state = MachineState::from_len(desired_state.len());
-    let res = pathfinding::directed::dijkstra::dijkstra(
-        &start_state,
-        |state| light_successors(state.clone(), actions),
-        |state| state == desired_state,
-    )
-    .ok_or_else(|| anyhow::anyhow!("No path found"))?;
-    //    println!("Path: {:?}", res);
-    Ok(res.1)
+fn print_report(report: &SolveReport) {
+    for (difficulty, count) in report.by_difficulty() {
+        println!("  {:?}: {} machines", difficulty, count);
+    }
+    if let Some(timing) = report.timing() {
+        println!("  Time per machine (ms): {}", timing);
+    }
+    for machine in report.slowest(SLOWEST_SHOWN) {
+        println!(
+            "  Machine {}: {} presses, {} nodes, {:?} ({:?})",
+            machine.index,
+            machine.presses,
+            machine.nodes,
+            machine.elapsed,
+            machine.difficulty()
+        );
+    }
 }
 
-fn overvoltage(joltage: &[u32], desired_joltage: &[u32]) -> bool {
-    joltage
+/// Replays a press sequence for every machine to check the part 1 search independently,
+/// and checks each sequence is as long as part 1's count for that machine.  Machines
+/// with too many lights for [`lights::press_sequence`] are skipped.  Machines are
+/// checked in input order, so the first one reported is the first that fails.
+fn verify_part1(data: &[MachineDescription], report: &SolveReport) -> Result<()> {
+    let checkable = data
         .iter()
+        .zip(&report.machines)
         .enumerate()
-        .any(|(i, j)| *j > desired_joltage[i])
-}
-
-fn joltage_successors(
-    joltage: Vec<u32>,
-    desired_joltage: &[u32],
-    actions: &[ButtonPressAction],
-) -> impl Iterator<Item = (Vec<u32>, u32)> {
-    let take = if overvoltage(&joltage, desired_joltage) {
-        0
-    } else {
-        actions.len()
-    };
-
-    actions
-        .iter()
-        .map(move |action| {
-            let mut new_joltage = joltage.clone();
-            apply_joltage_action(&mut new_joltage, action).expect("Invalid action");
-            (new_joltage, 1)
-        })
-        .take(take)
-}
-
-fn find_shortest_path_joltage(
-    desired_joltage: &[u32],
-    actions: &[ButtonPressAction],
-) -> Result<u32> {
-    let start_joltage = vec![0; desired_joltage.len()];
-    let res = pathfinding::directed::dijkstra::dijkstra(
-        &start_joltage,
-        |joltage| joltage_successors(joltage.clone(), desired_joltage, actions),
-        |joltage| *joltage == desired_joltage,
-    )
-    .ok_or_else(|| anyhow::anyhow!("No path found"))?;
-    Ok(res.1)
+        .filter(|(_, (desc, _))| desc.desired_state.len() <= lights::MAX_SEQUENCE_LIGHTS);
+    let checks = common::par::try_map_ordered(checkable, |(i, (desc, machine))| {
+        let check = || {
+            let presses = lights::press_sequence(&desc.desired_state, &desc.actions)?;
+            desc.verify(&presses)?;
+            if presses.len() != to_usize(machine.presses)? {
+                anyhow::bail!(
+                    "Replayed {} presses but part 1 counted {}",
+                    presses.len(),
+                    machine.presses
+                );
+            }
+            Ok(())
+        };
+        check().with_context(|| format!("Machine {}", i))
+    });
+    let verified = checks.fold_ok(0, |verified, ()| verified + 1)?;
+    println!(
+        "Part 1 verified: {} machines, {} skipped",
+        verified,
+        data.len() - verified
+    );
+    Ok(())
 }
 
-fn apply_joltage_action<'a>(
-    joltage: &'a mut [u32],
-    action: &ButtonPressAction,
-) -> Result<&'a [u32]> {
-    for increment in action.toggles.iter() {
-        *joltage
-            .get_mut(*increment)
-            .ok_or_else(|| anyhow::anyhow!("Invalid increment: {}", increment))? += 1;
-    }
-    Ok(joltage)
+fn part1(data: &[MachineDescription]) -> Result<SolveReport> {
+    SolveReport::solve(data, |desc| {
+        lights::fewest_presses_counted(&desc.desired_state, &desc.actions, Strategy::default())
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_apply_joltage_action() {
-        let mut joltage = vec![0, 0, 0];
-        let action = ButtonPressAction::new(vec![0, 1]);
-        apply_joltage_action(&mut joltage, &action).unwrap();
-        assert_eq!(joltage, vec![1, 1, 0]);
-    }
+fn part2(data: &[MachineDescription], deadline: Deadline) -> Result<SolveReport> {
+    SolveReport::solve(data, |desc| joltage::fewest_presses_counted(desc, deadline))
 }
@@ -31,10 +31,7 @@ fn button_press_parser(input: &str) -> IResult<&str, ButtonPressAction> {
     // Looks like (1,2,3)
     let (input, toggles) = nom::sequence::delimited(
         nom::character::complete::char('('),
-        nom::multi::separated_list0(
-            nom::character::complete::char(','),
-            nom::character::complete::usize,
-        ),
+        nom::multi::separated_list0(nom::character::complete::char(','), integer::<usize>),
         nom::character::complete::char(')'),
     )
     .parse(input)?;
@@ -57,15 +54,21 @@ fn parser_joltage_requirements(input: &str) -> IResult<&str, Vec<u32>> {
     // Looks like (1,2,3)
     nom::sequence::delimited(
         nom::character::complete::char('{'),
-        nom::multi::separated_list0(
-            nom::character::complete::char(','),
-            nom::character::complete::u32,
-        ),
+        nom::multi::separated_list0(nom::character::complete::char(','), integer::<u32>),
         nom::character::complete::char('}'),
     )
     .parse(input)
 }
 
+/// Parses an integer, accepting the forms [`common::parse::int`] does.
+fn integer<T: std::str::FromStr>(input: &str) -> IResult<&str, T> {
+    nom::combinator::map_res(
+        nom::bytes::complete::take_while1(common::parse::is_int_char),
+        common::parse::int::<T>,
+    )
+    .parse(input)
+}
+
 fn button_parser(input: &str) -> IResult<&str, Light> {
     // Consume a single character and parse it as a button, and use map_res to convert the error
     nom::combinator::map_res(nom::character::complete::one_of(".#"), Light::from_char).parse(input)
@@ -92,6 +95,9 @@ mod tests {
         let (remaining, action) = button_press_parser(input).unwrap();
         assert_eq!(remaining, "");
         assert_eq!(action, ButtonPressAction::new(vec![1, 2, 3]));
+        let (_, action) = button_press_parser("(+1,1_0)").unwrap();
+        assert_eq!(action, ButtonPressAction::new(vec![1, 10]));
+        assert!(button_press_parser("(1__0)").is_err());
     }
 
     #[test]
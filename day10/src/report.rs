@@ -0,0 +1,175 @@
+//! Per-machine timing and search effort, to find the machines worth a smarter solver.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use common::{deadline::is_expired, par::prelude::*, stats::Summary, topk::top_k_by_key};
+
+use crate::MachineDescription;
+
+/// How hard a machine was for the search, by how many nodes it expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Fewer than 1,000 nodes.
+    Easy,
+    /// Fewer than 100,000 nodes.
+    Medium,
+    /// Anything bigger.  These are the machines to hand to a linear solver.
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_nodes(nodes: usize) -> Self {
+        match nodes {
+            0..1_000 => Difficulty::Easy,
+            1_000..100_000 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+/// How solving one machine went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineReport {
+    /// The machine's position in the input.
+    pub index: usize,
+    pub presses: u32,
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+impl MachineReport {
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::from_nodes(self.nodes)
+    }
+}
+
+/// The answer for every machine together, with how each machine went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveReport {
+    pub total: u32,
+    /// One report per machine, in input order.
+    pub machines: Vec<MachineReport>,
+}
+
+impl SolveReport {
+    /// Solves every machine in parallel with `solve`, which gives the presses and how
+    /// many search nodes it expanded, and times each one.  If `solve` runs out of time
+    /// on any machine, the error says how many machines were solved.  `solve` runs on
+    /// any thread in any order, so anything it printed would come out shuffled; print
+    /// from the reports instead.
+    pub fn solve(
+        machines: &[MachineDescription],
+        solve: impl Fn(&MachineDescription) -> Result<(u32, usize)> + Sync,
+    ) -> Result<Self> {
+        let results = machines
+            .par_iter()
+            .enumerate()
+            .map(|(index, machine)| {
+                let start = Instant::now();
+                let (presses, nodes) =
+                    solve(machine).with_context(|| format!("Machine {}", index))?;
+                Ok(MachineReport {
+                    index,
+                    presses,
+                    nodes,
+                    elapsed: start.elapsed(),
+                })
+            })
+            .collect::<Vec<Result<_>>>();
+        if let Some(error) = results
+            .iter()
+            .find_map(|res| res.as_ref().err().filter(|error| is_expired(error)))
+        {
+            let solved = results.iter().filter(|res| res.is_ok()).count();
+            anyhow::bail!(
+                "Solved {} of {} machines before running out of time ({:#})",
+                solved,
+                results.len(),
+                error
+            );
+        }
+        let machines = results.into_iter().collect::<Result<Vec<_>>>()?;
+        let total = machines
+            .iter()
+            .try_fold(0_u32, |total, machine| total.checked_add(machine.presses))
+            .ok_or_else(|| anyhow::anyhow!("Total presses overflowed"))?;
+        Ok(Self { total, machines })
+    }
+
+    /// Counts the machines of each difficulty, easiest first.
+    pub fn by_difficulty(&self) -> [(Difficulty, usize); 3] {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard].map(|difficulty| {
+            let count = self
+                .machines
+                .iter()
+                .filter(|machine| machine.difficulty() == difficulty)
+                .count();
+            (difficulty, count)
+        })
+    }
+
+    /// Gets the `k` machines that took longest, slowest first.
+    pub fn slowest(&self, k: usize) -> Vec<&MachineReport> {
+        top_k_by_key(&self.machines, k, |machine| machine.elapsed)
+    }
+
+    /// Summarizes how long the machines took, in milliseconds.
+    pub fn timing(&self) -> Option<Summary> {
+        Summary::of(
+            self.machines
+                .iter()
+                .map(|machine| machine.elapsed.as_secs_f64() * 1000.0),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "nom", feature = "pathfinding"))]
+mod tests {
+    use super::*;
+    use crate::lights::{Strategy, fewest_presses_counted};
+
+    #[test]
+    fn test_report_on_sample() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let machines = crate::parse_data(&data).unwrap();
+        let report = SolveReport::solve(&machines, |m| {
+            fewest_presses_counted(&m.desired_state, &m.actions, Strategy::Bfs)
+        })
+        .unwrap();
+        assert_eq!(report.total, 7);
+        assert_eq!(
+            report.machines.iter().map(|m| m.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(report.machines.iter().all(|m| m.nodes > 0));
+        assert_eq!(report.by_difficulty()[0], (Difficulty::Easy, 3));
+        assert_eq!(report.slowest(2).len(), 2);
+        assert_eq!(report.timing().unwrap().count, 3);
+    }
+
+    #[test]
+    fn test_report_out_of_time() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let machines = crate::parse_data(&data).unwrap();
+        let error = SolveReport::solve(&machines, |m| {
+            if m.desired_state == machines[1].desired_state {
+                Err(common::deadline::Expired.into())
+            } else {
+                fewest_presses_counted(&m.desired_state, &m.actions, Strategy::Bfs)
+            }
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Solved 2 of 3 machines before running out of time (Machine 1: Ran out of time)"
+        );
+    }
+
+    #[test]
+    fn test_difficulty() {
+        assert_eq!(Difficulty::from_nodes(0), Difficulty::Easy);
+        assert_eq!(Difficulty::from_nodes(1_000), Difficulty::Medium);
+        assert_eq!(Difficulty::from_nodes(100_000), Difficulty::Hard);
+    }
+}
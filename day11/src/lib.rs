@@ -1,19 +1,74 @@
 use anyhow::Result;
+use common::{error::ErrorContext, iter::IterExt, parse::ExpectFormat};
 use std::collections::HashMap;
 
+pub mod paths;
+
 pub type Graph<'a> = HashMap<&'a str, Vec<&'a str>>;
 
+/// Like [`Graph`], with the cost of following each output.
+pub type WeightedGraph<'a> = HashMap<&'a str, Vec<(&'a str, u64)>>;
+
+/// Parses each device and its outputs.  Any weights on the outputs are dropped.
 pub fn parse_data(data: &str) -> Result<Graph<'_>> {
-    let mut node_map = Graph::new();
+    Ok(parse_weighted_data(data)?
+        .into_iter()
+        .map(|(name, outputs)| {
+            (
+                name,
+                outputs.into_iter().map(|(output, _)| output).collect(),
+            )
+        })
+        .collect())
+}
+
+/// Parses each device and its outputs, where an output can have a weight like
+/// `aaa: bbb=3 ccc=5`.  Outputs without one cost 1.
+pub fn parse_weighted_data(data: &str) -> Result<WeightedGraph<'_>> {
+    let mut node_map = WeightedGraph::new();
 
     // Read all the data into the node map.
-    for line in data.lines() {
+    for (number, line) in (1..).zip(data.lines()) {
         let (name, connections) = line
             .split_once(": ")
-            .ok_or_else(|| anyhow::anyhow!("Invalid line: {}", line))?;
-        let connections = connections.split(" ").map(|c| c.trim()).collect::<Vec<_>>();
+            .ok_or_else(|| anyhow::anyhow!("Missing ': '"))
+            .expect_format(LINE_FORMAT, line)
+            .with_line(number)?;
+        let connections = connections
+            .split(" ")
+            .map(|c| parse_output(c.trim()))
+            .try_collect_vec()
+            .expect_format(LINE_FORMAT, line)
+            .with_line(number)?;
         node_map.insert(name, connections);
     }
 
     Ok(node_map)
 }
+
+const LINE_FORMAT: &str = "a device and its outputs like 'aaa: you hhh' or 'aaa: you=3 hhh=5'";
+
+/// Parses an output with its optional weight, like `bbb` or `bbb=3`.
+fn parse_output(output: &str) -> Result<(&str, u64)> {
+    match output.split_once('=') {
+        Some((name, weight)) => Ok((name, common::parse::int(weight)?)),
+        None => Ok((output, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_weights() {
+        let graph = parse_weighted_data("aaa: bbb=3 ccc\nbbb: out=5").unwrap();
+        assert_eq!(graph["aaa"], vec![("bbb", 3), ("ccc", 1)]);
+        assert_eq!(
+            parse_data("aaa: bbb=3 ccc").unwrap()["aaa"],
+            vec!["bbb", "ccc"]
+        );
+        let error = parse_weighted_data("aaa: bbb\nbbb: out=x").unwrap_err();
+        assert!(error.to_string().starts_with("line 2: Expected a device"));
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::Result;
+use common::recurse::{Step, trampoline, trampoline_with_cache};
+use std::collections::HashMap;
+
+use crate::Graph;
+#[cfg(feature = "pathfinding")]
+use crate::WeightedGraph;
+
+/// Gets the children of a node.  Nodes with no line in the input, like "out", have none.
+fn children<'a>(graph: &Graph<'a>, node: &str) -> impl Iterator<Item = &'a str> {
+    graph.get(node).into_iter().flatten().copied()
+}
+
+/// How path counts are added up.  Dense graphs have more paths than fit in a `usize`,
+/// so the default fails rather than wrapping round to a wrong answer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PathArithmetic {
+    /// Counts in a `usize`, failing if the count overflows.
+    #[default]
+    Checked,
+    /// Counts in a `u128`, failing if even that overflows.
+    Wide,
+    /// Counts modulo a number, which never overflows.
+    Modulo(u128),
+}
+
+impl PathArithmetic {
+    /// Adds two counts that are already in range.
+    fn add(&self, a: u128, b: u128) -> Option<u128> {
+        match self {
+            PathArithmetic::Checked => a.checked_add(b).filter(|sum| usize::try_from(*sum).is_ok()),
+            PathArithmetic::Wide => a.checked_add(b),
+            // Both are below the modulus, so subtracting once is enough, and going via
+            // the gap to the modulus keeps a huge modulus from overflowing.
+            PathArithmetic::Modulo(modulus) => Some(if a >= modulus - b {
+                a - (modulus - b)
+            } else {
+                a + b
+            }),
+        }
+    }
+}
+
+/// Counts the distinct paths from `start` to `end`, failing if the count overflows or
+/// the graph has a cycle on the way.
+pub fn count_paths<'a>(graph: &Graph<'a>, start: &'a str, end: &str) -> Result<usize> {
+    let count = count_paths_with(graph, start, end, PathArithmetic::Checked)?;
+    Ok(usize::try_from(count)?)
+}
+
+/// Counts the distinct paths from `start` to `end` modulo `modulus`.
+pub fn count_paths_mod<'a>(
+    graph: &Graph<'a>,
+    start: &'a str,
+    end: &str,
+    modulus: u128,
+) -> Result<u128> {
+    count_paths_with(graph, start, end, PathArithmetic::Modulo(modulus))
+}
+
+/// Counts the distinct paths from `start` to `end` with the given arithmetic, failing
+/// if the count overflows it or the graph has a cycle on the way.
+pub fn count_paths_with<'a>(
+    graph: &Graph<'a>,
+    start: &'a str,
+    end: &str,
+    arithmetic: PathArithmetic,
+) -> Result<u128> {
+    let one = match arithmetic {
+        PathArithmetic::Modulo(0) => anyhow::bail!("Modulus must be positive"),
+        PathArithmetic::Modulo(modulus) => 1 % modulus,
+        PathArithmetic::Checked | PathArithmetic::Wide => 1,
+    };
+    // Many paths share the same nodes, so each node's count to the end is worked out
+    // once.
+    trampoline(
+        start,
+        |node| {
+            Ok(if *node == end {
+                Step::Leaf(one)
+            } else {
+                Step::Children(children(graph, node).collect())
+            })
+        },
+        |node, children| {
+            children
+                .iter()
+                .try_fold(0_u128, |count, (_, child_count)| {
+                    arithmetic.add(count, *child_count)
+                })
+                .ok_or_else(|| anyhow::anyhow!("Path count from {:?} overflowed", node))
+        },
+    )
+}
+
+/// Counts the paths that start at the first waypoint, visit every other waypoint in
+/// order, and finish at the last one.  Fails if a waypoint isn't in the graph, if the
+/// waypoints can only be visited in a different order, or if the count overflows.
+pub fn count_paths_via<'a>(graph: &Graph<'a>, waypoints: &[&'a str]) -> Result<usize> {
+    if waypoints.len() < 2 {
+        anyhow::bail!("Need at least a start and an end, got {:?}", waypoints);
+    }
+    if let Some(missing) = waypoints.iter().find(|node| !contains_node(graph, node)) {
+        anyhow::bail!("Waypoint {:?} is not in the graph", missing);
+    }
+
+    common::product_results(waypoints.windows(2).map(|pair| {
+        let (from, to) = (pair[0], pair[1]);
+        let count = count_paths(graph, from, to)?;
+        if count == 0 && count_paths(graph, to, from)? > 0 {
+            anyhow::bail!("Waypoint {:?} comes before {:?}, not after", to, from);
+        }
+        Ok(count)
+    }))
+}
+
+/// Is the node in the graph, either with its own line or as a child of another node?
+fn contains_node(graph: &Graph<'_>, node: &str) -> bool {
+    graph.contains_key(node) || graph.values().flatten().any(|child| *child == node)
+}
+
+/// Finds a path from `start` to `end` with the fewest steps, as the sequence of nodes
+/// visited including both ends.  Returns None if `end` can't be reached.
+#[cfg(feature = "pathfinding")]
+pub fn shortest_path<'a>(graph: &Graph<'a>, start: &'a str, end: &str) -> Option<Vec<&'a str>> {
+    pathfinding::directed::bfs::bfs(
+        &start,
+        |node| children(graph, node).collect::<Vec<_>>(),
+        |node| *node == end,
+    )
+}
+
+/// Finds the cheapest path from `start` to `end` by total weight, as the sequence of
+/// nodes visited including both ends along with its weight.  Returns None if `end`
+/// can't be reached.
+#[cfg(feature = "pathfinding")]
+pub fn shortest_weighted_path<'a>(
+    graph: &WeightedGraph<'a>,
+    start: &'a str,
+    end: &str,
+) -> Option<(Vec<&'a str>, u64)> {
+    pathfinding::directed::dijkstra::dijkstra(
+        &start,
+        |node| {
+            graph
+                .get(node)
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>()
+        },
+        |node| *node == end,
+    )
+}
+
+/// Finds a path from `start` to `end` with the most steps, as the sequence of nodes
+/// visited including both ends.  Returns None if `end` can't be reached, and fails if
+/// the graph has a cycle reachable from `start`, since the longest path would be unbounded.
+pub fn longest_path_dag<'a>(
+    graph: &Graph<'a>,
+    start: &'a str,
+    end: &str,
+) -> Result<Option<Vec<&'a str>>> {
+    let mut longest_cache = HashMap::new();
+    if longest_from(graph, start, end, &mut longest_cache)?.is_none() {
+        return Ok(None);
+    }
+
+    // Follow the recorded choices from the start to rebuild the path.
+    let mut path = vec![start];
+    let mut node = start;
+    while node != end {
+        let Some(Some((_, Some(next)))) = longest_cache.get(node) else {
+            anyhow::bail!("Path from {:?} was not recorded", node);
+        };
+        node = next;
+        path.push(node);
+    }
+    Ok(Some(path))
+}
+
+/// The number of steps on the longest path from a node to the end, and the child to
+/// take first.  None if the end can't be reached from the node.
+type LongestFrom<'a> = Option<(usize, Option<&'a str>)>;
+
+fn longest_from<'a>(
+    graph: &Graph<'a>,
+    node: &'a str,
+    end: &str,
+    cache: &mut HashMap<&'a str, LongestFrom<'a>>,
+) -> Result<LongestFrom<'a>> {
+    trampoline_with_cache(
+        node,
+        cache,
+        |node| {
+            Ok(if *node == end {
+                Step::Leaf(Some((0, None)))
+            } else {
+                Step::Children(children(graph, node).collect())
+            })
+        },
+        |_, children| {
+            let mut longest: LongestFrom<'a> = None;
+            for (child, child_longest) in children {
+                if let Some((steps, _)) = child_longest
+                    && longest.is_none_or(|(best, _)| steps + 1 > best)
+                {
+                    longest = Some((steps + 1, Some(child)));
+                }
+            }
+            Ok(longest)
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_path(graph: &Graph<'_>, path: &[&str]) -> bool {
+        path.windows(2)
+            .all(|pair| children(graph, pair[0]).any(|child| child == pair[1]))
+    }
+
+    #[test]
+    fn test_count_paths() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let graph = crate::parse_data(&data).unwrap();
+        assert_eq!(count_paths(&graph, "you", "out").unwrap(), 5);
+        assert_eq!(count_paths(&graph, "out", "you").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_count_paths_via() {
+        let data = std::fs::read_to_string("sample2.txt").unwrap();
+        let graph = crate::parse_data(&data).unwrap();
+        assert_eq!(
+            count_paths_via(&graph, &["svr", "fft", "dac", "out"]).unwrap(),
+            2
+        );
+        assert_eq!(count_paths_via(&graph, &["svr", "out"]).unwrap(), 8);
+
+        let err = count_paths_via(&graph, &["svr", "dac", "fft", "out"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Waypoint \"fft\" comes before \"dac\", not after"
+        );
+        assert!(count_paths_via(&graph, &["svr", "xyz", "out"]).is_err());
+        assert!(count_paths_via(&graph, &["svr"]).is_err());
+    }
+
+    #[test]
+    fn test_count_paths_overflow() {
+        // Each layer doubles the number of paths.
+        let data = (0..70)
+            .map(|i| {
+                format!(
+                    "n{}: a{} b{}\na{}: n{}\nb{}: n{}",
+                    i,
+                    i,
+                    i,
+                    i,
+                    i + 1,
+                    i,
+                    i + 1
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let graph = crate::parse_data(&data).unwrap();
+        assert_eq!(count_paths(&graph, "n0", "n10").unwrap(), 1024);
+        assert!(count_paths(&graph, "n0", "n70").is_err());
+        assert!(count_paths_via(&graph, &["n0", "n40", "n70"]).is_err());
+
+        let wide = count_paths_with(&graph, "n0", "n70", PathArithmetic::Wide).unwrap();
+        assert_eq!(wide, 1 << 70);
+        assert_eq!(count_paths_mod(&graph, "n0", "n10", 1000).unwrap(), 24);
+        assert_eq!(
+            count_paths_mod(&graph, "n0", "n70", 1_000_000_007).unwrap(),
+            (1_u128 << 70) % 1_000_000_007
+        );
+        assert_eq!(
+            count_paths_mod(&graph, "n0", "n70", u128::MAX).unwrap(),
+            1 << 70
+        );
+        assert_eq!(count_paths_mod(&graph, "n0", "n70", 1).unwrap(), 0);
+        assert!(count_paths_mod(&graph, "n0", "n70", 0).is_err());
+    }
+
+    #[cfg(feature = "pathfinding")]
+    #[test]
+    fn test_shortest_path() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let graph = crate::parse_data(&data).unwrap();
+        let path = shortest_path(&graph, "you", "out").unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!((path[0], path[3]), ("you", "out"));
+        assert!(is_path(&graph, &path));
+        assert_eq!(shortest_path(&graph, "you", "you").unwrap(), vec!["you"]);
+        assert_eq!(shortest_path(&graph, "out", "you"), None);
+    }
+
+    #[cfg(feature = "pathfinding")]
+    #[test]
+    fn test_shortest_weighted_path() {
+        let data = "you: aaa=1 bbb=5\naaa: ccc=10 out=20\nbbb: out=3\nccc: out=1";
+        let graph = crate::parse_weighted_data(data).unwrap();
+        assert_eq!(
+            shortest_weighted_path(&graph, "you", "out"),
+            Some((vec!["you", "bbb", "out"], 8))
+        );
+        assert_eq!(shortest_weighted_path(&graph, "out", "you"), None);
+
+        // Without weights every step costs 1, so it agrees with the unweighted search.
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let graph = crate::parse_weighted_data(&data).unwrap();
+        let (path, weight) = shortest_weighted_path(&graph, "you", "out").unwrap();
+        assert_eq!(weight as usize, path.len() - 1);
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn test_longest_path_dag() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let graph = crate::parse_data(&data).unwrap();
+        let path = longest_path_dag(&graph, "you", "out").unwrap().unwrap();
+        assert_eq!(path.len(), 5);
+        assert!(is_path(&graph, &path));
+
+        let data = std::fs::read_to_string("sample2.txt").unwrap();
+        let graph = crate::parse_data(&data).unwrap();
+        let path = longest_path_dag(&graph, "svr", "out").unwrap().unwrap();
+        assert_eq!(path.len(), 9);
+        assert!(is_path(&graph, &path));
+        assert_eq!(longest_path_dag(&graph, "out", "svr").unwrap(), None);
+    }
+
+    #[test]
+    fn test_longest_path_cycle() {
+        let graph = crate::parse_data("a: b\nb: c\nc: a out").unwrap();
+        assert!(longest_path_dag(&graph, "a", "out").is_err());
+    }
+}
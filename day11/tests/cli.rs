@@ -0,0 +1,9 @@
+#[test]
+fn test_sample_output() {
+    common::golden::check_stdout(
+        env!("CARGO_BIN_EXE_day11"),
+        "sample2.txt",
+        "tests/golden/sample2.stdout",
+    )
+    .unwrap();
+}
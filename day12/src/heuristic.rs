@@ -0,0 +1,335 @@
+//! A fast packer that doesn't search: it places the presents greedily, then moves them
+//! around until none overlap.
+//!
+//! The greedy pass puts each present, largest first, at the first spot it fits.  A
+//! present with no free spot goes where it overlaps least.  The repair pass then
+//! repeatedly takes an overlapping present and either moves it somewhere at random or
+//! swaps it with another present, keeping the change if it doesn't add overlap and
+//! sometimes even if it does, less often as the run goes on, like simulated annealing.
+//!
+//! A packing with no overlap is a real packing, so a success is certain.  Running out
+//! of steps proves nothing: the region may still be packable, and only the exact
+//! search can tell.
+
+use common::{bounds::bounding_box, grid::XY, random::Random};
+
+use crate::{
+    PresentId, Region,
+    solver::{Orientation, Placement, PresentOrder},
+};
+
+/// How many repair steps to try for each present in the region.
+pub const STEPS_PER_PRESENT: usize = 2_000;
+
+/// The seed for the repair pass, fixed so runs are repeatable.
+pub const SEED: u64 = 12;
+
+/// How much overlap a change can add and still usually be kept, at the start.
+const START_TEMPERATURE: f64 = 2.0;
+
+/// The temperature is multiplied by this each step.
+const COOLING: f64 = 0.9995;
+
+/// The coolest the repair pass gets, so it never stops taking some bad changes.
+const MIN_TEMPERATURE: f64 = 0.05;
+
+/// Tries to pack the region's presents, given every present's distinct orientations,
+/// within `steps` repair steps.  Gives the placements if none overlap.
+pub fn pack(
+    region: &Region,
+    orientations: &[Vec<Orientation>],
+    steps: usize,
+    seed: u64,
+) -> Option<Vec<Placement>> {
+    let mut layout = Layout::new(region, orientations);
+    let presents = orientations
+        .iter()
+        .map(|orientations| orientations[0].present.clone())
+        .collect::<Vec<_>>();
+    for id in PresentOrder::LargestFirst.order(&presents) {
+        for _ in 0..region.present_count[id] {
+            let piece = layout.least_overlapping(id)?;
+            layout.add(&piece);
+            layout.pieces.push(piece);
+        }
+    }
+
+    let mut random = Random::new(seed);
+    let mut temperature = START_TEMPERATURE;
+    for _ in 0..steps {
+        if layout.overlap == 0 {
+            break;
+        }
+        let before = layout.overlap;
+        let moved = layout.random_overlapping(&mut random);
+        let undo = if random.index(2) == 0 {
+            layout.relocate(moved, &mut random)
+        } else {
+            layout.swap(moved, &mut random)
+        };
+        let Some(undo) = undo else {
+            continue;
+        };
+        let worse = layout.overlap.saturating_sub(before) as f64;
+        if worse > 0.0 && random.unit() >= (-worse / temperature).exp() {
+            layout.undo(undo);
+        }
+        temperature = (temperature * COOLING).max(MIN_TEMPERATURE);
+    }
+
+    (layout.overlap == 0).then(|| {
+        layout
+            .pieces
+            .iter()
+            .map(|piece| Placement {
+                present: piece.present,
+                transform: orientations[piece.present][piece.orientation].transform,
+                offset: piece.offset.clone(),
+            })
+            .collect()
+    })
+}
+
+/// One present in the layout, which may overlap others.
+#[derive(Debug, Clone)]
+struct Piece {
+    present: PresentId,
+    /// The index into the present's orientations.
+    orientation: usize,
+    offset: XY,
+}
+
+/// What to put back to take a change to the layout back.
+enum Undo {
+    Relocate(usize, Piece),
+    Swap(usize, usize),
+}
+
+/// The presents laid out in the region, with how many cover each cell.
+struct Layout<'a> {
+    width: usize,
+    height: usize,
+    orientations: &'a [Vec<Orientation>],
+    /// How many presents cover each cell, row-major.
+    covers: Vec<u32>,
+    /// Cells covered more than once, counted once for each extra present.
+    overlap: usize,
+    pieces: Vec<Piece>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(region: &Region, orientations: &'a [Vec<Orientation>]) -> Self {
+        Layout {
+            width: region.xsize,
+            height: region.ysize,
+            orientations,
+            covers: vec![0; region.xsize * region.ysize],
+            overlap: 0,
+            pieces: Vec::new(),
+        }
+    }
+
+    /// Gets the row-major indexes of the cells a piece covers.  The piece must fit
+    /// inside the region.
+    fn cells(&self, piece: &Piece) -> impl Iterator<Item = usize> + use<'a> {
+        let width = self.width;
+        let offset = piece.offset.clone();
+        self.orientations[piece.present][piece.orientation]
+            .present
+            .occupied_cells()
+            .map(move |xy| (xy.y + offset.y) * width + xy.x + offset.x)
+    }
+
+    /// Gets how many columns and rows a present's filled cells reach from its offset.
+    /// Blank rows and columns in its grid don't count, since they needn't be in the
+    /// region.
+    fn reach(&self, present: PresentId, orientation: usize) -> (usize, usize) {
+        bounding_box(
+            self.orientations[present][orientation]
+                .present
+                .occupied_cells(),
+        )
+        .map_or((0, 0), |bounds| (bounds.max.x + 1, bounds.max.y + 1))
+    }
+
+    /// Checks if a piece lies inside the region.
+    fn fits(&self, piece: &Piece) -> bool {
+        let (width, height) = self.reach(piece.present, piece.orientation);
+        piece.offset.x + width <= self.width && piece.offset.y + height <= self.height
+    }
+
+    fn add(&mut self, piece: &Piece) {
+        for cell in self.cells(piece) {
+            if self.covers[cell] > 0 {
+                self.overlap += 1;
+            }
+            self.covers[cell] += 1;
+        }
+    }
+
+    fn remove(&mut self, piece: &Piece) {
+        for cell in self.cells(piece) {
+            self.covers[cell] -= 1;
+            if self.covers[cell] > 0 {
+                self.overlap -= 1;
+            }
+        }
+    }
+
+    /// Counts the cells a piece would cover that are already covered.
+    fn overlap_of(&self, piece: &Piece) -> usize {
+        self.cells(piece)
+            .filter(|cell| self.covers[*cell] > 0)
+            .count()
+    }
+
+    /// Finds the first spot, in row-major order, where a present overlaps least.  Gives
+    /// None if no orientation of it fits in the region at all.
+    fn least_overlapping(&self, present: PresentId) -> Option<Piece> {
+        let mut best: Option<(usize, Piece)> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for orientation in 0..self.orientations[present].len() {
+                    let piece = Piece {
+                        present,
+                        orientation,
+                        offset: XY::new(x, y),
+                    };
+                    if !self.fits(&piece) {
+                        continue;
+                    }
+                    let overlap = self.overlap_of(&piece);
+                    if overlap == 0 {
+                        return Some(piece);
+                    }
+                    if best.as_ref().is_none_or(|(least, _)| overlap < *least) {
+                        best = Some((overlap, piece));
+                    }
+                }
+            }
+        }
+        best.map(|(_, piece)| piece)
+    }
+
+    /// Picks a piece that overlaps another at random.  There must be overlap.
+    fn random_overlapping(&self, random: &mut Random) -> usize {
+        loop {
+            let i = random.index(self.pieces.len());
+            if self
+                .cells(&self.pieces[i])
+                .any(|cell| self.covers[cell] > 1)
+            {
+                return i;
+            }
+        }
+    }
+
+    /// Puts a new piece in place of the `i`th, giving the old one.
+    fn replace(&mut self, i: usize, piece: Piece) -> Piece {
+        self.add(&piece);
+        let old = std::mem::replace(&mut self.pieces[i], piece);
+        self.remove(&old);
+        old
+    }
+
+    /// Moves a piece to a random spot and orientation, if the one picked fits.
+    fn relocate(&mut self, i: usize, random: &mut Random) -> Option<Undo> {
+        let present = self.pieces[i].present;
+        let orientation = random.index(self.orientations[present].len());
+        let (width, height) = self.reach(present, orientation);
+        let offset = XY::new(
+            random.index((self.width + 1).checked_sub(width)?),
+            random.index((self.height + 1).checked_sub(height)?),
+        );
+        let piece = Piece {
+            present,
+            orientation,
+            offset,
+        };
+        Some(Undo::Relocate(i, self.replace(i, piece)))
+    }
+
+    /// Swaps where a piece and a random other present lie, each keeping its
+    /// orientation, if both still fit.
+    fn swap(&mut self, i: usize, random: &mut Random) -> Option<Undo> {
+        let j = random.index(self.pieces.len());
+        if self.pieces[i].present == self.pieces[j].present {
+            return None;
+        }
+        self.swap_offsets(i, j).then_some(Undo::Swap(i, j))
+    }
+
+    /// Swaps the offsets of two pieces, unless one would stick out of the region.
+    fn swap_offsets(&mut self, i: usize, j: usize) -> bool {
+        let mut first = self.pieces[i].clone();
+        let mut second = self.pieces[j].clone();
+        std::mem::swap(&mut first.offset, &mut second.offset);
+        if !self.fits(&first) || !self.fits(&second) {
+            return false;
+        }
+        self.replace(i, first);
+        self.replace(j, second);
+        true
+    }
+
+    fn undo(&mut self, undo: Undo) {
+        match undo {
+            Undo::Relocate(i, old) => {
+                self.replace(i, old);
+            }
+            Undo::Swap(i, j) => {
+                self.swap_offsets(i, j);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "nom"))]
+mod tests {
+    use super::*;
+    use crate::{Problem, parse::parse_problem, solver::all_orientations};
+
+    fn pack_all(problem: &Problem) -> Vec<Option<Vec<Placement>>> {
+        let orientations = problem
+            .presents
+            .iter()
+            .map(all_orientations)
+            .collect::<Vec<_>>();
+        problem
+            .regions
+            .iter()
+            .map(|region| {
+                let steps = STEPS_PER_PRESENT * region.present_count.iter().sum::<usize>();
+                pack(region, &orientations, steps, SEED)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pack_sample() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let solutions = pack_all(&problem);
+        assert!(solutions[0].is_some() && solutions[1].is_some());
+        // The third region can't be packed, so there is always some overlap left.
+        assert!(solutions[2].is_none());
+        // Drawing the packings checks they use the right presents without overlap.
+        let mut drawn = Vec::new();
+        problem.write_solution(&solutions, &mut drawn).unwrap();
+    }
+
+    #[test]
+    fn test_too_big_to_fit() {
+        let problem = parse_problem("0:\n###\n\n2x2: 1").unwrap();
+        assert_eq!(pack_all(&problem), vec![None]);
+    }
+
+    #[test]
+    fn test_blank_row_left_out() {
+        // The present's grid is 3x3, but its filled cells fit in two rows.
+        let problem = parse_problem("0:\n###\n###\n...\n\n3x2: 1").unwrap();
+        let solutions = pack_all(&problem);
+        assert!(solutions[0].is_some());
+        let mut drawn = Vec::new();
+        problem.write_solution(&solutions, &mut drawn).unwrap();
+    }
+}
@@ -1,6 +1,16 @@
-use anyhow::Result;
-use common::grid::{Grid, XY};
+use anyhow::{Context, Result};
+use common::{
+    bounds::bounding_box,
+    error::ErrorContext,
+    grid::{Grid, Transform, XY},
+    parse::ExpectFormat,
+};
+pub mod heuristic;
+#[cfg(feature = "nom")]
 pub mod parse;
+pub mod solver;
+
+use solver::Placement;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cell {
@@ -21,7 +31,8 @@ impl std::str::FromStr for Cell {
         match s {
             "." => Ok(Cell::Empty),
             "#" => Ok(Cell::Filled),
-            _ => Err(anyhow::anyhow!("Invalid cell: {}", s)),
+            _ => Err(anyhow::anyhow!("Invalid cell"))
+                .expect_format("'.' for empty or '#' for filled", s),
         }
     }
 }
@@ -68,8 +79,23 @@ impl Present {
     pub fn flip_vertical(&self) -> Self {
         Self::new(self.grid.flip_vertical())
     }
+    pub fn transform(&self, transform: Transform) -> Self {
+        Self::new(self.grid.transform(transform))
+    }
+    /// Gets the normal form of the present's filled cells, from
+    /// [`common::shapes::normalize`].  Two presents are congruent exactly when their
+    /// normal forms are equal, whatever blank rows or columns surround them.
+    pub fn normal_form(&self) -> Vec<XY> {
+        common::shapes::normalize(&self.occupied_cells)
+    }
 }
 
+common::define_index!(
+    /// The position of a present in `Problem::presents`, and of its count in
+    /// `Region::present_count`.
+    pub PresentId
+);
+
 #[derive(Debug, Clone)]
 pub struct Region {
     pub xsize: usize,
@@ -81,24 +107,105 @@ impl Region {
         &'a self,
         presents: &'a [Present],
     ) -> impl Iterator<Item = &'a Present> + Clone {
-        self.present_count
+        PresentId::range(self.present_count.len())
+            .map(|id| (id, self.present_count[id]))
+            .filter(|(_, count)| *count > 0)
+            .flat_map(move |(id, count)| {
+                let present = &presents[id];
+                (0..count).map(move |_| present)
+            })
+    }
+
+    /// Settles the region with cheap checks where possible, so the full search only
+    /// runs on the regions that need it.
+    pub fn quick_feasibility(&self, presents: &[Present]) -> Feasibility {
+        let needed = self.presents(presents).collect::<Vec<_>>();
+        let area = needed
             .iter()
-            .enumerate()
-            .filter(|(_, count)| **count > 0)
-            .flat_map(move |(i, count)| {
-                let present = presents.get(i).expect("present index out of bounds");
-                (0..*count).map(move |_| present)
+            .map(|present| present.occupied_cells.len())
+            .sum::<usize>();
+        if area > self.xsize * self.ysize {
+            return Feasibility::Impossible;
+        }
+        // Every present must fit inside the region one way round or the other.  Only
+        // its filled cells need to, not any blank rows or columns in its grid.
+        let fits = |width: usize, height: usize| width <= self.xsize && height <= self.ysize;
+        if !needed.iter().all(|present| {
+            bounding_box(present.occupied_cells()).is_none_or(|bounds| {
+                let (width, height) = (bounds.width(), bounds.height());
+                fits(width, height) || fits(height, width)
             })
+        }) {
+            return Feasibility::Impossible;
+        }
+        if self.tiled_placements(presents).is_some() {
+            return Feasibility::Trivial;
+        }
+        Feasibility::NeedsSearch
+    }
+}
+
+impl Region {
+    /// Packs the presents without searching, if they fit side by side: every present
+    /// gets its own tile the size of the largest bounding box, either as listed or with
+    /// every present turned on its side.  Tiles fill the region in row-major order.
+    pub fn tiled_placements(&self, presents: &[Present]) -> Option<Vec<Placement>> {
+        let needed = PresentId::range(self.present_count.len())
+            .flat_map(|id| std::iter::repeat_n(id, self.present_count[id]))
+            .collect::<Vec<_>>();
+        let max_width = needed.iter().map(|id| presents[*id].grid.width()).max()?;
+        let max_height = needed.iter().map(|id| presents[*id].grid.height()).max()?;
+        [
+            (Transform::Identity, max_width, max_height),
+            (Transform::R90, max_height, max_width),
+        ]
+        .into_iter()
+        .find_map(|(transform, width, height)| {
+            let columns = self.xsize / width.max(1);
+            let rows = self.ysize / height.max(1);
+            (needed.len() <= columns * rows).then(|| {
+                needed
+                    .iter()
+                    .enumerate()
+                    .map(|(i, id)| Placement {
+                        present: *id,
+                        transform,
+                        offset: XY::new(i % columns * width, i / columns * height),
+                    })
+                    .collect()
+            })
+        })
     }
 }
 
+/// What [`Region::quick_feasibility`] could tell without searching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feasibility {
+    /// The presents fit without any search, side by side in their bounding boxes.
+    Trivial,
+    /// The presents can't fit: they cover more cells than the region has, or one of
+    /// them is wider or taller than the region.
+    Impossible,
+    /// Only the full search can tell.
+    NeedsSearch,
+}
+
 #[derive(Debug)]
 pub struct Problem {
     pub presents: Vec<Present>,
     pub regions: Vec<Region>,
+    /// The presents as the input lists them, before congruent ones were merged.
+    pub listed_presents: Vec<Present>,
+    /// Each region's counts of the listed presents, indexed like `regions`.
+    pub listed_counts: Vec<Vec<usize>>,
+    /// The present in `presents` that each listed present was merged into.
+    pub merged_into: Vec<PresentId>,
 }
 impl Problem {
-    pub fn try_new(presents: Vec<Present>, regions: Vec<Region>) -> Result<Self> {
+    /// Builds a problem, merging presents that are congruent under rotation or
+    /// reflection so the solver doesn't explore the same shape twice.  The first of each
+    /// congruent group is kept, and each region's counts are added up to match.
+    pub fn try_new(presents: Vec<Present>, mut regions: Vec<Region>) -> Result<Self> {
         // length of each region's present count must be the same as the length of presents
         if regions
             .iter()
@@ -107,6 +214,446 @@ impl Problem {
             anyhow::bail!("Length of present count must be the same as the length of presents");
         }
 
-        Ok(Problem { presents, regions })
+        let listed_presents = presents.clone();
+        let listed_counts = regions
+            .iter()
+            .map(|region| region.present_count.clone())
+            .collect();
+        let mut kept = Vec::<Present>::new();
+        let mut normal_forms = Vec::<Vec<XY>>::new();
+        // Where each listed present ended up in `kept`.
+        let mut merged_into = Vec::with_capacity(presents.len());
+        for present in presents {
+            let form = present.normal_form();
+            match normal_forms.iter().position(|kept_form| *kept_form == form) {
+                Some(id) => merged_into.push(PresentId::new(id)),
+                None => {
+                    merged_into.push(PresentId::new(kept.len()));
+                    normal_forms.push(form);
+                    kept.push(present);
+                }
+            }
+        }
+        for region in &mut regions {
+            let mut present_count = vec![0; kept.len()];
+            for (id, count) in merged_into.iter().zip(&region.present_count) {
+                present_count[*id] += count;
+            }
+            region.present_count = present_count;
+        }
+
+        Ok(Problem {
+            presents: kept,
+            regions,
+            listed_presents,
+            listed_counts,
+            merged_into,
+        })
+    }
+    /// Writes each region in the input's own `<width>x<height>: <counts>` form, then
+    /// its packing with every placed present drawn in a letter of its own, like the
+    /// puzzle's examples.  The counts are of the presents as listed in the input, and
+    /// the letters go to the placed presents in the input's present order, so the
+    /// output lines up with the input even where congruent presents were merged.
+    /// `solutions` is indexed like `regions`, with `None` for a region that wasn't
+    /// packed.  Fails if a packing doesn't match its region.
+    pub fn write_solution(
+        &self,
+        solutions: &[Option<Vec<Placement>>],
+        mut writer: impl std::io::Write,
+    ) -> Result<()> {
+        if solutions.len() != self.regions.len() {
+            anyhow::bail!(
+                "{} solutions for {} regions",
+                solutions.len(),
+                self.regions.len()
+            );
+        }
+        for (i, (region, solution)) in self.regions.iter().zip(solutions).enumerate() {
+            if i > 0 {
+                writeln!(writer)?;
+            }
+            let counts = self.listed_counts[i]
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>();
+            writeln!(
+                writer,
+                "{}x{}: {}",
+                region.xsize,
+                region.ysize,
+                counts.join(" ")
+            )?;
+            match solution {
+                Some(placements) => {
+                    let grid = self
+                        .draw_packing(region, &self.in_listed_order(i, placements))
+                        .with_context(|| format!("Region {}", i))?;
+                    write!(writer, "{:?}", grid)?;
+                }
+                None => writeln!(writer, "no packing")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Orders the `i`th region's placements by the listed present each stands for:
+    /// each listed present, in input order, takes as many of the placements of the
+    /// present it was merged into as the region lists for it.
+    fn in_listed_order(&self, i: usize, placements: &[Placement]) -> Vec<Placement> {
+        let mut taken = vec![false; placements.len()];
+        let mut ordered = Vec::with_capacity(placements.len());
+        for (listed, count) in self.listed_counts[i].iter().enumerate() {
+            let merged = self.merged_into[listed];
+            for _ in 0..*count {
+                let Some(next) =
+                    (0..placements.len()).find(|&p| !taken[p] && placements[p].present == merged)
+                else {
+                    break;
+                };
+                taken[next] = true;
+                ordered.push(placements[next].clone());
+            }
+        }
+        // Anything left over doesn't match the region, which drawing reports.
+        ordered.extend(
+            placements
+                .iter()
+                .zip(&taken)
+                .filter(|(_, taken)| !**taken)
+                .map(|(placement, _)| placement.clone()),
+        );
+        ordered
+    }
+
+    /// Draws the placements into the region, checking they use exactly the region's
+    /// presents and don't overlap or stick out.
+    fn draw_packing(&self, region: &Region, placements: &[Placement]) -> Result<Grid<char>> {
+        let mut used = vec![0; self.presents.len()];
+        let mut grid = Grid::new_sized(region.xsize, region.ysize, '.');
+        let labels = ('A'..='Z').chain('a'..='z').cycle();
+        for (placement, label) in placements.iter().zip(labels) {
+            let present = self
+                .presents
+                .get(placement.present.index())
+                .ok_or_else(|| anyhow::anyhow!("No present {:?}", placement.present))?;
+            used[placement.present.index()] += 1;
+            for xy in present.transform(placement.transform).occupied_cells() {
+                let xy = xy.add(&placement.offset);
+                let cell = grid.get_mut(&xy).ok_or_else(|| {
+                    anyhow::anyhow!("Present placed outside the region at {:?}", xy)
+                })?;
+                if *cell != '.' {
+                    anyhow::bail!("Presents overlap at {:?}", xy);
+                }
+                *cell = label;
+            }
+        }
+        if used != region.present_count {
+            anyhow::bail!(
+                "Placed presents {:?} but the region needs {:?}",
+                used,
+                region.present_count
+            );
+        }
+        Ok(grid)
+    }
+}
+
+/// Writes the problem as the input listed it, before merging, so decoding merges it
+/// again.  Each present is `present <width> <height> <cells>`, its cells row-major as
+/// `#` for filled and `.` for empty, and each region as
+/// `region <width> <height> <counts...>`.  The cells are written here rather than
+/// through `Grid`'s Debug output, so changing how grids print can't break the cache.
+impl common::cache::Cacheable for Problem {
+    const VERSION: u32 = 2;
+
+    fn encode(&self) -> String {
+        let mut out = String::new();
+        for present in &self.listed_presents {
+            let cells = present
+                .grid
+                .rows()
+                .flatten()
+                .map(|cell| match cell {
+                    Cell::Empty => '.',
+                    Cell::Filled => '#',
+                })
+                .collect::<String>();
+            out.push_str(&format!(
+                "present {} {} {}\n",
+                present.grid.width(),
+                present.grid.height(),
+                cells
+            ));
+        }
+        for (region, counts) in self.regions.iter().zip(&self.listed_counts) {
+            out.push_str(&format!("region {} {}", region.xsize, region.ysize));
+            for count in counts {
+                out.push_str(&format!(" {}", count));
+            }
+            out.push('\n');
+        }
+        out
+    }
+    fn decode(s: &str) -> Result<Self> {
+        let mut presents = Vec::new();
+        let mut regions = Vec::new();
+        for (number, line) in (1..).zip(s.lines()) {
+            if let Some(present) = line.strip_prefix("present ") {
+                presents.push(decode_present(present).with_line(number)?);
+            } else if let Some(region) = line.strip_prefix("region ") {
+                let numbers = region
+                    .split(' ')
+                    .map(|n| n.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_line(number)?;
+                let [xsize, ysize, present_count @ ..] = numbers.as_slice() else {
+                    return Err(anyhow::anyhow!("Invalid cached region: {}", line))
+                        .with_line(number)?;
+                };
+                regions.push(Region {
+                    xsize: *xsize,
+                    ysize: *ysize,
+                    present_count: present_count.to_vec(),
+                });
+            } else {
+                return Err(anyhow::anyhow!("Invalid cache line: {}", line)).with_line(number)?;
+            }
+        }
+        Problem::try_new(presents, regions)
+    }
+}
+
+/// Reads a present written by [`Problem`]'s cache encoding, after `present `.
+fn decode_present(s: &str) -> Result<Present> {
+    let [width, height, cells] = s.split(' ').collect::<Vec<_>>()[..] else {
+        anyhow::bail!("Expected a width, a height and the cells");
+    };
+    let (width, height) = (width.parse::<usize>()?, height.parse::<usize>()?);
+    if cells.len() != width * height {
+        anyhow::bail!("{} cells for a {}x{} present", cells.len(), width, height);
+    }
+    let cells = cells
+        .chars()
+        .map(|c| match c {
+            '.' => Ok(Cell::Empty),
+            '#' => Ok(Cell::Filled),
+            _ => Err(anyhow::anyhow!("Invalid cached cell: {:?}", c)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let rows = cells
+        .chunks(width.max(1))
+        .map(|row| row.to_vec())
+        .collect::<Vec<_>>();
+    Ok(Present::new(Grid::from_rows(rows)?))
+}
+
+#[cfg(all(test, feature = "nom"))]
+mod tests {
+    use common::cache::Cacheable;
+
+    use super::*;
+
+    #[test]
+    fn test_write_solution() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let problem = parse::parse_problem(&data).unwrap();
+        let mut solver = solver::Solver::new(&problem.presents);
+        let solutions = vec![
+            solver
+                .solve_with_placements(&problem.regions[0])
+                .unwrap()
+                .map(|(_, placements)| placements),
+            None,
+            None,
+        ];
+        let mut out = Vec::new();
+        problem.write_solution(&solutions, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "4x4: 0 0 0 0 2 0");
+        // Two presents of seven cells, drawn as A and B.
+        let drawn = lines[1..5].concat();
+        assert_eq!(drawn.matches('A').count(), 7);
+        assert_eq!(drawn.matches('B').count(), 7);
+        assert_eq!(
+            &lines[5..],
+            [
+                "",
+                "12x5: 1 0 1 0 2 2",
+                "no packing",
+                "",
+                "12x5: 1 0 1 0 3 2",
+                "no packing"
+            ]
+        );
+
+        // The same present twice in one place overlaps.
+        let mut overlapping = solutions[0].clone().unwrap();
+        overlapping[1].offset = overlapping[0].offset.clone();
+        overlapping[1].transform = overlapping[0].transform;
+        assert!(
+            problem
+                .write_solution(&[Some(overlapping), None, None], std::io::sink())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_write_solution_lists_merged_presents() {
+        // The first and last presents are the same L, so they are merged.
+        let problem =
+            parse::parse_problem("0:\n#.\n#.\n##\n\n1:\n##\n##\n\n2:\n###\n#..\n\n4x4: 1 1 1")
+                .unwrap();
+        assert_eq!(problem.regions[0].present_count, vec![2, 1]);
+        let mut solver = solver::Solver::new(&problem.presents);
+        let (_, placements) = solver
+            .solve_with_placements(&problem.regions[0])
+            .unwrap()
+            .unwrap();
+        let mut out = Vec::new();
+        problem
+            .write_solution(&[Some(placements)], &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "4x4: 1 1 1");
+        // The letters follow the listed presents: B is the square, A and C the Ls.
+        let drawn = lines[1..].concat();
+        for (label, cells) in [('A', 4), ('B', 4), ('C', 4)] {
+            assert_eq!(drawn.matches(label).count(), cells, "{}", label);
+        }
+        let square = lines[1..]
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.char_indices().map(move |(x, c)| (x, y, c)))
+            .filter(|(_, _, c)| *c == 'B')
+            .map(|(x, y, _)| XY::new(x, y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            common::shapes::normalize(&square),
+            common::shapes::normalize(&problem.listed_presents[1].occupied_cells)
+        );
+    }
+
+    #[test]
+    fn test_tiled_placements_draw() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let problem = parse::parse_problem(&data).unwrap();
+        let region = Region {
+            xsize: 3,
+            ysize: 6,
+            present_count: vec![1, 0, 0, 0, 1, 0],
+        };
+        let placements = region.tiled_placements(&problem.presents).unwrap();
+        let grid = problem.draw_packing(&region, &placements).unwrap();
+        assert_eq!(format!("{:?}", grid), "AAA\nAA.\nAA.\nBBB\nB..\nBBB\n");
+    }
+
+    #[test]
+    fn test_quick_feasibility() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let problem = parse::parse_problem(&data).unwrap();
+        let region = |xsize, ysize, present_count: Vec<usize>| Region {
+            xsize,
+            ysize,
+            present_count,
+        };
+        let check = |region: Region| region.quick_feasibility(&problem.presents);
+        assert_eq!(
+            check(region(3, 3, vec![1, 0, 0, 0, 0, 0])),
+            Feasibility::Trivial
+        );
+        assert_eq!(
+            check(region(6, 3, vec![1, 0, 0, 0, 1, 0])),
+            Feasibility::Trivial
+        );
+        assert_eq!(
+            check(region(3, 6, vec![1, 0, 0, 0, 1, 0])),
+            Feasibility::Trivial
+        );
+        assert_eq!(
+            check(region(2, 9, vec![1, 0, 0, 0, 0, 0])),
+            Feasibility::Impossible
+        );
+        assert_eq!(
+            check(region(5, 5, vec![0, 0, 0, 0, 4, 0])),
+            Feasibility::Impossible
+        );
+        assert_eq!(check(problem.regions[0].clone()), Feasibility::NeedsSearch);
+    }
+
+    #[test]
+    fn test_blank_row_left_out() {
+        // The present's grid is 3x3, but its filled cells fit in the 3x2 region.
+        let problem = parse::parse_problem("0:\n###\n###\n...\n\n3x2: 1").unwrap();
+        let region = &problem.regions[0];
+        assert_eq!(
+            region.quick_feasibility(&problem.presents),
+            Feasibility::NeedsSearch
+        );
+        let solved = solver::Solver::new(&problem.presents)
+            .solve(region)
+            .unwrap();
+        assert!(solved.is_some());
+    }
+
+    #[test]
+    fn test_congruent_presents_merge() {
+        let present = |rows: &[&str]| Present::new(Grid::from_lines(rows).unwrap());
+        let l_shape = present(&["#.", "#.", "##"]);
+        assert_eq!(
+            l_shape.normal_form(),
+            present(&["##", ".#", ".#"]).normal_form()
+        );
+        assert_ne!(
+            l_shape.normal_form(),
+            present(&["##", "##", "#."]).normal_form()
+        );
+        // Blank rows don't change the form.
+        assert_eq!(
+            l_shape.normal_form(),
+            present(&["...", "###", "#.."]).normal_form()
+        );
+
+        let problem = Problem::try_new(
+            vec![
+                l_shape.clone(),
+                present(&["##", "##"]),
+                present(&["###", "#.."]),
+                // The same L with a blank row above it.
+                present(&["...", "###", "#.."]),
+            ],
+            vec![Region {
+                xsize: 4,
+                ysize: 4,
+                present_count: vec![1, 2, 3, 1],
+            }],
+        )
+        .unwrap();
+        assert_eq!(problem.presents, vec![l_shape, present(&["##", "##"])]);
+        assert_eq!(problem.regions[0].present_count, vec![5, 2]);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let problem = parse::parse_problem(&data).unwrap();
+        let encoded = problem.encode();
+        assert!(encoded.starts_with("present 3 3 #####.##.\n"));
+        let decoded = Problem::decode(&encoded).unwrap();
+        assert_eq!(decoded.presents, problem.presents);
+        assert_eq!(decoded.regions.len(), problem.regions.len());
+        for (decoded, region) in decoded.regions.iter().zip(problem.regions.iter()) {
+            assert_eq!(
+                (decoded.xsize, decoded.ysize, &decoded.present_count),
+                (region.xsize, region.ysize, &region.present_count)
+            );
+        }
+        assert_eq!(decoded.listed_presents, problem.listed_presents);
+        assert_eq!(decoded.listed_counts, problem.listed_counts);
+        assert!(Problem::decode("present 3 3 ####.##.\n").is_err());
+        assert!(Problem::decode("present 1 1 x\n").is_err());
     }
 }
@@ -1,141 +1,135 @@
-use std::{
-    collections::HashSet,
-    sync::atomic::{AtomicUsize, Ordering},
-};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::Result;
-use common::grid::{Grid, XY};
-use day12::{Cell, Present, Problem, parse::parse_problem};
-use rayon::prelude::*;
+use common::cache::ParsedCache;
+use common::deadline::{Deadline, is_expired};
+use common::error::ErrorContext;
+use common::par::prelude::*;
+use day12::{
+    Feasibility, Problem,
+    parse::parse_problem,
+    solver::{Placement, Solver, Strategy},
+};
+
+/// The flags that take a value, which can't be the input file.
+const FLAGS_WITH_VALUES: [&str; 3] = ["--profile", "--solution-out", "--strategy"];
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &FLAGS_WITH_VALUES)?;
+    // --cache keeps the parsed problem next to the input for later runs.
+    let use_cache = args.iter().any(|arg| arg == "--cache");
+    // --profile <path> writes folded stacks for a flamegraph (needs the profile feature).
+    let profile_path = args.iter().skip_while(|arg| *arg != "--profile").nth(1);
+    if profile_path.is_some() && !common::profile::enabled() {
+        anyhow::bail!("--profile needs day12 built with --features profile");
+    }
+    // --solution-out <path> writes every packing found in part 1 in the input's format.
+    let solution_path = args
+        .iter()
+        .skip_while(|arg| *arg != "--solution-out")
+        .nth(1);
+    // --strategy <exact|heuristic> picks how regions that need a search are packed.
+    let strategy = args
+        .iter()
+        .skip_while(|arg| *arg != "--strategy")
         .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let problem = parse_problem(&data)?;
-    println!("Part 1: {}", part1(&problem)?);
-    println!("Part 2: {}", part2(&problem)?);
+        .map(|strategy| strategy.parse())
+        .transpose()?
+        .unwrap_or_default();
+    let data = common::read_file(arg1)?;
+    let problem = common::alloc::phase("Parse", || {
+        if use_cache {
+            ParsedCache::for_input(arg1).load_or_parse(&data, parse_problem)
+        } else {
+            parse_problem(&data)
+        }
+    })
+    .with_file(arg1)
+    .with_phase("parse")
+    .with_day(12)?;
+    // AOC_TIMEOUT, set by `aoc run --timeout`, stops part 1 if it runs too long.
+    let deadline = Deadline::from_env()?;
+    let solutions = common::alloc::phase("Part 1", || part1(&problem, strategy, deadline))
+        .with_phase("part 1")
+        .with_day(12)?;
+    println!(
+        "Part 1: {}",
+        solutions
+            .iter()
+            .filter(|solution| solution.is_some())
+            .count()
+    );
+    if let Some(path) = solution_path {
+        problem.write_solution(
+            &solutions,
+            std::io::BufWriter::new(std::fs::File::create(path)?),
+        )?;
+        println!("Wrote packings to {}", path);
+    }
+    println!(
+        "Part 2: {}",
+        common::alloc::phase("Part 2", || part2(&problem))
+            .with_phase("part 2")
+            .with_day(12)?
+    );
+    if let Some(path) = profile_path {
+        common::profile::write_folded(std::fs::File::create(path)?)?;
+        println!("Wrote folded stacks to {}", path);
+    }
     Ok(())
 }
 
-fn part1(problem: &Problem) -> Result<usize> {
-    let grids = problem.regions.par_iter().map(|region| {
-        let presents_in_region = region.presents(&problem.presents);
-        let empty_grid = Grid::new_sized(region.xsize, region.ysize, Cell::Empty);
-        (empty_grid, presents_in_region)
-    });
-
+/// Packs every region that can be packed, giving the placements for each.  If the
+/// deadline passes, says how many regions were finished before it did.
+fn part1(
+    problem: &Problem,
+    strategy: Strategy,
+    deadline: Deadline,
+) -> Result<Vec<Option<Vec<Placement>>>> {
     let total_count = problem.regions.len();
     let count = AtomicUsize::new(0);
 
-    let solved_grids = grids
-        .map(|(grid, presents_in_region)| {
-            println!("Solving grid: {} x {}", grid.width(), grid.height());
-            let res = solve_grid(&grid, presents_in_region);
+    let results = problem
+        .regions
+        .par_iter()
+        .map(|region| {
+            match region.quick_feasibility(&problem.presents) {
+                Feasibility::Trivial => return Ok(region.tiled_placements(&problem.presents)),
+                Feasibility::Impossible => return Ok(None),
+                Feasibility::NeedsSearch => {}
+            }
+            println!("Solving grid: {} x {}", region.xsize, region.ysize);
+            let mut solver = Solver::new(&problem.presents)
+                .with_deadline(deadline)
+                .with_strategy(strategy);
+            let res = solver.solve_parallel_with_placements(region);
             let cur_count = count.fetch_add(1, Ordering::Relaxed);
             println!(
                 "Solved grid {} x {}.  {}/{}",
-                grid.width(),
-                grid.height(),
-                cur_count,
-                total_count
+                region.xsize, region.ysize, cur_count, total_count
             );
+            if let Ok(Some((grid, _))) = &res {
+                println!("Grid:\n{:?}", grid);
+            }
 
-            res
+            res.map(|solved| solved.map(|(_, placements)| placements))
         })
-        .collect::<Result<Vec<_>>>()?;
-    let solved_grid_count = solved_grids.iter().filter(|grid| grid.is_some()).count();
-    Ok(solved_grid_count)
+        .collect::<Vec<_>>();
+    if results
+        .iter()
+        .any(|res| res.as_ref().is_err_and(is_expired))
+    {
+        let finished = results.iter().filter(|res| res.is_ok()).count();
+        anyhow::bail!(
+            "Gave up on part 1 after finishing {} of {} regions",
+            finished,
+            total_count
+        );
+    }
+    results.into_iter().collect()
 }
 
 fn part2(_problem: &Problem) -> Result<usize> {
     Ok(0)
 }
-
-fn solve_grid<'a>(
-    grid: &Grid<Cell>,
-    mut presents: impl Iterator<Item = &'a Present> + Clone,
-) -> Result<Option<()>> {
-    let Some(this_present) = presents.next() else {
-        println!("Solved!");
-        println!("Grid:\n{:?}", grid);
-        return Ok(Some(()));
-    };
-
-    let all_orientations = all_orientations(this_present).collect::<HashSet<_>>();
-
-    for orientation in all_orientations {
-        // println!("Trying to place:\n{:?}", orientation.grid);
-        // println!("Inside grid:\n{:?}", grid);
-        // println!("--------------------------------");
-        let result = place_present_and_solve(grid, &orientation, presents.clone())?;
-        if result.is_some() {
-            return Ok(result);
-        }
-    }
-    Ok(None)
-}
-
-fn all_orientations(present: &Present) -> impl Iterator<Item = Present> + Clone + use<> {
-    [
-        present.clone(),
-        present.rotate_90(),
-        present.rotate_180(),
-        present.rotate_270(),
-        present.flip_horizontal(),
-        present.flip_vertical(),
-    ]
-    .into_iter()
-}
-
-fn place_present_and_solve<'a>(
-    grid: &Grid<Cell>,
-    present: &Present,
-    presents: impl Iterator<Item = &'a Present> + Clone,
-) -> Result<Option<()>> {
-    let possible_placements = xy_possibilities(
-        grid.width(),
-        grid.height(),
-        present.grid.width(),
-        present.grid.height(),
-    )
-    .filter(|xy| can_place_present(grid, present, xy));
-    for placement in possible_placements {
-        let mut grid = grid.clone();
-        place_present(&mut grid, present, &placement)?;
-        let result = solve_grid(&grid, presents.clone())?;
-        if result.is_some() {
-            return Ok(result);
-        }
-    }
-    Ok(None)
-}
-
-fn place_present(grid: &mut Grid<Cell>, present: &Present, offset: &XY) -> Result<()> {
-    for xy in present.occupied_cells().map(|xy| xy.add(offset)) {
-        let cell = grid
-            .get_mut(&xy)
-            .ok_or_else(|| anyhow::anyhow!("xy should be in grid"))?;
-        *cell = Cell::Filled;
-    }
-    Ok(())
-}
-
-fn xy_possibilities(
-    grid_xsize: usize,
-    grid_ysize: usize,
-    present_xsize: usize,
-    present_ysize: usize,
-) -> impl Iterator<Item = XY> {
-    (0..grid_xsize - present_xsize + 1)
-        .flat_map(move |x| (0..grid_ysize - present_ysize + 1).map(move |y| XY::new(x, y)))
-}
-
-fn can_place_present(grid: &Grid<Cell>, present: &Present, offset: &XY) -> bool {
-    present
-        .occupied_cells()
-        .map(|xy| xy.add(offset))
-        .map(|xy| grid.get(xy).expect("xy should be in grid"))
-        .all(|cell| cell.value() == &Cell::Empty)
-}
@@ -8,10 +8,14 @@
 // fit into that region by listing the quantity of each shape of present;
 
 use anyhow::Result;
-use common::grid::Grid;
+use common::{
+    error::{AocError, line_and_column},
+    grid::Grid,
+    parse::{expect_format, int, is_int_char},
+};
 use nom::{
     IResult, Parser,
-    bytes::complete::is_a,
+    bytes::complete::{is_a, take_while1},
     character::complete::{char, digit1, line_ending, space1},
     combinator::map_res,
     multi::{many1, separated_list0, separated_list1},
@@ -20,10 +24,25 @@ use nom::{
 
 use crate::{Present, Problem, Region};
 
+/// What the input should look like, for parse errors.
+const PROBLEM_FORMAT: &str =
+    "presents like '0:' followed by rows of '#' and '.', then regions like '12x5: 1 0 1 0 2 2'";
+
 /// Parses the entire problem from input string
 pub fn parse_problem(input: &str) -> Result<Problem> {
-    let (_, problem) = parse_problem_internal(input)
-        .map_err(|e| anyhow::anyhow!("Failed to parse problem: {}", e))?;
+    let (_, problem) = parse_problem_internal(input).map_err(|e| {
+        let error = anyhow::anyhow!("Failed to parse problem: {}", e);
+        match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                let (line, column) = line_and_column(input, input.len() - e.input.len());
+                let text = input.lines().nth(line - 1).unwrap_or_default();
+                AocError::new(expect_format(error, PROBLEM_FORMAT, text))
+                    .at_line(line)
+                    .at_column(column)
+            }
+            nom::Err::Incomplete(_) => AocError::new(error),
+        }
+    })?;
     Ok(problem)
 }
 
@@ -51,7 +70,7 @@ fn parse_present(input: &str) -> IResult<&str, Present> {
             line_ending,
             separated_list1(line_ending, parse_grid_line),
         ),
-        |(_index, _, grid_lines)| Grid::from_lines(grid_lines).map(|grid| Present::new(grid)),
+        |(_index, _, grid_lines)| Grid::from_lines(grid_lines).map(Present::new),
     )
     .parse(input)
 }
@@ -84,7 +103,7 @@ fn parse_dimensions(input: &str) -> IResult<&str, (usize, usize)> {
 }
 
 fn parse_usize(input: &str) -> IResult<&str, usize> {
-    map_res(digit1, |s: &str| s.parse::<usize>()).parse(input)
+    map_res(take_while1(is_int_char), int::<usize>).parse(input)
 }
 
 #[cfg(test)]
@@ -127,6 +146,18 @@ mod tests {
         assert_eq!(present.grid.height(), 3);
     }
 
+    #[test]
+    fn test_parse_error_names_the_format() {
+        let error = parse_problem("0:\n##\n#x\n\n4x4: 1").unwrap_err();
+        let message = error.to_string();
+        assert!(
+            message.starts_with("line 3, column 2: Expected presents like"),
+            "{}",
+            message
+        );
+        assert!(message.ends_with("got \"#x\""), "{}", message);
+    }
+
     #[test]
     fn test_parse_sample() {
         let sample = include_str!("../sample.txt");
@@ -0,0 +1,889 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use common::{
+    deadline::{Deadline, Expired},
+    grid::{Grid, Transform, XY},
+    parse::ExpectFormat,
+    profile::span,
+    scratch::ScratchPool,
+    search::{
+        BranchAndBound, DEADLINE_CHECK_INTERVAL, branch_and_bound_until, parallel_dfs_remembering,
+    },
+};
+
+use crate::{Cell, Present, PresentId, Region, heuristic};
+
+/// Hit and miss counters for a [`TranspositionTable`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TableStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// Remembers search states that are known to be unsolvable.
+///
+/// A state is keyed by the filled cells of the grid plus the multiset of presents
+/// still left to place, so a failed sub-state reached through a different placement
+/// order is never explored twice.  The whole state is kept rather than a hash of it,
+/// since two states sharing a hash would wrongly prune a solvable one.
+#[derive(Debug, Default)]
+pub struct TranspositionTable {
+    failed: HashSet<StateKey>,
+    stats: TableStats,
+}
+
+/// A search state as the transposition table and solution counts remember it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct StateKey {
+    /// One bit per cell, row-major, set if the cell is filled.
+    filled: Vec<u64>,
+    /// The first empty cell not yet decided.
+    anchor: usize,
+    /// How many of each present are left to place.
+    remaining: Vec<usize>,
+}
+
+impl TranspositionTable {
+    /// Returns true if the state is already known to be unsolvable.
+    fn is_known_failure(&mut self, key: &StateKey) -> bool {
+        let hit = self.failed.contains(key);
+        if hit {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        hit
+    }
+    fn record_failure(&mut self, key: StateKey) {
+        self.failed.insert(key);
+    }
+    /// Gets the hit and miss counters.
+    pub fn stats(&self) -> TableStats {
+        self.stats
+    }
+    /// Gets the number of failed states stored.
+    pub fn len(&self) -> usize {
+        self.failed.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The order in which presents are tried when covering a cell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PresentOrder {
+    /// The order the presents are listed in the problem.
+    Input,
+    /// Presents with the largest area first.
+    #[default]
+    LargestFirst,
+    /// Presents with the smallest area first.
+    SmallestFirst,
+}
+impl PresentOrder {
+    /// Returns the present indices in the order they should be tried.
+    pub fn order(&self, presents: &[Present]) -> Vec<PresentId> {
+        let mut order = PresentId::range(presents.len()).collect::<Vec<_>>();
+        // Stable sorts, so presents of equal area stay in input order.
+        match self {
+            PresentOrder::Input => {}
+            PresentOrder::LargestFirst => {
+                order.sort_by_key(|id| std::cmp::Reverse(presents[*id].occupied_cells.len()))
+            }
+            PresentOrder::SmallestFirst => {
+                order.sort_by_key(|id| presents[*id].occupied_cells.len())
+            }
+        }
+        order
+    }
+}
+
+/// How a [`Solver`] packs a region.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// The exact search, which always settles the region.
+    #[default]
+    Exact,
+    /// Try the [`heuristic`](crate::heuristic) packer first, which is fast on large
+    /// regions with room to spare, and only search the regions it can't pack.
+    Heuristic,
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "exact" => Ok(Strategy::Exact),
+            "heuristic" => Ok(Strategy::Heuristic),
+            _ => Err(anyhow::anyhow!("Unknown strategy")).expect_format("exact or heuristic", s),
+        }
+    }
+}
+
+/// One way a present can lie, with the transform of the present as given that makes it.
+#[derive(Debug, Clone)]
+pub struct Orientation {
+    pub transform: Transform,
+    pub present: Present,
+}
+
+/// Where one present went in a packing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub present: PresentId,
+    /// How the present was turned from its shape in the input.
+    pub transform: Transform,
+    /// Where the top left of the turned present's grid lies in the region.
+    pub offset: XY,
+}
+
+impl Placement {
+    /// Maps a cell of the region back to the cell of `original`, the present as given
+    /// in the input, that was placed on it.  Gives `None` for cells outside the
+    /// turned present's grid.
+    pub fn to_original(&self, xy: &XY, original: &Present) -> Option<XY> {
+        let oriented = XY::new(
+            xy.x.checked_sub(self.offset.x)?,
+            xy.y.checked_sub(self.offset.y)?,
+        );
+        let (width, height) = (original.grid.width(), original.grid.height());
+        let (width, height) = if self.transform.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        (oriented.x < width && oriented.y < height).then(|| {
+            self.transform
+                .inverse()
+                .apply_to_xy(&oriented, width, height)
+        })
+    }
+}
+
+/// Searches for a packing of presents into a region.
+///
+/// The search always works on the first empty cell in row-major order: either a
+/// present is placed so that its own first cell covers it, or the cell is left empty
+/// for good.  This visits each packing once instead of once per placement order.
+pub struct Solver {
+    // The distinct orientations of each present, indexed like `Problem::presents`.
+    orientations: Vec<Vec<Orientation>>,
+    // The area of each present.
+    areas: Vec<usize>,
+    // Presents in the order they are tried.
+    order: Vec<PresentId>,
+    table: TranspositionTable,
+    // Grids for the sequential search's nodes, reused instead of cloning a new one each
+    // placement.
+    grids: ScratchPool<Grid<Cell>>,
+    // When to give up on a region.
+    deadline: Deadline,
+    strategy: Strategy,
+}
+
+impl Solver {
+    pub fn new(presents: &[Present]) -> Self {
+        Self::with_order(presents, PresentOrder::default())
+    }
+
+    /// Creates a solver that tries presents in the given order.
+    pub fn with_order(presents: &[Present], order: PresentOrder) -> Self {
+        Self {
+            orientations: presents.iter().map(all_orientations).collect(),
+            areas: presents
+                .iter()
+                .map(|present| present.occupied_cells.len())
+                .collect(),
+            order: order.order(presents),
+            table: TranspositionTable::default(),
+            grids: ScratchPool::new(),
+            deadline: Deadline::never(),
+            strategy: Strategy::default(),
+        }
+    }
+
+    /// Makes the searches fail with [`Expired`](common::deadline::Expired) once
+    /// `deadline` passes, rather than running on.
+    pub fn with_deadline(self, deadline: Deadline) -> Self {
+        Self { deadline, ..self }
+    }
+
+    /// Makes the solver pack regions with `strategy`.
+    pub fn with_strategy(self, strategy: Strategy) -> Self {
+        Self { strategy, ..self }
+    }
+
+    /// Packs a region with the heuristic, if the strategy allows it and it succeeds.
+    fn heuristic_packing(&self, region: &Region) -> Option<(Grid<Cell>, Vec<Placement>)> {
+        if self.strategy != Strategy::Heuristic {
+            return None;
+        }
+        let presents = region.present_count.iter().sum::<usize>();
+        let placements = span("heuristic", || {
+            heuristic::pack(
+                region,
+                &self.orientations,
+                heuristic::STEPS_PER_PRESENT * presents,
+                heuristic::SEED,
+            )
+        })?;
+        let mut grid = Grid::new_sized(region.xsize, region.ysize, Cell::Empty);
+        for placement in &placements {
+            let orientation = self.orientations[placement.present]
+                .iter()
+                .find(|orientation| orientation.transform == placement.transform)?;
+            place_present(&mut grid, &orientation.present, &placement.offset).ok()?;
+        }
+        Some((grid, placements))
+    }
+
+    /// Gets the transposition table hit and miss counters.
+    pub fn stats(&self) -> TableStats {
+        self.table.stats()
+    }
+
+    /// Solves a region, returning the packed grid if all of its presents fit.
+    pub fn solve(&mut self, region: &Region) -> Result<Option<Grid<Cell>>> {
+        Ok(self.solve_with_placements(region)?.map(|(grid, _)| grid))
+    }
+
+    /// Like [`Solver::solve`], also giving where each present was placed.
+    pub fn solve_with_placements(
+        &mut self,
+        region: &Region,
+    ) -> Result<Option<(Grid<Cell>, Vec<Placement>)>> {
+        if region.present_count.len() != self.orientations.len() {
+            anyhow::bail!("Region present count does not match the number of presents");
+        }
+        if let Some(packed) = self.heuristic_packing(region) {
+            return Ok(Some(packed));
+        }
+        let mut packing = Packing {
+            branching: Branching {
+                orientations: &self.orientations,
+                areas: &self.areas,
+                order: &self.order,
+            },
+            table: &mut self.table,
+            grids: &self.grids,
+        };
+        let root = Node::empty(region);
+        let deadline = self.deadline;
+        let solved = span("solve", || {
+            branch_and_bound_until(&mut packing, root, deadline)
+        })?;
+        Ok(solved.map(|(_, node)| (node.grid, node.placements)))
+    }
+}
+
+impl Solver {
+    /// Solves a region like [`Solver::solve`], but spreads the search over rayon with
+    /// [`parallel_dfs_remembering`].  The branches share the transposition table, so a
+    /// state one of them fails in is skipped by the rest.
+    pub fn solve_parallel(&mut self, region: &Region) -> Result<Option<Grid<Cell>>> {
+        Ok(self
+            .solve_parallel_with_placements(region)?
+            .map(|(grid, _)| grid))
+    }
+
+    /// Like [`Solver::solve_parallel`], also giving where each present was placed.
+    pub fn solve_parallel_with_placements(
+        &mut self,
+        region: &Region,
+    ) -> Result<Option<(Grid<Cell>, Vec<Placement>)>> {
+        if region.present_count.len() != self.orientations.len() {
+            anyhow::bail!("Region present count does not match the number of presents");
+        }
+        if let Some(packed) = self.heuristic_packing(region) {
+            return Ok(Some(packed));
+        }
+        let root = Node::empty(region);
+        let branching = Branching {
+            orientations: &self.orientations,
+            areas: &self.areas,
+            order: &self.order,
+        };
+        let table = Mutex::new(std::mem::take(&mut self.table));
+        let key = |node: &Node| {
+            first_empty(&node.grid, node.cursor).map(|anchor| Packing::key(node, anchor))
+        };
+        // Timed as a whole rather than node by node, since a span on every node costs
+        // time even with profiling off.
+        let solved = span("solve", || {
+            parallel_dfs_remembering(
+                root,
+                |node| {
+                    let known_failure = key(node).is_some_and(|key| {
+                        table
+                            .lock()
+                            .expect("transposition table lock")
+                            .is_known_failure(&key)
+                    });
+                    if known_failure {
+                        Vec::new()
+                    } else {
+                        branching.expand(node)
+                    }
+                },
+                |node| branching.remaining_area(node) == 0,
+                |node| {
+                    if let Some(key) = key(node) {
+                        table
+                            .lock()
+                            .expect("transposition table lock")
+                            .record_failure(key);
+                    }
+                },
+                self.deadline,
+            )
+        });
+        self.table = table.into_inner().expect("transposition table lock");
+        Ok(solved?.map(|node| (node.grid, node.placements)))
+    }
+}
+
+/// Counts the distinct packings of a region's presents, rather than stopping at the
+/// first.  Presents with the same index can't be told apart, so swapping two of them
+/// is the same packing.
+///
+/// With `symmetry_reduction`, packings that turn into each other by flipping or
+/// rotating the region count once.  That has to keep every packing to compare them,
+/// while the plain count shares the counts of repeated states and only keeps those.
+///
+/// Gives up with [`Expired`](common::deadline::Expired) once `deadline` passes.
+pub fn count_solutions(
+    region: &Region,
+    presents: &[Present],
+    symmetry_reduction: bool,
+    deadline: Deadline,
+) -> Result<usize> {
+    if region.present_count.len() != presents.len() {
+        anyhow::bail!("Region present count does not match the number of presents");
+    }
+    let orientations = presents.iter().map(all_orientations).collect::<Vec<_>>();
+    let areas = presents
+        .iter()
+        .map(|present| present.occupied_cells.len())
+        .collect::<Vec<_>>();
+    let order = PresentOrder::default().order(presents);
+    let branching = Branching {
+        orientations: &orientations,
+        areas: &areas,
+        order: &order,
+    };
+    let root = Node::empty(region);
+    if !symmetry_reduction {
+        let mut counting = Counting {
+            branching,
+            counts: HashMap::new(),
+            deadline,
+            visited: 0,
+        };
+        return Ok(counting.count_from(root)?);
+    }
+
+    let mut distinct = HashSet::new();
+    let mut stack = vec![root];
+    let mut visited = 0_usize;
+    while let Some(node) = stack.pop() {
+        visited += 1;
+        if visited.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            deadline.check()?;
+        }
+        if branching.remaining_area(&node) == 0 {
+            distinct.insert(canonical_packing(&node.placements, presents, region));
+        } else {
+            stack.extend(branching.expand(&node));
+        }
+    }
+    Ok(distinct.len())
+}
+
+/// The plain count of [`count_solutions`], remembering the count for each state.
+struct Counting<'a> {
+    branching: Branching<'a>,
+    counts: HashMap<StateKey, usize>,
+    deadline: Deadline,
+    // Nodes counted so far, to know when to look at the deadline.
+    visited: usize,
+}
+
+impl Counting<'_> {
+    /// Counts the packings that complete a node.
+    fn count_from(&mut self, node: Node) -> Result<usize, Expired> {
+        self.visited += 1;
+        if self.visited.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            self.deadline.check()?;
+        }
+        if self.branching.remaining_area(&node) == 0 {
+            return Ok(1);
+        }
+        let Some(anchor) = first_empty(&node.grid, node.cursor) else {
+            return Ok(0);
+        };
+        let key = Packing::key(&node, anchor);
+        if let Some(count) = self.counts.get(&key) {
+            return Ok(*count);
+        }
+        let mut count = 0;
+        for child in self.branching.expand(&node) {
+            count += self.count_from(child)?;
+        }
+        self.counts.insert(key, count);
+        Ok(count)
+    }
+}
+
+/// A packing as its pieces, each a present and the (x, y) cells it covers, sorted.
+type Pieces = Vec<(PresentId, Vec<(usize, usize)>)>;
+
+/// Gets the smallest form of a packing that any symmetry of the region turns it into,
+/// so equivalent packings have the same form.
+fn canonical_packing(placements: &[Placement], presents: &[Present], region: &Region) -> Pieces {
+    let (width, height) = (region.xsize, region.ysize);
+    let pieces = placements
+        .iter()
+        .map(|placement| {
+            let turned = presents[placement.present].transform(placement.transform);
+            let cells = turned
+                .occupied_cells()
+                .map(|xy| xy.add(&placement.offset))
+                .collect::<Vec<_>>();
+            (placement.present, cells)
+        })
+        .collect::<Vec<_>>();
+    Transform::ALL
+        .into_iter()
+        // Only a square region can be turned a quarter.
+        .filter(|transform| width == height || !transform.swaps_axes())
+        .map(|transform| {
+            let mut moved = pieces
+                .iter()
+                .map(|(id, cells)| {
+                    let mut cells = cells
+                        .iter()
+                        .map(|xy| transform.apply_to_xy(xy, width, height))
+                        .map(|xy| (xy.x, xy.y))
+                        .collect::<Vec<_>>();
+                    cells.sort();
+                    (*id, cells)
+                })
+                .collect::<Pieces>();
+            moved.sort();
+            moved
+        })
+        .min()
+        .expect("the identity always applies")
+}
+
+/// The present tables the searches need to branch.
+struct Branching<'a> {
+    orientations: &'a [Vec<Orientation>],
+    areas: &'a [usize],
+    order: &'a [PresentId],
+}
+
+impl Branching<'_> {
+    fn remaining_area(&self, node: &Node) -> usize {
+        node.remaining
+            .iter()
+            .zip(self.areas)
+            .map(|(count, area)| count * area)
+            .sum()
+    }
+
+    /// Gets the states reachable from a node: each present placed over the first empty
+    /// cell, in the solver's present order, then that cell left empty.
+    fn expand(&self, node: &Node) -> Vec<Node> {
+        self.expand_with(node, Grid::clone)
+    }
+
+    /// Like [`Branching::expand`], copying the node's grid for each child with
+    /// `copy_grid`, so a search can hand out pooled grids.
+    fn expand_with(&self, node: &Node, copy_grid: impl Fn(&Grid<Cell>) -> Grid<Cell>) -> Vec<Node> {
+        let remaining_area = self.remaining_area(node);
+        if remaining_area == 0 || remaining_area > node.free {
+            return Vec::new();
+        }
+        let Some(anchor) = first_empty(&node.grid, node.cursor) else {
+            return Vec::new();
+        };
+        let width = node.grid.width();
+        let anchor_xy = XY::new(anchor % width, anchor / width);
+
+        let mut children = Vec::new();
+        for &id in self.order {
+            if node.remaining[id] == 0 {
+                continue;
+            }
+            for orientation in &self.orientations[id] {
+                let Some(offset) = anchored_offset(&node.grid, &orientation.present, &anchor_xy)
+                else {
+                    continue;
+                };
+                let mut child = Node {
+                    grid: copy_grid(&node.grid),
+                    cursor: anchor + 1,
+                    free: node.free - self.areas[id],
+                    remaining: node.remaining.clone(),
+                    placements: node.placements.clone(),
+                };
+                child.remaining[id] -= 1;
+                let placed = place_present(&mut child.grid, &orientation.present, &offset);
+                // anchored_offset already checked that every cell is in the grid, so
+                // this can only fail through a bug, which a debug build should show.
+                debug_assert!(placed.is_ok(), "{:?}", placed);
+                if placed.is_ok() {
+                    child.placements.push(Placement {
+                        present: id,
+                        transform: orientation.transform,
+                        offset,
+                    });
+                    children.push(child);
+                }
+            }
+        }
+        if node.free > remaining_area {
+            children.push(Node {
+                grid: copy_grid(&node.grid),
+                cursor: anchor + 1,
+                free: node.free - 1,
+                remaining: node.remaining.clone(),
+                placements: node.placements.clone(),
+            });
+        }
+        children
+    }
+}
+
+/// A state of the search, owning its grid so it can move between threads.
+struct Node {
+    grid: Grid<Cell>,
+    // Row-major index of the first undecided cell.
+    cursor: usize,
+    // Empty cells from the cursor on.
+    free: usize,
+    remaining: Vec<usize>,
+    // The presents placed so far.
+    placements: Vec<Placement>,
+}
+
+impl Node {
+    /// The empty region with all of its presents left to place.
+    fn empty(region: &Region) -> Self {
+        Node {
+            grid: Grid::new_sized(region.xsize, region.ysize, Cell::Empty),
+            cursor: 0,
+            free: region.xsize * region.ysize,
+            remaining: region.present_count.clone(),
+            placements: Vec::new(),
+        }
+    }
+}
+
+/// A region's packing as a branch-and-bound search.  Any packing will do, so the
+/// score is just `()`: the search stops at the first one found.
+struct Packing<'a> {
+    branching: Branching<'a>,
+    table: &'a mut TranspositionTable,
+    grids: &'a ScratchPool<Grid<Cell>>,
+}
+
+impl Packing<'_> {
+    /// Gets the transposition table key for a node.  The remaining counts are the
+    /// multiset of presents left to place.
+    fn key(node: &Node, anchor: usize) -> StateKey {
+        let mut filled = vec![0; (node.grid.width() * node.grid.height()).div_ceil(64)];
+        for (i, cell) in node.grid.rows().flatten().enumerate() {
+            if *cell == Cell::Filled {
+                filled[i / 64] |= 1 << (i % 64);
+            }
+        }
+        StateKey {
+            filled,
+            anchor,
+            remaining: node.remaining.clone(),
+        }
+    }
+}
+
+impl BranchAndBound for Packing<'_> {
+    type Node = Node;
+    type Score = ();
+
+    fn branch(&mut self, node: &Node) -> Vec<Node> {
+        let grids = self.grids;
+        self.branching.expand_with(node, |grid| {
+            let mut copy = grids.take();
+            copy.clone_from(grid);
+            copy
+        })
+    }
+
+    fn upper_bound(&mut self, node: &Node) -> Option<()> {
+        let remaining_area = self.branching.remaining_area(node);
+        if remaining_area == 0 {
+            return Some(());
+        }
+        // The presents left need more room than there is.
+        if remaining_area > node.free {
+            return None;
+        }
+        let anchor = first_empty(&node.grid, node.cursor)?;
+        (!self.table.is_known_failure(&Self::key(node, anchor))).then_some(())
+    }
+
+    fn score(&mut self, node: &Node) -> Option<()> {
+        (self.branching.remaining_area(node) == 0).then_some(())
+    }
+
+    fn exhausted(&mut self, node: &Node) {
+        if let Some(anchor) = first_empty(&node.grid, node.cursor) {
+            self.table.record_failure(Self::key(node, anchor));
+        }
+    }
+
+    fn discard(&mut self, node: Node) {
+        self.grids.put_back(node.grid);
+    }
+}
+
+/// Finds the row-major index of the first empty cell at or after `cursor`.
+fn first_empty(grid: &Grid<Cell>, cursor: usize) -> Option<usize> {
+    let width = grid.width();
+    if width == 0 {
+        return None;
+    }
+    let start = XY::new(cursor % width, cursor / width);
+    let xy = grid.find_from(&start, |cell| cell == &Cell::Empty)?.xy();
+    Some(xy.y * width + xy.x)
+}
+
+/// Gets the offset that puts the present's first cell on the anchor, if the present fits there.
+fn anchored_offset(grid: &Grid<Cell>, present: &Present, anchor: &XY) -> Option<XY> {
+    // Occupied cells are in row-major order, so the first one is the top-left most.
+    let first = present.occupied_cells().next()?;
+    let offset = XY::new(
+        anchor.x.checked_sub(first.x)?,
+        anchor.y.checked_sub(first.y)?,
+    );
+    let fits = present
+        .occupied_cells()
+        .map(|xy| xy.add(&offset))
+        .all(|xy| {
+            grid.get(xy)
+                .is_some_and(|cell| cell.value() == &Cell::Empty)
+        });
+    fits.then_some(offset)
+}
+
+/// Gets the distinct orientations of a present.
+pub(crate) fn all_orientations(present: &Present) -> Vec<Orientation> {
+    let mut seen = HashSet::new();
+    Transform::ALL
+        .into_iter()
+        .map(|transform| Orientation {
+            transform,
+            present: present.transform(transform),
+        })
+        .filter(|orientation| seen.insert(orientation.present.clone()))
+        .collect()
+}
+
+fn place_present(grid: &mut Grid<Cell>, present: &Present, offset: &XY) -> Result<()> {
+    for xy in present.occupied_cells().map(|xy| xy.add(offset)) {
+        let cell = grid
+            .get_mut(&xy)
+            .ok_or_else(|| anyhow::anyhow!("xy should be in grid"))?;
+        *cell = Cell::Filled;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "nom"))]
+mod tests {
+    use super::*;
+    use crate::parse::parse_problem;
+
+    /// Three of the sample's 7-cell presents, which can't be packed into a 5x5 region,
+    /// but quickly found not to be.
+    fn unsolvable_region() -> Region {
+        Region {
+            xsize: 5,
+            ysize: 5,
+            present_count: vec![0, 0, 0, 0, 3, 0],
+        }
+    }
+
+    #[test]
+    fn test_solve_sample() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let mut solver = Solver::new(&problem.presents);
+        assert!(solver.solve(&problem.regions[0]).unwrap().is_some());
+        assert!(solver.solve(&problem.regions[1]).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_placements_map_back_to_the_input_shapes() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let mut solver = Solver::new(&problem.presents);
+        let region = &problem.regions[1];
+        let (grid, placements) = solver.solve_with_placements(region).unwrap().unwrap();
+        assert_eq!(placements.len(), region.presents(&problem.presents).count());
+        for placement in &placements {
+            let original = &problem.presents[placement.present];
+            let turned = original.transform(placement.transform);
+            let mut back = HashSet::new();
+            for xy in turned.occupied_cells().map(|xy| xy.add(&placement.offset)) {
+                assert_eq!(grid.get(xy.clone()).unwrap().value(), &Cell::Filled);
+                back.insert(placement.to_original(&xy, original).unwrap());
+            }
+            assert_eq!(back, original.occupied_cells().cloned().collect());
+        }
+    }
+
+    #[test]
+    fn test_orders_agree() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        for order in [
+            PresentOrder::Input,
+            PresentOrder::LargestFirst,
+            PresentOrder::SmallestFirst,
+        ] {
+            let mut solver = Solver::with_order(&problem.presents, order);
+            // The last sample region is unsolvable and too slow for a debug build, so a
+            // smaller unsolvable one stands in for it.
+            let solved = problem.regions[..2]
+                .iter()
+                .chain([&unsolvable_region()])
+                .map(|region| solver.solve(region).unwrap().is_some())
+                .collect::<Vec<_>>();
+            assert_eq!(solved, vec![true, true, false], "{:?}", order);
+        }
+    }
+
+    #[test]
+    fn test_solve_parallel() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let mut solver = Solver::new(&problem.presents);
+        for region in &problem.regions[..2] {
+            let grid = solver.solve_parallel(region).unwrap().unwrap();
+            let filled = grid.count(|cell| cell.value() == &Cell::Filled);
+            let expected = region
+                .presents(&problem.presents)
+                .map(|present| present.occupied_cells.len())
+                .sum::<usize>();
+            assert_eq!(filled, expected);
+        }
+        // Proving a region can't be packed fills the shared table.
+        assert!(
+            solver
+                .solve_parallel(&unsolvable_region())
+                .unwrap()
+                .is_none()
+        );
+        assert!(solver.stats().hits > 0);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        // Already expired, so the search gives up before expanding the root however fast
+        // the machine is.
+        let mut solver = Solver::new(&problem.presents)
+            .with_deadline(Deadline::after(std::time::Duration::ZERO));
+        let error = solver.solve_parallel(&problem.regions[2]).unwrap_err();
+        assert!(common::deadline::is_expired(&error));
+        // Both counts look at the deadline as they go too.
+        for symmetry_reduction in [false, true] {
+            let expired = Deadline::after(std::time::Duration::ZERO);
+            let error = count_solutions(
+                &problem.regions[2],
+                &problem.presents,
+                symmetry_reduction,
+                expired,
+            )
+            .unwrap_err();
+            assert!(common::deadline::is_expired(&error));
+        }
+    }
+
+    #[test]
+    fn test_largest_first_order() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let order = PresentOrder::LargestFirst.order(&problem.presents);
+        let areas = order
+            .iter()
+            .map(|id| problem.presents[*id].occupied_cells.len())
+            .collect::<Vec<_>>();
+        assert!(areas.is_sorted_by(|a, b| a >= b));
+    }
+
+    #[test]
+    fn test_failed_states_are_reused() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let mut solver = Solver::new(&problem.presents);
+        assert!(solver.solve(&unsolvable_region()).unwrap().is_none());
+        let stats = solver.stats();
+        assert!(stats.hits > 0);
+        assert!(stats.misses > 0);
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        // Dominoes tile a 3x2 region three ways: all upright, or one upright at either
+        // end with two lying beside it, which are mirror images.
+        let problem = parse_problem("0:\n##\n\n3x2: 3\n2x2: 1").unwrap();
+        let (tiled, loose) = (&problem.regions[0], &problem.regions[1]);
+        assert_eq!(
+            count_solutions(tiled, &problem.presents, false, Deadline::never()).unwrap(),
+            3
+        );
+        assert_eq!(
+            count_solutions(tiled, &problem.presents, true, Deadline::never()).unwrap(),
+            2
+        );
+        // A lone domino in a 2x2 region can lie along any of the four sides, which a
+        // square region's turns all make the same.
+        assert_eq!(
+            count_solutions(loose, &problem.presents, false, Deadline::never()).unwrap(),
+            4
+        );
+        assert_eq!(
+            count_solutions(loose, &problem.presents, true, Deadline::never()).unwrap(),
+            1
+        );
+
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let region = &problem.regions[0];
+        let all = count_solutions(region, &problem.presents, false, Deadline::never()).unwrap();
+        let distinct = count_solutions(region, &problem.presents, true, Deadline::never()).unwrap();
+        // The two presents fit one way, which the square's flips and turns make four.
+        assert_eq!((all, distinct), (4, 1));
+    }
+
+    #[test]
+    fn test_heuristic_strategy() {
+        let problem = parse_problem(include_str!("../sample.txt")).unwrap();
+        let mut solver = Solver::new(&problem.presents).with_strategy("heuristic".parse().unwrap());
+        for region in &problem.regions[..2] {
+            let (grid, placements) = solver.solve_with_placements(region).unwrap().unwrap();
+            assert_eq!(placements.len(), region.presents(&problem.presents).count());
+            let filled = grid.count(|cell| cell.value() == &Cell::Filled);
+            let expected = region
+                .presents(&problem.presents)
+                .map(|present| present.occupied_cells.len())
+                .sum::<usize>();
+            assert_eq!(filled, expected);
+        }
+        // The heuristic can't pack this, so the exact search settles it.
+        assert!(solver.solve(&unsolvable_region()).unwrap().is_none());
+        assert!(solver.stats().misses > 0);
+        assert!("greedy".parse::<Strategy>().is_err());
+    }
+}
@@ -0,0 +1,24 @@
+/// Every region here is settled quickly: one by a short search, one by tiling and one
+/// by area.
+#[test]
+fn test_sample_output() {
+    common::golden::check_stdout(
+        env!("CARGO_BIN_EXE_day12"),
+        "sample2.txt",
+        "tests/golden/sample2.stdout",
+    )
+    .unwrap();
+}
+
+/// The puzzle's own sample, whose last region only the exhaustive search rules out.
+/// Takes about a minute in a debug build.
+#[test]
+#[ignore]
+fn test_full_sample_output() {
+    common::golden::check_stdout(
+        env!("CARGO_BIN_EXE_day12"),
+        "sample.txt",
+        "tests/golden/sample.stdout",
+    )
+    .unwrap();
+}
@@ -1,9 +1,11 @@
 use anyhow::Result;
+use common::{digits, parse::ExpectFormat, ranges::IntervalSet, strings::is_repeated_block};
 use std::str::FromStr;
 
-pub type RangeType = u64;
+/// Wide enough for the endpoints of the larger ranges, which overflow a u64.
+pub type RangeType = u128;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Range {
     start: RangeType,
     end: RangeType,
@@ -27,51 +29,124 @@ impl Range {
     }
 }
 
-// In the case of an empty iterator, return false.
-fn all_values_equal(iter: impl IntoIterator<Item = impl PartialEq>) -> bool {
-    let mut iter = iter.into_iter();
-    let first = iter.next();
-    if first.is_none() {
-        // Indicates the iterator is empty.
-        return false;
+/// Shows the range the way the input does, like `11-22`.
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
     }
-    iter.all(|x| &x == first.as_ref().unwrap())
 }
 
-fn combinations_of_str(s: &str, count: usize) -> impl Iterator<Item = &str> {
-    let ranges = (0..)
-        .map(move |offset| offset * count)
-        .map(move |offset| offset..offset + count);
-    ranges
-        .map(|range| s.get(range))
-        .take_while(|s| s.is_some())
-        .map(|s| s.unwrap())
+/// How many invalid ids one range has, and what they add up to, for each part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeReport {
+    pub range: Range,
+    pub part1_count: usize,
+    pub part1_sum: RangeType,
+    pub part2_count: usize,
+    pub part2_sum: RangeType,
 }
-
-fn has_repeating_values(s: &str, count: usize) -> bool {
-    // It can't generate equal substrings if the length is not divisible by the count.
-    if !s.len().is_multiple_of(count) {
-        return false;
+impl RangeReport {
+    /// Counts and sums a range's invalid ids.  Fails if a sum overflows.
+    pub fn new(range: Range) -> Result<Self> {
+        let tally = |mut ids: Box<dyn Iterator<Item = RangeType>>| {
+            ids.try_fold((0, 0 as RangeType), |(count, sum), id| {
+                Some((count + 1, sum.checked_add(id)?))
+            })
+            .ok_or_else(|| anyhow::anyhow!("Sum of invalid ids in {} overflowed", range))
+        };
+        let (part1_count, part1_sum) = tally(Box::new(range.invalid_ids()))?;
+        let (part2_count, part2_sum) = tally(Box::new(range.invalid_ids_part2()))?;
+        Ok(RangeReport {
+            range,
+            part1_count,
+            part1_sum,
+            part2_count,
+            part2_sum,
+        })
+    }
+}
+impl std::fmt::Display for RangeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: part 1 {} invalid (sum {}), part 2 {} invalid (sum {})",
+            self.range, self.part1_count, self.part1_sum, self.part2_count, self.part2_sum
+        )
     }
+}
 
-    all_values_equal(combinations_of_str(s, count))
+/// Reports each range's invalid ids in input order, for finding which range is to
+/// blame when a total is wrong.
+pub fn report(data: &str) -> impl Iterator<Item = Result<RangeReport>> + '_ {
+    parse_data_result(data).map(|range| range.and_then(RangeReport::new))
 }
 
-/// An invalid id is one where, if you split the string version of the
-/// number into two halves, the first half is the same as the second half.
-fn is_invalid_id(id: RangeType) -> bool {
-    // Get a string version of this number.
-    let id = id.to_string();
-    // Split it into the first and second half (don't worry if it's an odd split)
-    let (first, last) = id.split_at(id.len() / 2);
-    // Is the first half the same as the second half?
-    first == last
+/// An invalid id is one where, if you split the digits of the number into two
+/// halves, the first half is the same as the second half.
+pub fn is_invalid_id(id: RangeType) -> bool {
+    let length = digits::num_digits(id);
+    length.is_multiple_of(2) && is_repeated_unit(id, length, length / 2)
 }
 
-fn is_invalid_id_part2(id: RangeType) -> bool {
-    let id = id.to_string();
-    let mut substr_lengths = 1..=id.len() / 2;
-    substr_lengths.any(|length| has_repeating_values(&id, length))
+/// An invalid id is one made up of some sequence of digits repeated at least twice.
+pub fn is_invalid_id_part2(id: RangeType) -> bool {
+    let length = digits::num_digits(id);
+    (1..=length / 2)
+        .filter(|unit_length| length.is_multiple_of(*unit_length))
+        .any(|unit_length| is_repeated_unit(id, length, unit_length))
+}
+
+/// Is the id its leading `unit_length` digits repeated to fill all `length` digits?
+fn is_repeated_unit(id: RangeType, length: u32, unit_length: u32) -> bool {
+    // The leading digits never start with a zero, so repeating them can't drop digits.
+    let unit = id / RangeType::pow(10, length - unit_length);
+    let repeated = (1..length / unit_length).try_fold(unit, |acc, _| digits::concat(acc, unit));
+    repeated == Some(id)
+}
+
+/// A repeated sequence of digits that makes an id invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The repeated sequence of digits.
+    pub unit: String,
+    /// How many times the unit is repeated.
+    pub repeats: usize,
+}
+
+/// The rules for what makes an id invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPattern {
+    /// A sequence of digits repeated exactly twice (part 1).
+    Twice,
+    /// A sequence of digits repeated at least twice (part 2).
+    AtLeastTwice,
+}
+impl InvalidPattern {
+    /// Describes which repeated sequence makes the id invalid, or None if the id is valid.
+    /// For `AtLeastTwice` the shortest repeating unit is returned.
+    pub fn describe(&self, id: RangeType) -> Option<Pattern> {
+        let id = id.to_string();
+        let unit_length = match self {
+            InvalidPattern::Twice => Some(id.len() / 2).filter(|length| {
+                // Only an even number of digits can split into two equal halves.
+                *length > 0 && length * 2 == id.len()
+            }),
+            InvalidPattern::AtLeastTwice => {
+                (1..=id.len() / 2).find(|length| is_repeated_block(&id, *length))
+            }
+        }?;
+        if !is_repeated_block(&id, unit_length) {
+            return None;
+        }
+        Some(Pattern {
+            unit: id[..unit_length].to_string(),
+            repeats: id.len() / unit_length,
+        })
+    }
+    /// Checks if the id is invalid under these rules.
+    pub fn is_invalid(&self, id: RangeType) -> bool {
+        self.describe(id).is_some()
+    }
 }
 
 // Implement string parsing for range.  A range is ####-#####
@@ -86,10 +161,23 @@ impl FromStr for Range {
         if start.chars().nth(0) == Some('0') || end.chars().nth(0) == Some('0') {
             anyhow::bail!("Cannot have leading zeros");
         }
-        Range::try_new(start.parse()?, end.parse()?)
+        Range::try_new(parse_endpoint(start)?, parse_endpoint(end)?)
     }
 }
 
+/// Parses one end of a range, saying which number was bad if it isn't one.
+fn parse_endpoint(s: &str) -> Result<RangeType> {
+    common::parse::int(s).map_err(|e: std::num::ParseIntError| {
+        use std::num::IntErrorKind;
+        match e.kind() {
+            IntErrorKind::PosOverflow => {
+                anyhow::anyhow!("Range endpoint {} is larger than {}", s, RangeType::MAX)
+            }
+            _ => anyhow::anyhow!("Range endpoint {:?} is not a number: {}", s, e),
+        }
+    })
+}
+
 pub fn read_data(path: impl AsRef<str>) -> Result<String> {
     Ok(std::fs::read_to_string(path.as_ref())?)
 }
@@ -97,7 +185,35 @@ pub fn read_data(path: impl AsRef<str>) -> Result<String> {
 /// Safe version of parsing that incrementally parses and provides a Result Range
 /// in case the Range::parse fails.
 pub fn parse_data_result(data: &str) -> impl Iterator<Item = Result<Range>> + Clone {
-    data.split(',').map(|pair| pair.trim().parse::<Range>())
+    data.split(',').map(|pair| {
+        let pair = pair.trim();
+        pair.parse::<Range>()
+            .expect_format("a range of ids like '11-22' without leading zeros", pair)
+    })
+}
+
+/// How ranges that overlap each other are handled when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlaps {
+    /// Keep the ranges as they are in the input, so ids in overlapping ranges are seen more than once.
+    Preserve,
+    /// Merge overlapping and adjacent ranges, so every id is seen once.
+    Merge,
+}
+
+/// Parses all the ranges, optionally merging any that overlap or are adjacent.
+pub fn parse_and_normalize(data: &str, overlaps: Overlaps) -> Result<Vec<Range>> {
+    let ranges = parse_data_result(data).collect::<Result<Vec<_>>>()?;
+    match overlaps {
+        Overlaps::Preserve => Ok(ranges),
+        Overlaps::Merge => ranges
+            .into_iter()
+            .map(|range| range.start..=range.end)
+            .collect::<IntervalSet<_>>()
+            .into_iter()
+            .map(|range| Range::try_new(*range.start(), *range.end()))
+            .collect(),
+    }
 }
 
 /// Unsafe version that will panic on an invalid range.
@@ -117,18 +233,63 @@ mod tests {
     }
 
     #[test]
-    fn test_combinations() {
+    fn test_ranges_past_u64() {
+        // 20 digit ids past u64::MAX, which is about 1.8 * 10^19.
+        let range = Range::from_str("98765432109876543210-98765432109876543219").unwrap();
+        assert_eq!(
+            range.invalid_ids().collect::<Vec<_>>(),
+            vec![98765432109876543210]
+        );
+        assert!(is_invalid_id_part2(12121212121212121212121212));
+
+        let too_big = format!("1-{}0", RangeType::MAX);
         assert_eq!(
-            combinations_of_str("1234567", 3).collect::<Vec<_>>(),
-            vec!["123", "456"]
+            Range::from_str(&too_big).unwrap_err().to_string(),
+            format!(
+                "Range endpoint {}0 is larger than {}",
+                RangeType::MAX,
+                RangeType::MAX
+            )
+        );
+        assert!(
+            Range::from_str("1-x2")
+                .unwrap_err()
+                .to_string()
+                .starts_with("Range endpoint \"x2\" is not a number")
         );
+        let error = parse_data_result("1-5, 07-9").nth(1).unwrap().unwrap_err();
         assert_eq!(
-            combinations_of_str("123456789", 3).collect::<Vec<_>>(),
-            vec!["123", "456", "789"]
+            error.to_string(),
+            "Expected a range of ids like '11-22' without leading zeros, got \"07-9\""
         );
+        assert_eq!(error.root_cause().to_string(), "Cannot have leading zeros");
     }
 
     #[test]
+    fn test_report() {
+        let reports = report("11-22, 95-115,1-x")
+            .take(2)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            reports[0].to_string(),
+            "11-22: part 1 2 invalid (sum 33), part 2 2 invalid (sum 33)"
+        );
+        assert_eq!(
+            reports[1],
+            RangeReport {
+                range: Range::try_new(95, 115).unwrap(),
+                part1_count: 1,
+                part1_sum: 99,
+                part2_count: 2,
+                part2_sum: 210,
+            }
+        );
+        assert!(report("11-22, 95-115,1-x").nth(2).unwrap().is_err());
+    }
+
+    #[test]
+    #[allow(clippy::bool_comparison)]
     fn test_invalid_id() {
         assert!(is_invalid_id(1) == false);
         assert!(is_invalid_id(10) == false);
@@ -157,17 +318,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_all_values_equal() {
-        assert!(all_values_equal(vec![1, 1, 1]));
-        assert!(all_values_equal(vec![1, 1, 1, 1]));
-        assert!(!all_values_equal(vec![1, 1, 2]));
-        assert!(!all_values_equal(vec![1, 2, 1]));
-        assert!(!all_values_equal(vec![2, 1, 1]));
-        assert!(all_values_equal(vec![1]));
-        assert!(!all_values_equal(Vec::<i32>::new()));
-    }
-
     #[test]
     fn test_invalid_id_part2() {
         let invalid_ids = [11, 1010, 123123, 123123123];
@@ -180,4 +330,58 @@ mod tests {
             assert!(!is_invalid_id_part2(id), "{} is valid", id);
         }
     }
+
+    #[test]
+    fn test_describe() {
+        let pattern = |unit: &str, repeats| {
+            Some(Pattern {
+                unit: unit.to_string(),
+                repeats,
+            })
+        };
+        assert_eq!(InvalidPattern::Twice.describe(1010), pattern("10", 2));
+        assert_eq!(InvalidPattern::Twice.describe(111111), pattern("111", 2));
+        assert_eq!(InvalidPattern::Twice.describe(123123123), None);
+        assert_eq!(InvalidPattern::Twice.describe(7), None);
+        assert_eq!(
+            InvalidPattern::AtLeastTwice.describe(111111),
+            pattern("1", 6)
+        );
+        assert_eq!(
+            InvalidPattern::AtLeastTwice.describe(123123123),
+            pattern("123", 3)
+        );
+        assert_eq!(InvalidPattern::AtLeastTwice.describe(12312312), None);
+    }
+
+    #[test]
+    fn test_describe_matches_predicates() {
+        for id in 1..20000 {
+            assert_eq!(InvalidPattern::Twice.is_invalid(id), is_invalid_id(id));
+            assert_eq!(
+                InvalidPattern::AtLeastTwice.is_invalid(id),
+                is_invalid_id_part2(id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_and_normalize() {
+        let data = "10-30,20-40,41-45";
+        let preserved = parse_and_normalize(data, Overlaps::Preserve).unwrap();
+        let merged = parse_and_normalize(data, Overlaps::Merge).unwrap();
+        assert_eq!(preserved.len(), 3);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].ids().count(), 36);
+
+        // 22 is in both of the overlapping ranges, so is counted twice unless merged.
+        let sum = |ranges: &[Range]| {
+            ranges
+                .iter()
+                .flat_map(|r| r.invalid_ids())
+                .sum::<RangeType>()
+        };
+        assert_eq!(sum(&preserved), 11 + 22 + 22 + 33 + 44);
+        assert_eq!(sum(&merged), 11 + 22 + 33 + 44);
+    }
 }
@@ -1,12 +1,22 @@
 use anyhow::Result;
-use common::CheckedSum;
+use common::{CheckedSum, error::ErrorContext};
 
 fn main() -> Result<()> {
-    let file = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    part1_resulted(&day2::read_data(&file)?)?;
-    part2(&day2::read_data(&file)?)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let file = common::args::input_path(&args, &[])?;
+    // --verbose shows each range's invalid ids, to find the range behind a wrong total.
+    let verbose = args.iter().any(|arg| arg == "--verbose");
+    part1_resulted(&day2::read_data(file)?)
+        .with_phase("part 1")
+        .with_day(2)?;
+    part2(&day2::read_data(file)?)
+        .with_phase("part 2")
+        .with_day(2)?;
+    if verbose {
+        for range in day2::report(&day2::read_data(file)?) {
+            println!("  {}", range?);
+        }
+    }
     Ok(())
 }
 
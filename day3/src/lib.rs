@@ -1,4 +1,5 @@
 use anyhow::Result;
+use common::parse::ExpectWithLine;
 use std::str::FromStr;
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq)]
@@ -6,10 +7,10 @@ pub struct Battery {
     joltage: u64,
 }
 impl Battery {
-    fn from_char(c: char) -> Result<Self> {
+    fn from_char(c: char, radix: u32) -> Result<Self> {
         Ok(Battery {
             joltage: c
-                .to_digit(10)
+                .to_digit(radix)
                 .ok_or_else(|| anyhow::anyhow!("Invalid joltage: {}", c))?
                 as u64,
         })
@@ -18,30 +19,72 @@ impl Battery {
 
 pub struct BatteryBank {
     batteries: Vec<Battery>,
+    // The radix the joltages were parsed in, which is also the one they join in.
+    radix: u64,
 }
 impl FromStr for BatteryBank {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BatteryBank::from_str_radix(s, 10)
+    }
+}
+impl BatteryBank {
+    /// Parses a bank where each battery's joltage is a single digit in the given radix,
+    /// e.g. 16 for hex batteries or 36 for 0-9 followed by a-z.  The radix must be
+    /// between 2 and 36, since digits past z have no character.  Joltages are joined
+    /// in the same radix.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self> {
+        if !(2..=36).contains(&radix) {
+            anyhow::bail!("Radix must be between 2 and 36, not {}", radix);
+        }
         let batteries = s
             .chars()
-            .map(Battery::from_char)
+            .map(|c| Battery::from_char(c, radix))
             .collect::<Result<Vec<_>>>()?;
         // must have at least 2 batteries
         if batteries.len() < 2 {
             anyhow::bail!("Must have at least 2 batteries");
         }
-        Ok(BatteryBank { batteries })
+        Ok(BatteryBank {
+            batteries,
+            radix: radix as u64,
+        })
     }
-}
-impl BatteryBank {
-    pub fn recursive_max_joltage(&self, num_batteries: u32) -> u64 {
-        recursive_max_joltage(&self.batteries, num_batteries)
-            .expect("Must have at least 2 batteries")
+    /// Gets the largest joltage from turning on `num_batteries` batteries, reading their
+    /// joltages in order as the digits of a number in the bank's radix.
+    pub fn recursive_max_joltage(&self, num_batteries: u32) -> Result<u64> {
+        self.max_joltage(num_batteries, TieBreak::First)
+    }
+    /// Like [`BatteryBank::recursive_max_joltage`], choosing between batteries with
+    /// equal joltage by `tie_break`.  Only [`TieBreak::First`] is sure to find the
+    /// largest.
+    pub fn max_joltage(&self, num_batteries: u32, tie_break: TieBreak) -> Result<u64> {
+        // The largest joltage possible has every digit at radix - 1.
+        let largest = (0..num_batteries).try_fold(0_u64, |joltage, _| {
+            joltage.checked_mul(self.radix)?.checked_add(self.radix - 1)
+        });
+        if largest.is_none() {
+            anyhow::bail!(
+                "{} base {} digits overflow a u64",
+                num_batteries,
+                self.radix
+            );
+        }
+        recursive_max_joltage(&self.batteries, num_batteries, self.radix, tie_break).ok_or_else(
+            || {
+                anyhow::anyhow!(
+                    "Can't turn on {} of {} batteries",
+                    num_batteries,
+                    self.batteries.len()
+                )
+            },
+        )
     }
     pub fn max_pairs(&self) -> u64 {
         let batteries = self.batteries.as_slice();
 
-        let (pos, max) = first_max(batteries).expect("There are always at least 2 batteries");
+        let (pos, max) =
+            first_max(batteries, TieBreak::First).expect("There are always at least 2 batteries");
         let next_max = batteries
             .get(pos + 1..)
             .and_then(|batteries| batteries.iter().max());
@@ -53,8 +96,8 @@ impl BatteryBank {
             let batteries_without_the_end = batteries
                 .get(0..batteries.len() - 1)
                 .expect("There are always at least 2 batteries");
-            let (pos, max) =
-                first_max(batteries_without_the_end).expect("there is always at least one battery");
+            let (pos, max) = first_max(batteries_without_the_end, TieBreak::First)
+                .expect("there is always at least one battery");
             let batteries_starting_at_max = batteries
                 .get(pos + 1..)
                 .expect("There are always at least 2 batteries");
@@ -65,11 +108,17 @@ impl BatteryBank {
             (max, next_max)
         };
 
-        first.joltage * 10 + second.joltage
+        // Two digits in a radix of at most 36 can't overflow.
+        first.joltage * self.radix + second.joltage
     }
 }
 
-fn recursive_max_joltage(batteries: &[Battery], num_batteries: u32) -> Option<u64> {
+fn recursive_max_joltage(
+    batteries: &[Battery],
+    num_batteries: u32,
+    radix: u64,
+    tie_break: TieBreak,
+) -> Option<u64> {
     if num_batteries == 0 {
         return Some(0);
     }
@@ -77,17 +126,19 @@ fn recursive_max_joltage(batteries: &[Battery], num_batteries: u32) -> Option<u6
         return None;
     }
 
-    let mut less_than = 10;
+    let mut less_than = radix;
 
     while less_than > 0 {
         let without = batteries
             .iter()
             .filter(|battery| battery.joltage < less_than);
-        let (pos, max) = first_max(without)?;
+        let (pos, max) = first_max(without, tie_break)?;
         let batteries_after = batteries.get(pos + 1..)?;
-        if let Some(child_max) = recursive_max_joltage(batteries_after, num_batteries - 1) {
-            // Multiplier is actually a 10's based shift.  So 1 is 1, 2 is 10, 3 is 100, etc.
-            let multiplier = 10_u64.pow(num_batteries - 1);
+        if let Some(child_max) =
+            recursive_max_joltage(batteries_after, num_batteries - 1, radix, tie_break)
+        {
+            // Multiplier is a shift in the radix.  In base 10, 1 is 1, 2 is 10, 3 is 100, etc.
+            let multiplier = radix.pow(num_batteries - 1);
             return Some(max.joltage * multiplier + child_max);
         } else {
             less_than = max.joltage;
@@ -96,14 +147,31 @@ fn recursive_max_joltage(batteries: &[Battery], num_batteries: u32) -> Option<u6
     None
 }
 
-pub fn first_max<V>(iter: impl IntoIterator<Item = V>) -> Option<(usize, V)>
+/// Which of several equal maximums [`first_max`] picks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The earliest, which leaves the most batteries after it.
+    #[default]
+    First,
+    /// The latest.
+    Last,
+}
+
+/// Gets the position and value of the largest item, with ties going to the first or
+/// last of them.
+pub fn first_max<V>(iter: impl IntoIterator<Item = V>, tie_break: TieBreak) -> Option<(usize, V)>
 where
     V: Ord,
 {
     let mut iter = iter.into_iter().enumerate();
     let mut max = iter.next();
     for (pos, v) in iter {
-        if v > max.as_ref().unwrap().1 {
+        let best = &max.as_ref().unwrap().1;
+        let replace = match tie_break {
+            TieBreak::First => v > *best,
+            TieBreak::Last => v >= *best,
+        };
+        if replace {
             max.replace((pos, v));
         }
     }
@@ -115,13 +183,31 @@ pub fn read_input(path: impl AsRef<str>) -> Result<String> {
 }
 
 pub fn parse_input(input: &str) -> Result<Vec<BatteryBank>> {
-    input.lines().map(BatteryBank::from_str).collect()
+    input
+        .lines()
+        .expect_with_line(
+            "a bank of at least 2 battery digits like '987654'",
+            BatteryBank::from_str,
+        )
+        .map_err(Into::into)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    common::sample_tests! {
+        part1: |input: &str| -> Result<u64> {
+            Ok(parse_input(input)?.iter().map(BatteryBank::max_pairs).sum())
+        } => 357,
+        part2: |input: &str| -> Result<u64> {
+            parse_input(input)?
+                .iter()
+                .map(|bank| bank.recursive_max_joltage(12))
+                .sum()
+        } => 3121910778619,
+    }
+
     #[test]
     fn test_parse_input() {
         let input = "1234567890\n123";
@@ -130,6 +216,12 @@ mod tests {
         assert_eq!(banks.len(), 2);
         let first_bank = banks.first().expect("No bank");
         assert_eq!(first_bank.batteries.len(), 10);
+
+        let error = parse_input("12\n1x3").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2: Expected a bank of at least 2 battery digits like '987654', got \"1x3\""
+        );
     }
 
     static TEST_DATA: &[(&str, u64)] = &[
@@ -151,7 +243,65 @@ mod tests {
     fn test_recursive_max_joltage() {
         for (input, expected) in TEST_DATA.iter() {
             let bank = BatteryBank::from_str(input).expect("Failed to parse input");
-            assert_eq!(recursive_max_joltage(&bank.batteries, 2), Some(*expected));
+            assert_eq!(
+                recursive_max_joltage(&bank.batteries, 2, 10, TieBreak::First),
+                Some(*expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_radix() {
+        for (input, expected) in TEST_DATA.iter() {
+            let bank = BatteryBank::from_str(input).expect("Failed to parse input");
+            assert_eq!(bank.recursive_max_joltage(2).unwrap(), *expected);
+        }
+        let bank = BatteryBank::from_str_radix("1f3a2c", 16).expect("Failed to parse input");
+        assert_eq!(bank.max_pairs(), 0xfc);
+        assert_eq!(bank.recursive_max_joltage(2).unwrap(), 0xfc);
+        assert_eq!(bank.recursive_max_joltage(3).unwrap(), 0xfac);
+        let bank = BatteryBank::from_str_radix("z0az9", 36).expect("Failed to parse input");
+        assert_eq!(bank.max_pairs(), 35 * 36 + 35);
+        assert_eq!(bank.recursive_max_joltage(2).unwrap(), 35 * 36 + 35);
+        // 16 hex digits fill a u64 exactly.
+        let bank = BatteryBank::from_str_radix(&"f".repeat(17), 16).unwrap();
+        assert_eq!(bank.recursive_max_joltage(16).unwrap(), u64::MAX);
+        assert!(bank.recursive_max_joltage(17).is_err());
+    }
+
+    #[test]
+    fn test_tie_break() {
+        assert_eq!(first_max([3, 9, 1, 9], TieBreak::First), Some((1, 9)));
+        assert_eq!(first_max([3, 9, 1, 9], TieBreak::Last), Some((3, 9)));
+        assert_eq!(first_max(Vec::<u8>::new(), TieBreak::Last), None);
+
+        // Taking the last 9 leaves nothing after it, so the first digit falls back to 8.
+        let bank = BatteryBank::from_str("989").unwrap();
+        assert_eq!(bank.max_joltage(2, TieBreak::First).unwrap(), 99);
+        assert_eq!(bank.max_joltage(2, TieBreak::Last).unwrap(), 89);
+        // Without ties, both agree.
+        for (input, expected) in TEST_DATA.iter().take(1) {
+            let bank = BatteryBank::from_str(input).unwrap();
+            assert_eq!(bank.max_joltage(2, TieBreak::Last).unwrap(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_max_joltage_radix_errors() {
+        assert!(BatteryBank::from_str_radix("1g", 16).is_err());
+        assert!(BatteryBank::from_str("1f3a").is_err());
+        let bank = BatteryBank::from_str_radix("1f3a", 16).expect("Failed to parse input");
+        assert!(bank.recursive_max_joltage(5).is_err());
+        assert!(bank.recursive_max_joltage(20).is_err());
+        // Out of char::to_digit's range, which would panic.
+        for radix in [0, 1, 37] {
+            let error = BatteryBank::from_str_radix("11", radix)
+                .map(|_| ())
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Radix must be between 2 and 36, not {}", radix)
+            );
         }
     }
 }
@@ -1,26 +1,33 @@
 use anyhow::Result;
+use common::error::ErrorContext;
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = day3::read_input(&arg1)?;
-    let banks = day3::parse_input(&data)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &[])?;
+    let data = day3::read_input(arg1)?;
+    let banks = day3::parse_input(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(3)?;
 
-    println!(
-        "Part 1 with part 2 logic: {:?}",
-        banks
-            .iter()
-            .map(|bank| bank.recursive_max_joltage(2))
-            .sum::<u64>()
-    );
+    let part1_recursive = common::try_fold_checked(
+        banks.iter().map(|bank| bank.recursive_max_joltage(2)),
+        0,
+        u64::checked_add,
+    )
+    .with_phase("part 1")
+    .with_day(3)?;
+    println!("Part 1 with part 2 logic: {:?}", part1_recursive);
 
     let max_pairs = banks.iter().map(|bank| bank.max_pairs());
     println!("Part 1: {}", max_pairs.sum::<u64>());
-    let part2 = banks
-        .iter()
-        .map(|bank| bank.recursive_max_joltage(12))
-        .sum::<u64>();
+    let part2 = common::try_fold_checked(
+        banks.iter().map(|bank| bank.recursive_max_joltage(12)),
+        0,
+        u64::checked_add,
+    )
+    .with_phase("part 2")
+    .with_day(3)?;
     println!("Part 2: {}", part2);
     Ok(())
 }
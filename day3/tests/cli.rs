@@ -0,0 +1,26 @@
+#[test]
+fn test_sample_output() {
+    common::golden::check_stdout(
+        env!("CARGO_BIN_EXE_day3"),
+        "sample.txt",
+        "tests/golden/sample.stdout",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_errors_name_the_day_and_phase() {
+    common::golden::check_error(
+        env!("CARGO_BIN_EXE_day3"),
+        "tests/bad_bank.txt",
+        "Day 3 parse: tests/bad_bank.txt:2: Expected a bank of at least 2 battery digits like '987654', got \"1x3\"",
+    )
+    .unwrap();
+    // Banks of fewer than 12 batteries are enough for part 1 but not part 2.
+    common::golden::check_error(
+        env!("CARGO_BIN_EXE_day3"),
+        "tests/short_bank.txt",
+        "Day 3 part 2: Can't turn on 12 of 6 batteries",
+    )
+    .unwrap();
+}
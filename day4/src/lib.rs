@@ -1,8 +1,12 @@
 use anyhow::Result;
-use common::grid::{CellInGrid, Grid, XY};
+use common::{
+    automaton::{self, StepSim},
+    grid::{CellInGrid, Connectivity, Grid, XY},
+    parse::ExpectFormat,
+};
 
 /// Represents a cell in the grid, either empty or containing paper.
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Cell {
     Empty,
     Paper,
@@ -19,7 +23,8 @@ impl std::str::FromStr for Cell {
         match s {
             "." => Ok(Cell::Empty),
             "@" => Ok(Cell::Paper),
-            _ => Err(anyhow::anyhow!("Invalid cell: {}", s)),
+            _ => Err(anyhow::anyhow!("Invalid cell"))
+                .expect_format("'.' for empty or '@' for paper", s),
         }
     }
 }
@@ -52,10 +57,140 @@ pub fn is_paper(cell: &CellInGrid<Cell>) -> bool {
     matches!(cell.value(), Cell::Paper)
 }
 
+/// The rule that decides whether a paper cell can be accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessRule {
+    /// The most adjacent paper cells an accessible cell may have.
+    pub max_neighbors: usize,
+    /// Which surrounding cells count as adjacent.
+    pub connectivity: Connectivity,
+}
+impl AccessRule {
+    /// The puzzle's rule, fewer than 4 adjacent paper cells, with a choice of which
+    /// cells are adjacent.
+    pub fn puzzle(connectivity: Connectivity) -> Self {
+        AccessRule {
+            max_neighbors: 3,
+            connectivity,
+        }
+    }
+}
+impl Default for AccessRule {
+    /// A cell is accessible if it has less than 4 adjacent paper cells, including diagonals.
+    fn default() -> Self {
+        AccessRule::puzzle(Connectivity::All)
+    }
+}
+
 /// Checks if a cell is accessible based on the number of adjacent paper cells.
-/// A cell is accessible if it has less than 4 adjacent paper cells.
-pub fn is_accessible(cell: &CellInGrid<Cell>) -> bool {
-    let adjacent_cells = cell.adjacent_cells();
+pub fn is_accessible(cell: &CellInGrid<Cell>, rule: &AccessRule) -> bool {
+    let adjacent_cells = cell.neighbor_cells(rule.connectivity);
     let adjacent_cells_with_paper = adjacent_cells.filter(is_paper);
-    adjacent_cells_with_paper.count() < 4
+    adjacent_cells_with_paper.count() <= rule.max_neighbors
+}
+
+/// Counts the paper cells that are accessible under the rule.
+pub fn count_accessible(grid: &Grid<Cell>, rule: &AccessRule) -> usize {
+    grid.count(|cell| is_paper(cell) && is_accessible(cell, rule))
+}
+
+/// Removing paper as a cellular simulation: each step, every accessible paper cell
+/// is removed at once.
+pub struct PaperRemoval {
+    pub rule: AccessRule,
+}
+impl StepSim for PaperRemoval {
+    type Cell = Cell;
+    fn next_value(&self, cell: &CellInGrid<Cell>) -> Option<Cell> {
+        (is_paper(cell) && is_accessible(cell, &self.rule)).then_some(Cell::Empty)
+    }
+}
+
+/// Repeatedly removes all accessible paper cells until none are left to remove.
+/// Returns each removed cell with the round it went in, starting from round 1.  Cells
+/// come in round order, and row-major within a round, so the order is the same every run.
+pub fn remove_all_accessible(grid: &mut Grid<Cell>, rule: &AccessRule) -> Result<Vec<(XY, usize)>> {
+    let sim = PaperRemoval { rule: *rule };
+    let mut removals = Vec::new();
+    // Removing paper can't bring earlier paper back, so the simulation always settles.
+    for round in 1.. {
+        let changes = automaton::step(&sim, grid);
+        if changes.is_empty() {
+            break;
+        }
+        removals.extend(changes.into_iter().map(|(xy, _)| (xy, round)));
+    }
+    Ok(removals)
+}
+
+/// Scores removals from [`remove_all_accessible`] by adding up the weight of the round
+/// each cell was removed in.
+pub fn score_by_round(removals: &[(XY, usize)], weights: impl Fn(usize) -> u64) -> u64 {
+    removals.iter().map(|(_, round)| weights(*round)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Grid<Cell> {
+        common::grid::parse_data_into_grid(include_str!("../sample.txt")).unwrap()
+    }
+
+    #[test]
+    fn test_invalid_cell() {
+        let error = common::grid::parse_data_into_grid::<Cell>("..@\n.#.")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 2: Expected '.' for empty or '@' for paper, got \"#\""
+        );
+    }
+
+    #[test]
+    fn test_default_rule() {
+        let grid = sample();
+        assert_eq!(count_accessible(&grid, &AccessRule::default()), 13);
+        assert_eq!(
+            remove_all_accessible(&mut grid.clone(), &AccessRule::default())
+                .unwrap()
+                .len(),
+            43
+        );
+    }
+
+    #[test]
+    fn test_removal_order() {
+        let removals = remove_all_accessible(&mut sample(), &AccessRule::default()).unwrap();
+        // The first round is everything part 1 counts.
+        assert_eq!(removals.iter().filter(|(_, round)| *round == 1).count(), 13);
+        assert!(removals.is_sorted_by_key(|(xy, round)| (*round, xy.y, xy.x)));
+        assert_eq!(
+            removals,
+            remove_all_accessible(&mut sample(), &AccessRule::default()).unwrap()
+        );
+        assert_eq!(score_by_round(&removals, |_| 1), 43);
+        assert_eq!(score_by_round(&removals, |round| (round == 1) as u64), 13);
+    }
+
+    #[test]
+    fn test_cardinal_rule() {
+        let grid = sample();
+        let all = AccessRule {
+            max_neighbors: 1,
+            connectivity: Connectivity::All,
+        };
+        let cardinal = AccessRule {
+            max_neighbors: 1,
+            connectivity: Connectivity::Cardinal,
+        };
+        // Ignoring diagonals can only make more cells accessible.
+        assert!(count_accessible(&grid, &cardinal) > count_accessible(&grid, &all));
+        assert_eq!(AccessRule::puzzle(Connectivity::All), AccessRule::default());
+        assert!(
+            count_accessible(&grid, &AccessRule::puzzle(Connectivity::Cardinal))
+                >= count_accessible(&grid, &AccessRule::default())
+        );
+    }
 }
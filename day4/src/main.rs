@@ -1,29 +1,48 @@
 use anyhow::Result;
-use common::grid::Grid;
-use day4::Cell;
+use common::error::ErrorContext;
+use common::grid::{Connectivity, Grid};
+use day4::{AccessRule, Cell};
 
 fn main() -> Result<()> {
     // Read data
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let mut cells = common::grid::parse_data_into_grid::<Cell>(&data)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &[])?;
+    let data = common::read_file(arg1)?;
+    let mut cells = common::grid::parse_data_into_grid::<Cell>(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(4)?;
+
+    // --cardinal only counts paper above, below and beside a cell as adjacent.
+    let connectivity = if args.iter().any(|arg| arg == "--cardinal") {
+        Connectivity::Cardinal
+    } else {
+        Connectivity::All
+    };
+    let rule = AccessRule::puzzle(connectivity);
 
     // Run both parts 1 and 2
-    part1(&cells)?;
-    part2(&mut cells)?;
-    isabel(&arg1)?;
+    part1(&cells, &rule).with_phase("part 1").with_day(4)?;
+    part2(&mut cells, &rule).with_phase("part 2").with_day(4)?;
+    if connectivity != Connectivity::All {
+        // Isabel's version only knows the puzzle's rule.
+        return Ok(());
+    }
+    let isabel_grid = isabel(arg1)?;
+
+    // Both part 2 implementations should leave the same paper behind.
+    let isabel_grid = Grid::<Cell>::from_lines(isabel_grid.iter().map(String::from_iter))?;
+    let differences = common::grid::diff(&cells, &isabel_grid);
+    if !differences.is_empty() {
+        anyhow::bail!("Part 2 implementations disagree:\n{}", differences);
+    }
 
     Ok(())
 }
 
 /// Part 1: Count the number of accessible paper cells.
-fn part1(grid: &Grid<Cell>) -> Result<()> {
-    let all_cells = grid.cells();
-    let cells_with_paper = all_cells.filter(day4::is_paper);
-    let accessible_paper_cells = cells_with_paper.filter(day4::is_accessible);
-    let number_of_accessible_paper_cells = accessible_paper_cells.count();
+fn part1(grid: &Grid<Cell>, rule: &AccessRule) -> Result<()> {
+    let number_of_accessible_paper_cells = day4::count_accessible(grid, rule);
     println!(
         "Part 1: Accessible paper cells: {}",
         number_of_accessible_paper_cells
@@ -31,30 +50,18 @@ fn part1(grid: &Grid<Cell>) -> Result<()> {
     Ok(())
 }
 
-fn part2(grid: &mut Grid<Cell>) -> Result<()> {
-    let mut removed_count = 0;
-    loop {
-        let all_cells = grid.cells();
-        let cells_with_paper = all_cells.filter(day4::is_paper);
-        let accessible_paper_cell = cells_with_paper.filter(day4::is_accessible);
-        // We have to collect the XYs into a vector because the grid needs to be mutable.
-        let xy_to_remove = accessible_paper_cell.map(|c| c.xy()).collect::<Vec<_>>();
-
-        // Remove each of these cells from the grid
-        let cleared_count = day4::remove_cells(grid, xy_to_remove)?;
-        if cleared_count == 0 {
-            break;
-        }
-        removed_count += cleared_count;
-    }
-    println!("Part 2: Removed count: {}", removed_count);
+fn part2(grid: &mut Grid<Cell>, rule: &AccessRule) -> Result<()> {
+    let removals = day4::remove_all_accessible(grid, rule)?;
+    println!("Part 2: Removed count: {}", removals.len());
     Ok(())
 }
 
 use std::fs::File;
 use std::io::{self, BufRead};
 
-fn isabel(file: &str) -> io::Result<()> {
+use common::convert::{to_i64, to_usize};
+
+fn isabel(file: &str) -> Result<Vec<Vec<char>>> {
     let file = File::open(file)?;
     let reader = io::BufReader::new(file);
 
@@ -86,16 +93,15 @@ fn isabel(file: &str) -> io::Result<()> {
                     ];
                     let mut num_neighbors = 0;
                     for (di, dj) in directions.iter() {
-                        let ni = i as isize + di;
-                        let nj = j as isize + dj;
+                        let ni = to_i64(i)? + di;
+                        let nj = to_i64(j)? + dj;
                         if ni >= 0
-                            && ni < input_matrix.len() as isize
+                            && ni < to_i64(input_matrix.len())?
                             && nj >= 0
-                            && nj < input_matrix[i].len() as isize
+                            && nj < to_i64(input_matrix[i].len())?
+                            && input_matrix[to_usize(ni)?][to_usize(nj)?] == '@'
                         {
-                            if input_matrix[ni as usize][nj as usize] == '@' {
-                                num_neighbors += 1;
-                            }
+                            num_neighbors += 1;
                         }
                     }
                     if num_neighbors < 4 {
@@ -115,5 +121,5 @@ fn isabel(file: &str) -> io::Result<()> {
     //     let line: String = row.iter().collect();
     //     println!("{}", line);
     // }
-    Ok(())
+    Ok(input_matrix)
 }
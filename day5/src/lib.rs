@@ -0,0 +1,251 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{Context, Result};
+use common::{
+    error::ErrorContext,
+    parse::{ExpectFormat, int},
+    random::Random,
+    ranges::IntervalSet,
+    sweep::{ActiveSet, Event, SweepLine},
+};
+
+pub type RangeType = u64;
+
+/// Parses the fresh ranges, a blank line, then the available ingredient IDs, one per
+/// line.
+pub fn parse_data(data: &str) -> Result<(Vec<RangeInclusive<RangeType>>, Vec<RangeType>)> {
+    let mut ranges = Vec::new();
+    let mut ingredients = Vec::new();
+    let mut in_ranges = true;
+    for (number, line) in (1..).zip(data.lines()) {
+        let line = line.trim();
+        if line.is_empty() {
+            in_ranges = false;
+        } else if in_ranges {
+            ranges.push(parse_range(line).with_line(number)?);
+        } else {
+            let id = int(line)
+                .expect_format("an ingredient ID like '17'", line)
+                .with_line(number)?;
+            ingredients.push(id);
+        }
+    }
+    Ok((ranges, ingredients))
+}
+
+fn parse_range(line: &str) -> Result<RangeInclusive<RangeType>> {
+    let parsed = line
+        .split_once('-')
+        .ok_or_else(|| anyhow::anyhow!("Missing '-'"))
+        .and_then(|(start, end)| Ok(int::<RangeType>(start)?..=int(end)?));
+    parsed.expect_format("a range of IDs like '3-5'", line)
+}
+
+/// The fresh ingredient ranges, merged and sorted so membership is a binary search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergedRanges {
+    ranges: IntervalSet<RangeType>,
+}
+
+impl MergedRanges {
+    /// Checks if an ingredient is in any of the ranges.
+    pub fn contains(&self, value: RangeType) -> bool {
+        self.ranges.contains(&value)
+    }
+
+    /// Counts the ingredients that are in any of the ranges.
+    pub fn count_contained(&self, values: &[RangeType]) -> usize {
+        values.iter().filter(|value| self.contains(**value)).count()
+    }
+
+    /// Gets the IDs between the ranges that no range covers, in ascending order.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<RangeType>> {
+        self.ranges.gaps()
+    }
+
+    /// Gets the IDs in `universe` that no range covers.  The smallest uncovered ID is
+    /// the start of the first of these.
+    pub fn complement(&self, universe: RangeInclusive<RangeType>) -> MergedRanges {
+        MergedRanges {
+            ranges: self.ranges.complement(universe),
+        }
+    }
+
+    /// Gets the merged ranges, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &RangeInclusive<RangeType>> {
+        self.ranges.iter()
+    }
+}
+
+impl FromIterator<RangeInclusive<RangeType>> for MergedRanges {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<RangeType>>>(iter: I) -> Self {
+        MergedRanges {
+            ranges: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Counts the IDs in ranges that don't overlap.  Fails if there are more than fit in a
+/// [`RangeType`], which only every ID at once has.
+pub fn covered_ids<'a>(
+    ranges: impl IntoIterator<Item = &'a RangeInclusive<RangeType>>,
+) -> Result<RangeType> {
+    common::try_fold_checked(
+        ranges
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .map(|range| Ok(range.end() - range.start())),
+        0,
+        // Each range's end minus start is one less than its size.
+        |total: RangeType, span| total.checked_add(span)?.checked_add(1),
+    )
+    .context("Too many IDs to count")
+}
+
+/// Merges the ranges by sweeping across their starts and ends, giving the same ranges
+/// as [`MergedRanges`] in ascending order.
+pub fn merge_by_sweep(ranges: &[RangeInclusive<RangeType>]) -> Vec<RangeInclusive<RangeType>> {
+    let mut sweep = SweepLine::new();
+    for (i, range) in ranges
+        .iter()
+        .enumerate()
+        .filter(|(_, range)| !range.is_empty())
+    {
+        sweep.push(*range.start(), Event::Insert(i));
+        // Ranges end just past their last ID, so touching ranges merge.
+        if let Some(after) = range.end().checked_add(1) {
+            sweep.push(after, Event::Remove(i));
+        }
+    }
+    let mut active = ActiveSet::new();
+    let mut merged = Vec::new();
+    let mut merging_from = None;
+    for (position, events) in sweep.batches() {
+        events.into_iter().for_each(|event| active.apply(event));
+        match (merging_from, active.is_empty()) {
+            (None, false) => merging_from = Some(position),
+            (Some(start), true) => {
+                merged.push(start..=position - 1);
+                merging_from = None;
+            }
+            _ => {}
+        }
+    }
+    // Only a range ending at the largest ID is still open.
+    if let Some(start) = merging_from {
+        merged.push(start..=RangeType::MAX);
+    }
+    merged
+}
+
+/// Counts the ingredients in any of the ranges by checking every range, the way part 1
+/// used to.  Kept to benchmark against [`MergedRanges::count_contained`].
+pub fn count_contained_linear(ranges: &[RangeInclusive<RangeType>], values: &[RangeType]) -> usize {
+    values
+        .iter()
+        .filter(|value| ranges.iter().any(|range| range.contains(value)))
+        .count()
+}
+
+/// Generates `count` ranges up to `width` long and `count` ingredients, spread up to
+/// `max`, from a fixed seed so benchmarks are repeatable.
+pub fn generate(
+    count: usize,
+    width: RangeType,
+    max: RangeType,
+    seed: u64,
+) -> (Vec<RangeInclusive<RangeType>>, Vec<RangeType>) {
+    let mut random = Random::new(seed);
+    let mut next = move |below: RangeType| random.below(below);
+    let ranges = (0..count)
+        .map(|_| {
+            let start = next(max);
+            start..=start + next(width)
+        })
+        .collect();
+    let values = (0..count).map(|_| next(max)).collect();
+    (ranges, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data() {
+        let data = std::fs::read_to_string("sample.txt").unwrap();
+        let (ranges, ingredients) = parse_data(&data).unwrap();
+        assert_eq!(ranges.len(), 4);
+        assert_eq!(ingredients, vec![1, 5, 8, 11, 17, 32]);
+        let (ranges, ingredients) = parse_data("1_000-+2_000\n\n1_500").unwrap();
+        assert_eq!((ranges, ingredients), (vec![1000..=2000], vec![1500]));
+        let error = parse_data("3-5\n10:14\n\n1").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: Expected a range of IDs like '3-5', got \"10:14\""
+        );
+        assert!(parse_data("3-5\n\nx").is_err());
+    }
+
+    #[test]
+    fn test_count_contained() {
+        let ranges = vec![3..=5, 10..=14, 16..=20, 12..=18];
+        let values = [1, 5, 8, 11, 17, 32];
+        let merged = ranges.iter().cloned().collect::<MergedRanges>();
+        assert_eq!(merged.iter().count(), 2);
+        assert!(merged.contains(15));
+        assert_eq!(merged.count_contained(&values), 3);
+        assert_eq!(count_contained_linear(&ranges, &values), 3);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let merged = [3..=5, 10..=14, 16..=20, 12..=18]
+            .into_iter()
+            .collect::<MergedRanges>();
+        assert_eq!(merged.gaps().collect::<Vec<_>>(), vec![6..=9]);
+        let uncovered = merged.complement(3..=30);
+        assert_eq!(uncovered.iter().next(), Some(&(6..=9)));
+        assert_eq!(uncovered.iter().last(), Some(&(21..=30)));
+        assert!(!uncovered.contains(4));
+    }
+
+    #[test]
+    fn test_merge_by_sweep() {
+        let ranges = vec![3..=5, 10..=14, 16..=20, 12..=18, 6..=6, 30..=RangeType::MAX];
+        let merged = ranges.iter().cloned().collect::<MergedRanges>();
+        assert_eq!(
+            merge_by_sweep(&ranges),
+            vec![3..=6, 10..=20, 30..=RangeType::MAX]
+        );
+        assert!(merge_by_sweep(&ranges).iter().eq(merged.iter()));
+        assert!(merge_by_sweep(&[]).is_empty());
+
+        let (ranges, _) = generate(500, 1_000, 1_000_000, 5);
+        let merged = ranges.iter().cloned().collect::<MergedRanges>();
+        assert!(merge_by_sweep(&ranges).iter().eq(merged.iter()));
+    }
+
+    #[test]
+    fn test_covered_ids() {
+        assert_eq!(covered_ids(&[3..=5, 10..=10]).unwrap(), 4);
+        assert_eq!(covered_ids(&[]).unwrap(), 0);
+        // Ranges reaching the largest ID don't overflow counting them.
+        assert_eq!(
+            covered_ids(&[10..=RangeType::MAX]).unwrap(),
+            RangeType::MAX - 9
+        );
+        assert_eq!(covered_ids(&[1..=RangeType::MAX]).unwrap(), RangeType::MAX);
+        assert!(covered_ids(&[0..=RangeType::MAX]).is_err());
+    }
+
+    #[test]
+    fn test_generated_agree() {
+        let (ranges, values) = generate(500, 1_000, 1_000_000, 5);
+        let merged = ranges.iter().cloned().collect::<MergedRanges>();
+        assert_eq!(
+            merged.count_contained(&values),
+            count_contained_linear(&ranges, &values)
+        );
+    }
+}
@@ -1,120 +1,173 @@
-use std::ops::RangeInclusive;
+use std::time::Instant;
 
 use anyhow::Result;
+use common::{error::ErrorContext, stats::Summary};
+use day5::{MergedRanges, count_contained_linear};
 
-type RangeType = u64;
+/// How many times --bench times each version.
+const BENCH_RUNS: usize = 5;
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let (ranges, ing) = data.split_once("\n\n").unwrap();
-    let ranges = ranges
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let (start, end) = line.split_once("-").unwrap();
-            let start = start.parse::<RangeType>().unwrap();
-            let end = end.parse::<RangeType>().unwrap();
-            start..=end
-        })
-        .collect::<Vec<_>>();
-    let ing = ing
-        .lines()
-        .map(|line| line.parse::<RangeType>().unwrap())
-        .collect::<Vec<_>>();
-
-    let ing_within_ranges = ing.iter().filter(|&i| ranges.iter().any(|r| r.contains(i)));
-    let ing_not_in_ranges_count = ing_within_ranges.collect::<Vec<_>>();
-    println!("Part 1: {}", ing_not_in_ranges_count.len());
-
-    // Deconflict the ranges.  For all the ranges, find the overlapping ones
-    // split and combine.
-
-    // Sort ranges by start
-    let mut ranges = ranges;
-    ranges.sort_by_key(|r| *r.start());
-
-    loop {
-        if !do_one_merge(&mut ranges) {
-            break;
-        }
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // --bench <count> times part 1's membership checks on generated ranges instead of
+    // solving.
+    if let Some(count) = args.iter().skip_while(|arg| *arg != "--bench").nth(1) {
+        return bench(count.parse()?);
     }
+    let arg1 = common::args::input_path(&args, &["--bench"])?;
+    let data = common::read_file(arg1)?;
+    let (ranges, ing) = day5::parse_data(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(5)?;
+
+    let fresh = ranges.iter().cloned().collect::<MergedRanges>();
+    println!("Part 1: {}", fresh.count_contained(&ing));
+
+    // Deconflict the ranges by sweeping across where they start and end.
+    let ranges = day5::merge_by_sweep(&ranges);
 
     // Sum all ranges
-    let sum: RangeType = ranges.iter().map(|r| r.end() + 1 - r.start()).sum();
+    let sum = day5::covered_ids(&ranges)
+        .with_phase("part 2")
+        .with_day(5)?;
 
     println!("Part2: {sum:?}");
 
     Ok(())
 }
 
-// Performs one merge operation with the provided ranges.
-fn do_one_merge(ranges: &mut Vec<RangeInclusive<RangeType>>) -> bool {
-    // Using indexed looping instead of iterators because we need to control mutability to modify the ranges
-    for r1_pos in 0..ranges.len() {
-        for r2_pos in r1_pos + 1..ranges.len() {
-            let r1 = &ranges[r1_pos];
-            let r2 = &ranges[r2_pos];
-            if let Some(action) = what_action(r1, r2) {
-                match action {
-                    Action::RemoveR1 => {
-                        //println!("Given: {r2:?} Removing: {r1:?}");
-                        ranges.remove(r1_pos);
-                    }
-                    Action::RemoveR2 => {
-                        //println!("Given: {r1:?} Removing: {r2:?}");
-                        ranges.remove(r2_pos);
-                    }
-                    Action::Merge(new_r1) => {
-                        //println!("Given: {r1:?} Replacing: {new_r1:?} and removing {r2:?}");
-                        ranges[r1_pos] = new_r1;
-                        ranges.remove(r2_pos);
-                    }
-                }
-                return true;
-            }
+/// Compares checking every ingredient against every range with binary searching the
+/// merged ranges, timing each a few times.
+fn bench(count: usize) -> Result<()> {
+    let (ranges, values) = day5::generate(count, 1_000_000, 1 << 40, 2025);
+    let mut linear_ms = Vec::new();
+    let mut merged_ms = Vec::new();
+    for _ in 0..BENCH_RUNS {
+        let start = Instant::now();
+        let linear = count_contained_linear(&ranges, &values);
+        linear_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        let start = Instant::now();
+        let merged = ranges
+            .iter()
+            .cloned()
+            .collect::<MergedRanges>()
+            .count_contained(&values);
+        merged_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        if linear != merged {
+            anyhow::bail!("Merged count {} differs from linear {}", merged, linear);
         }
     }
-    return false;
+    println!("{} ranges and ingredients, times in ms", count);
+    for (name, times) in [("linear", linear_ms), ("merged", merged_ms)] {
+        if let Some(summary) = Summary::of(times) {
+            println!("{:>10}: {}", name, summary);
+        }
+    }
+    Ok(())
 }
 
-enum Action {
-    Merge(RangeInclusive<RangeType>),
-    RemoveR2,
-    RemoveR1,
-}
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use day5::RangeType;
 
-fn what_action(r1: &RangeInclusive<RangeType>, r2: &RangeInclusive<RangeType>) -> Option<Action> {
-    // r2 is entirely within r1
-    if entirely_within(r1, r2) {
-        return Some(Action::RemoveR2);
+    /// Merges overlapping ranges a pair at a time until none overlap, the way part 2
+    /// used to, to check [`day5::merge_by_sweep`] against.
+    fn merge_pairwise(
+        mut ranges: Vec<RangeInclusive<RangeType>>,
+    ) -> Vec<RangeInclusive<RangeType>> {
+        // Sort ranges by start
+        ranges.sort_by_key(|r| *r.start());
+
+        loop {
+            if !do_one_merge(&mut ranges) {
+                break;
+            }
+        }
+        ranges
     }
-    if entirely_within(r2, r1) {
-        return Some(Action::RemoveR1);
+
+    // Performs one merge operation with the provided ranges.
+    fn do_one_merge(ranges: &mut Vec<RangeInclusive<RangeType>>) -> bool {
+        // Using indexed looping instead of iterators because we need to control mutability to modify the ranges
+        for r1_pos in 0..ranges.len() {
+            for r2_pos in r1_pos + 1..ranges.len() {
+                let r1 = &ranges[r1_pos];
+                let r2 = &ranges[r2_pos];
+                if let Some(action) = what_action(r1, r2) {
+                    match action {
+                        Action::RemoveR1 => {
+                            //println!("Given: {r2:?} Removing: {r1:?}");
+                            ranges.remove(r1_pos);
+                        }
+                        Action::RemoveR2 => {
+                            //println!("Given: {r1:?} Removing: {r2:?}");
+                            ranges.remove(r2_pos);
+                        }
+                        Action::Merge(new_r1) => {
+                            //println!("Given: {r1:?} Replacing: {new_r1:?} and removing {r2:?}");
+                            ranges[r1_pos] = new_r1;
+                            ranges.remove(r2_pos);
+                        }
+                    }
+                    return true;
+                }
+            }
+        }
+        false
     }
 
-    // r2 start is within r1
-    if second_range_within_first(r1, r2) {
-        return Some(Action::Merge(*r1.start()..=*r2.end()));
+    enum Action {
+        Merge(RangeInclusive<RangeType>),
+        RemoveR2,
+        RemoveR1,
     }
 
-    // r1 start is within r2
-    if second_range_within_first(r2, r1) {
-        return Some(Action::Merge(*r2.start()..=*r1.end()));
+    fn what_action(
+        r1: &RangeInclusive<RangeType>,
+        r2: &RangeInclusive<RangeType>,
+    ) -> Option<Action> {
+        // r2 is entirely within r1
+        if entirely_within(r1, r2) {
+            return Some(Action::RemoveR2);
+        }
+        if entirely_within(r2, r1) {
+            return Some(Action::RemoveR1);
+        }
+
+        // r2 start is within r1
+        if second_range_within_first(r1, r2) {
+            return Some(Action::Merge(*r1.start()..=*r2.end()));
+        }
+
+        // r1 start is within r2
+        if second_range_within_first(r2, r1) {
+            return Some(Action::Merge(*r2.start()..=*r1.end()));
+        }
+
+        None
     }
 
-    None
-}
+    // True if r2 is entirely within r1
+    fn entirely_within<T: Ord>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool {
+        r2.start() >= r1.start() && r2.end() <= r1.end()
+    }
 
-// True if r2 is entirely within r1
-fn entirely_within<T: Ord>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool {
-    r2.start() >= r1.start() && r2.end() <= r1.end()
-}
+    // Does r2 start within r1
+    fn second_range_within_first<T: Ord>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool {
+        r2.start() >= r1.start() && r2.start() <= r1.end()
+    }
 
-// Does r2 start within r1
-fn second_range_within_first<T: Ord>(r1: &RangeInclusive<T>, r2: &RangeInclusive<T>) -> bool {
-    r2.start() >= r1.start() && r2.start() <= r1.end()
+    #[test]
+    fn test_pairwise_merge_matches_sweep() {
+        let (sample, _) = day5::parse_data(common::include_sample!()).unwrap();
+        let (generated, _) = day5::generate(200, 1_000, 100_000, 5);
+        for ranges in [sample, generated] {
+            let swept = day5::covered_ids(&day5::merge_by_sweep(&ranges)).unwrap();
+            let pairwise = day5::covered_ids(&merge_pairwise(ranges)).unwrap();
+            assert_eq!(pairwise, swept);
+        }
+    }
 }
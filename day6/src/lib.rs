@@ -1,4 +1,10 @@
 use anyhow::Context;
+use common::{
+    error::{AocError, ErrorContext, caret_snippet},
+    grid::Grid,
+    parse::{ExpectFormat, int},
+    strings::split_fixed_width,
+};
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +23,9 @@ impl FromStr for Operation {
     }
 }
 
+/// What each field of the operations line should be.
+const OPERATION_FORMAT: &str = "'+' or '*'";
+
 pub struct Worksheet {
     grid: Vec<Vec<u64>>,
     operations: Vec<Operation>,
@@ -30,29 +39,39 @@ impl FromStr for Worksheet {
             .split_last()
             .ok_or_else(|| anyhow::anyhow!("No operations line found"))?;
 
-        let grid = grid_lines
-            .iter()
-            .map(|line| {
-                line.split_whitespace()
-                    .map(|s| s.parse::<u64>().context("Failed to parse grid cell"))
-                    .collect::<Result<Vec<_>, _>>()
+        let operation_line = grid_lines.len() + 1;
+        let operations = fields(operations)
+            .map(|(column, field)| {
+                Operation::from_str(field)
+                    .expect_format(OPERATION_FORMAT, field)
+                    .map_err(|e| at(operation_line, column, operations, e))
             })
-            .collect::<Result<Vec<Vec<u64>>, _>>()
-            .context("Failed to parse grid")?;
-        let operations = operations
-            .split_whitespace()
-            .map(Operation::from_str)
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to parse operations")?;
+            .collect::<Result<Vec<_>, _>>()?;
 
-        // Make sure the column count is the same for all rows and operations
-        {
-            let operation_count = operations.len();
-            if grid.iter().any(|row| row.len() != operation_count) {
-                return Err(anyhow::anyhow!(
-                    "Grid row count does not match operation count"
-                ));
+        let mut grid = Vec::with_capacity(grid_lines.len());
+        for (row_index, line) in grid_lines.iter().enumerate() {
+            let mut row = Vec::with_capacity(operations.len());
+            for (column, field) in fields(line) {
+                let value = int::<u64>(field)
+                    .expect_format("a non-negative integer", field)
+                    .map_err(|e| at(row_index + 1, column, line, e))?;
+                row.push(value);
             }
+            // Every row needs one number per operation.
+            if row.len() != operations.len() {
+                let column = fields(line)
+                    .nth(operations.len())
+                    .map(|(column, _)| column)
+                    .unwrap_or(line.chars().count() + 1);
+                let error = anyhow::anyhow!(
+                    "Row {} has {} columns, expected {} to match the operations",
+                    row_index,
+                    row.len(),
+                    operations.len()
+                );
+                return Err(at(row_index + 1, column, line, error).into());
+            }
+            grid.push(row);
         }
 
         Ok(Worksheet { grid, operations })
@@ -62,14 +81,14 @@ impl FromStr for Worksheet {
 pub fn parse_part2(input: &str) -> anyhow::Result<(Vec<Vec<u64>>, Vec<Operation>)> {
     let lines = input.lines().collect::<Vec<_>>();
     // Split lines into the grid and operations.  Operations is the last line.
-    let (operations, grid_lines) = lines
+    let (operations_line, grid_lines) = lines
         .split_last()
         .ok_or_else(|| anyhow::anyhow!("No operations line found"))?;
 
     // Split out the operations first, this will help tell how wide the columns are.
     // Operations look like "*    *  +   *  "
     // where the number of spaces between the operations is the width of the columns.
-    let operations = split_operations_part2(operations).collect::<Vec<_>>();
+    let operations = split_operations_part2(operations_line).collect::<Vec<_>>();
     // Create a nxm grid of chars.
     let grid = grid_lines
         .iter()
@@ -79,30 +98,99 @@ pub fn parse_part2(input: &str) -> anyhow::Result<(Vec<Vec<u64>>, Vec<Operation>
     // Assert all rows are the same length.
     {
         let row_length = grid
-            .get(0)
+            .first()
             .ok_or_else(|| anyhow::anyhow!("No grid rows found"))?
             .len();
-        if grid.iter().any(|row| row.len() != row_length) {
-            return Err(anyhow::anyhow!("Grid row lengths do not match"));
+        if let Some(row_index) = grid.iter().position(|row| row.len() != row_length) {
+            let found = grid[row_index].len();
+            let error = anyhow::anyhow!(
+                "Row {} is {} characters long, expected {} like row 0",
+                row_index,
+                found,
+                row_length
+            );
+            let line = grid_lines[row_index];
+            return Err(at(row_index + 1, found.min(row_length) + 1, line, error).into());
         }
     }
 
-    let column_indices = column_indices(operations.iter().map(|operation| operation.len()));
+    let column_indices =
+        column_indices(operations.iter().map(|operation| operation.len())).collect::<Vec<_>>();
 
     let column_numbers = column_indices
-        .map(|(start_index, end_index)| {
+        .iter()
+        .map(|&(start_index, end_index)| {
             column_numbers(&grid, start_index, end_index).collect::<Vec<_>>()
         })
         .collect::<Vec<_>>();
 
-    let operations = operations
+    let operation_line = grid_lines.len() + 1;
+    let operations = column_indices
         .iter()
-        .map(|op| Operation::from_str(op.trim()).context("Failed to parse operation"))
+        .zip(&operations)
+        .map(|((start_index, _), op)| {
+            Operation::from_str(op.trim())
+                .expect_format(OPERATION_FORMAT, op.trim())
+                .map_err(|e| at(operation_line, start_index + 1, operations_line, e))
+        })
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok((column_numbers, operations))
 }
 
+/// Checks whether the numbers are laid out in fixed-width columns; see
+/// [`Worksheet::parse_auto`].
+fn is_columnar(input: &str) -> bool {
+    let lines = input.lines().collect::<Vec<_>>();
+    let Some((operations, rows)) = lines.split_last() else {
+        return false;
+    };
+    let Some(width) = rows.first().map(|row| row.chars().count()) else {
+        return false;
+    };
+    if rows.iter().any(|row| row.chars().count() != width) {
+        return false;
+    }
+    fields(operations).skip(1).all(|(column, _)| {
+        // `column` is 1-based, so the separator before it is at index `column - 2`.
+        rows.iter()
+            .all(|row| row.chars().nth(column - 2) == Some(' '))
+    })
+}
+
+/// Splits a line on whitespace, giving each field with its 1-based column.
+fn fields(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = None;
+    let mut chars = line.char_indices().enumerate().peekable();
+    std::iter::from_fn(move || {
+        while let Some((column, (offset, c))) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start.get_or_insert((column, offset));
+            let end = match chars.peek() {
+                Some((_, (next_offset, next))) if next.is_whitespace() => *next_offset,
+                Some(_) => continue,
+                None => line.len(),
+            };
+            let (column, offset) = start.take()?;
+            return Some((column + 1, &line[offset..end]));
+        }
+        None
+    })
+}
+
+/// Places an error at a line and column of the worksheet, with the line shown under it.
+fn at(line_number: usize, column: usize, line: &str, error: anyhow::Error) -> AocError {
+    AocError::new(anyhow::anyhow!(
+        "{:#}\n{}",
+        error,
+        caret_snippet(line, column)
+    ))
+    .at_line(line_number)
+    .at_column(column)
+}
+
 fn column_numbers<IT: Iterator<Item = impl AsRef<[char]>> + DoubleEndedIterator>(
     grid: impl IntoIterator<Item = impl AsRef<[char]>, IntoIter = IT> + Clone,
     start_index: usize,
@@ -146,30 +234,205 @@ fn column_indices(
     })
 }
 
+/// Splits the operations line into one field per column.  Each operation starts a
+/// column, and every column but the last is followed by a single separating space.
 pub fn split_operations_part2(operations: &str) -> impl Iterator<Item = &str> {
-    let mut start_index = 0;
-    let mut chars = operations.chars().peekable();
-    std::iter::from_fn(move || {
-        let _operation = chars.next()?;
-        let mut space_count = 0;
-        while let Some(c) = chars.peek() {
-            if c.is_whitespace() {
-                space_count += 1;
-                chars.next();
+    let starts = operations
+        .chars()
+        .enumerate()
+        .filter(|(i, c)| *i == 0 || !c.is_whitespace())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let widths = starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&operations.chars().count()]))
+        .map(|(start, end)| end - start)
+        .collect::<Vec<_>>();
+    let columns = widths.len();
+    split_fixed_width(operations, widths)
+        .enumerate()
+        .map(move |(i, field)| {
+            if i + 1 < columns {
+                field.strip_suffix(' ').unwrap_or(field)
             } else {
-                space_count -= 1;
-                break;
+                field
             }
+        })
+}
+
+/// The totals of both parts, from [`Worksheet::evaluate_streaming`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamingTotals {
+    pub part1: u64,
+    pub part2: u64,
+}
+
+/// Both possible results for a column, since the operation isn't known until the last
+/// line.  None means that result overflowed.
+#[derive(Debug, Clone, Copy)]
+struct Accumulator {
+    sum: Option<u64>,
+    product: Option<u64>,
+}
+impl Default for Accumulator {
+    fn default() -> Self {
+        Accumulator {
+            sum: Some(0),
+            product: Some(1),
+        }
+    }
+}
+impl Accumulator {
+    fn push(&mut self, value: u64) {
+        self.sum = self.sum.and_then(|sum| sum.checked_add(value));
+        self.product = self.product.and_then(|product| product.checked_mul(value));
+    }
+    fn result(&self, operation: Operation) -> anyhow::Result<u64> {
+        match operation {
+            Operation::Add => self.sum,
+            Operation::Multiply => self.product,
         }
-        let end_index = start_index + space_count + 1;
+        .ok_or_else(|| anyhow::anyhow!("Overflow applying {:?} to column", operation))
+    }
+}
 
-        let ret = operations.get(start_index..end_index)?;
-        start_index = end_index + 1;
-        Some(ret)
-    })
+/// A worksheet read in whichever layout its file uses, from [`Worksheet::parse_auto`].
+pub enum ParsedWorksheet {
+    /// Numbers separated by any amount of whitespace.  Only part 1 can be read from
+    /// it, since part 2 needs the digits lined up in columns.
+    Tokens(Worksheet),
+    /// Numbers in fixed-width columns, the way the puzzle gives them, which can be
+    /// read both ways.
+    Columns {
+        worksheet: Worksheet,
+        /// The numbers of each column read top to bottom, for part 2.
+        column_numbers: Vec<Vec<u64>>,
+        operations: Vec<Operation>,
+    },
+}
+impl ParsedWorksheet {
+    /// The worksheet read by whitespace, for part 1.
+    pub fn worksheet(&self) -> &Worksheet {
+        match self {
+            ParsedWorksheet::Tokens(worksheet) | ParsedWorksheet::Columns { worksheet, .. } => {
+                worksheet
+            }
+        }
+    }
 }
 
 impl Worksheet {
+    /// Parses a worksheet in either layout.  It is read as fixed-width columns when
+    /// every number row is the same length and each operation after the first has a
+    /// space above it in every row, separating it from the column before.
+    pub fn parse_auto(input: &str) -> anyhow::Result<ParsedWorksheet> {
+        let worksheet = Worksheet::from_str(input)?;
+        if !is_columnar(input) {
+            return Ok(ParsedWorksheet::Tokens(worksheet));
+        }
+        let (column_numbers, operations) = parse_part2(input)?;
+        Ok(ParsedWorksheet::Columns {
+            worksheet,
+            column_numbers,
+            operations,
+        })
+    }
+
+    /// Evaluates both parts while reading the worksheet a line at a time, so memory use
+    /// depends on the width of the worksheet but not its height.
+    pub fn evaluate_streaming(reader: impl std::io::BufRead) -> anyhow::Result<StreamingTotals> {
+        // Part 1 accumulates each whitespace separated column.
+        let mut columns = Vec::<Accumulator>::new();
+        // Part 2 reads each character column top to bottom as one number.
+        let mut char_columns = Vec::<u64>::new();
+        // A line is only known to be a number row once another line follows it.
+        let mut previous: Option<String> = None;
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.context("Failed to read worksheet")?;
+            let Some(row) = previous.replace(line) else {
+                continue;
+            };
+            // `line_index` is the line after the row, so it is the row's 1-based line
+            // number and one past its 0-based index.
+            let (row_index, line_number) = (line_index - 1, line_index);
+            let values = row
+                .split_whitespace()
+                .map(|s| int::<u64>(s).expect_format("a non-negative integer", s))
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("Failed to parse row {}", row_index))
+                .with_line(line_number)?;
+            if line_index == 1 {
+                columns.resize(values.len(), Accumulator::default());
+                char_columns.resize(row.chars().count(), 0);
+            }
+            if values.len() != columns.len() {
+                let error = anyhow::anyhow!(
+                    "Row {} has {} columns, expected {}",
+                    row_index,
+                    values.len(),
+                    columns.len()
+                );
+                return Err(AocError::new(error).at_line(line_number).into());
+            }
+            for (column, value) in columns.iter_mut().zip(values) {
+                column.push(value);
+            }
+            if row.chars().count() != char_columns.len() {
+                let error = anyhow::anyhow!("Grid row lengths do not match");
+                return Err(AocError::new(error).at_line(line_number).into());
+            }
+            for (number, c) in char_columns.iter_mut().zip(row.chars()) {
+                if let Some(digit) = c.to_digit(10) {
+                    *number = number
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit as u64))
+                        .ok_or_else(|| anyhow::anyhow!("Column number overflowed"))?;
+                }
+            }
+        }
+        let operations_line =
+            previous.ok_or_else(|| anyhow::anyhow!("No operations line found"))?;
+
+        let operations = operations_line
+            .split_whitespace()
+            .map(|s| Operation::from_str(s).expect_format(OPERATION_FORMAT, s))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to parse operations")?;
+        if operations.len() != columns.len() {
+            return Err(anyhow::anyhow!(
+                "Grid row count does not match operation count"
+            ));
+        }
+        let part1 = common::try_fold_checked(
+            columns
+                .iter()
+                .zip(operations.iter())
+                .map(|(column, operation)| column.result(*operation)),
+            0,
+            u64::checked_add,
+        )
+        .context("Failed to total part 1")?;
+
+        let groups = split_operations_part2(&operations_line).collect::<Vec<_>>();
+        let part2 = column_indices(groups.iter().map(|group| group.len()))
+            .zip(operations)
+            .map(|((start_index, end_index), operation)| {
+                let mut accumulator = Accumulator::default();
+                for number in char_columns.get(start_index..end_index).unwrap_or_default() {
+                    accumulator.push(*number);
+                }
+                accumulator.result(operation)
+            });
+        let part2 = common::try_fold_checked(part2, 0, u64::checked_add)
+            .context("Failed to total part 2")?;
+
+        Ok(StreamingTotals { part1, part2 })
+    }
+
+    /// Gets the numbers as a grid, one row per worksheet line.
+    pub fn to_grid(&self) -> Grid<u64> {
+        Grid::from_rows(self.grid.clone()).expect("worksheet rows are checked when parsed")
+    }
     pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = u64>> {
         self.grid.iter().map(|row| row.iter().copied())
     }
@@ -185,6 +448,22 @@ impl Worksheet {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_grid() {
+        let input = common::include_sample!();
+        let worksheet = Worksheet::from_str(input).unwrap();
+        let grid = worksheet.to_grid();
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        let columns = grid.transpose();
+        assert_eq!(columns.rows().next().unwrap(), &[123, 45, 6]);
+        assert!(
+            columns
+                .rows()
+                .map(<[u64]>::to_vec)
+                .eq(worksheet.columns().map(Iterator::collect::<Vec<_>>))
+        );
+    }
+
     #[test]
     fn test_split_operations_part2() {
         let input = "*   +   *  + ";
@@ -195,9 +474,103 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_evaluate_streaming_sample() {
+        let input = common::include_sample!();
+        let totals = Worksheet::evaluate_streaming(input.as_bytes()).unwrap();
+        assert_eq!(
+            totals,
+            StreamingTotals {
+                part1: 4277556,
+                part2: 3263827
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluate_streaming_errors() {
+        assert!(Worksheet::evaluate_streaming("".as_bytes()).is_err());
+        let error = Worksheet::evaluate_streaming("1 2\n3\n+ +".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: Row 1 has 1 columns, expected 2");
+        let error = Worksheet::evaluate_streaming("1 2\n3 x\n+ +".as_bytes()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("line 2: Failed to parse row 1"),
+            "{}",
+            error
+        );
+        assert!(Worksheet::evaluate_streaming("1 2\n3 4\n+ -".as_bytes()).is_err());
+        // Each column fits, but not their total.
+        let error =
+            Worksheet::evaluate_streaming("18446744073709551615 1\n+ +".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "Failed to total part 1");
+    }
+
+    #[test]
+    fn test_worksheet_errors_point_at_the_row() {
+        let error = Worksheet::from_str("1 2 3\n4 5\n+ * +").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 4: Row 1 has 2 columns, expected 3 to match the operations\n  4 5\n     ^"
+        );
+        let error = Worksheet::from_str("1 2\n3 x4\n+ *").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: Expected a non-negative integer, got \"x4\": invalid digit found in string\n  3 x4\n    ^"
+        );
+        let error = Worksheet::from_str("1 2\n+ -").err().unwrap();
+        assert!(
+            error
+                .to_string()
+                .starts_with("line 2, column 3: Expected '+' or '*', got \"-\"")
+        );
+    }
+
+    #[test]
+    fn test_parse_part2_errors_point_at_the_row() {
+        let error = parse_part2("12 3\n4 5\n*  +").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 4: Row 1 is 3 characters long, expected 4 like row 0\n  4 5\n     ^"
+        );
+        let error = parse_part2("12 3\n45 6\n*  -").err().unwrap();
+        assert!(error.to_string().starts_with("line 3, column 4: "));
+    }
+
+    #[test]
+    fn test_parse_auto() {
+        let sample = common::include_sample!();
+        let ParsedWorksheet::Columns { column_numbers, .. } =
+            Worksheet::parse_auto(sample).unwrap()
+        else {
+            panic!("The sample is in columns");
+        };
+        assert_eq!(column_numbers[0], vec![1, 24, 356]);
+
+        // The same numbers, no longer lined up.
+        let tokens = Worksheet::parse_auto(
+            "123 328 51 64
+45 64 387 23
+6 98 215 314
+* + * +",
+        );
+        let Ok(ParsedWorksheet::Tokens(worksheet)) = tokens else {
+            panic!("Not in columns");
+        };
+        assert_eq!(worksheet.operations().count(), 4);
+        assert!(
+            Worksheet::parse_auto(
+                "1 2
++ -"
+            )
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_column_indices() {
-        let column_widths = vec![3, 3, 3, 3];
+        let column_widths = [3, 3, 3, 3];
         let indices = column_indices(column_widths.iter().copied());
         assert_eq!(
             indices.collect::<Vec<_>>(),
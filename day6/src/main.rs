@@ -1,36 +1,59 @@
 use anyhow::Result;
-use day6::Operation;
-use std::str::FromStr;
+use common::error::ErrorContext;
+use day6::{Operation, ParsedWorksheet, Worksheet};
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let input = common::read_file(&arg1)?;
-    let worksheet = day6::Worksheet::from_str(&input)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &[])?;
+    let input = common::read_file(arg1)?;
+    let parsed = Worksheet::parse_auto(&input)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(6)?;
+    let worksheet = parsed.worksheet();
 
-    let results = worksheet
-        .columns()
+    let columns = worksheet.to_grid().transpose();
+    let results = columns
+        .rows()
         .zip(worksheet.operations())
-        .map(|(column, operation)| apply_operation(column, operation));
+        .map(|(column, operation)| apply_operation(column.iter().copied(), operation));
 
-    println!("Part 1: {}", results.sum::<u64>());
+    let part1 = common::try_fold_checked(results, 0, u64::checked_add)
+        .with_phase("part 1")
+        .with_day(6)?;
+    println!("Part 1: {}", part1);
 
-    let (column_numbers, operations) = day6::parse_part2(&input)?;
+    let ParsedWorksheet::Columns {
+        column_numbers,
+        operations,
+        ..
+    } = parsed
+    else {
+        // Not a "Part 2:" line, so the runner doesn't record it as an answer.
+        println!("Skipping part 2: the numbers aren't lined up in fixed-width columns");
+        return Ok(());
+    };
     let results = column_numbers
         .into_iter()
-        .zip(operations.into_iter())
+        .zip(operations)
         .map(|(column, operation)| apply_operation(column, operation));
-    println!("Part 2: {:?}", results.sum::<u64>());
+    let part2 = common::try_fold_checked(results, 0, u64::checked_add)
+        .with_phase("part 2")
+        .with_day(6)?;
+    println!("Part 2: {}", part2);
 
     Ok(())
 }
 
-fn apply_operation(row: impl IntoIterator<Item = u64>, operation: Operation) -> u64 {
-    row.into_iter()
-        .reduce(|acc, value| match operation {
-            Operation::Add => acc + value,
-            Operation::Multiply => acc * value,
-        })
-        .unwrap_or(0)
+/// Applies the column's operation to its numbers, failing if the result overflows.
+fn apply_operation(row: impl IntoIterator<Item = u64>, operation: Operation) -> Result<u64> {
+    let mut row = row.into_iter();
+    let Some(first) = row.next() else {
+        return Ok(0);
+    };
+    row.try_fold(first, |acc, value| match operation {
+        Operation::Add => acc.checked_add(value),
+        Operation::Multiply => acc.checked_mul(value),
+    })
+    .ok_or_else(|| anyhow::anyhow!("Overflow applying {:?} to column", operation))
 }
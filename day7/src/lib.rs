@@ -1,11 +1,19 @@
 use std::str::FromStr;
 
+use common::parse::ExpectFormat;
+
+pub mod sim;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Cell {
     Empty,
     Start,
     Splitter,
     Beam,
+    /// Reflects beams like `/`.
+    MirrorSlash,
+    /// Reflects beams like `\`.
+    MirrorBackslash,
 }
 impl std::fmt::Display for Cell {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,6 +22,8 @@ impl std::fmt::Display for Cell {
             Cell::Start => write!(f, "S"),
             Cell::Splitter => write!(f, "^"),
             Cell::Beam => write!(f, "|"),
+            Cell::MirrorSlash => write!(f, "/"),
+            Cell::MirrorBackslash => write!(f, "\\"),
         }
     }
 }
@@ -26,7 +36,10 @@ impl FromStr for Cell {
             "S" => Ok(Cell::Start),
             "^" => Ok(Cell::Splitter),
             "|" => Ok(Cell::Beam),
-            _ => Err(anyhow::anyhow!("Invalid cell: {}", s)),
+            "/" => Ok(Cell::MirrorSlash),
+            "\\" => Ok(Cell::MirrorBackslash),
+            _ => Err(anyhow::anyhow!("Invalid cell"))
+                .expect_format("one of '.', 'S', '^', '|', '/' or '\\'", s),
         }
     }
 }
@@ -1,27 +1,64 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader},
+};
 
 use anyhow::Result;
+use common::answer::Answer;
+use common::error::ErrorContext;
 use common::grid::{CellInGrid, Grid, XY};
-use day7::Cell;
+use day7::{
+    Cell,
+    sim::{BeamSim, StartObjective},
+};
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let grid = common::grid::parse_data_into_grid::<Cell>(&data)?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let arg1 = common::args::input_path(&args, &["--split"])?;
+    // --stream runs part 1 a row at a time without building the grid, for tall inputs.
+    if args.iter().any(|arg| arg == "--stream") {
+        let file = std::fs::File::open(arg1)?;
+        let report = BeamSim::run_streaming(BufReader::new(file).lines())
+            .with_file(arg1)
+            .with_phase("part 1")
+            .with_day(7)?;
+        println!("Part 1 streamed: {}", report.splits);
+        return Ok(());
+    }
+    let data = common::read_file(arg1)?;
+    let grid = common::grid::parse_data_into_grid::<Cell>(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(7)?;
+    // --split <left-right|three-way|absorb-every-second> changes what splitters do in
+    // the simulation.
+    let mut sim = BeamSim::new(&grid);
+    if let Some(rule) = args.iter().skip_while(|arg| *arg != "--split").nth(1) {
+        sim = sim.with_rule(day7::sim::split_rule(rule)?);
+    }
 
-    println!("Part 1: {}", part1(&mut grid.clone())?);
-    println!("Part 1 again: {}", part1_again(&grid)?);
-    println!("Part 2: {}", part2(&grid)?);
+    let part1 = part1(&mut grid.clone()).with_phase("part 1").with_day(7)?;
+    println!("Part 1: {}", part1);
+    let part1_again = part1_again(&grid).with_phase("part 1").with_day(7)?;
+    println!("Part 1 again: {}", part1_again);
+    let report = sim.run().with_phase("part 1").with_day(7)?;
+    println!("Part 1 sim: {}", report.splits);
+    println!("Part 2: {}", part2(&grid).with_phase("part 2").with_day(7)?);
+    // --best-start finds the start column that gives the most timelines, which
+    // simulates every column.
+    if args.iter().any(|arg| arg == "--best-start")
+        && let Some((x, timelines)) =
+            BeamSim::new(&grid).best_start_column(StartObjective::Timelines)?
+    {
+        println!("Best start column: {} ({} timelines)", x, timelines);
+    }
     Ok(())
 }
 
 fn part1_again(grid: &Grid<Cell>) -> Result<u64> {
     // Find the starting position.
     let start_pos = grid
-        .cells()
-        .find(|c| c.value() == &Cell::Start)
+        .find(|c| c == &Cell::Start)
         .ok_or_else(|| anyhow::anyhow!("No start position found"))?;
 
     // Store the active beams in a set.
@@ -37,7 +74,7 @@ fn part1_again(grid: &Grid<Cell>) -> Result<u64> {
             // beam that just goes down (and a second None value).
             .flat_map(|beam| match beam.value() {
                 Cell::Splitter => {
-                    split_count = split_count + 1;
+                    split_count += 1;
                     [
                         beam.left().and_then(|b| b.down()),
                         beam.right().and_then(|b| b.down()),
@@ -47,16 +84,15 @@ fn part1_again(grid: &Grid<Cell>) -> Result<u64> {
                 _ => [beam.down(), None],
             })
             // Filter out any None values (off the edge of the grid).
-            .filter_map(|b| b)
+            .flatten()
             .collect::<HashSet<_>>();
     }
     Ok(split_count)
 }
 
-fn part1(grid: &mut Grid<Cell>) -> Result<impl std::fmt::Display> {
+fn part1(grid: &mut Grid<Cell>) -> Result<Answer> {
     let start_pos = grid
-        .cells()
-        .find(|c| c.value() == &Cell::Start)
+        .find(|c| c == &Cell::Start)
         .ok_or_else(|| anyhow::anyhow!("No start position found"))?
         .xy();
 
@@ -90,7 +126,7 @@ fn part1(grid: &mut Grid<Cell>) -> Result<impl std::fmt::Display> {
         }
     }
 
-    Ok(split_count)
+    Ok(split_count.into())
 }
 
 fn split_beam<'a>(beam: &CellInGrid<'a, Cell>) -> [Option<CellInGrid<'a, Cell>>; 2] {
@@ -100,14 +136,13 @@ fn split_beam<'a>(beam: &CellInGrid<'a, Cell>) -> [Option<CellInGrid<'a, Cell>>;
     }
 }
 
-fn part2(grid: &Grid<Cell>) -> Result<impl std::fmt::Display> {
+fn part2(grid: &Grid<Cell>) -> Result<Answer> {
     let start_pos = grid
-        .cells()
-        .find(|c| c.value() == &Cell::Start)
+        .find(|c| c == &Cell::Start)
         .ok_or_else(|| anyhow::anyhow!("No start position found"))?;
 
     // Add 1 to include our own timeline.
-    Ok(part2_recursive(Some(start_pos), &mut HashMap::new()) + 1)
+    Ok((part2_recursive(Some(start_pos), &mut HashMap::new()) + 1).into())
 }
 
 // The saving grace here is to keep a cache of what the count is from a given position.
@@ -0,0 +1,533 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use common::par::prelude::*;
+use common::{
+    convert::to_u64,
+    error::{AocError, ErrorContext},
+    grid::{Grid, XY},
+    parse::{ExpectFormat, expect_format},
+    recurse::{Cycle, Step, trampoline},
+};
+
+use crate::Cell;
+
+/// The direction a beam is travelling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+impl Direction {
+    /// Gets the position one step from `xy` in this direction.
+    pub fn step(&self, xy: &XY) -> Option<XY> {
+        match self {
+            Direction::Up => xy.up(),
+            Direction::Right => xy.right(),
+            Direction::Down => xy.down(),
+            Direction::Left => xy.left(),
+        }
+    }
+    /// Gets the two directions at right angles to this one.
+    pub fn perpendicular(&self) -> [Direction; 2] {
+        match self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+        }
+    }
+    /// Gets the direction after bouncing off a `/` mirror.
+    fn reflect_slash(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Down,
+        }
+    }
+    /// Gets the direction after bouncing off a `\` mirror.
+    fn reflect_backslash(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Down,
+        }
+    }
+}
+
+/// A beam at a position, travelling in a direction.
+pub type BeamState = (XY, Direction);
+
+/// The beam states that have already been simulated.  Tracking the direction as well
+/// as the position means a beam crossing a cell it has already crossed the other way
+/// is still followed, and a beam caught in a loop of mirrors terminates.
+pub type Visited = HashSet<BeamState>;
+
+/// What happened while simulating a beam.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BeamReport {
+    /// The number of times a beam was split.
+    pub splits: usize,
+    /// The number of distinct cells a beam passed through.
+    pub energized: usize,
+}
+
+/// Where a beam leaves a splitter, relative to the way it came in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// One step to a side, then on in the original direction, like the puzzle's split.
+    Side(Direction),
+    /// Straight through the splitter.
+    Straight,
+}
+
+/// What happens to a beam that reaches a splitter in [`BeamSim::run_from`].
+pub trait SplitRule: Send + Sync {
+    /// Gets where the beam goes from a splitter it reached heading in `direction`,
+    /// after `hits` beams reached the same splitter before it.  Giving no exits absorbs
+    /// the beam, and more than one counts as a split.
+    fn exits(&self, direction: Direction, hits: usize) -> Vec<Exit>;
+}
+
+/// The puzzle's rule: the beam goes out to either side and carries on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LeftRight;
+impl SplitRule for LeftRight {
+    fn exits(&self, direction: Direction, _hits: usize) -> Vec<Exit> {
+        direction.perpendicular().map(Exit::Side).to_vec()
+    }
+}
+
+/// The beam goes out to either side and also straight through.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThreeWay;
+impl SplitRule for ThreeWay {
+    fn exits(&self, direction: Direction, hits: usize) -> Vec<Exit> {
+        let mut exits = LeftRight.exits(direction, hits);
+        exits.push(Exit::Straight);
+        exits
+    }
+}
+
+/// Each splitter splits the first beam to reach it, absorbs the second, splits the
+/// third, and so on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AbsorbEverySecond;
+impl SplitRule for AbsorbEverySecond {
+    fn exits(&self, direction: Direction, hits: usize) -> Vec<Exit> {
+        if hits % 2 == 1 {
+            Vec::new()
+        } else {
+            LeftRight.exits(direction, hits)
+        }
+    }
+}
+
+/// Gets a split rule by the name `--split` takes.
+pub fn split_rule(name: &str) -> Result<Box<dyn SplitRule>> {
+    match name {
+        "left-right" => Ok(Box::new(LeftRight)),
+        "three-way" => Ok(Box::new(ThreeWay)),
+        "absorb-every-second" => Ok(Box::new(AbsorbEverySecond)),
+        _ => Err(anyhow::anyhow!("Unknown split rule"))
+            .expect_format("left-right, three-way or absorb-every-second", name),
+    }
+}
+
+/// Follows beams through a grid of splitters and mirrors.
+pub struct BeamSim<'a> {
+    grid: &'a Grid<Cell>,
+    rule: Box<dyn SplitRule>,
+}
+impl<'a> BeamSim<'a> {
+    /// A simulation splitting beams by the puzzle's [`LeftRight`] rule.
+    pub fn new(grid: &'a Grid<Cell>) -> Self {
+        Self {
+            grid,
+            rule: Box::new(LeftRight),
+        }
+    }
+
+    /// Changes what splitters do in [`BeamSim::run_from`].  Timelines and streaming
+    /// always use the puzzle's rule.
+    pub fn with_rule(mut self, rule: Box<dyn SplitRule>) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Gets the position of the start cell.
+    pub fn start(&self) -> Result<XY> {
+        self.grid
+            .find(|c| c == &Cell::Start)
+            .map(|c| c.xy())
+            .ok_or_else(|| anyhow::anyhow!("No start position found"))
+    }
+
+    /// Runs the simulation from the start cell with the beam heading down.
+    pub fn run(&self) -> Result<BeamReport> {
+        Ok(self.run_from(self.start()?, Direction::Down))
+    }
+
+    /// Runs the simulation with a single beam entering at `xy` heading in `direction`.
+    pub fn run_from(&self, xy: XY, direction: Direction) -> BeamReport {
+        let mut visited = Visited::new();
+        let mut hits = HashMap::<XY, usize>::new();
+        let mut splits = 0;
+        let mut pending = vec![(xy, direction)];
+        while let Some(state) = pending.pop() {
+            let Some(cell) = self.grid.get(state.0.clone()) else {
+                // Off the edge of the grid.
+                continue;
+            };
+            if !visited.insert(state.clone()) {
+                continue;
+            }
+            let (xy, direction) = state;
+            match cell.value() {
+                Cell::Splitter => {
+                    let hit = hits.entry(xy.clone()).or_default();
+                    let exits = self.rule.exits(direction, *hit);
+                    *hit += 1;
+                    if exits.len() > 1 {
+                        splits += 1;
+                    }
+                    for exit in exits {
+                        match exit {
+                            Exit::Side(side) => pending
+                                .extend(side.step(&xy).and_then(|beside| {
+                                    Some((direction.step(&beside)?, direction))
+                                })),
+                            Exit::Straight => next(&mut pending, &xy, direction),
+                        }
+                    }
+                }
+                Cell::MirrorSlash => next(&mut pending, &xy, direction.reflect_slash()),
+                Cell::MirrorBackslash => next(&mut pending, &xy, direction.reflect_backslash()),
+                Cell::Empty | Cell::Start | Cell::Beam => next(&mut pending, &xy, direction),
+            }
+        }
+        let energized = visited
+            .iter()
+            .map(|(xy, _)| xy)
+            .collect::<HashSet<_>>()
+            .len();
+        BeamReport { splits, energized }
+    }
+}
+
+impl BeamSim<'_> {
+    /// Runs the simulation from the start cell with the beam heading down, reading the
+    /// grid a row at a time so only the columns with a beam in them are kept.  Gives
+    /// the same report as [`BeamSim::run`], for inputs too tall to hold in memory.
+    ///
+    /// Beams only ever travel down a row at a time, so this fails on mirrors, which
+    /// need the whole grid.
+    pub fn run_streaming(
+        lines: impl IntoIterator<Item = std::io::Result<String>>,
+    ) -> Result<BeamReport> {
+        let mut report = BeamReport::default();
+        // The columns with a beam entering the current row, heading down.
+        let mut beams = BTreeSet::new();
+        let mut started = false;
+        for (number, line) in (1..).zip(lines) {
+            let line = line.context("Failed to read grid")?;
+            let row = line.chars().collect::<Vec<_>>();
+            if !started && let Some(x) = row.iter().position(|c| *c == 'S') {
+                started = true;
+                beams.insert(x);
+            }
+            report.energized += beams.iter().filter(|x| **x < row.len()).count();
+            let mut below = BTreeSet::new();
+            for x in beams {
+                let Some(c) = row.get(x) else {
+                    // Off the edge of a shorter row.
+                    continue;
+                };
+                let cell = c
+                    .to_string()
+                    .parse::<Cell>()
+                    .with_column(x + 1)
+                    .with_line(number)?;
+                match cell {
+                    Cell::Splitter => {
+                        report.splits += 1;
+                        below.extend(x.checked_sub(1));
+                        below.insert(x + 1);
+                    }
+                    Cell::MirrorSlash | Cell::MirrorBackslash => {
+                        let error = expect_format(
+                            anyhow::anyhow!("Mirror in a streamed grid"),
+                            "a grid of '.', 'S', '^' and '|'",
+                            &line,
+                        );
+                        return Err(AocError::new(error).at_line(number).at_column(x + 1).into());
+                    }
+                    Cell::Empty | Cell::Start | Cell::Beam => {
+                        below.insert(x);
+                    }
+                }
+            }
+            beams = below;
+        }
+        if !started {
+            anyhow::bail!("No start position found");
+        }
+        Ok(report)
+    }
+}
+
+/// What to maximize when choosing where a beam enters the grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StartObjective {
+    /// The number of times the beam is split.
+    #[default]
+    Splits,
+    /// The number of distinct cells the beam passes through.
+    Energized,
+    /// The number of distinct paths the beam can take out of the grid.
+    Timelines,
+}
+
+impl BeamSim<'_> {
+    /// Counts the distinct paths a beam entering at `xy` can take until it leaves the
+    /// grid, where each split doubles the paths.  Fails if a path loops forever.
+    pub fn timelines_from(&self, xy: XY, direction: Direction) -> Result<u64> {
+        trampoline(
+            Some((xy, direction)),
+            |state: &Option<BeamState>| {
+                // Off the edge of the grid is one finished timeline.
+                let Some((xy, direction)) = state else {
+                    return Ok(Step::Leaf(1));
+                };
+                let Some(cell) = self.grid.get(xy.clone()) else {
+                    return Ok(Step::Leaf(1));
+                };
+                let step = |direction: Direction| Some((direction.step(xy)?, direction));
+                Ok(Step::Children(match cell.value() {
+                    Cell::Splitter => direction
+                        .perpendicular()
+                        .into_iter()
+                        .map(|side| {
+                            side.step(xy)
+                                .and_then(|beside| Some((direction.step(&beside)?, *direction)))
+                        })
+                        .collect(),
+                    Cell::MirrorSlash => vec![step(direction.reflect_slash())],
+                    Cell::MirrorBackslash => vec![step(direction.reflect_backslash())],
+                    Cell::Empty | Cell::Start | Cell::Beam => vec![step(*direction)],
+                }))
+            },
+            |_, children| {
+                children
+                    .iter()
+                    .try_fold(0_u64, |total, (_, count)| total.checked_add(*count))
+                    .ok_or_else(|| anyhow::anyhow!("Timeline count overflowed"))
+            },
+        )
+        .map_err(|e| {
+            if e.is::<Cycle>() {
+                e.context("Beam loops forever")
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Tries a beam entering every column of the top row heading down, in parallel,
+    /// and returns the column that scores best along with its score.  Ties go to the
+    /// leftmost column.
+    pub fn best_start_column(&self, objective: StartObjective) -> Result<Option<(usize, u64)>> {
+        let scores = (0..self.grid.width())
+            .into_par_iter()
+            .map(|x| {
+                let xy = XY::new(x, 0);
+                let score = match objective {
+                    StartObjective::Splits => to_u64(self.run_from(xy, Direction::Down).splits)?,
+                    StartObjective::Energized => {
+                        to_u64(self.run_from(xy, Direction::Down).energized)?
+                    }
+                    StartObjective::Timelines => self.timelines_from(xy, Direction::Down)?,
+                };
+                Ok((x, score))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(scores
+            .into_iter()
+            .max_by_key(|(x, score)| (*score, std::cmp::Reverse(*x))))
+    }
+}
+
+/// Queues the beam state one step from `xy` in `direction`.
+fn next(pending: &mut Vec<BeamState>, xy: &XY, direction: Direction) {
+    if let Some(xy) = direction.step(xy) {
+        pending.push((xy, direction));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaming_matches_grid() {
+        let data = common::include_sample!();
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let lines = data.lines().map(|line| Ok(line.to_string()));
+        assert_eq!(
+            BeamSim::run_streaming(lines).unwrap(),
+            BeamSim::new(&grid).run().unwrap()
+        );
+
+        let mirrored = ["..S..", "..^..", ".\\...", "....."];
+        let error = BeamSim::run_streaming(mirrored.map(|line| Ok(line.to_string())))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with("line 3, column 2: Expected a grid"),
+            "{}",
+            error
+        );
+        assert!(BeamSim::run_streaming([Ok("...".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_sample_splits() {
+        let data = common::include_sample!();
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        assert_eq!(BeamSim::new(&grid).run().unwrap().splits, 21);
+    }
+
+    #[test]
+    fn test_sample_timelines() {
+        let data = common::include_sample!();
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let sim = BeamSim::new(&grid);
+        assert_eq!(
+            sim.timelines_from(sim.start().unwrap(), Direction::Down)
+                .unwrap(),
+            40
+        );
+    }
+
+    #[test]
+    fn test_best_start_column() {
+        let data = "\
+.....
+.^...
+..^..
+.....";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let sim = BeamSim::new(&grid);
+        assert_eq!(
+            sim.best_start_column(StartObjective::Splits).unwrap(),
+            Some((1, 2))
+        );
+        assert_eq!(
+            sim.best_start_column(StartObjective::Timelines).unwrap(),
+            Some((1, 3))
+        );
+        assert_eq!(
+            sim.best_start_column(StartObjective::Energized).unwrap(),
+            Some((1, 7))
+        );
+    }
+
+    #[test]
+    fn test_timelines_loop_fails() {
+        let data = "\
+......
+./..\\.
+......
+.\\../.
+......";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        assert!(
+            BeamSim::new(&grid)
+                .timelines_from(XY::new(2, 1), Direction::Right)
+                .unwrap_err()
+                .to_string()
+                == "Beam loops forever"
+        );
+    }
+
+    #[test]
+    fn test_split_rules() {
+        let data = "\
+..S..
+.....
+..^..
+.....
+.^.^.
+.....";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let run = |rule| BeamSim::new(&grid).with_rule(rule).run().unwrap();
+        assert_eq!(
+            run(Box::new(LeftRight)),
+            BeamReport {
+                splits: 3,
+                energized: 10
+            }
+        );
+        // The beam straight through the first splitter goes between the other two.
+        assert_eq!(
+            run(Box::new(ThreeWay)),
+            BeamReport {
+                splits: 3,
+                energized: 14
+            }
+        );
+        assert!(split_rule("sideways").is_err());
+    }
+
+    #[test]
+    fn test_absorb_every_second() {
+        // The mirrors bring the right-hand beam back up to the splitter, where it is
+        // absorbed the second time instead of splitting again.
+        let data = "\
+..S...
+......
+..^...
+......
+..\\/..
+......";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let run = |rule| BeamSim::new(&grid).with_rule(rule).run().unwrap();
+        assert_eq!(run(Box::new(LeftRight)).splits, 2);
+        assert_eq!(run(Box::new(AbsorbEverySecond)).splits, 1);
+        assert!(run(Box::new(AbsorbEverySecond)).energized < run(Box::new(LeftRight)).energized);
+    }
+
+    #[test]
+    fn test_mirror_loop_terminates() {
+        // The beam goes round the four mirrors forever unless visited states stop it.
+        let data = "\
+......
+./..\\.
+......
+.\\../.
+......";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let report = BeamSim::new(&grid).run_from(XY::new(2, 1), Direction::Right);
+        assert_eq!(report.splits, 0);
+        assert_eq!(report.energized, 10);
+    }
+
+    #[test]
+    fn test_crossing_beams_are_both_followed() {
+        // The beam crosses (2, 2) heading right, then again heading down on its way
+        // to the splitter.  A position-only visited set would stop it at the crossing.
+        let data = "\
+../.\\
+.....
+S.../
+.....
+..^..
+.....";
+        let grid = common::grid::parse_data_into_grid::<Cell>(data).unwrap();
+        let report = BeamSim::new(&grid).run_from(XY::new(0, 2), Direction::Right);
+        assert_eq!(report.splits, 1);
+        assert_eq!(report.energized, 14);
+    }
+}
@@ -1,39 +1,43 @@
 use anyhow::{Context, Result};
-use itertools::Itertools;
+use common::bounds::{BoundingBox, bounding_box_3d};
+use common::grid::coordinate_fields;
+use common::par::prelude::*;
+use common::parse::{ExpectWithLine, int};
+use common::random::Random;
 
 use std::{
     collections::{HashMap, HashSet},
     str::FromStr,
 };
 
-/// Represents a 3D coordinate point with x, y, and z components.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Represents a 3D coordinate point with x, y, and z components.  Ordered by x, then
+/// y, then z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct XYZ {
-    x: u64,
-    y: u64,
-    z: u64,
+    x: i64,
+    y: i64,
+    z: i64,
 }
 
-/// Parses an XYZ coordinate from a string in the format "x,y,z".
+/// Parses an XYZ coordinate from a string in the format "x,y,z".  Coordinates may be
+/// negative, and fields may also be separated by ", ", semicolons or whitespace.
 impl FromStr for XYZ {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s_iter = s.split(',');
+        let fields = coordinate_fields(s);
+        let mut s_iter = fields.into_iter();
         let x = s_iter
             .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing x: {}", s))?
-            .parse()
-            .context("x")?;
+            .ok_or_else(|| anyhow::anyhow!("Missing x: {}", s))?;
+        let x = int(x).context("x")?;
         let y = s_iter
             .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing y: {}", s))?
-            .parse()
-            .context("y")?;
+            .ok_or_else(|| anyhow::anyhow!("Missing y: {}", s))?;
+        let y = int(y).context("y")?;
         let z = s_iter
             .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing z: {}", s))?
-            .parse()
-            .context("z")?;
+            .ok_or_else(|| anyhow::anyhow!("Missing z: {}", s))?;
+        let z = int(z).context("z")?;
         if s_iter.next().is_some() {
             anyhow::bail!("Extra values: {}", s);
         }
@@ -42,6 +46,10 @@ impl FromStr for XYZ {
 }
 
 impl XYZ {
+    /// Gets the coordinates as an [x, y, z] array.
+    pub fn coordinates(&self) -> [i64; 3] {
+        [self.x, self.y, self.z]
+    }
     /// Calculates the squared Euclidean distance between two points.
     pub fn sqr_distance(a: &XYZ, b: &XYZ) -> u64 {
         let dx = a.x.abs_diff(b.x).pow(2);
@@ -51,18 +59,62 @@ impl XYZ {
     }
 }
 
+/// How far apart two junctions are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// The straight-line distance, squared so it stays an integer.  The puzzle's metric.
+    #[default]
+    SquaredEuclidean,
+    /// The sum of the distances along each axis.
+    Manhattan,
+    /// The largest distance along any one axis.
+    Chebyshev,
+}
+impl Metric {
+    /// Gets the distance between two junctions.
+    pub fn distance(&self, a: &XYZ, b: &XYZ) -> u64 {
+        let deltas = [a.x.abs_diff(b.x), a.y.abs_diff(b.y), a.z.abs_diff(b.z)];
+        match self {
+            Metric::SquaredEuclidean => XYZ::sqr_distance(a, b),
+            Metric::Manhattan => deltas.iter().sum(),
+            Metric::Chebyshev => deltas.into_iter().max().unwrap_or(0),
+        }
+    }
+}
+impl FromStr for Metric {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "euclidean" => Ok(Metric::SquaredEuclidean),
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            _ => anyhow::bail!(
+                "Unknown metric {:?}, expected euclidean, manhattan or chebyshev",
+                s
+            ),
+        }
+    }
+}
+
 /// A vector that can only be appended to, not modified.
 ///
 /// This append-only behavior is critical for compile-time correctness of algorithms
 /// that use position indicies of the vector.  Position indicies can not
-/// be invalidated by modifications to the vector.
-#[derive(Default)]
+/// be invalidated by modifications to the vector.  Positions are [`CircuitId`]s.
 struct AppendOnlyVec<T> {
     inner: Vec<T>,
 }
+impl<T> Default for AppendOnlyVec<T> {
+    fn default() -> Self {
+        Self { inner: Vec::new() }
+    }
+}
 impl<T> AppendOnlyVec<T> {
-    pub fn push(&mut self, value: T) {
+    /// Appends a value, returning its index.
+    pub fn push(&mut self, value: T) -> CircuitId {
+        let index = CircuitId::new(self.len());
         self.inner.push(value);
+        index
     }
     pub fn len(&self) -> usize {
         self.inner.len()
@@ -70,20 +122,133 @@ impl<T> AppendOnlyVec<T> {
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.inner.iter()
     }
-    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
-        self.inner.get_mut(index)
+    pub fn get(&self, index: CircuitId) -> Option<&T> {
+        self.inner.get(index.index())
+    }
+    pub fn get_mut(&mut self, index: CircuitId) -> Option<&mut T> {
+        self.inner.get_mut(index.index())
     }
 }
 
 /// Parses input data into a vector of XYZ coordinates, one per line.
 pub fn parse_data(data: &str) -> Result<Vec<XYZ>> {
-    data.lines().map(XYZ::from_str).collect::<Result<Vec<_>>>()
+    data.lines()
+        .expect_with_line("'x,y,z' with integer coordinates", XYZ::from_str)
+        .map_err(Into::into)
+}
+
+/// Gets the smallest box containing all the junctions, or None if there are none.
+pub fn extent(xyzs: &[XYZ]) -> Option<BoundingBox<[i64; 3]>> {
+    bounding_box_3d(xyzs.iter().map(XYZ::coordinates))
+}
+
+/// One connection that joined two junctions which weren't already in the same circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeEvent {
+    /// The distance between the two junctions, by the metric used.
+    pub distance: u64,
+    pub a: XYZ,
+    pub b: XYZ,
+    /// The number of junctions in the circuit after the merge.
+    pub merged_size: usize,
+}
+
+/// Every merge made while connecting junctions closest first, in order.  Each merge
+/// joins two circuits, counting an unconnected junction as a circuit of one.
+#[derive(Debug, Clone)]
+pub struct MergeHistory {
+    junctions: Vec<XYZ>,
+    events: Vec<MergeEvent>,
+}
+impl MergeHistory {
+    pub fn events(&self) -> &[MergeEvent] {
+        &self.events
+    }
+    /// Gets the number of circuits left after the first `merges` merges.
+    pub fn circuits_after(&self, merges: usize) -> usize {
+        self.junctions.len() - merges.min(self.events.len())
+    }
+    /// Gets the number of merges made by connecting every pair within `distance`.
+    fn merges_within(&self, distance: u64) -> usize {
+        // Events are in distance order, so binary search for the first one past it.
+        self.events
+            .partition_point(|event| event.distance <= distance)
+    }
+    /// Gets the number of circuits when every pair within `distance` is
+    /// connected.
+    pub fn circuit_count_at_distance(&self, distance: u64) -> usize {
+        self.circuits_after(self.merges_within(distance))
+    }
+    /// Gets the circuits formed by connecting every pair within `distance`,
+    /// largest first.  Junctions with no connections are circuits of their own.
+    pub fn circuits_at_distance(&self, distance: u64) -> Vec<Vec<XYZ>> {
+        let index = self
+            .junctions
+            .iter()
+            .enumerate()
+            .map(|(i, junction)| (junction, i))
+            .collect::<HashMap<_, _>>();
+        let mut parents = (0..self.junctions.len()).collect::<Vec<_>>();
+        for event in &self.events[..self.merges_within(distance)] {
+            let a = find_root(&mut parents, index[&event.a]);
+            let b = find_root(&mut parents, index[&event.b]);
+            parents[b] = a;
+        }
+
+        let mut circuits = HashMap::<usize, Vec<XYZ>>::new();
+        for (i, junction) in self.junctions.iter().enumerate() {
+            circuits
+                .entry(find_root(&mut parents, i))
+                .or_default()
+                .push(*junction);
+        }
+        let mut circuits = circuits.into_values().collect::<Vec<_>>();
+        circuits.sort_by_key(|circuit| std::cmp::Reverse(circuit.len()));
+        circuits
+    }
+    /// Gets the smallest distance at which connecting every closer pair leaves
+    /// at most `circuits` circuits, or None if that never happens.
+    pub fn distance_for_circuits(&self, circuits: usize) -> Option<u64> {
+        if self.junctions.len() <= circuits {
+            return Some(0);
+        }
+        let merges_needed = self.junctions.len() - circuits;
+        self.events
+            .get(merges_needed - 1)
+            .map(|event| event.distance)
+    }
+}
+
+/// Connects every pair of junctions closest first by `metric`, recording each merge.
+pub fn merge_history(xyzs: &[XYZ], metric: Metric) -> MergeHistory {
+    let mut circuits_manager = CircuitManager {
+        metric,
+        ..Default::default()
+    };
+    for (junction0, junction1) in pairs_by_distance(xyzs, metric) {
+        circuits_manager.combine_junctions(junction0, junction1);
+    }
+    MergeHistory {
+        junctions: xyzs.to_vec(),
+        events: circuits_manager.history,
+    }
+}
+
+/// Finds the representative of a junction's set, shortening the path as it goes.
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
 }
 
 #[derive(Default)]
 struct CircuitManager<'a> {
+    metric: Metric,
     circuits: AppendOnlyVec<Circuit<'a>>,
-    junction_to_circuit: HashMap<Junction<'a>, usize>,
+    junction_to_circuit: HashMap<Junction<'a>, CircuitId>,
+    history: Vec<MergeEvent>,
 }
 impl<'a> CircuitManager<'a> {
     pub fn circuits(&self) -> impl Iterator<Item = &Circuit<'a>> {
@@ -96,44 +261,165 @@ impl<'a> CircuitManager<'a> {
 
 type Junction<'a> = &'a XYZ;
 
+common::define_index!(
+    /// The position of a circuit in `CircuitManager::circuits`.
+    CircuitId
+);
+
 /// A circuit is a set of connected junctions (XYZ points).
 type Circuit<'a> = HashSet<&'a XYZ>;
 
+/// Above this many pairs, [`closest_pairs`] sorts them through temporary files instead
+/// of in memory.  A pair takes 24 bytes to sort, so this is about 1.2 GB.
+const EXTERNAL_SORT_PAIRS: usize = 50_000_000;
+
+/// How many pairs each of the external sort's temporary files holds.
+const EXTERNAL_SORT_CHUNK: usize = 10_000_000;
+
+/// Pairs of junctions closest first, which may fail to read once they've gone to disk.
+type Pairs<'a> = Box<dyn Iterator<Item = Result<(&'a XYZ, &'a XYZ)>> + 'a>;
+
 /// Initializes the data structures needed for circuit processing: an empty circuits vector,
 /// a mapping from junctions to circuit indices, and all pairs of possible junctions sorted by distance.
-fn initialize_circuits<'a>(xyzs: &'a [XYZ]) -> (CircuitManager<'a>, Vec<(&'a XYZ, &'a XYZ)>) {
-    // Get all pairs of junctions and sort them by distance.
-    let mut all_pairs = xyzs.iter().tuple_combinations().collect::<Vec<_>>();
-    all_pairs.sort_by_key(|pair: &(&XYZ, &XYZ)| XYZ::sqr_distance(pair.0, pair.1));
+fn initialize_circuits<'a>(
+    xyzs: &'a [XYZ],
+    metric: Metric,
+) -> Result<(CircuitManager<'a>, Pairs<'a>)> {
+    let manager = CircuitManager {
+        metric,
+        ..Default::default()
+    };
+    Ok((manager, closest_pairs(xyzs, metric)?))
+}
+
+/// Gets every pair of junctions in the order of [`pairs_by_distance`], sorting them in
+/// memory unless there are too many to fit.
+pub fn closest_pairs(xyzs: &[XYZ], metric: Metric) -> Result<Pairs<'_>> {
+    let pairs = xyzs.len() * xyzs.len().saturating_sub(1) / 2;
+    Ok(if pairs > EXTERNAL_SORT_PAIRS {
+        Box::new(pairs_by_distance_external(
+            xyzs,
+            metric,
+            EXTERNAL_SORT_CHUNK,
+        )?)
+    } else {
+        Box::new(pairs_by_distance(xyzs, metric).into_iter().map(Ok))
+    })
+}
+
+/// Gets the `count` closest pairs of junctions, in the order of [`pairs_by_distance`],
+/// holding only `count` pairs at once however many junctions there are.
+pub fn closest_k_pairs(xyzs: &[XYZ], metric: Metric, count: usize) -> Vec<(&XYZ, &XYZ)> {
+    let pairs = xyzs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| xyzs[i + 1..].iter().map(move |b| sort_key(metric, a, b)));
+    // The key is a total order, so ties in input order never matter.
+    common::topk::smallest_k_by_key(pairs, count, |pair| *pair)
+        .into_iter()
+        .map(|(_, a, b)| (a, b))
+        .collect()
+}
 
-    (Default::default(), all_pairs)
+/// Gets every pair of junctions, closest first by `metric`.  Each pair has its smaller
+/// junction first.
+///
+/// Pairs at the same distance are ordered by their smaller junction and then their
+/// larger one, comparing coordinates x, then y, then z.  So the order, and with it
+/// which merge comes last, only depends on the junctions and not on the order they
+/// were listed in or the sort used.
+pub fn pairs_by_distance(xyzs: &[XYZ], metric: Metric) -> Vec<(&XYZ, &XYZ)> {
+    let mut pairs = (0..xyzs.len())
+        .into_par_iter()
+        .flat_map_iter(|i| (i + 1..xyzs.len()).map(move |j| sort_key(metric, &xyzs[i], &xyzs[j])))
+        .collect::<Vec<_>>();
+    // The key is a total order, so an unstable sort gives the same order as a stable one.
+    pairs.par_sort_unstable();
+    pairs.into_iter().map(|(_, a, b)| (a, b)).collect()
+}
+
+/// The single threaded version of [`pairs_by_distance`], kept to benchmark against.
+pub fn pairs_by_distance_sequential(xyzs: &[XYZ], metric: Metric) -> Vec<(&XYZ, &XYZ)> {
+    let mut all_pairs = xyzs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| xyzs[i + 1..].iter().map(move |b| sort_key(metric, a, b)))
+        .collect::<Vec<_>>();
+    all_pairs.sort_unstable();
+    all_pairs.into_iter().map(|(_, a, b)| (a, b)).collect()
+}
+
+/// Gets a pair's distance and its junctions, smaller first, which together order the
+/// pairs for [`pairs_by_distance`].
+fn sort_key<'a>(metric: Metric, a: &'a XYZ, b: &'a XYZ) -> (u64, &'a XYZ, &'a XYZ) {
+    (metric.distance(a, b), a.min(b), a.max(b))
+}
+
+/// Like [`pairs_by_distance`], but sorts through temporary files holding `chunk_len`
+/// pairs each, so the pairs needn't fit in memory.  They are read back as they are
+/// used.
+pub fn pairs_by_distance_external(
+    xyzs: &[XYZ],
+    metric: Metric,
+    chunk_len: usize,
+) -> Result<impl Iterator<Item = Result<(&XYZ, &XYZ)>>> {
+    // Pairs go to disk as the ranks of their junctions in coordinate order, so comparing
+    // ranks breaks ties the same way comparing the junctions does.  Equal junctions
+    // share a rank.
+    let mut distinct = (0..xyzs.len()).collect::<Vec<_>>();
+    distinct.sort_by_key(|i| xyzs[*i]);
+    distinct.dedup_by_key(|i| xyzs[*i]);
+    let rank = |xyz: &XYZ| distinct.partition_point(|i| xyzs[*i] < *xyz);
+    let ranks = xyzs.iter().map(rank).collect::<Vec<_>>();
+    let pairs = (0..xyzs.len()).flat_map(|i| {
+        let ranks = &ranks;
+        (i + 1..xyzs.len()).map(move |j| {
+            let distance = metric.distance(&xyzs[i], &xyzs[j]);
+            (distance, ranks[i].min(ranks[j]), ranks[i].max(ranks[j]))
+        })
+    });
+    let sorted = common::extsort::sort_by_key(pairs, chunk_len, |pair| *pair)?;
+    Ok(sorted.map(move |pair| {
+        let (_, a, b) = pair?;
+        Ok((&xyzs[distinct[a]], &xyzs[distinct[b]]))
+    }))
+}
+
+/// Generates `count` junctions spread over a cube `size` wide, from a fixed seed so
+/// benchmarks are repeatable.
+pub fn generate_junctions(count: usize, size: u64, seed: u64) -> Vec<XYZ> {
+    let mut random = Random::new(seed);
+    let mut next = move || random.below(size) as i64;
+    (0..count)
+        .map(|_| XYZ {
+            x: next(),
+            y: next(),
+            z: next(),
+        })
+        .collect()
 }
 
 /// Processes the first 1000 closest junction pairs to form circuits, then returns the product
 /// of the sizes of the three largest circuits.
-pub fn part1(xyzs: &[XYZ]) -> Result<usize> {
-    let (mut circuits_manager, all_pairs) = initialize_circuits(xyzs);
+pub fn part1(xyzs: &[XYZ], metric: Metric) -> Result<usize> {
+    let mut circuits_manager = CircuitManager {
+        metric,
+        ..Default::default()
+    };
 
-    for (junction0, junction1) in all_pairs.into_iter().take(1000) {
+    // Only the closest 1000 are used, so there's no need to sort every pair.
+    for (junction0, junction1) in closest_k_pairs(xyzs, metric, 1000) {
         circuits_manager.combine_junctions(junction0, junction1);
     }
 
     // Map the circuits to how many junctions are in each circuit.
-    let mut num_circuits_in_active_circuits = circuits_manager
+    let num_circuits_in_active_circuits = circuits_manager
         .active_circuits()
-        .map(|circuit| circuit.len())
-        .collect::<Vec<_>>();
-    // Sort the circuits by size.
-    num_circuits_in_active_circuits.sort();
+        .map(|circuit| circuit.len());
 
     // Take the three largest circuits and return the product of their sizes.
-    let product = num_circuits_in_active_circuits
-        .into_iter()
-        .rev()
-        .take(3)
-        .product::<usize>();
-
-    Ok(product)
+    let largest = common::topk::top_k_by_key(num_circuits_in_active_circuits, 3, |len| *len);
+    common::product_results(largest.into_iter().map(Ok))
 }
 
 /// Represents the action to take when combining two junctions into circuits.
@@ -143,16 +429,16 @@ enum Action {
     // New circuit, so create a new circuit with the two junctions.
     NewCircuit,
     // Add the second junction to the first circuit.
-    Add1to0(usize),
+    Add1to0(CircuitId),
     // Add the first junction to the second circuit.
-    Add0to1(usize),
+    Add0to1(CircuitId),
     // Combine the two circuits.
-    CombineCircuits(usize, usize),
+    CombineCircuits(CircuitId, CircuitId),
 }
 
 /// Determines what action should be taken when combining two junctions based on whether
 /// they already belong to circuits.
-fn how_to_combine_junctions(circuit1: Option<&usize>, circuit2: Option<&usize>) -> Action {
+fn how_to_combine_junctions(circuit1: Option<&CircuitId>, circuit2: Option<&CircuitId>) -> Action {
     match (circuit1, circuit2) {
         (Some(circuit1), Some(circuit2)) => {
             // In the same circuit, do nothing.
@@ -160,19 +446,19 @@ fn how_to_combine_junctions(circuit1: Option<&usize>, circuit2: Option<&usize>)
                 return Action::DoNothing;
             }
             // In different circuits, combine them.
-            return Action::CombineCircuits(*circuit1, *circuit2);
+            Action::CombineCircuits(*circuit1, *circuit2)
         }
         (Some(circuit), None) => {
             // The second doesn't have a circuit, so add it to the first.
-            return Action::Add1to0(*circuit);
+            Action::Add1to0(*circuit)
         }
         (None, Some(circuit)) => {
             // The first doesn't have a circuit, so add it to the second.
-            return Action::Add0to1(*circuit);
+            Action::Add0to1(*circuit)
         }
         (None, None) => {
             // Neither has a circuit, so create a new circuit.
-            return Action::NewCircuit;
+            Action::NewCircuit
         }
     }
 }
@@ -193,10 +479,9 @@ impl<'a> CircuitManager<'a> {
             Action::NewCircuit => {
                 // Create a new circuit with the two junctions.
                 let circuit = Circuit::from([junction0, junction1]);
-                circuits.push(circuit);
+                let circuit_index = circuits.push(circuit);
 
                 // Setup the index pointers in junction_to_circuit to point to the new circuit.
-                let circuit_index = circuits.len() - 1;
                 junction_to_circuit.insert(junction0, circuit_index);
                 junction_to_circuit.insert(junction1, circuit_index);
             }
@@ -218,10 +503,7 @@ impl<'a> CircuitManager<'a> {
                 // We're going to clear circuit2 and add its junctions to circuit1.
 
                 // Take circuit2 from circuits and replace it with an empty set.
-                let circuit2 = std::mem::replace(
-                    circuits.get_mut(circuit2_index).expect("circuit2"),
-                    HashSet::new(),
-                );
+                let circuit2 = std::mem::take(circuits.get_mut(circuit2_index).expect("circuit2"));
 
                 let circuit1 = circuits.get_mut(circuit1_index).expect("circuit1");
                 circuit1.extend(circuit2.iter());
@@ -233,17 +515,30 @@ impl<'a> CircuitManager<'a> {
                 }
             }
         }
+        if !matches!(action, Action::DoNothing) {
+            let merged_size = junction_to_circuit
+                .get(junction0)
+                .and_then(|circuit| circuits.get(*circuit))
+                .map_or(0, |circuit| circuit.len());
+            self.history.push(MergeEvent {
+                distance: self.metric.distance(junction0, junction1),
+                a: *junction0,
+                b: *junction1,
+                merged_size,
+            });
+        }
         action
     }
 }
 
 /// Processes all junction pairs in order of distance, forming circuits. Returns the product
 /// of the x coordinates of the last pair that resulted in a circuit combination.
-pub fn part2(xyzs: &[XYZ]) -> Result<u64> {
-    let (mut circuits_manager, all_pairs) = initialize_circuits(xyzs);
+pub fn part2(xyzs: &[XYZ], metric: Metric) -> Result<i64> {
+    let (mut circuits_manager, all_pairs) = initialize_circuits(xyzs, metric)?;
 
     let mut last_x_coordinates = None;
-    for (junction0, junction1) in all_pairs {
+    for pair in all_pairs {
+        let (junction0, junction1) = pair?;
         match circuits_manager.combine_junctions(junction0, junction1) {
             Action::DoNothing => {}
             _ => {
@@ -255,13 +550,108 @@ pub fn part2(xyzs: &[XYZ]) -> Result<u64> {
     let Some(last_x_coordinates) = last_x_coordinates else {
         return Err(anyhow::anyhow!("No last x coordinates found"));
     };
-    Ok(last_x_coordinates.1 * last_x_coordinates.0)
+    last_x_coordinates
+        .1
+        .checked_mul(last_x_coordinates.0)
+        .ok_or_else(|| anyhow::anyhow!("Product of x coordinates overflowed"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_pairs_by_distance_matches_sequential() {
+        // A small cube, so there are plenty of ties.
+        let xyzs = generate_junctions(200, 8, 1);
+        for metric in [
+            Metric::SquaredEuclidean,
+            Metric::Manhattan,
+            Metric::Chebyshev,
+        ] {
+            assert_eq!(
+                pairs_by_distance(&xyzs, metric),
+                pairs_by_distance_sequential(&xyzs, metric)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ties_ordered_by_coordinates() {
+        // The corners of a unit square: the four edges tie at 1 and the two diagonals
+        // at 2, so only the coordinates decide the order within each.
+        let xyzs = parse_data("1,1,0\n0,1,0\n1,0,0\n0,0,0").unwrap();
+        let [p0, p1, p2, p3] = [&xyzs[3], &xyzs[1], &xyzs[2], &xyzs[0]];
+        let expected = vec![(p0, p1), (p0, p2), (p1, p3), (p2, p3), (p0, p3), (p1, p2)];
+        assert_eq!(pairs_by_distance(&xyzs, Metric::SquaredEuclidean), expected);
+        // Listing the junctions in another order doesn't change the pairs' order, or
+        // which merge is last.
+        let mut reversed = xyzs.clone();
+        reversed.reverse();
+        assert_eq!(
+            pairs_by_distance_sequential(&reversed, Metric::SquaredEuclidean),
+            expected
+        );
+        let history = merge_history(&reversed, Metric::SquaredEuclidean);
+        let last = history.events().last().unwrap();
+        assert_eq!((&last.a, &last.b), (p1, p3));
+    }
+
+    #[test]
+    fn test_pairs_by_distance_external() {
+        let xyzs = generate_junctions(200, 8, 1);
+        let expected = pairs_by_distance(&xyzs, Metric::Manhattan);
+        for chunk_len in [1_000, 100_000] {
+            let external = pairs_by_distance_external(&xyzs, Metric::Manhattan, chunk_len)
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(external, expected);
+        }
+    }
+
+    #[test]
+    fn test_closest_k_pairs() {
+        // Lots of ties at this density, so this also checks they break the same way.
+        let xyzs = generate_junctions(200, 8, 1);
+        let expected = pairs_by_distance(&xyzs, Metric::Manhattan);
+        for count in [0, 1, 1000, expected.len() + 1] {
+            let closest = closest_k_pairs(&xyzs, Metric::Manhattan, count);
+            assert_eq!(closest, expected[..count.min(expected.len())]);
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        let xyzs = parse_data("0,0,0\n3,3,0\n5,0,0").unwrap();
+        let [a, b, c] = [&xyzs[0], &xyzs[1], &xyzs[2]];
+        assert_eq!(
+            [
+                Metric::SquaredEuclidean,
+                Metric::Manhattan,
+                Metric::Chebyshev
+            ]
+            .map(|metric| metric.distance(a, b)),
+            [18, 6, 3]
+        );
+        // b to c is closest as the crow flies, but a to c wins when only axis steps
+        // count, and a to b when only the longest axis does.
+        assert_eq!(
+            pairs_by_distance(&xyzs, Metric::SquaredEuclidean),
+            vec![(b, c), (a, b), (a, c)]
+        );
+        assert_eq!(
+            pairs_by_distance(&xyzs, Metric::Manhattan),
+            vec![(a, c), (b, c), (a, b)]
+        );
+        assert_eq!(
+            pairs_by_distance(&xyzs, Metric::Chebyshev),
+            vec![(a, b), (b, c), (a, c)]
+        );
+        assert_eq!("manhattan".parse::<Metric>().unwrap(), Metric::Manhattan);
+        assert!("taxicab".parse::<Metric>().is_err());
+    }
+
     #[test]
     fn test_parse_data() {
         let data = "1,2,3\n4,5,6\n7,8,9";
@@ -269,11 +659,88 @@ mod tests {
         assert_eq!(xyzs.len(), 3);
     }
 
+    #[test]
+    fn test_extent() {
+        let data = "1,20,3\n40,5,6\n7,8,90";
+        let xyzs = parse_data(data).unwrap();
+        let bounds = extent(&xyzs).unwrap();
+        assert_eq!(bounds.min, [1, 5, 3]);
+        assert_eq!(bounds.max, [40, 20, 90]);
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        let xyzs = parse_data("-3,4,-5\n3,-4,5\n-2,3,-4").unwrap();
+        assert_eq!(xyzs[0].coordinates(), [-3, 4, -5]);
+        assert_eq!(XYZ::sqr_distance(&xyzs[0], &xyzs[1]), 36 + 64 + 100);
+        assert_eq!(extent(&xyzs).unwrap().min, [-3, -4, -5]);
+        // The last merge joins (3,-4,5) to the nearer of the other two.
+        assert_eq!(part2(&xyzs, Metric::default()).unwrap(), -6);
+        assert!(parse_data("1,--2,3").is_err());
+        assert_eq!(parse_data("-3, 4;\t-5\n3 -4 5").unwrap(), xyzs[..2]);
+    }
+
     #[test]
     fn test_part1() {
         let data = common::read_file("sample.txt").unwrap();
         let xyzs = parse_data(&data).unwrap();
         // Note: this is for 1000 pairs, not 10 in the sample data.
-        assert_eq!(part1(&xyzs).unwrap(), 20);
+        assert_eq!(part1(&xyzs, Metric::default()).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_merge_history() {
+        let data = common::read_file("sample.txt").unwrap();
+        let xyzs = parse_data(&data).unwrap();
+        let history = merge_history(&xyzs, Metric::default());
+        // Every junction ends up in one circuit.
+        assert_eq!(history.events().len(), xyzs.len() - 1);
+        assert_eq!(history.circuits_after(usize::MAX), 1);
+        let last = history.events().last().unwrap();
+        assert_eq!(last.merged_size, xyzs.len());
+        assert_eq!(
+            last.a.x * last.b.x,
+            part2(&xyzs, Metric::default()).unwrap()
+        );
+        // Merges happen closest first.
+        assert!(
+            history
+                .events()
+                .windows(2)
+                .all(|pair| pair[0].distance <= pair[1].distance)
+        );
+        assert_eq!(history.events()[0].merged_size, 2);
+        assert_eq!(history.distance_for_circuits(1), Some(last.distance));
+        assert_eq!(history.distance_for_circuits(xyzs.len()), Some(0));
+        assert_eq!(
+            history.distance_for_circuits(xyzs.len() - 1),
+            Some(history.events()[0].distance)
+        );
+    }
+
+    #[test]
+    fn test_circuits_at_distance() {
+        let data = common::read_file("sample.txt").unwrap();
+        let xyzs = parse_data(&data).unwrap();
+        let history = merge_history(&xyzs, Metric::default());
+
+        // Nothing is connected below the closest pair.
+        let first = history.events()[0].distance;
+        assert_eq!(history.circuits_at_distance(first - 1).len(), xyzs.len());
+        assert_eq!(history.circuit_count_at_distance(first), xyzs.len() - 1);
+        assert_eq!(history.circuits_at_distance(first)[0].len(), 2);
+
+        // The ten closest pairs give the sample's part 1 circuits: 5, 4, 2, 2 and singles.
+        // One of those pairs is already connected, so that's nine merges.
+        let tenth = history.distance_for_circuits(xyzs.len() - 9).unwrap();
+        let circuits = history.circuits_at_distance(tenth);
+        assert_eq!(circuits.len(), 11);
+        assert_eq!(circuits.len(), history.circuit_count_at_distance(tenth));
+        let sizes = circuits.iter().map(|c| c.len()).take(4).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![5, 4, 2, 2]);
+        assert_eq!(circuits.iter().map(|c| c.len()).sum::<usize>(), xyzs.len());
+
+        let all = history.circuits_at_distance(u64::MAX);
+        assert_eq!(all.len(), 1);
     }
 }
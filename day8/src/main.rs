@@ -1,13 +1,81 @@
+use std::time::Instant;
+
 use anyhow::Result;
-use day8::{part1, part2};
+use common::{
+    error::ErrorContext,
+    stats::{Summary, histogram},
+};
+use day8::{Metric, XYZ, part1, part2};
+
+/// How many times --bench times each version.
+const BENCH_RUNS: usize = 5;
 
 fn main() -> Result<()> {
-    let arg1 = std::env::args()
-        .nth(1)
-        .ok_or_else(|| anyhow::anyhow!("No input file provided"))?;
-    let data = common::read_file(&arg1)?;
-    let xyzs = day8::parse_data(&data)?;
-    println!("Part 1: {}", part1(&xyzs)?);
-    println!("Part 2: {}", part2(&xyzs)?);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // --bench <count> times the pair sort on generated junctions instead of solving.
+    if let Some(count) = args.iter().skip_while(|arg| *arg != "--bench").nth(1) {
+        return bench(count.parse()?);
+    }
+    let arg1 = common::args::input_path(&args, &["--bench", "--metric"])?;
+    let data = common::read_file(arg1)?;
+    let xyzs = day8::parse_data(&data)
+        .with_file(arg1)
+        .with_phase("parse")
+        .with_day(8)?;
+    // --metric <euclidean|manhattan|chebyshev> changes how junction distance is measured.
+    let metric = match args.iter().skip_while(|arg| *arg != "--metric").nth(1) {
+        Some(metric) => metric.parse()?,
+        None => Metric::default(),
+    };
+    // --stats shows how far apart the junctions are before solving.
+    if args.iter().any(|arg| arg == "--stats") {
+        stats(&xyzs);
+    }
+    println!(
+        "Part 1: {}",
+        part1(&xyzs, metric).with_phase("part 1").with_day(8)?
+    );
+    println!(
+        "Part 2: {}",
+        part2(&xyzs, metric).with_phase("part 2").with_day(8)?
+    );
+    Ok(())
+}
+
+/// Prints the spread of distances between every pair of junctions.
+fn stats(xyzs: &[XYZ]) {
+    let distances = day8::pairs_by_distance(xyzs, Metric::SquaredEuclidean)
+        .into_iter()
+        .map(|(a, b)| (XYZ::sqr_distance(a, b) as f64).sqrt())
+        .collect::<Vec<_>>();
+    if let Some(summary) = Summary::of(distances.iter().copied()) {
+        println!("Pair distances: {}", summary);
+        print!("{}", histogram(distances, 20, 50));
+    }
+}
+
+/// Compares sorting every pair by distance on one thread and on rayon, timing each a
+/// few times.
+fn bench(count: usize) -> Result<()> {
+    let xyzs = day8::generate_junctions(count, 100_000, 2025);
+    let mut sequential_ms = Vec::new();
+    let mut parallel_ms = Vec::new();
+    for _ in 0..BENCH_RUNS {
+        let start = Instant::now();
+        let sequential = day8::pairs_by_distance_sequential(&xyzs, Metric::default());
+        sequential_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        let start = Instant::now();
+        let parallel = day8::pairs_by_distance(&xyzs, Metric::default());
+        parallel_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+        if sequential != parallel {
+            anyhow::bail!("Parallel pair order differs from sequential");
+        }
+    }
+    println!("{} junctions, times in ms", count);
+    for (name, times) in [("sequential", sequential_ms), ("parallel", parallel_ms)] {
+        if let Some(summary) = Summary::of(times) {
+            println!("{:>10}: {}", name, summary);
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,102 @@
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+use common::grid::Grid;
+
+use crate::Tile;
+
+/// Writes a classified grid to a file: SVG if the path ends in `.svg`, run-length
+/// encoded if it ends in `.rle`, otherwise text with one character per tile.
+pub fn write_grid(grid: &Grid<Tile>, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    if extension == "svg" {
+        write_svg(grid, out)
+    } else if extension == "rle" {
+        writeln!(out, "{}", grid.to_rle())?;
+        out.flush()?;
+        Ok(())
+    } else {
+        write_text(grid, out)
+    }
+}
+
+/// Writes one line per row, using each tile's Display character.
+pub fn write_text(grid: &Grid<Tile>, mut out: impl Write) -> Result<()> {
+    for row in grid.rows() {
+        for tile in row {
+            write!(out, "{}", tile)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes an SVG with one unit square per tile.  Runs of the same tile in a row are
+/// drawn as a single rectangle to keep large grids a manageable size.
+pub fn write_svg(grid: &Grid<Tile>, mut out: impl Write) -> Result<()> {
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        grid.width(),
+        grid.height()
+    )?;
+    for (y, row) in grid.rows().enumerate() {
+        let mut x = 0;
+        for run in row.chunk_by(|a, b| a == b) {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#,
+                x,
+                y,
+                run.len(),
+                color(&run[0])
+            )?;
+            x += run.len();
+        }
+    }
+    writeln!(out, "</svg>")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn color(tile: &Tile) -> &'static str {
+    match tile {
+        Tile::Red => "red",
+        Tile::Green => "green",
+        Tile::Empty => "white",
+        Tile::Inside => "lightgreen",
+        Tile::Outside => "lightgray",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_sample() {
+        let data = crate::parse_data(include_str!("../sample.txt")).unwrap();
+        let grid = crate::classified_grid(&data).unwrap();
+
+        let mut text = Vec::new();
+        write_text(&grid, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text.lines().count(), grid.height());
+        assert_eq!(text.lines().nth(1), Some("OOOOOOO#XXX#O"));
+
+        let mut svg = Vec::new();
+        write_svg(&grid, &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"<rect x="7" y="1" width="1" height="1" fill="red"/>"#));
+    }
+}
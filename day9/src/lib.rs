@@ -1,23 +1,46 @@
 use anyhow::Result;
-use common::grid::XY;
+use common::{
+    bounds::bounding_box,
+    grid::{Connectivity, Grid, XY},
+    iter::IterExt,
+    parse::ExpectWithLine,
+    search::{BranchAndBound, branch_and_bound},
+};
+use itertools::Itertools;
+use std::collections::{BTreeMap, VecDeque};
 
-pub fn parse_data(data: &str) -> Result<Vec<XY>> {
-    data.lines().map(|line| line.parse::<XY>()).collect()
+pub mod dump;
+pub mod polygon;
+
+/// Parses one or more loops of red tiles, one point per line, with blank lines between
+/// the loops.
+pub fn parse_data(data: &str) -> Result<Vec<Vec<XY>>> {
+    // Blank lines parse as None, so the line numbers in errors count them.
+    let points =
+        data.lines()
+            .expect_with_line(
+                "'x,y' with non-negative integer coordinates",
+                |line| match line.trim() {
+                    "" => Ok(None),
+                    _ => XY::from_str_lenient(line).map(Some),
+                },
+            )?;
+    Ok(points
+        .split(Option::is_none)
+        .filter(|points| !points.is_empty())
+        .map(|points| points.iter().flatten().cloned().collect())
+        .collect())
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub enum Tile {
     Red,
     Green,
+    #[default]
     Empty,
     Inside,
     Outside,
 }
-impl Default for Tile {
-    fn default() -> Self {
-        Tile::Empty
-    }
-}
 impl std::fmt::Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -29,3 +52,483 @@ impl std::fmt::Display for Tile {
         }
     }
 }
+
+/// An axis-aligned rectangle of tiles, inclusive of both corners.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Rect {
+    /// The top-left corner.
+    pub min: XY,
+    /// The bottom-right corner.
+    pub max: XY,
+}
+impl Rect {
+    /// Creates the rectangle spanned by two opposite corners, in any order.
+    pub fn from_corners(xy1: &XY, xy2: &XY) -> Self {
+        Rect {
+            min: XY::new(xy1.x.min(xy2.x), xy1.y.min(xy2.y)),
+            max: XY::new(xy1.x.max(xy2.x), xy1.y.max(xy2.y)),
+        }
+    }
+    /// Gets the number of tiles covered by the rectangle.
+    pub fn area(&self) -> usize {
+        let dx = self.max.x - self.min.x + 1;
+        let dy = self.max.y - self.min.y + 1;
+        dx * dy
+    }
+    /// Is `other` completely inside this rectangle?
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.min.x >= self.min.x
+            && other.max.x <= self.max.x
+            && other.min.y >= self.min.y
+            && other.max.y <= self.max.y
+    }
+    /// Gets the rectangle covered by both this rectangle and `other`, if they overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let min = XY::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y));
+        let max = XY::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y));
+        (min.x <= max.x && min.y <= max.y).then_some(Rect { min, max })
+    }
+    /// Returns an iterator over every position in the rectangle.
+    pub fn positions(&self) -> impl Iterator<Item = XY> + use<> {
+        let (min_y, max_y) = (self.min.y, self.max.y);
+        (self.min.x..=self.max.x).flat_map(move |x| (min_y..=max_y).map(move |y| XY::new(x, y)))
+    }
+}
+
+/// Returns every rectangle that has two of the points as opposite corners.
+pub fn corner_rectangles(data: &[XY]) -> impl Iterator<Item = Rect> {
+    data.iter()
+        .tuple_combinations()
+        .map(|(xy1, xy2)| Rect::from_corners(xy1, xy2))
+}
+
+/// Finds the largest rectangle with red tiles in opposite corners that only covers
+/// red or green tiles.  The corners may come from different loops.  Returns the
+/// rectangle along with its area.
+///
+/// Checks rectangles against the loops' [`polygon::InsideColumns`], so it needs no
+/// grid however far apart the tiles are.
+pub fn largest_interior_rectangle_with_corners(loops: &[Vec<XY>]) -> Result<Option<(Rect, usize)>> {
+    polygon::validate_loops(loops)?;
+    let inside = polygon::InsideColumns::new(loops);
+    Ok(largest_interior_rectangle(&loops.concat(), |rect| {
+        inside.contains_rect(rect)
+    }))
+}
+
+/// Like [`largest_interior_rectangle_with_corners`], but checking rectangles against
+/// a grid already classified by [`classified_grid`].
+pub fn largest_interior_rectangle_in_grid(data: &[XY], grid: &Grid<Tile>) -> Option<(Rect, usize)> {
+    largest_interior_rectangle(data, |rect| rectangle_area_inside(rect, grid).is_some())
+}
+
+fn largest_interior_rectangle(
+    data: &[XY],
+    fits: impl FnMut(&Rect) -> bool,
+) -> Option<(Rect, usize)> {
+    let mut search = InteriorRectangles {
+        fits,
+        rects: corner_rectangles(data).collect(),
+        best: 0,
+        bad_rectangles: BTreeMap::new(),
+    };
+    let (area, rect) = branch_and_bound(&mut search, None)?;
+    Some((rect?, area))
+}
+
+/// The search for the largest interior corner rectangle.  The root node is None, and
+/// its children are the candidate rectangles from largest to smallest, so the first
+/// one that fits is the answer and the area bound prunes the rest.
+struct InteriorRectangles<F> {
+    // Whether a rectangle only covers red or green tiles.
+    fits: F,
+    rects: Vec<Rect>,
+    // The largest area found to fit so far.
+    best: usize,
+    // Rectangles known to cover an outside tile, by their left edge.  Anything
+    // containing one does too.
+    bad_rectangles: BTreeMap<usize, Vec<Rect>>,
+}
+
+impl<F> InteriorRectangles<F> {
+    /// Does the rectangle contain one already known to cover an outside tile?  Only
+    /// bad rectangles whose left edge lies within it need looking at.
+    fn contains_bad(&self, rect: &Rect) -> bool {
+        self.bad_rectangles
+            .range(rect.min.x..=rect.max.x)
+            .flat_map(|(_, bad)| bad)
+            .any(|bad| rect.contains_rect(bad))
+    }
+}
+
+impl<F: FnMut(&Rect) -> bool> BranchAndBound for InteriorRectangles<F> {
+    type Node = Option<Rect>;
+    type Score = usize;
+
+    fn branch(&mut self, node: &Option<Rect>) -> Vec<Option<Rect>> {
+        if node.is_some() {
+            return Vec::new();
+        }
+        // Stable, so rectangles of equal area stay in corner pair order.
+        let mut rects = std::mem::take(&mut self.rects);
+        rects.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+        rects.into_iter().map(Some).collect()
+    }
+
+    fn upper_bound(&mut self, node: &Option<Rect>) -> Option<usize> {
+        match node {
+            None => self.rects.iter().map(Rect::area).max(),
+            // No bigger than the best, so the search prunes it on area alone.
+            Some(rect) if rect.area() <= self.best => Some(rect.area()),
+            Some(rect) if self.contains_bad(rect) => None,
+            Some(rect) => Some(rect.area()),
+        }
+    }
+
+    fn score(&mut self, node: &Option<Rect>) -> Option<usize> {
+        let rect = node.as_ref()?;
+        if !(self.fits)(rect) {
+            self.bad_rectangles
+                .entry(rect.min.x)
+                .or_default()
+                .push(rect.clone());
+            return None;
+        }
+        self.best = self.best.max(rect.area());
+        Some(rect.area())
+    }
+}
+
+/// Draws each loop of red tiles joined by green lines and classifies every other tile
+/// as inside or outside.  Loops may sit inside other loops, where they cut holes: a
+/// tile is inside if it is within an odd number of loops.
+pub fn classified_grid(loops: &[Vec<XY>]) -> Result<Grid<Tile>> {
+    // Diagonal or crossing edges would draw loops the flood fill can't classify.
+    polygon::validate_loops(loops)?;
+
+    // Create a grid of the data
+    let bounds =
+        bounding_box(loops.iter().flatten()).ok_or_else(|| anyhow::anyhow!("No points found"))?;
+
+    // Leave a border of empty tiles past the maximum so the flood fill can get around the loop.
+    let padded = bounds.expand(1);
+    let mut grid = Grid::<Tile>::new_sized(padded.max.x + 1, padded.max.y + 1, Tile::Empty);
+
+    // Populate the grid with the data
+    for data in loops {
+        let coordinates = data.iter().cycle().take(data.len() + 1);
+        for [xy1, xy2] in coordinates.windows_tuple::<2>() {
+            drawline(&mut grid, xy1, xy2)?;
+        }
+    }
+
+    classify_tiles(&mut grid)?;
+    //classify_tiles_ray_casting(&mut grid)?;
+
+    // Where loops touch, the fill has no empty tile between them to cross, so it never
+    // reaches what the inner loop encloses.  Those tiles go by crossing parity instead.
+    let unreached = grid
+        .cells()
+        .filter(|cell| cell.value() == &Tile::Empty)
+        .map(|cell| cell.xy())
+        .collect::<Vec<_>>();
+    for xy in unreached {
+        let tile = match polygon::is_enclosed(loops, &xy) {
+            true => Tile::Inside,
+            false => Tile::Outside,
+        };
+        *grid
+            .get_mut(&xy)
+            .ok_or_else(|| anyhow::anyhow!("Cell not found"))? = tile;
+    }
+
+    Ok(grid)
+}
+
+fn rectangle_area_inside(rect: &Rect, grid: &Grid<Tile>) -> Option<usize> {
+    let any_outside = rect.positions().any(|xy| {
+        grid.get(xy)
+            .map(|t| t.value() == &Tile::Outside)
+            .unwrap_or(true)
+    });
+    if any_outside {
+        return None;
+    }
+    Some(rect.area())
+}
+
+fn drawline(grid: &mut Grid<Tile>, xy1: &XY, xy2: &XY) -> Result<()> {
+    let line = xy1.line_to(xy2)?;
+    let mut first = None;
+    let mut last = None;
+    for xy in line {
+        if first.is_none() {
+            first = Some(xy.clone());
+        }
+        last = Some(xy.clone());
+        *grid
+            .get_mut(&xy)
+            .ok_or_else(|| anyhow::anyhow!("Cell not found"))? = Tile::Green;
+    }
+    if let (Some(first), Some(last)) = (first, last) {
+        *grid
+            .get_mut(&first)
+            .ok_or_else(|| anyhow::anyhow!("Cell not found"))? = Tile::Red;
+        *grid
+            .get_mut(&last)
+            .ok_or_else(|| anyhow::anyhow!("Cell not found"))? = Tile::Red;
+    }
+    Ok(())
+}
+
+fn classify_tiles(grid: &mut Grid<Tile>) -> Result<()> {
+    // Find grid dimensions by iterating through cells
+    let mut max_x = 0;
+    let mut max_y = 0;
+    for cell in grid.cells() {
+        let xy = cell.xy();
+        max_x = max_x.max(xy.x);
+        max_y = max_y.max(xy.y);
+    }
+    let width = max_x + 1;
+    let height = max_y + 1;
+
+    // Flood fill from all edge tiles.  Each fill stops at the loops around it, and
+    // the tiles just across them start the next fill, which is the other way round.
+    let mut queue = VecDeque::new();
+    let mut fill = Tile::Outside;
+
+    // Marks an Empty tile with the current fill and queues it.
+    let mark = |grid: &mut Grid<Tile>, queue: &mut VecDeque<XY>, xy: XY, fill: &Tile| {
+        if let Some(tile) = grid.get_mut(&xy)
+            && matches!(*tile, Tile::Empty)
+        {
+            *tile = fill.clone();
+            queue.push_back(xy);
+        }
+    };
+
+    // Add all edge tiles to the queue if they're Empty
+    // Top and bottom rows
+    for x in 0..width {
+        mark(grid, &mut queue, XY::new(x, 0), &fill);
+        if height > 1 {
+            mark(grid, &mut queue, XY::new(x, height - 1), &fill);
+        }
+    }
+
+    // Left and right columns
+    for y in 0..height {
+        mark(grid, &mut queue, XY::new(0, y), &fill);
+        if width > 1 {
+            mark(grid, &mut queue, XY::new(width - 1, y), &fill);
+        }
+    }
+
+    while !queue.is_empty() {
+        // BFS flood fill, noting the loop tiles it runs into.
+        let mut boundary = Vec::new();
+        while let Some(xy) = queue.pop_front() {
+            let neighbors = grid
+                .neighbors(&xy, Connectivity::Cardinal)
+                .map(|(xy, tile)| (xy, tile.clone()))
+                .collect::<Vec<_>>();
+            for (neighbor_xy, tile) in neighbors {
+                match tile {
+                    Tile::Empty => mark(grid, &mut queue, neighbor_xy, &fill),
+                    Tile::Red | Tile::Green => boundary.push(neighbor_xy),
+                    Tile::Inside | Tile::Outside => {}
+                }
+            }
+        }
+
+        // Cross the loops into the next fill.
+        fill = match fill {
+            Tile::Outside => Tile::Inside,
+            _ => Tile::Outside,
+        };
+        for xy in boundary {
+            let empty_neighbors = grid
+                .neighbors(&xy, Connectivity::Cardinal)
+                .filter(|(_, tile)| matches!(tile, Tile::Empty))
+                .map(|(xy, _)| xy)
+                .collect::<Vec<_>>();
+            for neighbor_xy in empty_neighbors {
+                mark(grid, &mut queue, neighbor_xy, &fill);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn classify_tiles_ray_casting(grid: &mut Grid<Tile>) -> Result<()> {
+    // Find grid dimensions by iterating through cells
+    let mut max_x = 0;
+    let mut max_y = 0;
+    for cell in grid.cells() {
+        let xy = cell.xy();
+        max_x = max_x.max(xy.x);
+        max_y = max_y.max(xy.y);
+    }
+    let width = max_x + 1;
+    let height = max_y + 1;
+
+    // First, collect all Empty tile positions to avoid borrowing issues
+    let mut empty_tiles = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let xy = XY::new(x, y);
+            if let Some(cell) = grid.get(xy.clone())
+                && matches!(cell.value(), Tile::Empty)
+            {
+                empty_tiles.push(xy);
+            }
+        }
+    }
+
+    // For each Empty tile, use ray casting to determine if it's inside or outside
+    // Collect classifications first to avoid borrowing conflicts
+    let mut classifications = Vec::new();
+    for xy in &empty_tiles {
+        // Cast a ray horizontally to the right and count boundary intersections
+        let intersections = count_boundary_intersections(xy, grid, width);
+        // Odd number of intersections = inside, even = outside
+        let new_tile = if intersections % 2 == 1 {
+            Tile::Inside
+        } else {
+            Tile::Outside
+        };
+        classifications.push((xy.clone(), new_tile));
+    }
+
+    // Now apply classifications
+    for (xy, new_tile) in classifications {
+        if let Some(tile) = grid.get_mut(&xy) {
+            *tile = new_tile;
+        }
+    }
+
+    Ok(())
+}
+
+fn count_boundary_intersections(start: &XY, grid: &Grid<Tile>, width: usize) -> usize {
+    let y = start.y;
+    let mut intersections = 0;
+    let mut was_on_boundary = false;
+
+    // Cast ray horizontally to the right
+    for x in (start.x + 1)..width {
+        let xy = XY::new(x, y);
+        if let Some(cell) = grid.get(xy) {
+            let is_boundary = matches!(cell.value(), Tile::Green | Tile::Red);
+
+            // Count a crossing when we transition from non-boundary to boundary
+            // Consecutive boundary tiles count as a single crossing
+            if is_boundary {
+                if !was_on_boundary {
+                    // Entering boundary - count as intersection
+                    intersections += 1;
+                }
+                was_on_boundary = true;
+            } else {
+                was_on_boundary = false;
+            }
+        } else {
+            // Out of bounds - treat as non-boundary
+            was_on_boundary = false;
+        }
+    }
+
+    intersections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_largest_interior_rectangle_with_corners() {
+        let loops = parse_data(include_str!("../sample.txt")).unwrap();
+        let (rect, area) = largest_interior_rectangle_with_corners(&loops)
+            .unwrap()
+            .unwrap();
+        assert_eq!(area, 24);
+        assert_eq!(rect.area(), area);
+        assert_eq!(rect, Rect::from_corners(&XY::new(9, 5), &XY::new(2, 3)));
+        let grid = classified_grid(&loops).unwrap();
+        assert_eq!(
+            largest_interior_rectangle_in_grid(&loops.concat(), &grid),
+            Some((rect, area))
+        );
+    }
+
+    #[test]
+    fn test_classified_grid() {
+        let loops = parse_data(include_str!("../sample.txt")).unwrap();
+        assert_eq!(loops.len(), 1);
+        let grid = classified_grid(&loops).unwrap();
+        let count = |tile: Tile| grid.count(|cell| cell.value() == &tile);
+        assert_eq!(count(Tile::Red), loops[0].len());
+        assert_eq!(count(Tile::Empty), 0);
+        // Every tile is on the loop, inside it or outside it.
+        assert_eq!(
+            count(Tile::Red) + count(Tile::Green) + count(Tile::Inside) + count(Tile::Outside),
+            grid.width() * grid.height()
+        );
+    }
+
+    #[test]
+    fn test_parse_loops() {
+        let loops = parse_data("0,0\n2,0\n2,2\n0,2\n\n\n5,5\n6,5\n6,6\n5,6\n").unwrap();
+        assert_eq!(loops.iter().map(Vec::len).collect::<Vec<_>>(), vec![4, 4]);
+        assert_eq!(loops[1][0], XY::new(5, 5));
+        let error = parse_data("0,0\n\n1,x").unwrap_err();
+        assert!(error.to_string().starts_with("line 3: "), "{}", error);
+    }
+
+    #[test]
+    fn test_holes() {
+        // A square with a square hole in the middle, and an island in the hole.
+        let data = [
+            "0,0\n12,0\n12,12\n0,12",
+            "3,3\n9,3\n9,9\n3,9",
+            "5,5\n7,5\n7,7\n5,7",
+        ];
+        let loops = parse_data(&data.join("\n\n")).unwrap();
+        let grid = classified_grid(&loops).unwrap();
+        let tile = |x, y| grid.get(XY::new(x, y)).unwrap().value().clone();
+        assert_eq!(tile(1, 1), Tile::Inside);
+        assert_eq!(tile(4, 4), Tile::Outside);
+        assert_eq!(tile(6, 6), Tile::Inside);
+        assert_eq!(tile(13, 13), Tile::Outside);
+        assert_eq!(
+            grid.count(|cell| cell.value() == &Tile::Inside),
+            11 * 11 - 7 * 7 + 1
+        );
+
+        // The biggest rectangle runs down one side of the hole, not across it.
+        let (_, area) = largest_interior_rectangle_with_corners(&loops)
+            .unwrap()
+            .unwrap();
+        assert_eq!(area, 4 * 10);
+    }
+
+    #[test]
+    fn test_hole_touching_its_loop() {
+        // No tile lies between the loops, so the fill can't cross into the hole.
+        let loops = parse_data("0,0\n8,0\n8,8\n0,8\n\n1,1\n7,1\n7,7\n1,7").unwrap();
+        let grid = classified_grid(&loops).unwrap();
+        assert_eq!(grid.count(|cell| cell.value() == &Tile::Empty), 0);
+        assert_eq!(grid.get(XY::new(4, 4)).unwrap().value(), &Tile::Outside);
+        let (rect, area) = largest_interior_rectangle_with_corners(&loops)
+            .unwrap()
+            .unwrap();
+        assert_eq!(area, 16);
+        assert_eq!(
+            largest_interior_rectangle_in_grid(&loops.concat(), &grid),
+            Some((rect, area))
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{Context, Result};
+use common::{
+    grid::XY,
+    ranges::IntervalSet,
+    sweep::{ActiveSet, Event, SweepLine},
+};
+
+use crate::Rect;
+
+/// A closed loop of points where every edge is horizontal or vertical and no two
+/// edges cross or overlap.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RectilinearPolygon {
+    vertices: Vec<XY>,
+}
+impl RectilinearPolygon {
+    pub fn vertices(&self) -> &[XY] {
+        &self.vertices
+    }
+    /// Returns each edge as its two end points, including the edge from the last
+    /// vertex back to the first.
+    pub fn edges(&self) -> impl Iterator<Item = (&XY, &XY)> {
+        edges(&self.vertices)
+    }
+    /// Gets the length of the loop.  See [`perimeter`].
+    pub fn perimeter(&self) -> usize {
+        perimeter(&self.vertices)
+    }
+    /// Gets every tile on the loop once.  See [`boundary_cells`].
+    pub fn boundary_cells(&self) -> impl Iterator<Item = XY> {
+        boundary_cells(&self.vertices)
+    }
+}
+
+/// Gets the length of the closed loop through the points, measuring each edge by its
+/// Manhattan distance.  For a rectilinear polygon this is also the number of tiles on
+/// the loop.
+pub fn perimeter(points: &[XY]) -> usize {
+    edges(points)
+        .map(|(from, to)| from.x.abs_diff(to.x) + from.y.abs_diff(to.y))
+        .sum()
+}
+
+/// Gets every tile on the closed loop through the points, once each, starting at the
+/// first point.  Diagonal edges are walked along x and then along y, so the count
+/// always matches [`perimeter`].
+pub fn boundary_cells(points: &[XY]) -> impl Iterator<Item = XY> {
+    edges(points).flat_map(|(from, to)| {
+        // Leave off the end of each edge, since it starts the next one.
+        let length = from.x.abs_diff(to.x) + from.y.abs_diff(to.y);
+        manhattan_path(from, to).take(length)
+    })
+}
+
+/// Gets the tiles on a horizontal or vertical segment in order from `from` to `to`,
+/// including both ends.  Use [`XY::line_to`] to allow diagonals too.
+pub fn segment(from: &XY, to: &XY) -> Result<impl Iterator<Item = XY> + use<>> {
+    if from.x != to.x && from.y != to.y {
+        anyhow::bail!(
+            "Segment from {} to {} is not horizontal or vertical",
+            describe(from),
+            describe(to)
+        );
+    }
+    from.line_to(to)
+}
+
+/// Walks from one tile to another along x and then along y, including both ends.
+fn manhattan_path(from: &XY, to: &XY) -> impl Iterator<Item = XY> + use<> {
+    let (from, to) = (from.clone(), to.clone());
+    let along_x = steps(from.x, to.x).map(move |x| XY::new(x, from.y));
+    let along_y = steps(from.y, to.y).skip(1).map(move |y| XY::new(to.x, y));
+    along_x.chain(along_y)
+}
+
+/// Counts from `start` to `end` inclusive, in whichever direction that is.
+fn steps(start: usize, end: usize) -> impl Iterator<Item = usize> {
+    (0..=start.abs_diff(end)).map(move |i| if end >= start { start + i } else { start - i })
+}
+
+fn edges(vertices: &[XY]) -> impl Iterator<Item = (&XY, &XY)> {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .take(vertices.len())
+}
+
+/// Checks that the points form a rectilinear polygon: consecutive points (wrapping
+/// around at the end) must share a row or column, and edges may only touch where
+/// consecutive edges meet at a vertex.
+pub fn validate(points: &[XY]) -> Result<RectilinearPolygon> {
+    if points.len() < 4 {
+        anyhow::bail!("A polygon needs at least 4 points, got {}", points.len());
+    }
+
+    let edge_rects = edges(points)
+        .enumerate()
+        .map(|(i, (from, to))| {
+            if from == to {
+                anyhow::bail!("Edge {} has zero length at {}", i, describe(from));
+            }
+            if from.x != to.x && from.y != to.y {
+                anyhow::bail!(
+                    "Edge {} from {} to {} is not horizontal or vertical",
+                    i,
+                    describe(from),
+                    describe(to)
+                );
+            }
+            Ok(Rect::from_corners(from, to))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let count = edge_rects.len();
+    for (i, a) in edge_rects.iter().enumerate() {
+        for (j, b) in edge_rects.iter().enumerate().skip(i + 1) {
+            let Some(overlap) = a.intersection(b) else {
+                continue;
+            };
+            // Consecutive edges meet at their shared vertex, which is fine as long as
+            // they don't double back over each other.
+            let consecutive = j == i + 1 || (i == 0 && j == count - 1);
+            if consecutive && overlap.area() == 1 {
+                continue;
+            }
+            anyhow::bail!(
+                "Edge {} ({} to {}) and edge {} ({} to {}) intersect at {}",
+                i,
+                describe(&points[i]),
+                describe(&points[(i + 1) % count]),
+                j,
+                describe(&points[j]),
+                describe(&points[(j + 1) % count]),
+                describe(&overlap.min)
+            );
+        }
+    }
+
+    Ok(RectilinearPolygon {
+        vertices: points.to_vec(),
+    })
+}
+
+/// Validates each loop with [`validate`], and checks that no two loops share a tile,
+/// so that every loop separates the tiles on its two sides.  Loops may still run
+/// side by side with no tile between them.
+pub fn validate_loops(loops: &[Vec<XY>]) -> Result<Vec<RectilinearPolygon>> {
+    let polygons = loops
+        .iter()
+        .enumerate()
+        .map(|(i, points)| validate(points).with_context(|| format!("Loop {}", i)))
+        .collect::<Result<Vec<_>>>()?;
+    for (i, a) in polygons.iter().enumerate() {
+        for (j, b) in polygons.iter().enumerate().skip(i + 1) {
+            for (from_a, to_a) in a.edges() {
+                for (from_b, to_b) in b.edges() {
+                    let edge_a = Rect::from_corners(from_a, to_a);
+                    if let Some(overlap) = edge_a.intersection(&Rect::from_corners(from_b, to_b)) {
+                        anyhow::bail!(
+                            "Loop {} ({} to {}) and loop {} ({} to {}) intersect at {}",
+                            i,
+                            describe(from_a),
+                            describe(to_a),
+                            j,
+                            describe(from_b),
+                            describe(to_b),
+                            describe(&overlap.min)
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(polygons)
+}
+
+/// Checks whether a tile that isn't on any of the loops is inside an odd number of
+/// them, by counting the vertical edges on a ray from the tile to the right.  Each edge
+/// covers the rows from its top end up to but not including its bottom end, so a ray
+/// through a vertex counts it once.
+pub fn is_enclosed(loops: &[Vec<XY>], xy: &XY) -> bool {
+    let crossings = loops
+        .iter()
+        .flat_map(|points| edges(points))
+        .filter(|(from, to)| {
+            from.x == to.x && from.x > xy.x && (from.y.min(to.y)..from.y.max(to.y)).contains(&xy.y)
+        })
+        .count();
+    crossings % 2 == 1
+}
+
+/// The tiles on or inside a set of loops, column by column, for checking whole
+/// rectangles without drawing a grid.  Columns only change where a loop has a vertical
+/// edge, so runs of identical columns share one set of rows.
+#[derive(Clone, Debug)]
+pub struct InsideColumns {
+    // Runs of columns in ascending order, each with the rows inside in those columns.
+    bands: Vec<(RangeInclusive<usize>, IntervalSet<usize>)>,
+}
+impl InsideColumns {
+    /// Sweeps left to right across the horizontal edges of the loops, which must not
+    /// share a tile (see [`validate_loops`]).  Between two vertical edges, the rows
+    /// inside are those between alternate horizontal edges, so a loop inside another
+    /// cuts a hole in it.
+    pub fn new(loops: &[Vec<XY>]) -> Self {
+        let mut sweep = SweepLine::new();
+        for (from, to) in loops.iter().flat_map(|points| edges(points)) {
+            if from.y == to.y && from.x != to.x {
+                sweep.push(from.x.min(to.x), Event::Insert(from.y));
+                sweep.push(from.x.max(to.x), Event::Remove(from.y));
+            }
+        }
+        let mut active = ActiveSet::new();
+        let mut bands = Vec::new();
+        // The rows inside just left of the current column.
+        let mut before = IntervalSet::new();
+        let mut batches = sweep.batches().peekable();
+        while let Some((x, events)) = batches.next() {
+            events.into_iter().for_each(|event| active.apply(event));
+            let rows = active.iter().copied().collect::<Vec<_>>();
+            let after = rows
+                .chunks(2)
+                .filter_map(|pair| Some(pair[0]..=*pair.get(1)?))
+                .collect::<IntervalSet<_>>();
+            // Tiles on a vertical edge are inside on one side of it or the other.
+            let column = before.iter().chain(after.iter()).cloned().collect();
+            bands.push((x..=x, column));
+            if let Some((next, _)) = batches.peek()
+                && x + 1 < *next
+            {
+                bands.push((x + 1..=next - 1, after.clone()));
+            }
+            before = after;
+        }
+        InsideColumns { bands }
+    }
+
+    /// Checks whether every tile in the rectangle is on or inside a loop.
+    pub fn contains_rect(&self, rect: &Rect) -> bool {
+        // The bands run without gaps from the leftmost loop to the rightmost.
+        let (Some((first, _)), Some((last, _))) = (self.bands.first(), self.bands.last()) else {
+            return false;
+        };
+        if rect.min.x < *first.start() || rect.max.x > *last.end() {
+            return false;
+        }
+        let start = self
+            .bands
+            .partition_point(|(columns, _)| *columns.end() < rect.min.x);
+        let rows = rect.min.y..=rect.max.y;
+        self.bands[start..]
+            .iter()
+            .take_while(|(columns, _)| *columns.start() <= rect.max.x)
+            .all(|(_, inside)| inside.contains_range(&rows))
+    }
+}
+
+fn describe(xy: &XY) -> String {
+    format!("({}, {})", xy.x, xy.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coordinates: &[(usize, usize)]) -> Vec<XY> {
+        coordinates.iter().map(|(x, y)| XY::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn test_validate_sample() {
+        let data = crate::parse_data(include_str!("../sample.txt"))
+            .unwrap()
+            .concat();
+        let polygon = validate(&data).unwrap();
+        assert_eq!(polygon.vertices(), data.as_slice());
+        assert_eq!(polygon.edges().count(), data.len());
+    }
+
+    #[test]
+    fn test_diagonal_edge() {
+        let err = validate(&points(&[(0, 0), (5, 0), (5, 5), (1, 4)])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Edge 2 from (5, 5) to (1, 4) is not horizontal or vertical"
+        );
+    }
+
+    #[test]
+    fn test_self_intersection() {
+        // A figure of eight: the top and bottom loops cross in the middle.
+        let eight = points(&[
+            (0, 0),
+            (4, 0),
+            (4, 4),
+            (2, 4),
+            (2, 6),
+            (6, 6),
+            (6, 2),
+            (0, 2),
+        ]);
+        let err = validate(&eight).unwrap_err();
+        assert!(err.to_string().contains("intersect"), "{}", err);
+    }
+
+    #[test]
+    fn test_doubling_back() {
+        // The third edge runs back along the second.
+        let err = validate(&points(&[(0, 0), (4, 0), (4, 4), (4, 2), (0, 2)])).unwrap_err();
+        assert!(err.to_string().contains("intersect"), "{}", err);
+    }
+
+    #[test]
+    fn test_perimeter_and_boundary() {
+        let data = crate::parse_data(include_str!("../sample.txt"))
+            .unwrap()
+            .concat();
+        let polygon = validate(&data).unwrap();
+        let boundary = polygon.boundary_cells().collect::<Vec<_>>();
+        assert_eq!(polygon.perimeter(), 30);
+        assert_eq!(boundary.len(), polygon.perimeter());
+        assert_eq!(boundary.first(), data.first());
+        let unique = boundary.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), boundary.len());
+
+        // A diagonal edge is walked as an L.
+        let triangle = points(&[(0, 0), (2, 0), (0, 2)]);
+        assert_eq!(perimeter(&triangle), 8);
+        assert_eq!(boundary_cells(&triangle).count(), 8);
+    }
+
+    #[test]
+    fn test_segment() {
+        let cells = |from: (usize, usize), to: (usize, usize)| {
+            segment(&XY::new(from.0, from.1), &XY::new(to.0, to.1))
+                .map(|cells| cells.map(|xy| (xy.x, xy.y)).collect::<Vec<_>>())
+        };
+        assert_eq!(cells((3, 1), (1, 1)).unwrap(), vec![(3, 1), (2, 1), (1, 1)]);
+        assert_eq!(cells((0, 0), (0, 2)).unwrap(), vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(cells((4, 4), (4, 4)).unwrap(), vec![(4, 4)]);
+        assert!(cells((0, 0), (2, 2)).is_err());
+    }
+
+    #[test]
+    fn test_too_few_points() {
+        assert!(validate(&points(&[(0, 0), (4, 0), (4, 4)])).is_err());
+    }
+
+    #[test]
+    fn test_validate_loops() {
+        let square =
+            |min: usize, max: usize| points(&[(min, min), (max, min), (max, max), (min, max)]);
+        assert_eq!(
+            validate_loops(&[square(0, 8), square(2, 6)]).unwrap().len(),
+            2
+        );
+        let err = validate_loops(&[square(0, 8), square(4, 10)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Loop 0 ((8, 0) to (8, 8)) and loop 1 ((4, 4) to (10, 4)) intersect at (8, 4)"
+        );
+        let err = validate_loops(&[square(0, 8), points(&[(1, 1), (2, 2)])]).unwrap_err();
+        assert!(err.to_string().starts_with("Loop 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_inside_columns_match_grid() {
+        let holes = "0,0\n12,0\n12,12\n0,12\n\n3,3\n9,3\n9,9\n3,9\n\n5,5\n7,5\n7,7\n5,7";
+        let touching = "0,0\n8,0\n8,8\n0,8\n\n1,1\n7,1\n7,7\n1,7";
+        for data in [include_str!("../sample.txt"), holes, touching] {
+            let loops = crate::parse_data(data).unwrap();
+            let grid = crate::classified_grid(&loops).unwrap();
+            let inside = InsideColumns::new(&loops);
+            for rect in crate::corner_rectangles(&loops.concat()) {
+                let on_grid = rect
+                    .positions()
+                    .all(|xy| grid.get(xy).unwrap().value() != &crate::Tile::Outside);
+                assert_eq!(inside.contains_rect(&rect), on_grid, "{:?}", rect);
+            }
+        }
+        let loops = crate::parse_data(include_str!("../sample.txt")).unwrap();
+        let inside = InsideColumns::new(&loops);
+        let beyond = Rect::from_corners(&XY::new(7, 1), &XY::new(20, 1));
+        assert!(!inside.contains_rect(&beyond));
+    }
+}